@@ -1,11 +1,16 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use grass::StyleSheet;
 
 pub fn big_for(c: &mut Criterion) {
     c.bench_function("big_for", |b| {
-        b.iter(|| StyleSheet::new(black_box(include_str!("big_for.scss").to_string())))
+        b.iter(|| grass::from_string(black_box(include_str!("big_for.scss").to_string())))
     });
 }
 
-criterion_group!(benches, big_for);
+pub fn big_loop_body(c: &mut Criterion) {
+    c.bench_function("big_loop_body", |b| {
+        b.iter(|| grass::from_string(black_box(include_str!("big_loop_body.scss").to_string())))
+    });
+}
+
+criterion_group!(benches, big_for, big_loop_body);
 criterion_main!(benches);
@@ -0,0 +1,74 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// A stylesheet with `rules` style rules, a placeholder selector, and one
+/// rule extending the placeholder and two existing selectors for every eight
+/// rules
+///
+/// Each `.c` selector appears in 40 rules, so that the size of the output
+/// grows linearly with `rules`.
+fn many_extends(rules: usize) -> String {
+    let shared = rules / 40;
+    let mut input = String::new();
+    for i in 0..rules {
+        input.push_str(&format!(
+            ".a{} .b{}, .c{}:hover {{ color: red; }}\n",
+            i,
+            i % 50,
+            i % shared
+        ));
+    }
+    input.push_str("%placeholder { color: blue; }\n");
+    for i in 0..rules / 8 {
+        input.push_str(&format!(
+            ".x{} {{ @extend %placeholder; @extend .a{}; @extend .c{}; }}\n",
+            i,
+            (i * 7) % rules,
+            i % shared
+        ));
+    }
+    input
+}
+
+/// A stylesheet with `rules` style rules that all contain `.btn`, and
+/// `extends` rules that each extend `.btn`
+fn one_target(rules: usize, extends: usize) -> String {
+    let mut input = String::new();
+    for i in 0..rules {
+        input.push_str(&format!(
+            ".btn .item{}, .card{} > .btn:hover {{ color: red; width: {}px; }}\n",
+            i,
+            i % 100,
+            i
+        ));
+    }
+    for i in 0..extends {
+        input.push_str(&format!(".x{} {{ @extend .btn; }}\n", i));
+    }
+    input
+}
+
+pub fn extend(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extend");
+    for rules in [500, 1000, 2000, 4000].iter() {
+        let input = many_extends(*rules);
+        group.bench_with_input(BenchmarkId::from_parameter(rules), &input, |b, input| {
+            b.iter(|| grass::from_string(black_box(input.clone())))
+        });
+    }
+    group.finish();
+}
+
+pub fn extend_one_target(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extend_one_target");
+    group.sample_size(10);
+    for extends in [25, 50, 100, 200].iter() {
+        let input = one_target(1000, *extends);
+        group.bench_with_input(BenchmarkId::from_parameter(extends), &input, |b, input| {
+            b.iter(|| grass::from_string(black_box(input.clone())))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, extend, extend_one_target);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+pub fn many_includes(c: &mut Criterion) {
+    c.bench_function("many_includes", |b| {
+        b.iter(|| grass::from_string(black_box(include_str!("many_includes.scss").to_string())))
+    });
+}
+
+pub fn recursive_function(c: &mut Criterion) {
+    c.bench_function("recursive_function", |b| {
+        b.iter(|| {
+            grass::from_string(black_box(
+                include_str!("recursive_function.scss").to_string(),
+            ))
+        })
+    });
+}
+
+criterion_group!(benches, many_includes, recursive_function);
+criterion_main!(benches);
@@ -1,15 +1,31 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use grass::StyleSheet;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 
 pub fn many_variable_redeclarations(c: &mut Criterion) {
     c.bench_function("many_variable_redeclarations", |b| {
         b.iter(|| {
-            StyleSheet::new(black_box(
+            grass::from_string(black_box(
                 include_str!("many_variable_redeclarations.scss").to_string(),
             ))
         })
     });
 }
 
-criterion_group!(benches, many_variable_redeclarations);
+/// Building up a list or map in a loop should take linear time
+pub fn append_in_loop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("append_in_loop");
+    for n in [1000, 2000, 4000] {
+        let input = format!(
+            "$l: ();\n@for $i from 1 through {n} {{\n  $l: append($l, $i, comma);\n}}\n\
+             $m: ();\n@for $i from 1 through {n} {{\n  $m: map-merge($m, ($i: $i));\n}}\n\
+             a {{\n  b: length($l);\n  c: length($m);\n}}\n",
+            n = n
+        );
+        group.bench_with_input(BenchmarkId::from_parameter(n), &input, |b, input| {
+            b.iter(|| grass::from_string(black_box(input.clone())))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, many_variable_redeclarations, append_in_loop);
 criterion_main!(benches);
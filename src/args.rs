@@ -2,21 +2,15 @@ use std::collections::HashMap;
 
 use codemap::{Span, Spanned};
 
-use crate::{
-    common::Identifier,
-    error::SassResult,
-    parse::Parser,
-    value::Value,
-    {Cow, Token},
-};
-
-#[derive(Debug, Clone, Eq, PartialEq)]
+use crate::{ast::Expr, common::Identifier, error::SassResult, value::Value, Cow};
+
+#[derive(Debug, Clone)]
 pub(crate) struct FuncArgs(pub Vec<FuncArg>);
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone)]
 pub(crate) struct FuncArg {
     pub name: Identifier,
-    pub default: Option<Vec<Token>>,
+    pub default: Option<Expr>,
     pub is_variadic: bool,
 }
 
@@ -61,11 +55,7 @@ impl CallArg {
 }
 
 impl CallArgs {
-    pub fn new(span: Span) -> Self {
-        CallArgs(HashMap::new(), span)
-    }
-
-    pub fn to_css_string(self, parser: &mut Parser<'_>) -> SassResult<Spanned<String>> {
+    pub fn to_css_string(self) -> SassResult<Spanned<String>> {
         let mut string = String::with_capacity(2 + self.len() * 10);
         string.push('(');
         let mut span = self.1;
@@ -77,7 +67,7 @@ impl CallArgs {
             });
         }
 
-        let args = match parser.variadic_args(self) {
+        let args = match self.into_positional() {
             Ok(v) => v,
             Err(..) => {
                 return Err(("Plain CSS functions don't support keyword arguments.", span).into())
@@ -98,6 +88,44 @@ impl CallArgs {
         Ok(Spanned { node: string, span })
     }
 
+    /// Take all arguments in order of position, without dividing any
+    /// slash-separated numbers
+    ///
+    /// Errors if any argument was passed by name
+    pub fn into_positional(self) -> SassResult<Vec<Spanned<Value>>> {
+        let span = self.1;
+        let mut args = match self
+            .0
+            .into_iter()
+            .map(|(a, v)| Ok((a.position()?, v)))
+            .collect::<Result<Vec<(usize, SassResult<Spanned<Value>>)>, String>>()
+        {
+            Ok(v) => v,
+            Err(e) => return Err((format!("No argument named ${}.", e), span).into()),
+        };
+        args.sort_by(|(a1, _), (a2, _)| a1.cmp(a2));
+        args.into_iter().map(|(_, v)| v).collect()
+    }
+
+    /// Take all positional arguments in order of position, along with all
+    /// named arguments in the order they were passed
+    pub fn into_positional_and_named(
+        self,
+    ) -> SassResult<(Vec<Spanned<Value>>, Vec<(Identifier, Spanned<Value>)>)> {
+        let mut positional = Vec::new();
+        let mut named = Vec::new();
+        for (arg, value) in self.0 {
+            match arg {
+                CallArg::Positional(position) => positional.push((position, value?)),
+                CallArg::Named(name) => named.push((name, value?)),
+            }
+        }
+        positional.sort_by_key(|(position, _)| *position);
+        // arguments splatted from a map share a span, so fall back to their names
+        named.sort_by_key(|(name, value)| (value.span.low(), name.resolve()));
+        Ok((positional.into_iter().map(|(_, v)| v).collect(), named))
+    }
+
     /// Get argument by name
     ///
     /// Removes the argument
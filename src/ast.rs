@@ -0,0 +1,487 @@
+//! An unevaluated representation of a stylesheet
+//!
+//! A stylesheet is first parsed into a tree of `AstStmt`s, which is then
+//! evaluated by `Parser::eval_ast` to produce the `Stmt`s that make up the
+//! output CSS. SassScript expressions are parsed into `AstExpr`s at the same
+//! time, so evaluating a statement never needs to look at its source again,
+//! no matter how many times it's evaluated by a loop or an `@include`.
+//!
+//! The tree refers to names by interned `Identifier`s, so it may only be
+//! evaluated by the `Compiler` that parsed it, which caches the trees of
+//! imported files between compilations.
+
+use std::rc::Rc;
+
+use codemap::{Span, Spanned};
+use once_cell::unsync::OnceCell;
+
+use crate::{
+    args::FuncArgs,
+    common::{Brackets, Identifier, ListSeparator, Op, QuoteKind},
+    selector::Selector,
+    unit::Unit,
+    value::{CalculationName, Number, Value},
+};
+
+/// An unevaluated SassScript expression
+pub(crate) type Expr = Spanned<AstExpr>;
+
+#[derive(Debug, Clone)]
+pub(crate) enum AstExpr {
+    /// A value that doesn't need to be evaluated, such as `red` or `null`
+    Literal(Value),
+    /// A number written directly in the stylesheet, which may be separated
+    /// from another by `/` rather than divided by it
+    Number(Number, Unit),
+    /// `$name` or `namespace.$name`
+    Variable {
+        namespace: Option<Spanned<Identifier>>,
+        name: Spanned<Identifier>,
+    },
+    /// `&`
+    ParentSelector,
+    /// A string that contains interpolation, or that is quoted
+    String(Interpolation, QuoteKind),
+    FunctionCall(Box<FunctionCall>),
+    /// A function whose name contains interpolation, which is always a plain
+    /// CSS function
+    InterpolatedFunction(Box<(Interpolation, ArgumentInvocation)>),
+    Calculation {
+        name: CalculationName,
+        args: Vec<CalcArg>,
+    },
+    BinaryOp(Box<Expr>, Op, Box<Expr>),
+    /// `-x`, `+x`, `not x`, or `/x`
+    UnaryOp(Op, Box<Expr>),
+    /// An expression in parentheses, which divides slash-separated numbers
+    Paren(Box<Expr>),
+    List(Vec<Expr>, ListSeparator, Brackets),
+    Map(Vec<(Expr, Expr)>),
+}
+
+impl AstExpr {
+    pub const fn span(self, span: Span) -> Expr {
+        Spanned { node: self, span }
+    }
+
+    /// Whether evaluating this expression might read the variable `$name`
+    ///
+    /// Any function call might read it, as it may be a user-defined function
+    /// that refers to a global variable.
+    pub fn may_read_var(&self, name: Identifier) -> bool {
+        match self {
+            AstExpr::Variable {
+                namespace: None,
+                name: var,
+            } => var.node == name,
+            AstExpr::FunctionCall(..) | AstExpr::InterpolatedFunction(..) => true,
+            AstExpr::String(interpolation, ..) => interpolation.may_read_var(name),
+            AstExpr::Calculation { args, .. } => args.iter().any(|arg| arg.may_read_var(name)),
+            AstExpr::BinaryOp(lhs, _, rhs) => {
+                lhs.node.may_read_var(name) || rhs.node.may_read_var(name)
+            }
+            AstExpr::UnaryOp(_, expr) | AstExpr::Paren(expr) => expr.node.may_read_var(name),
+            AstExpr::List(elems, ..) => elems.iter().any(|elem| elem.node.may_read_var(name)),
+            AstExpr::Map(pairs) => pairs
+                .iter()
+                .any(|(key, value)| key.node.may_read_var(name) || value.node.may_read_var(name)),
+            AstExpr::Literal(..)
+            | AstExpr::Number(..)
+            | AstExpr::Variable { .. }
+            | AstExpr::ParentSelector => false,
+        }
+    }
+}
+
+/// A call to a function whose name is known without evaluating anything
+///
+/// Whether this is a Sass function or a plain CSS function isn't known until
+/// it's evaluated, as functions may be declared at any time.
+#[derive(Debug, Clone)]
+pub(crate) struct FunctionCall {
+    pub namespace: Option<Spanned<Identifier>>,
+    pub name: Identifier,
+    /// The name as written, which is emitted for plain CSS functions
+    pub css_name: String,
+    pub args: ArgumentInvocation,
+    pub span: Span,
+}
+
+/// An argument to `calc()`, `min()`, `max()` or `clamp()`
+#[derive(Debug, Clone)]
+pub(crate) enum CalcArg {
+    /// A number, variable or function call
+    Expr(Expr),
+    /// Interpolation, which makes everything up to the closing parenthesis an
+    /// unevaluated string
+    Interpolation(Interpolation),
+    Operation(Box<CalcArg>, Op, Box<CalcArg>, Span),
+    Paren(Box<CalcArg>),
+}
+
+impl CalcArg {
+    fn may_read_var(&self, name: Identifier) -> bool {
+        match self {
+            CalcArg::Expr(expr) => expr.node.may_read_var(name),
+            CalcArg::Interpolation(interpolation) => interpolation.may_read_var(name),
+            CalcArg::Operation(lhs, _, rhs, _) => lhs.may_read_var(name) || rhs.may_read_var(name),
+            CalcArg::Paren(arg) => arg.may_read_var(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Argument {
+    Positional(Expr),
+    Named(Identifier, Expr),
+    /// `$args...`
+    Rest(Expr),
+}
+
+impl Argument {
+    pub fn expr(&self) -> &Expr {
+        match self {
+            Argument::Positional(expr) | Argument::Named(_, expr) | Argument::Rest(expr) => expr,
+        }
+    }
+}
+
+/// The arguments passed to a function or mixin
+#[derive(Debug, Clone)]
+pub(crate) struct ArgumentInvocation {
+    pub args: Vec<Argument>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum InterpolationPart {
+    Literal(String),
+    /// The contents of `#{...}`
+    Expr(Expr),
+}
+
+/// Plain text that may contain interpolated expressions, e.g. the selector
+/// `.foo-#{$i}` or the property `#{$prop}-width`
+#[derive(Debug, Clone)]
+pub(crate) struct Interpolation {
+    pub parts: Vec<InterpolationPart>,
+    pub span: Span,
+}
+
+impl Interpolation {
+    fn may_read_var(&self, name: Identifier) -> bool {
+        self.parts.iter().any(|part| match part {
+            InterpolationPart::Literal(..) => false,
+            InterpolationPart::Expr(expr) => expr.node.may_read_var(name),
+        })
+    }
+
+    pub const fn new(span: Span) -> Self {
+        Self {
+            parts: Vec::new(),
+            span,
+        }
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        if let Some(InterpolationPart::Literal(s)) = self.parts.last_mut() {
+            s.push(c);
+        } else {
+            self.parts.push(InterpolationPart::Literal(c.to_string()));
+        }
+    }
+
+    pub fn push_str(&mut self, string: &str) {
+        if let Some(InterpolationPart::Literal(s)) = self.parts.last_mut() {
+            s.push_str(string);
+        } else if !string.is_empty() {
+            self.parts
+                .push(InterpolationPart::Literal(string.to_owned()));
+        }
+    }
+
+    pub fn push_expr(&mut self, expr: Expr) {
+        self.parts.push(InterpolationPart::Expr(expr));
+    }
+
+    pub fn append(&mut self, other: Interpolation) {
+        for part in other.parts {
+            match part {
+                InterpolationPart::Literal(s) => self.push_str(&s),
+                InterpolationPart::Expr(expr) => self.push_expr(expr),
+            }
+        }
+    }
+
+    /// The text of this interpolation, if it doesn't contain any expressions
+    pub fn as_plain(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [] => Some(""),
+            [InterpolationPart::Literal(s)] => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+}
+
+/// A single `@if` or `@else if` clause
+#[derive(Debug, Clone)]
+pub(crate) struct Branch {
+    pub cond: Expr,
+    pub body: Vec<AstStmt>,
+}
+
+impl Branch {
+    pub fn new(cond: Expr, body: Vec<AstStmt>) -> Branch {
+        Branch { cond, body }
+    }
+}
+
+/// The parsed form of a selector that contains no interpolation, which is
+/// the same every time its style rule is evaluated
+///
+/// A style rule in a mixin may be included both at the root of the document
+/// and within another style rule, which differ in whether `&` is allowed, so
+/// each is parsed separately.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SelectorCache {
+    top_level: OnceCell<Selector>,
+    nested: OnceCell<Selector>,
+}
+
+impl SelectorCache {
+    pub fn get(&self, allows_parent: bool) -> &OnceCell<Selector> {
+        if allows_parent {
+            &self.nested
+        } else {
+            &self.top_level
+        }
+    }
+}
+
+/// The block passed to a mixin by `@include`, along with the arguments it
+/// accepts with `using`
+#[derive(Debug, Clone)]
+pub(crate) struct ContentBlock {
+    pub args: Option<FuncArgs>,
+    pub body: Vec<AstStmt>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum AstStmt {
+    /// A style rule: `a { color: red; }`
+    RuleSet {
+        selector: Interpolation,
+        /// The parsed selector, if it contains no interpolation
+        parsed: SelectorCache,
+        body: Vec<AstStmt>,
+        span: Span,
+    },
+    /// A declaration, along with any nested properties:
+    /// `font: 12px { family: serif; }`
+    Style {
+        property: Interpolation,
+        value: Option<Expr>,
+        body: Vec<AstStmt>,
+        span: Span,
+    },
+    /// `$a: b !default !global`
+    VariableDecl {
+        name: Identifier,
+        value: Expr,
+        global: bool,
+        default: bool,
+        span: Span,
+    },
+    /// `@if`, along with any `@else if` and `@else` clauses
+    If {
+        branches: Vec<Branch>,
+        else_: Vec<AstStmt>,
+        span: Span,
+    },
+    /// `@each $key, $value in $map`
+    Each {
+        vars: Vec<Spanned<Identifier>>,
+        list: Expr,
+        body: Vec<AstStmt>,
+        span: Span,
+    },
+    /// `@for $i from 1 through 10`
+    For {
+        var: Spanned<Identifier>,
+        from: Expr,
+        to: Expr,
+        through: bool,
+        body: Vec<AstStmt>,
+        span: Span,
+    },
+    While {
+        cond: Expr,
+        body: Vec<AstStmt>,
+        span: Span,
+    },
+    /// A loud comment: `/* ... */`
+    Comment {
+        text: Interpolation,
+        span: Span,
+    },
+    Return {
+        value: Expr,
+        span: Span,
+    },
+    Warn {
+        message: Expr,
+        span: Span,
+    },
+    Debug {
+        message: Expr,
+        span: Span,
+    },
+    Error {
+        message: Expr,
+        span: Span,
+    },
+    Mixin {
+        name: Identifier,
+        args: FuncArgs,
+        body: Rc<Vec<AstStmt>>,
+        /// Whether the body contains `@content`
+        accepts_content_block: bool,
+        span: Span,
+    },
+    Function {
+        name: Identifier,
+        args: FuncArgs,
+        body: Rc<Vec<AstStmt>>,
+        span: Span,
+    },
+    Include {
+        namespace: Option<Spanned<Identifier>>,
+        name: Spanned<Identifier>,
+        args: ArgumentInvocation,
+        content: Option<Rc<ContentBlock>>,
+        span: Span,
+    },
+    /// `@content`, along with the arguments passed to the content block
+    Content {
+        args: ArgumentInvocation,
+        span: Span,
+    },
+    /// `@import`, which is either evaluated or emitted as a plain CSS import
+    /// depending on its url
+    Import {
+        url: Expr,
+        span: Span,
+    },
+    /// `@use "sass:math" as m`
+    Use {
+        url: String,
+        namespace: Option<String>,
+        span: Span,
+    },
+    Media {
+        query: Interpolation,
+        body: Vec<AstStmt>,
+        span: Span,
+    },
+    Supports {
+        params: Interpolation,
+        body: Vec<AstStmt>,
+        span: Span,
+    },
+    AtRoot {
+        selector: Option<Interpolation>,
+        body: Vec<AstStmt>,
+        span: Span,
+    },
+    Extend {
+        selector: Interpolation,
+        is_optional: bool,
+        span: Span,
+    },
+    Keyframes {
+        name: Interpolation,
+        body: Vec<AstStmt>,
+        span: Span,
+    },
+    /// An at-rule that Sass doesn't know about, which is emitted as-is
+    UnknownAtRule {
+        name: Interpolation,
+        params: Interpolation,
+        /// `None` if the at-rule has no block, e.g. `@foo;`
+        body: Option<Vec<AstStmt>>,
+        span: Span,
+    },
+}
+
+impl AstStmt {
+    /// The name of the at-rule, or the selector, property or variable name,
+    /// that begins this statement
+    pub fn span(&self) -> Span {
+        match self {
+            AstStmt::RuleSet { span, .. }
+            | AstStmt::Style { span, .. }
+            | AstStmt::VariableDecl { span, .. }
+            | AstStmt::If { span, .. }
+            | AstStmt::Each { span, .. }
+            | AstStmt::For { span, .. }
+            | AstStmt::While { span, .. }
+            | AstStmt::Comment { span, .. }
+            | AstStmt::Return { span, .. }
+            | AstStmt::Warn { span, .. }
+            | AstStmt::Debug { span, .. }
+            | AstStmt::Error { span, .. }
+            | AstStmt::Mixin { span, .. }
+            | AstStmt::Function { span, .. }
+            | AstStmt::Include { span, .. }
+            | AstStmt::Content { span, .. }
+            | AstStmt::Import { span, .. }
+            | AstStmt::Use { span, .. }
+            | AstStmt::Media { span, .. }
+            | AstStmt::Supports { span, .. }
+            | AstStmt::AtRoot { span, .. }
+            | AstStmt::Extend { span, .. }
+            | AstStmt::Keyframes { span, .. }
+            | AstStmt::UnknownAtRule { span, .. } => *span,
+        }
+    }
+
+    /// Whether `@content` appears anywhere in this statement, other than in
+    /// the declaration of another mixin
+    pub fn contains_content_rule(&self) -> bool {
+        let any = |body: &[AstStmt]| body.iter().any(AstStmt::contains_content_rule);
+        match self {
+            AstStmt::Content { .. } => true,
+            AstStmt::RuleSet { body, .. }
+            | AstStmt::Style { body, .. }
+            | AstStmt::Each { body, .. }
+            | AstStmt::For { body, .. }
+            | AstStmt::While { body, .. }
+            | AstStmt::Media { body, .. }
+            | AstStmt::Supports { body, .. }
+            | AstStmt::AtRoot { body, .. }
+            | AstStmt::Keyframes { body, .. } => any(body),
+            AstStmt::UnknownAtRule { body, .. } => body.as_deref().map_or(false, any),
+            AstStmt::If {
+                branches, else_, ..
+            } => branches.iter().any(|branch| any(&branch.body)) || any(else_),
+            AstStmt::Include { content, .. } => {
+                content.as_ref().map_or(false, |content| any(&content.body))
+            }
+            AstStmt::VariableDecl { .. }
+            | AstStmt::Comment { .. }
+            | AstStmt::Return { .. }
+            | AstStmt::Warn { .. }
+            | AstStmt::Debug { .. }
+            | AstStmt::Error { .. }
+            | AstStmt::Mixin { .. }
+            | AstStmt::Function { .. }
+            | AstStmt::Import { .. }
+            | AstStmt::Use { .. }
+            | AstStmt::Extend { .. } => false,
+        }
+    }
+}
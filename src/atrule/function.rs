@@ -1,12 +1,16 @@
+use std::rc::Rc;
+
 use codemap::Span;
 
-use crate::{args::FuncArgs, scope::Scope, Token};
+use crate::{args::FuncArgs, ast::AstStmt, scope::Scopes};
 
 #[derive(Debug, Clone)]
 pub(crate) struct Function {
-    pub scope: Scope,
+    /// The scopes the function was declared in
+    pub scope: Scopes,
     pub args: FuncArgs,
-    pub body: Vec<Token>,
+    /// The body of the function, parsed once when the function is declared
+    pub body: Rc<Vec<AstStmt>>,
     pos: Span,
 }
 
@@ -19,7 +23,7 @@ impl PartialEq for Function {
 impl Eq for Function {}
 
 impl Function {
-    pub fn new(scope: Scope, args: FuncArgs, body: Vec<Token>, pos: Span) -> Self {
+    pub fn new(scope: Scopes, args: FuncArgs, body: Rc<Vec<AstStmt>>, pos: Span) -> Self {
         Function {
             scope,
             args,
@@ -1,18 +1,28 @@
-use crate::{args::FuncArgs, scope::Scope, Token};
+use std::rc::Rc;
+
+use crate::{
+    args::FuncArgs,
+    ast::{AstStmt, ContentBlock},
+    scope::Scopes,
+};
 
 #[derive(Debug, Clone)]
 pub(crate) struct Mixin {
-    pub scope: Scope,
+    /// The scopes the mixin was declared in
+    pub scope: Scopes,
     pub args: FuncArgs,
-    pub body: Vec<Token>,
+    /// The body of the mixin, parsed once when the mixin is declared
+    pub body: Rc<Vec<AstStmt>>,
+    /// Whether the body contains `@content`, which is required for a
+    /// content block to be passed to the mixin
     pub accepts_content_block: bool,
 }
 
 impl Mixin {
     pub fn new(
-        scope: Scope,
+        scope: Scopes,
         args: FuncArgs,
-        body: Vec<Token>,
+        body: Rc<Vec<AstStmt>>,
         accepts_content_block: bool,
     ) -> Self {
         Mixin {
@@ -26,17 +36,17 @@ impl Mixin {
 
 #[derive(Debug, Clone)]
 pub(crate) struct Content {
-    pub content: Option<Vec<Token>>,
-    pub content_args: Option<FuncArgs>,
-    pub scope: Scope,
+    /// The block passed by `@include`, along with the arguments it accepts
+    pub content: Option<Rc<ContentBlock>>,
+    /// The scopes of the `@include` the content block was passed to
+    pub scope: Scopes,
 }
 
 impl Content {
     pub fn new() -> Self {
         Self {
             content: None,
-            content_args: None,
-            scope: Scope::new(),
+            scope: Scopes::new(),
         }
     }
 }
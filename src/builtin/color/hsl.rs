@@ -1,4 +1,4 @@
-use super::{Builtin, GlobalFunctionMap};
+use super::{Builtin, GlobalFunctionMap, Module};
 
 use num_traits::One;
 
@@ -19,7 +19,7 @@ fn inner_hsl(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
 
     if args.len() == 1 {
         let mut channels = match parser.arg(&mut args, 0, "channels")? {
-            Value::List(v, ..) => v,
+            v @ Value::List(..) => v.as_list(),
             _ => return Err(("Missing argument $channels.", args.span()).into()),
         };
 
@@ -35,7 +35,7 @@ fn inner_hsl(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
         }
 
         let lightness = match channels.pop() {
-            Some(Value::Dimension(n, _)) => n / Number::from(100),
+            Some(Value::Dimension(n, _, _)) => n / Number::from(100),
             Some(v) => {
                 return Err((
                     format!(
@@ -50,7 +50,7 @@ fn inner_hsl(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
         };
 
         let saturation = match channels.pop() {
-            Some(Value::Dimension(n, _)) => n / Number::from(100),
+            Some(Value::Dimension(n, _, _)) => n / Number::from(100),
             Some(v) => {
                 return Err((
                     format!(
@@ -65,7 +65,7 @@ fn inner_hsl(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
         };
 
         let hue = match channels.pop() {
-            Some(Value::Dimension(n, _)) => n,
+            Some(Value::Dimension(n, _, _)) => n,
             Some(v) => {
                 return Err((
                     format!("$hue: {} is not a number.", v.to_css_string(args.span())?),
@@ -84,7 +84,7 @@ fn inner_hsl(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
         ))))
     } else {
         let hue = match parser.arg(&mut args, 0, "hue")? {
-            Value::Dimension(n, _) => n,
+            Value::Dimension(n, _, _) => n,
             v if v.is_special_function() => {
                 let saturation = parser.arg(&mut args, 1, "saturation")?;
                 let lightness = parser.arg(&mut args, 2, "lightness")?;
@@ -115,7 +115,7 @@ fn inner_hsl(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
             }
         };
         let saturation = match parser.arg(&mut args, 1, "saturation")? {
-            Value::Dimension(n, _) => n / Number::from(100),
+            Value::Dimension(n, _, _) => n / Number::from(100),
             v if v.is_special_function() => {
                 let lightness = parser.arg(&mut args, 2, "lightness")?;
                 let mut string = format!(
@@ -148,7 +148,7 @@ fn inner_hsl(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
             }
         };
         let lightness = match parser.arg(&mut args, 2, "lightness")? {
-            Value::Dimension(n, _) => n / Number::from(100),
+            Value::Dimension(n, _, _) => n / Number::from(100),
             v if v.is_special_function() => {
                 let mut string = format!(
                     "{}({}, {}, {}",
@@ -183,10 +183,10 @@ fn inner_hsl(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
             &mut args,
             3,
             "alpha",
-            Value::Dimension(Number::one(), Unit::None),
+            Value::Dimension(Number::one(), Unit::None, None),
         )? {
-            Value::Dimension(n, Unit::None) => n,
-            Value::Dimension(n, Unit::Percent) => n / Number::from(100),
+            Value::Dimension(n, Unit::None, _) => n,
+            Value::Dimension(n, Unit::Percent, _) => n / Number::from(100),
             v @ Value::Dimension(..) => {
                 return Err((
                     format!(
@@ -235,7 +235,7 @@ fn hsla(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 fn hue(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "color")? {
-        Value::Color(c) => Ok(Value::Dimension(c.hue(), Unit::Deg)),
+        Value::Color(c) => Ok(Value::Dimension(c.hue(), Unit::Deg, None)),
         v => Err((
             format!("$color: {} is not a color.", v.to_css_string(args.span())?),
             args.span(),
@@ -247,7 +247,7 @@ fn hue(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 fn saturation(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "color")? {
-        Value::Color(c) => Ok(Value::Dimension(c.saturation(), Unit::Percent)),
+        Value::Color(c) => Ok(Value::Dimension(c.saturation(), Unit::Percent, None)),
         v => Err((
             format!("$color: {} is not a color.", v.to_css_string(args.span())?),
             args.span(),
@@ -259,7 +259,7 @@ fn saturation(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
 fn lightness(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "color")? {
-        Value::Color(c) => Ok(Value::Dimension(c.lightness(), Unit::Percent)),
+        Value::Color(c) => Ok(Value::Dimension(c.lightness(), Unit::Percent, None)),
         v => Err((
             format!("$color: {} is not a color.", v.to_css_string(args.span())?),
             args.span(),
@@ -281,7 +281,7 @@ fn adjust_hue(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
         }
     };
     let degrees = match parser.arg(&mut args, 1, "degrees")? {
-        Value::Dimension(n, _) => n,
+        Value::Dimension(n, _, _) => n,
         v => {
             return Err((
                 format!(
@@ -309,7 +309,7 @@ fn lighten(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         }
     };
     let amount = match parser.arg(&mut args, 1, "amount")? {
-        Value::Dimension(n, u) => bound!(args, "amount", n, u, 0, 100) / Number::from(100),
+        Value::Dimension(n, u, _) => bound!(args, "amount", n, u, 0, 100) / Number::from(100),
         v => {
             return Err((
                 format!(
@@ -337,7 +337,7 @@ fn darken(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         }
     };
     let amount = match parser.arg(&mut args, 1, "amount")? {
-        Value::Dimension(n, u) => bound!(args, "amount", n, u, 0, 100) / Number::from(100),
+        Value::Dimension(n, u, _) => bound!(args, "amount", n, u, 0, 100) / Number::from(100),
         v => {
             return Err((
                 format!(
@@ -367,7 +367,7 @@ fn saturate(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     }
 
     let amount = match parser.arg(&mut args, 1, "amount")? {
-        Value::Dimension(n, u) => bound!(args, "amount", n, u, 0, 100) / Number::from(100),
+        Value::Dimension(n, u, _) => bound!(args, "amount", n, u, 0, 100) / Number::from(100),
         v => {
             return Err((
                 format!(
@@ -381,7 +381,7 @@ fn saturate(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     };
     let color = match parser.arg(&mut args, 0, "color")? {
         Value::Color(c) => c,
-        Value::Dimension(n, u) => {
+        Value::Dimension(n, u, _) => {
             return Ok(Value::String(
                 format!("saturate({}{})", n, u),
                 QuoteKind::None,
@@ -411,7 +411,7 @@ fn desaturate(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
         }
     };
     let amount = match parser.arg(&mut args, 1, "amount")? {
-        Value::Dimension(n, u) => bound!(args, "amount", n, u, 0, 100) / Number::from(100),
+        Value::Dimension(n, u, _) => bound!(args, "amount", n, u, 0, 100) / Number::from(100),
         v => {
             return Err((
                 format!(
@@ -430,7 +430,7 @@ fn grayscale(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     let color = match parser.arg(&mut args, 0, "color")? {
         Value::Color(c) => c,
-        Value::Dimension(n, u) => {
+        Value::Dimension(n, u, _) => {
             return Ok(Value::String(
                 format!("grayscale({}{})", n, u),
                 QuoteKind::None,
@@ -468,9 +468,9 @@ fn invert(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         &mut args,
         1,
         "weight",
-        Value::Dimension(Number::from(100), Unit::Percent),
+        Value::Dimension(Number::from(100), Unit::Percent, None),
     )? {
-        Value::Dimension(n, u) => bound!(args, "weight", n, u, 0, 100) / Number::from(100),
+        Value::Dimension(n, u, _) => bound!(args, "weight", n, u, 0, 100) / Number::from(100),
         v => {
             return Err((
                 format!(
@@ -484,7 +484,7 @@ fn invert(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     };
     match parser.arg(&mut args, 0, "color")? {
         Value::Color(c) => Ok(Value::Color(Box::new(c.invert(weight)))),
-        Value::Dimension(n, Unit::Percent) => {
+        Value::Dimension(n, Unit::Percent, _) => {
             Ok(Value::String(format!("invert({}%)", n), QuoteKind::None))
         }
         Value::Dimension(..) => Err((
@@ -515,3 +515,12 @@ pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     f.insert("complement", Builtin::new(complement));
     f.insert("invert", Builtin::new(invert));
 }
+
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("hue", hue);
+    m.insert_builtin("saturation", saturation);
+    m.insert_builtin("lightness", lightness);
+    m.insert_builtin("grayscale", grayscale);
+    m.insert_builtin("complement", complement);
+    m.insert_builtin("invert", invert);
+}
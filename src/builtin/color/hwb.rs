@@ -0,0 +1,129 @@
+use super::{
+    space::{alpha_value, parse_channels},
+    Builtin, GlobalFunctionMap, Module,
+};
+
+use num_traits::One;
+
+use crate::{
+    args::CallArgs,
+    color::{Color, ColorSpace},
+    error::SassResult,
+    parse::Parser,
+    unit::Unit,
+    value::{Number, Value},
+};
+
+/// Get the `$whiteness` or `$blackness` argument of `hwb()`, which must be
+/// a percentage between `0%` and `100%`
+fn percent_arg(
+    parser: &mut Parser<'_>,
+    args: &mut CallArgs,
+    position: usize,
+    name: &'static str,
+) -> SassResult<Number> {
+    match parser.arg(args, position, name)? {
+        Value::Dimension(n, Unit::Percent, _) => {
+            if n > Number::from(100) || n < Number::from(0) {
+                return Err((
+                    format!("${}: Expected {}% to be within 0% and 100%.", name, n),
+                    args.span(),
+                )
+                    .into());
+            }
+            Ok(n)
+        }
+        Value::Dimension(n, u, _) => Err((
+            format!("${}: Expected {}{} to have unit \"%\".", name, n, u),
+            args.span(),
+        )
+            .into()),
+        v => Err((
+            format!("${}: {} is not a number.", name, v.inspect(args.span())?),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+fn hwb(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(4)?;
+
+    if args.len() == 1 {
+        let channels = parser.arg(&mut args, 0, "channels")?;
+        return parse_channels("hwb", Some(ColorSpace::Hwb), channels, args.span());
+    }
+
+    let hue = match parser.arg(&mut args, 0, "hue")? {
+        Value::Dimension(n, Unit::None, _) => n,
+        Value::Dimension(n, u, _) => match u.conversion_factor(&Unit::Deg) {
+            Some(factor) => n * factor,
+            None => {
+                return Err((
+                    format!(
+                        "$hue: Expected {}{} to have an angle unit (deg, grad, rad, turn).",
+                        n, u
+                    ),
+                    args.span(),
+                )
+                    .into())
+            }
+        },
+        v => {
+            return Err((
+                format!("$hue: {} is not a number.", v.inspect(args.span())?),
+                args.span(),
+            )
+                .into())
+        }
+    };
+    let whiteness = percent_arg(parser, &mut args, 1, "whiteness")?;
+    let blackness = percent_arg(parser, &mut args, 2, "blackness")?;
+    let alpha = match parser.default_arg(&mut args, 3, "alpha", Value::Null)? {
+        Value::Null => Number::one(),
+        v => alpha_value(v, args.span())?,
+    };
+
+    Ok(Value::Color(Box::new(Color::new_in_space(
+        ColorSpace::Hwb,
+        [Some(hue), Some(whiteness), Some(blackness)],
+        alpha,
+    ))))
+}
+
+/// The value of `color`'s channel at `index` in the HWB space, as a percentage
+fn hwb_channel(index: usize, mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    match parser.arg(&mut args, 0, "color")? {
+        Value::Color(c) => Ok(Value::Dimension(
+            Number::from(c.channels_in(ColorSpace::Hwb)[index].unwrap_or(0.0)),
+            Unit::Percent,
+            None,
+        )),
+        v => Err((
+            format!("$color: {} is not a color.", v.to_css_string(args.span())?),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+fn whiteness(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    hwb_channel(1, args, parser)
+}
+
+fn blackness(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    hwb_channel(2, args, parser)
+}
+
+pub(crate) fn declare(f: &mut GlobalFunctionMap) {
+    f.insert("hwb", Builtin::new(hwb));
+    f.insert("whiteness", Builtin::new(whiteness));
+    f.insert("blackness", Builtin::new(blackness));
+}
+
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("hwb", hwb);
+    m.insert_builtin("whiteness", whiteness);
+    m.insert_builtin("blackness", blackness);
+}
@@ -1,13 +1,26 @@
-use super::{Builtin, GlobalFunctionMap};
+use super::{Builtin, GlobalFunctionMap, Module};
 
 mod hsl;
+mod hwb;
 mod opacity;
 mod other;
 mod rgb;
+mod space;
 
 pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     hsl::declare(f);
+    hwb::declare(f);
     opacity::declare(f);
     other::declare(f);
     rgb::declare(f);
+    space::declare(f);
+}
+
+pub(crate) fn declare_module(m: &mut Module) {
+    hsl::declare_module(m);
+    hwb::declare_module(m);
+    opacity::declare_module(m);
+    other::declare_module(m);
+    rgb::declare_module(m);
+    space::declare_module(m);
 }
@@ -1,4 +1,4 @@
-use super::{Builtin, GlobalFunctionMap};
+use super::{Builtin, GlobalFunctionMap, Module};
 
 use crate::{
     args::CallArgs, common::QuoteKind, error::SassResult, parse::Parser, unit::Unit, value::Number,
@@ -8,7 +8,7 @@ use crate::{
 fn alpha(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "color")? {
-        Value::Color(c) => Ok(Value::Dimension(c.alpha(), Unit::None)),
+        Value::Color(c) => Ok(Value::Dimension(c.alpha(), Unit::None, None)),
         v => Err((
             format!("$color: {} is not a color.", v.to_css_string(args.span())?),
             args.span(),
@@ -20,8 +20,8 @@ fn alpha(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 fn opacity(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "color")? {
-        Value::Color(c) => Ok(Value::Dimension(c.alpha(), Unit::None)),
-        Value::Dimension(num, unit) => Ok(Value::String(
+        Value::Color(c) => Ok(Value::Dimension(c.alpha(), Unit::None, None)),
+        Value::Dimension(num, unit, _) => Ok(Value::String(
             format!("opacity({}{})", num, unit),
             QuoteKind::None,
         )),
@@ -46,7 +46,7 @@ fn opacify(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         }
     };
     let amount = match parser.arg(&mut args, 1, "amount")? {
-        Value::Dimension(n, u) => bound!(args, "amount", n, u, 0, 1),
+        Value::Dimension(n, u, _) => bound!(args, "amount", n, u, 0, 1),
         v => {
             return Err((
                 format!(
@@ -74,7 +74,7 @@ fn fade_in(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         }
     };
     let amount = match parser.arg(&mut args, 1, "amount")? {
-        Value::Dimension(n, u) => bound!(args, "amount", n, u, 0, 1),
+        Value::Dimension(n, u, _) => bound!(args, "amount", n, u, 0, 1),
         v => {
             return Err((
                 format!(
@@ -102,7 +102,7 @@ fn transparentize(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Val
         }
     };
     let amount = match parser.arg(&mut args, 1, "amount")? {
-        Value::Dimension(n, u) => bound!(args, "amount", n, u, 0, 1),
+        Value::Dimension(n, u, _) => bound!(args, "amount", n, u, 0, 1),
         v => {
             return Err((
                 format!(
@@ -130,7 +130,7 @@ fn fade_out(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         }
     };
     let amount = match parser.arg(&mut args, 1, "amount")? {
-        Value::Dimension(n, u) => bound!(args, "amount", n, u, 0, 1),
+        Value::Dimension(n, u, _) => bound!(args, "amount", n, u, 0, 1),
         v => {
             return Err((
                 format!(
@@ -153,3 +153,7 @@ pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     f.insert("transparentize", Builtin::new(transparentize));
     f.insert("fade-out", Builtin::new(fade_out));
 }
+
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("alpha", alpha);
+}
@@ -1,10 +1,13 @@
-use super::{Builtin, GlobalFunctionMap};
+use super::{
+    space::{named_space_arg, update_in_space, ChannelUpdate},
+    Builtin, GlobalFunctionMap, Module,
+};
 
 use num_traits::{One, Signed, Zero};
 
 use crate::{
-    args::CallArgs,
-    color::Color,
+    args::{CallArg, CallArgs},
+    color::{Color, ColorSpace},
     common::QuoteKind,
     error::SassResult,
     parse::Parser,
@@ -15,7 +18,7 @@ use crate::{
 macro_rules! opt_rgba {
     ($args:ident, $name:ident, $arg:literal, $low:literal, $high:literal, $parser:ident) => {
         let $name = match $parser.default_named_arg(&mut $args, $arg, Value::Null)? {
-            Value::Dimension(n, u) => Some(bound!($args, $arg, n, u, $low, $high)),
+            Value::Dimension(n, u, _) => Some(bound!($args, $arg, n, u, $low, $high)),
             Value::Null => None,
             v => {
                 return Err((
@@ -35,7 +38,7 @@ macro_rules! opt_rgba {
 macro_rules! opt_hsl {
     ($args:ident, $name:ident, $arg:literal, $low:literal, $high:literal, $parser:ident) => {
         let $name = match $parser.default_named_arg(&mut $args, $arg, Value::Null)? {
-            Value::Dimension(n, u) => {
+            Value::Dimension(n, u, _) => {
                 Some(bound!($args, $arg, n, u, $low, $high) / Number::from(100))
             }
             Value::Null => None,
@@ -54,6 +57,36 @@ macro_rules! opt_hsl {
     };
 }
 
+/// The space to update the channels of `color` in, if it's not a legacy
+/// color, a `$space` was passed, or the HWB channels `$whiteness` or
+/// `$blackness` were passed
+fn in_space(
+    color: &Color,
+    args: &mut CallArgs,
+    parser: &mut Parser<'_>,
+) -> SassResult<Option<ColorSpace>> {
+    let space = named_space_arg(parser, args)?;
+    if space.is_none() && color.is_legacy() {
+        let has_any = |names: &[&str]| {
+            names
+                .iter()
+                .any(|name| args.0.contains_key(&CallArg::Named((*name).into())))
+        };
+        let is_rgb = has_any(&["red", "green", "blue"]);
+        let is_hwb = has_any(&["whiteness", "blackness"]);
+        // `$hue` is shared by HSL and HWB
+        let is_hsl = has_any(&["saturation", "lightness"]) || (!is_hwb && has_any(&["hue"]));
+        let message = match (is_rgb, is_hsl, is_hwb) {
+            (true, true, _) => "RGB parameters may not be passed along with HSL parameters.",
+            (true, _, true) => "RGB parameters may not be passed along with HWB parameters.",
+            (_, true, true) => "HSL parameters may not be passed along with HWB parameters.",
+            _ => return Ok(if is_hwb { Some(ColorSpace::Hwb) } else { None }),
+        };
+        return Err((message, args.span()).into());
+    }
+    Ok(Some(space.unwrap_or_else(|| color.space())))
+}
+
 fn change_color(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     if parser.positional_arg(&mut args, 1).is_some() {
         return Err((
@@ -74,6 +107,16 @@ fn change_color(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value
         }
     };
 
+    if let Some(space) = in_space(&color, &mut args, parser)? {
+        return Ok(Value::Color(Box::new(update_in_space(
+            ChannelUpdate::Change,
+            &color,
+            space,
+            &mut args,
+            parser,
+        )?)));
+    }
+
     opt_rgba!(args, alpha, "alpha", 0, 1, parser);
     opt_rgba!(args, red, "red", 0, 255, parser);
     opt_rgba!(args, green, "green", 0, 255, parser);
@@ -89,7 +132,7 @@ fn change_color(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value
     }
 
     let hue = match parser.default_named_arg(&mut args, "hue", Value::Null)? {
-        Value::Dimension(n, _) => Some(n),
+        Value::Dimension(n, _, _) => Some(n),
         Value::Null => None,
         v => {
             return Err((
@@ -133,6 +176,16 @@ fn adjust_color(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value
         }
     };
 
+    if let Some(space) = in_space(&color, &mut args, parser)? {
+        return Ok(Value::Color(Box::new(update_in_space(
+            ChannelUpdate::Adjust,
+            &color,
+            space,
+            &mut args,
+            parser,
+        )?)));
+    }
+
     opt_rgba!(args, alpha, "alpha", -1, 1, parser);
     opt_rgba!(args, red, "red", -255, 255, parser);
     opt_rgba!(args, green, "green", -255, 255, parser);
@@ -148,7 +201,7 @@ fn adjust_color(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value
     }
 
     let hue = match parser.default_named_arg(&mut args, "hue", Value::Null)? {
-        Value::Dimension(n, _) => Some(n),
+        Value::Dimension(n, _, _) => Some(n),
         Value::Null => None,
         v => {
             return Err((
@@ -203,10 +256,20 @@ fn scale_color(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
         }
     };
 
+    if let Some(space) = in_space(&color, &mut args, parser)? {
+        return Ok(Value::Color(Box::new(update_in_space(
+            ChannelUpdate::Scale,
+            &color,
+            space,
+            &mut args,
+            parser,
+        )?)));
+    }
+
     macro_rules! opt_scale_arg {
         ($args:ident, $name:ident, $arg:literal, $low:literal, $high:literal, $parser:ident) => {
             let $name = match $parser.default_named_arg(&mut $args, $arg, Value::Null)? {
-                Value::Dimension(n, Unit::Percent) => {
+                Value::Dimension(n, Unit::Percent, _) => {
                     Some(bound!($args, $arg, n, Unit::Percent, $low, $high) / Number::from(100))
                 }
                 v @ Value::Dimension(..) => {
@@ -321,3 +384,10 @@ pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     f.insert("scale-color", Builtin::new(scale_color));
     f.insert("ie-hex-str", Builtin::new(ie_hex_str));
 }
+
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("change", change_color);
+    m.insert_builtin("adjust", adjust_color);
+    m.insert_builtin("scale", scale_color);
+    m.insert_builtin("ie-hex-str", ie_hex_str);
+}
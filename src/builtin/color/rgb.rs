@@ -1,4 +1,4 @@
-use super::{Builtin, GlobalFunctionMap};
+use super::{space::parse_interpolation_method, Builtin, GlobalFunctionMap, Module};
 
 use num_traits::One;
 
@@ -22,7 +22,7 @@ fn inner_rgb(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
 
     if args.len() == 1 {
         let mut channels = match parser.arg(&mut args, 0, "channels")? {
-            Value::List(v, ..) => v,
+            v @ Value::List(..) => v.as_list(),
             _ => return Err(("Missing argument $channels.", args.span()).into()),
         };
 
@@ -38,8 +38,10 @@ fn inner_rgb(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
         }
 
         let blue = match channels.pop() {
-            Some(Value::Dimension(n, Unit::None)) => n,
-            Some(Value::Dimension(n, Unit::Percent)) => (n / Number::from(100)) * Number::from(255),
+            Some(Value::Dimension(n, Unit::None, _)) => n,
+            Some(Value::Dimension(n, Unit::Percent, _)) => {
+                (n / Number::from(100)) * Number::from(255)
+            }
             Some(v) if v.is_special_function() => {
                 let green = channels.pop().unwrap();
                 let red = channels.pop().unwrap();
@@ -65,8 +67,10 @@ fn inner_rgb(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
         };
 
         let green = match channels.pop() {
-            Some(Value::Dimension(n, Unit::None)) => n,
-            Some(Value::Dimension(n, Unit::Percent)) => (n / Number::from(100)) * Number::from(255),
+            Some(Value::Dimension(n, Unit::None, _)) => n,
+            Some(Value::Dimension(n, Unit::Percent, _)) => {
+                (n / Number::from(100)) * Number::from(255)
+            }
             Some(v) if v.is_special_function() => {
                 let string = match channels.pop() {
                     Some(red) => format!(
@@ -91,8 +95,10 @@ fn inner_rgb(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
         };
 
         let red = match channels.pop() {
-            Some(Value::Dimension(n, Unit::None)) => n,
-            Some(Value::Dimension(n, Unit::Percent)) => (n / Number::from(100)) * Number::from(255),
+            Some(Value::Dimension(n, Unit::None, _)) => n,
+            Some(Value::Dimension(n, Unit::Percent, _)) => {
+                (n / Number::from(100)) * Number::from(255)
+            }
             Some(v) if v.is_special_function() => {
                 return Ok(Value::String(
                     format!(
@@ -142,8 +148,8 @@ fn inner_rgb(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
             }
         };
         let alpha = match parser.arg(&mut args, 1, "alpha")? {
-            Value::Dimension(n, Unit::None) => n,
-            Value::Dimension(n, Unit::Percent) => n / Number::from(100),
+            Value::Dimension(n, Unit::None, _) => n,
+            Value::Dimension(n, Unit::Percent, _) => n / Number::from(100),
             v @ Value::Dimension(..) => {
                 return Err((
                     format!(
@@ -178,8 +184,8 @@ fn inner_rgb(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
         Ok(Value::Color(Box::new(color.with_alpha(alpha))))
     } else {
         let red = match parser.arg(&mut args, 0, "red")? {
-            Value::Dimension(n, Unit::None) => n,
-            Value::Dimension(n, Unit::Percent) => (n / Number::from(100)) * Number::from(255),
+            Value::Dimension(n, Unit::None, _) => n,
+            Value::Dimension(n, Unit::Percent, _) => (n / Number::from(100)) * Number::from(255),
             v @ Value::Dimension(..) => {
                 return Err((
                     format!(
@@ -220,8 +226,8 @@ fn inner_rgb(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
             }
         };
         let green = match parser.arg(&mut args, 1, "green")? {
-            Value::Dimension(n, Unit::None) => n,
-            Value::Dimension(n, Unit::Percent) => (n / Number::from(100)) * Number::from(255),
+            Value::Dimension(n, Unit::None, _) => n,
+            Value::Dimension(n, Unit::Percent, _) => (n / Number::from(100)) * Number::from(255),
             v @ Value::Dimension(..) => {
                 return Err((
                     format!(
@@ -261,8 +267,8 @@ fn inner_rgb(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
             }
         };
         let blue = match parser.arg(&mut args, 2, "blue")? {
-            Value::Dimension(n, Unit::None) => n,
-            Value::Dimension(n, Unit::Percent) => (n / Number::from(100)) * Number::from(255),
+            Value::Dimension(n, Unit::None, _) => n,
+            Value::Dimension(n, Unit::Percent, _) => (n / Number::from(100)) * Number::from(255),
             v @ Value::Dimension(..) => {
                 return Err((
                     format!(
@@ -304,10 +310,10 @@ fn inner_rgb(name: &'static str, mut args: CallArgs, parser: &mut Parser<'_>) ->
             &mut args,
             3,
             "alpha",
-            Value::Dimension(Number::one(), Unit::None),
+            Value::Dimension(Number::one(), Unit::None, None),
         )? {
-            Value::Dimension(n, Unit::None) => n,
-            Value::Dimension(n, Unit::Percent) => n / Number::from(100),
+            Value::Dimension(n, Unit::None, _) => n,
+            Value::Dimension(n, Unit::Percent, _) => n / Number::from(100),
             v @ Value::Dimension(..) => {
                 return Err((
                     format!(
@@ -354,7 +360,7 @@ fn rgba(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 fn red(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "color")? {
-        Value::Color(c) => Ok(Value::Dimension(c.red(), Unit::None)),
+        Value::Color(c) => Ok(Value::Dimension(c.red(), Unit::None, None)),
         v => Err((
             format!("$color: {} is not a color.", v.to_css_string(args.span())?),
             args.span(),
@@ -366,7 +372,7 @@ fn red(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 fn green(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "color")? {
-        Value::Color(c) => Ok(Value::Dimension(c.green(), Unit::None)),
+        Value::Color(c) => Ok(Value::Dimension(c.green(), Unit::None, None)),
         v => Err((
             format!("$color: {} is not a color.", v.to_css_string(args.span())?),
             args.span(),
@@ -378,7 +384,7 @@ fn green(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 fn blue(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "color")? {
-        Value::Color(c) => Ok(Value::Dimension(c.blue(), Unit::None)),
+        Value::Color(c) => Ok(Value::Dimension(c.blue(), Unit::None, None)),
         v => Err((
             format!("$color: {} is not a color.", v.to_css_string(args.span())?),
             args.span(),
@@ -388,7 +394,7 @@ fn blue(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 }
 
 fn mix(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
-    args.max_args(3)?;
+    args.max_args(4)?;
     let color1 = match parser.arg(&mut args, 0, "color1")? {
         Value::Color(c) => c,
         v => {
@@ -415,9 +421,9 @@ fn mix(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         &mut args,
         2,
         "weight",
-        Value::Dimension(Number::from(50), Unit::None),
+        Value::Dimension(Number::from(50), Unit::None, None),
     )? {
-        Value::Dimension(n, u) => bound!(args, "weight", n, u, 0, 100) / Number::from(100),
+        Value::Dimension(n, u, _) => bound!(args, "weight", n, u, 0, 100) / Number::from(100),
         v => {
             return Err((
                 format!(
@@ -429,7 +435,23 @@ fn mix(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
                 .into())
         }
     };
-    Ok(Value::Color(Box::new(color1.mix(&color2, weight))))
+
+    match parser.default_arg(&mut args, 3, "method", Value::Null)? {
+        Value::Null if !color1.is_legacy() || !color2.is_legacy() => Err((
+            "To use color.mix() with non-legacy colors, you must provide a $method.",
+            args.span(),
+        )
+            .into()),
+        Value::Null => Ok(Value::Color(Box::new(color1.mix(&color2, weight)))),
+        method => {
+            let method = parse_interpolation_method(method, args.span())?;
+            Ok(Value::Color(Box::new(color1.interpolate(
+                &color2,
+                method,
+                weight.to_f64(),
+            ))))
+        }
+    }
 }
 
 pub(crate) fn declare(f: &mut GlobalFunctionMap) {
@@ -440,3 +462,10 @@ pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     f.insert("blue", Builtin::new(blue));
     f.insert("mix", Builtin::new(mix));
 }
+
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("red", red);
+    m.insert_builtin("green", green);
+    m.insert_builtin("blue", blue);
+    m.insert_builtin("mix", mix);
+}
@@ -0,0 +1,664 @@
+use super::{Builtin, GlobalFunctionMap, Module};
+
+use codemap::Span;
+use num_traits::{One, Signed, Zero};
+
+use crate::{
+    args::{CallArg, CallArgs},
+    color::{Color, ColorSpace, GamutMapMethod, HueInterpolationMethod, InterpolationMethod},
+    common::{Brackets, ListSeparator, QuoteKind},
+    error::SassResult,
+    parse::Parser,
+    unit::Unit,
+    value::{Number, Value},
+};
+
+fn color_arg(
+    parser: &mut Parser<'_>,
+    args: &mut CallArgs,
+    position: usize,
+    name: &'static str,
+) -> SassResult<Box<Color>> {
+    match parser.arg(args, position, name)? {
+        Value::Color(c) => Ok(c),
+        v => Err((
+            format!(
+                "${}: {} is not a color.",
+                name,
+                v.to_css_string(args.span())?
+            ),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+/// Parse the name of a color space, e.g. `display-p3`
+fn parse_space(value: &Value, name: &str, span: Span) -> SassResult<ColorSpace> {
+    match value {
+        Value::String(s, ..) => match ColorSpace::from_name(s) {
+            Some(space) => Ok(space),
+            None => Err((format!("${}: Unknown color space \"{}\".", name, s), span).into()),
+        },
+        v => Err((
+            format!("${}: {} is not a string.", name, v.inspect(span)?),
+            span,
+        )
+            .into()),
+    }
+}
+
+/// Get the optional `$space` argument, which defaults to `null`
+fn space_arg(
+    parser: &mut Parser<'_>,
+    args: &mut CallArgs,
+    position: usize,
+) -> SassResult<Option<ColorSpace>> {
+    match parser.default_arg(args, position, "space", Value::Null)? {
+        Value::Null => Ok(None),
+        v => parse_space(&v, "space", args.span()).map(Some),
+    }
+}
+
+/// Get the optional `$space` argument of `color.change()` and friends, which
+/// may only be passed by name
+pub(super) fn named_space_arg(
+    parser: &mut Parser<'_>,
+    args: &mut CallArgs,
+) -> SassResult<Option<ColorSpace>> {
+    match parser.default_named_arg(args, "space", Value::Null)? {
+        Value::Null => Ok(None),
+        v => parse_space(&v, "space", args.span()).map(Some),
+    }
+}
+
+/// Parse the value of a channel as written in a color space function or
+/// passed to `color.change()`, returning `None` for `none`
+fn channel_value(
+    space: ColorSpace,
+    index: usize,
+    value: Value,
+    span: Span,
+) -> SassResult<Option<Number>> {
+    Ok(channel_number(space, index, value, span)?.map(|n| clamp_channel(space, index, n)))
+}
+
+/// Parse a channel value, converting percentages and angles to the units
+/// of the channel
+fn channel_number(
+    space: ColorSpace,
+    index: usize,
+    value: Value,
+    span: Span,
+) -> SassResult<Option<Number>> {
+    let name = space.channel_names()[index];
+    let is_hue = space.hue_index() == Some(index);
+    let number = match value {
+        Value::String(s, QuoteKind::None) if s.eq_ignore_ascii_case("none") => return Ok(None),
+        Value::Dimension(n, Unit::None, _) => n,
+        Value::Dimension(n, Unit::Percent, _) if !is_hue => {
+            let (_, max) = space.channel_range(index);
+            n * Number::from(max) / Number::from(100)
+        }
+        Value::Dimension(n, unit, _) if is_hue => match unit.conversion_factor(&Unit::Deg) {
+            Some(factor) => n * factor,
+            None => {
+                return Err((
+                    format!(
+                        "${}: Expected {}{} to have an angle unit (deg, grad, rad, turn).",
+                        name, n, unit
+                    ),
+                    span,
+                )
+                    .into())
+            }
+        },
+        Value::Dimension(n, unit, _) => {
+            return Err((
+                format!(
+                    "${}: Expected {}{} to have unit \"%\" or no units.",
+                    name, n, unit
+                ),
+                span,
+            )
+                .into())
+        }
+        v => {
+            return Err((
+                format!("${}: {} is not a number.", name, v.inspect(span)?),
+                span,
+            )
+                .into())
+        }
+    };
+    Ok(Some(number))
+}
+
+/// Clamp the lightness and chroma of the Lab and LCH-like spaces to the
+/// values they may take
+fn clamp_channel(space: ColorSpace, index: usize, number: Number) -> Number {
+    match (space, index) {
+        (ColorSpace::Lab, 0) | (ColorSpace::Lch, 0) => number.clamp(0, 100),
+        (ColorSpace::Oklab, 0) | (ColorSpace::Oklch, 0) => number.clamp(0, 1),
+        (ColorSpace::Lch, 1) | (ColorSpace::Oklch, 1) if number.is_negative() => Number::zero(),
+        _ => number,
+    }
+}
+
+fn clamp_f64(space: ColorSpace, index: usize, channel: f64) -> f64 {
+    match (space, index) {
+        (ColorSpace::Lab, 0) | (ColorSpace::Lch, 0) => channel.max(0.0).min(100.0),
+        (ColorSpace::Oklab, 0) | (ColorSpace::Oklch, 0) => channel.max(0.0).min(1.0),
+        (ColorSpace::Lch, 1) | (ColorSpace::Oklch, 1) => channel.max(0.0),
+        _ => channel,
+    }
+}
+
+pub(super) fn alpha_value(value: Value, span: Span) -> SassResult<Number> {
+    Ok(alpha_number(value, span)?.clamp(0, 1))
+}
+
+fn alpha_number(value: Value, span: Span) -> SassResult<Number> {
+    match value {
+        Value::Dimension(n, Unit::None, _) => Ok(n),
+        Value::Dimension(n, Unit::Percent, _) => Ok(n / Number::from(100)),
+        Value::Dimension(n, unit, _) => Err((
+            format!(
+                "$alpha: Expected {}{} to have unit \"%\" or no units.",
+                n, unit
+            ),
+            span,
+        )
+            .into()),
+        v => Err((
+            format!("$alpha: {} is not a number.", v.inspect(span)?),
+            span,
+        )
+            .into()),
+    }
+}
+
+/// Parse the `$channels` argument of a color space function such as
+/// `lab(50% 20 30 / 0.5)`, or of `color()` if `space` is `None`
+///
+/// If any of the channels are special functions such as `var()`, the
+/// function is returned as an unquoted string to be emitted as plain CSS.
+pub(super) fn parse_channels(
+    name: &str,
+    space: Option<ColorSpace>,
+    channels: Value,
+    span: Span,
+) -> SassResult<Value> {
+    let (mut components, alpha) = match channels {
+        Value::List(list, ListSeparator::Slash, Brackets::None) if list.len() == 2 => {
+            (list[0].clone().as_list(), Some(list[1].clone()))
+        }
+        list @ Value::List(_, ListSeparator::Space, Brackets::None) => {
+            let mut list = list.as_list();
+            match list.pop() {
+                Some(Value::Dimension(_, _, Some(slash))) => {
+                    let (last, alpha) = *slash;
+                    list.push(last);
+                    (list, Some(alpha))
+                }
+                Some(last) => {
+                    list.push(last);
+                    (list, None)
+                }
+                None => (list, None),
+            }
+        }
+        v @ Value::List(..) => {
+            return Err((
+                format!(
+                    "$channels: Expected a space-separated list, was {}.",
+                    v.inspect(span)?
+                ),
+                span,
+            )
+                .into())
+        }
+        Value::Dimension(_, _, Some(slash)) => (vec![slash.0], Some(slash.1)),
+        v => (vec![v], None),
+    };
+
+    // an alpha channel given by a special function, such as `30/var(--a)`,
+    // can't be separated from the last channel, so is left as a string
+    let has_slash_string = matches!(
+        components.last(),
+        Some(Value::String(s, QuoteKind::None)) if s.contains('/')
+    );
+
+    if has_slash_string
+        || components
+            .iter()
+            .chain(alpha.iter())
+            .any(Value::is_special_function)
+    {
+        let mut css = components
+            .iter()
+            .map(|v| v.to_css_string(span))
+            .collect::<SassResult<Vec<_>>>()?
+            .join(" ");
+        if let Some(alpha) = &alpha {
+            css.push_str(" / ");
+            css.push_str(&alpha.to_css_string(span)?);
+        }
+        return Ok(Value::String(format!("{}({})", name, css), QuoteKind::None));
+    }
+
+    let space = match space {
+        Some(space) => space,
+        None => {
+            let space = match components.first() {
+                Some(Value::String(s, QuoteKind::None)) => ColorSpace::from_name(s)
+                    .filter(|space| {
+                        !space.is_legacy()
+                            && !matches!(
+                                space,
+                                ColorSpace::Lab
+                                    | ColorSpace::Lch
+                                    | ColorSpace::Oklab
+                                    | ColorSpace::Oklch
+                            )
+                    })
+                    .ok_or_else(|| {
+                        (
+                            format!("$description: Unknown color space \"{}\".", s),
+                            span,
+                        )
+                    })?,
+                _ => return Err(("$description: Expected a color space.", span).into()),
+            };
+            components.remove(0);
+            space
+        }
+    };
+
+    if components.len() != 3 {
+        let list = components
+            .iter()
+            .map(|v| v.inspect(span))
+            .collect::<SassResult<Vec<_>>>()?
+            .join(" ");
+        return Err((
+            format!(
+                "$channels: The {} color space has 3 channels but {} has {}.",
+                space.name(),
+                if components.len() == 1 {
+                    list
+                } else {
+                    format!("({})", list)
+                },
+                components.len()
+            ),
+            span,
+        )
+            .into());
+    }
+
+    let mut channels = [None, None, None];
+    for (i, value) in components.into_iter().enumerate() {
+        channels[i] = channel_value(space, i, value, span)?;
+    }
+
+    let alpha = match alpha {
+        Some(alpha) => alpha_value(alpha, span)?,
+        None => Number::one(),
+    };
+
+    Ok(Value::Color(Box::new(Color::new_in_space(
+        space, channels, alpha,
+    ))))
+}
+
+macro_rules! color_space_fn {
+    ($fn:ident, $name:literal, $space:expr) => {
+        fn $fn(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+            args.max_args(1)?;
+            let channels = parser.arg(&mut args, 0, "channels")?;
+            parse_channels($name, Some($space), channels, args.span())
+        }
+    };
+}
+
+color_space_fn!(lab, "lab", ColorSpace::Lab);
+color_space_fn!(lch, "lch", ColorSpace::Lch);
+color_space_fn!(oklab, "oklab", ColorSpace::Oklab);
+color_space_fn!(oklch, "oklch", ColorSpace::Oklch);
+
+fn color(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    let description = parser.arg(&mut args, 0, "description")?;
+    parse_channels("color", None, description, args.span())
+}
+
+fn space(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    let color = color_arg(parser, &mut args, 0, "color")?;
+    Ok(Value::String(
+        color.space().name().to_owned(),
+        QuoteKind::None,
+    ))
+}
+
+fn to_space(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(2)?;
+    let color = color_arg(parser, &mut args, 0, "color")?;
+    let space = parser.arg(&mut args, 1, "space")?;
+    let space = parse_space(&space, "space", args.span())?;
+    Ok(Value::Color(Box::new(color.to_space(space))))
+}
+
+fn is_legacy(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    let color = color_arg(parser, &mut args, 0, "color")?;
+    Ok(Value::bool(color.is_legacy()))
+}
+
+/// Get the name of a channel passed to `color.channel()` or
+/// `color.is-missing()`, which must be quoted
+fn channel_name_arg(parser: &mut Parser<'_>, args: &mut CallArgs) -> SassResult<String> {
+    match parser.arg(args, 1, "channel")? {
+        Value::String(s, QuoteKind::Quoted) => Ok(s),
+        Value::String(s, QuoteKind::None) => Err((
+            format!("$channel: Expected {} to be a quoted string.", s),
+            args.span(),
+        )
+            .into()),
+        v => Err((
+            format!("$channel: {} is not a string.", v.inspect(args.span())?),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+fn no_channel_error(
+    color: &Color,
+    space: ColorSpace,
+    channel: &str,
+    span: Span,
+) -> SassResult<Value> {
+    Err((
+        format!(
+            "$channel: Color {} has no channel named {}{}.",
+            color,
+            channel,
+            if space == color.space() {
+                String::new()
+            } else {
+                format!(" in the {} color space", space.name())
+            }
+        ),
+        span,
+    )
+        .into())
+}
+
+fn channel(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(3)?;
+    let color = color_arg(parser, &mut args, 0, "color")?;
+    let channel = channel_name_arg(parser, &mut args)?;
+    let space = space_arg(parser, &mut args, 2)?.unwrap_or_else(|| color.space());
+
+    if channel == "alpha" {
+        return Ok(Value::Dimension(color.alpha(), Unit::None, None));
+    }
+
+    let index = match space.channel_index(&channel) {
+        Some(index) => index,
+        None => return no_channel_error(&color, space, &channel, args.span()),
+    };
+
+    let value = color.channels_in(space)[index].unwrap_or(0.0);
+
+    Ok(if space.hue_index() == Some(index) {
+        Value::Dimension(Number::from(value), Unit::Deg, None)
+    } else if space.is_percent_channel(index) {
+        let (_, max) = space.channel_range(index);
+        Value::Dimension(Number::from(value * 100.0 / max), Unit::Percent, None)
+    } else {
+        Value::Dimension(Number::from(value), Unit::None, None)
+    })
+}
+
+fn is_missing(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(2)?;
+    let color = color_arg(parser, &mut args, 0, "color")?;
+    let channel = channel_name_arg(parser, &mut args)?;
+
+    if channel == "alpha" {
+        return Ok(Value::False);
+    }
+
+    match color.space().channel_index(&channel) {
+        Some(index) => Ok(Value::bool(color.channels()[index].is_none())),
+        None => no_channel_error(&color, color.space(), &channel, args.span()),
+    }
+}
+
+fn is_in_gamut(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(2)?;
+    let color = color_arg(parser, &mut args, 0, "color")?;
+    let space = space_arg(parser, &mut args, 1)?.unwrap_or_else(|| color.space());
+    Ok(Value::bool(color.is_in_gamut(space)))
+}
+
+fn to_gamut(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(3)?;
+    let color = color_arg(parser, &mut args, 0, "color")?;
+    let space = space_arg(parser, &mut args, 1)?.unwrap_or_else(|| color.space());
+    let method = match parser.default_arg(&mut args, 2, "method", Value::Null)? {
+        Value::Null => return Err((
+            "color.to-gamut() requires a $method argument for forwards-compatibility with changes in the CSS spec. Suggestion:\n\n$method: local-minde",
+            args.span(),
+        )
+            .into()),
+        Value::String(s, ..) => match GamutMapMethod::from_name(&s) {
+            Some(method) => method,
+            None => {
+                return Err((
+                    format!("$method: Unknown gamut map method \"{}\".", s),
+                    args.span(),
+                )
+                    .into())
+            }
+        },
+        v => {
+            return Err((
+                format!("$method: {} is not a string.", v.inspect(args.span())?),
+                args.span(),
+            )
+                .into())
+        }
+    };
+    Ok(Value::Color(Box::new(color.to_gamut(space, method))))
+}
+
+/// Parse the `$method` argument of `mix()`, e.g. `oklch longer hue`
+pub(super) fn parse_interpolation_method(
+    value: Value,
+    span: Span,
+) -> SassResult<InterpolationMethod> {
+    let inspected = value.inspect(span)?.into_owned();
+    let mut words = value.as_list().into_iter();
+
+    let space = match words.next() {
+        Some(space) => parse_space(&space, "method", span)?,
+        None => return Err(("$method: Expected a color space.", span).into()),
+    };
+
+    let hue = match (words.next(), words.next(), words.next()) {
+        (None, ..) => HueInterpolationMethod::Shorter,
+        (
+            Some(Value::String(method, QuoteKind::None)),
+            Some(Value::String(hue, QuoteKind::None)),
+            None,
+        ) if hue.eq_ignore_ascii_case("hue") => {
+            if space.hue_index().is_none() {
+                return Err((
+                    format!(
+                        "$method: Hue interpolation method \"{} hue\" may not be set for rectangular color space {}.",
+                        method,
+                        space.name()
+                    ),
+                    span,
+                )
+                    .into());
+            }
+            match HueInterpolationMethod::from_name(&method.to_ascii_lowercase()) {
+                Some(hue) => hue,
+                None => {
+                    return Err((
+                        format!("$method: Unknown hue interpolation method {}.", method),
+                        span,
+                    )
+                        .into())
+                }
+            }
+        }
+        _ => return Err((
+            format!(
+                "$method: Expected a color space and an optional hue interpolation method, was {}.",
+                inspected
+            ),
+            span,
+        )
+            .into()),
+    };
+
+    Ok(InterpolationMethod { space, hue })
+}
+
+/// How `color.change()`, `color.adjust()` and `color.scale()` update the
+/// channels of a color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ChannelUpdate {
+    Change,
+    Adjust,
+    Scale,
+}
+
+/// Change, adjust or scale the channels of `color` in `space`, given by
+/// named arguments such as `$lightness` or `$chroma`
+///
+/// The result is in the same space as `color`.
+pub(super) fn update_in_space(
+    update: ChannelUpdate,
+    color: &Color,
+    space: ColorSpace,
+    args: &mut CallArgs,
+    parser: &mut Parser<'_>,
+) -> SassResult<Color> {
+    let span = args.span();
+    let mut channels = color.channels_in(space);
+
+    for (i, name) in space.channel_names().iter().enumerate() {
+        let value = parser.default_named_arg(args, name, Value::Null)?;
+        if value == Value::Null {
+            continue;
+        }
+        channels[i] = match update {
+            ChannelUpdate::Change => channel_value(space, i, value, span)?.map(|n| n.to_f64()),
+            ChannelUpdate::Adjust => {
+                let by = match channel_number(space, i, value, span)? {
+                    Some(by) => by.to_f64(),
+                    None => return Err((format!("${}: none is not a number.", name), span).into()),
+                };
+                Some(clamp_f64(space, i, channels[i].unwrap_or(0.0) + by))
+            }
+            ChannelUpdate::Scale => {
+                if space.hue_index() == Some(i) {
+                    return Err((format!("${}: Channel isn't scalable.", name), span).into());
+                }
+                let by = scale_amount(name, value, span)?;
+                let (min, max) = space.channel_range(i);
+                let current = channels[i].unwrap_or(0.0);
+                Some(clamp_f64(
+                    space,
+                    i,
+                    if by > 0.0 {
+                        current + (max - current) * by
+                    } else {
+                        current + (current - min) * by
+                    },
+                ))
+            }
+        };
+    }
+
+    let alpha = color.alpha();
+    let alpha = match parser.default_named_arg(args, "alpha", Value::Null)? {
+        Value::Null => alpha,
+        value => match update {
+            ChannelUpdate::Change => alpha_value(value, span)?,
+            ChannelUpdate::Adjust => (alpha + alpha_number(value, span)?).clamp(0, 1),
+            ChannelUpdate::Scale => {
+                let by = Number::from(scale_amount("alpha", value, span)?);
+                if by.is_positive() {
+                    alpha.clone() + (Number::one() - alpha) * by
+                } else {
+                    alpha.clone() + alpha * by
+                }
+            }
+        },
+    };
+
+    if let Some(name) = args.0.keys().find_map(|arg| match arg {
+        CallArg::Named(name) => Some(*name),
+        CallArg::Positional(..) => None,
+    }) {
+        return Err((format!("No argument named ${}.", name), span).into());
+    }
+
+    Ok(Color::from_space(space, channels, alpha).to_space(color.space()))
+}
+
+/// Get the amount to scale a channel by, from -1 to 1
+fn scale_amount(name: &str, value: Value, span: Span) -> SassResult<f64> {
+    match value {
+        Value::Dimension(n, Unit::Percent, _) => {
+            if n > Number::from(100) || n < Number::from(-100) {
+                return Err((
+                    format!("${}: Expected {}% to be within -100% and 100%.", name, n),
+                    span,
+                )
+                    .into());
+            }
+            Ok(n.to_f64() / 100.0)
+        }
+        v @ Value::Dimension(..) => Err((
+            format!(
+                "${}: Expected {} to have unit \"%\".",
+                name,
+                v.inspect(span)?
+            ),
+            span,
+        )
+            .into()),
+        v => Err((
+            format!("${}: {} is not a number.", name, v.inspect(span)?),
+            span,
+        )
+            .into()),
+    }
+}
+
+pub(crate) fn declare(f: &mut GlobalFunctionMap) {
+    f.insert("lab", Builtin::new(lab));
+    f.insert("lch", Builtin::new(lch));
+    f.insert("oklab", Builtin::new(oklab));
+    f.insert("oklch", Builtin::new(oklch));
+    f.insert("color", Builtin::new(color));
+}
+
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("channel", channel);
+    m.insert_builtin("is-in-gamut", is_in_gamut);
+    m.insert_builtin("is-legacy", is_legacy);
+    m.insert_builtin("is-missing", is_missing);
+    m.insert_builtin("space", space);
+    m.insert_builtin("to-gamut", to_gamut);
+    m.insert_builtin("to-space", to_space);
+}
@@ -1,4 +1,6 @@
-use super::{Builtin, GlobalFunctionMap};
+use super::{Builtin, GlobalFunctionMap, Module};
+
+use std::rc::Rc;
 
 use num_traits::{Signed, ToPrimitive, Zero};
 
@@ -6,7 +8,7 @@ use crate::{
     args::CallArgs,
     common::{Brackets, ListSeparator, QuoteKind},
     error::SassResult,
-    parse::{HigherIntermediateValue, Parser, ValueVisitor},
+    parse::{Parser, ValueVisitor},
     unit::Unit,
     value::{Number, Value},
 };
@@ -16,6 +18,7 @@ fn length(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     Ok(Value::Dimension(
         Number::from(parser.arg(&mut args, 0, "list")?.as_list().len()),
         Unit::None,
+        None,
     ))
 }
 
@@ -23,7 +26,7 @@ fn nth(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(2)?;
     let mut list = parser.arg(&mut args, 0, "list")?.as_list();
     let n = match parser.arg(&mut args, 1, "n")? {
-        Value::Dimension(num, _) => num,
+        Value::Dimension(num, _, _) => num,
         v => {
             return Err((
                 format!("$n: {} is not a number.", v.to_css_string(args.span())?),
@@ -76,11 +79,11 @@ fn set_nth(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(3)?;
     let (mut list, sep, brackets) = match parser.arg(&mut args, 0, "list")? {
         Value::List(v, sep, b) => (v, sep, b),
-        Value::Map(m) => (m.as_list(), ListSeparator::Comma, Brackets::None),
-        v => (vec![v], ListSeparator::Space, Brackets::None),
+        Value::Map(m) => (Rc::new(m.as_list()), ListSeparator::Comma, Brackets::None),
+        v => (Rc::new(vec![v]), ListSeparator::Space, Brackets::None),
     };
     let n = match parser.arg(&mut args, 1, "n")? {
-        Value::Dimension(num, _) => num,
+        Value::Dimension(num, _, _) => num,
         v => {
             return Err((
                 format!("$n: {} is not a number.", v.to_css_string(args.span())?),
@@ -110,10 +113,12 @@ fn set_nth(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 
     let val = parser.arg(&mut args, 2, "value")?;
 
+    let list_mut = Rc::make_mut(&mut list);
+
     if n.is_positive() {
-        list[n.to_integer().to_usize().unwrap_or(std::usize::MAX) - 1] = val;
+        list_mut[n.to_integer().to_usize().unwrap_or(std::usize::MAX) - 1] = val;
     } else {
-        list[len - n.abs().to_integer().to_usize().unwrap_or(std::usize::MAX)] = val;
+        list_mut[len - n.abs().to_integer().to_usize().unwrap_or(std::usize::MAX)] = val;
     }
 
     Ok(Value::List(list, sep, brackets))
@@ -123,7 +128,7 @@ fn append(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(3)?;
     let (mut list, sep, brackets) = match parser.arg(&mut args, 0, "list")? {
         Value::List(v, sep, b) => (v, sep, b),
-        v => (vec![v], ListSeparator::Space, Brackets::None),
+        v => (Rc::new(vec![v]), ListSeparator::Space, Brackets::None),
     };
     let val = parser.arg(&mut args, 1, "val")?;
     let sep = match parser.default_arg(
@@ -136,9 +141,10 @@ fn append(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
             "auto" => sep,
             "comma" => ListSeparator::Comma,
             "space" => ListSeparator::Space,
+            "slash" => ListSeparator::Slash,
             _ => {
                 return Err((
-                    "$separator: Must be \"space\", \"comma\", or \"auto\".",
+                    "$separator: Must be \"space\", \"comma\", \"slash\", or \"auto\".",
                     args.span(),
                 )
                     .into())
@@ -156,7 +162,7 @@ fn append(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         }
     };
 
-    list.push(val);
+    Rc::make_mut(&mut list).push(val);
 
     Ok(Value::List(list, sep, brackets))
 }
@@ -165,13 +171,13 @@ fn join(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(4)?;
     let (mut list1, sep1, brackets) = match parser.arg(&mut args, 0, "list1")? {
         Value::List(v, sep, brackets) => (v, sep, brackets),
-        Value::Map(m) => (m.as_list(), ListSeparator::Comma, Brackets::None),
-        v => (vec![v], ListSeparator::Space, Brackets::None),
+        Value::Map(m) => (Rc::new(m.as_list()), ListSeparator::Comma, Brackets::None),
+        v => (Rc::new(vec![v]), ListSeparator::Space, Brackets::None),
     };
     let (list2, sep2) = match parser.arg(&mut args, 1, "list2")? {
         Value::List(v, sep, ..) => (v, sep),
-        Value::Map(m) => (m.as_list(), ListSeparator::Comma),
-        v => (vec![v], ListSeparator::Space),
+        Value::Map(m) => (Rc::new(m.as_list()), ListSeparator::Comma),
+        v => (Rc::new(vec![v]), ListSeparator::Space),
     };
     let sep = match parser.default_arg(
         &mut args,
@@ -189,9 +195,10 @@ fn join(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
             }
             "comma" => ListSeparator::Comma,
             "space" => ListSeparator::Space,
+            "slash" => ListSeparator::Slash,
             _ => {
                 return Err((
-                    "$separator: Must be \"space\", \"comma\", or \"auto\".",
+                    "$separator: Must be \"space\", \"comma\", \"slash\", or \"auto\".",
                     args.span(),
                 )
                     .into())
@@ -228,7 +235,7 @@ fn join(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         }
     };
 
-    list1.extend(list2);
+    Rc::make_mut(&mut list1).extend(list2.iter().cloned());
 
     Ok(Value::List(list1, sep, brackets))
 }
@@ -251,17 +258,14 @@ fn index(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     // TODO: find a way to propagate any errors here
     // Potential input to fuzz: index(1px 1in 1cm, 96px + 1rem)
     let index = match list.into_iter().position(|v| {
-        ValueVisitor::new(parser, args.span())
-            .equal(
-                HigherIntermediateValue::Literal(v),
-                HigherIntermediateValue::Literal(value.clone()),
-            )
+        ValueVisitor::new(args.span())
+            .equal(v, value.clone())
             .map_or(false, |v| v.is_true())
     }) {
         Some(v) => Number::from(v + 1),
         None => return Ok(Value::Null),
     };
-    Ok(Value::Dimension(index, Unit::None))
+    Ok(Value::Dimension(index, Unit::None, None))
 }
 
 fn zip(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
@@ -275,7 +279,7 @@ fn zip(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 
     if len == 0 {
         return Ok(Value::List(
-            Vec::new(),
+            Rc::new(Vec::new()),
             ListSeparator::Comma,
             Brackets::None,
         ));
@@ -284,11 +288,27 @@ fn zip(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     let result = (0..len)
         .map(|i| {
             let items = lists.iter().map(|v| v[i].clone()).collect();
-            Value::List(items, ListSeparator::Space, Brackets::None)
+            Value::List(Rc::new(items), ListSeparator::Space, Brackets::None)
         })
         .collect();
 
-    Ok(Value::List(result, ListSeparator::Comma, Brackets::None))
+    Ok(Value::List(Rc::new(result), ListSeparator::Comma, Brackets::None))
+}
+
+fn slash(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    let span = args.span();
+    let mut elements = parser.variadic_args(args)?;
+    let elements = if elements.len() == 1 {
+        elements.remove(0).node.as_list()
+    } else {
+        elements.into_iter().map(|v| v.node).collect()
+    };
+
+    if elements.len() < 2 {
+        return Err(("At least two elements are required.", span).into());
+    }
+
+    Ok(Value::List(Rc::new(elements), ListSeparator::Slash, Brackets::None))
 }
 
 pub(crate) fn declare(f: &mut GlobalFunctionMap) {
@@ -302,3 +322,16 @@ pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     f.insert("index", Builtin::new(index));
     f.insert("zip", Builtin::new(zip));
 }
+
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("append", append);
+    m.insert_builtin("index", index);
+    m.insert_builtin("is-bracketed", is_bracketed);
+    m.insert_builtin("join", join);
+    m.insert_builtin("length", length);
+    m.insert_builtin("separator", list_separator);
+    m.insert_builtin("nth", nth);
+    m.insert_builtin("set-nth", set_nth);
+    m.insert_builtin("slash", slash);
+    m.insert_builtin("zip", zip);
+}
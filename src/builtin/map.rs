@@ -1,4 +1,6 @@
-use super::{Builtin, GlobalFunctionMap};
+use super::{Builtin, GlobalFunctionMap, Module};
+
+use std::rc::Rc;
 
 use crate::{
     args::CallArgs,
@@ -8,38 +10,117 @@ use crate::{
     value::{SassMap, Value},
 };
 
+/// Get the map argument `name`, treating an empty list as an empty map
+fn map_arg(
+    parser: &mut Parser<'_>,
+    args: &mut CallArgs,
+    position: usize,
+    name: &'static str,
+) -> SassResult<SassMap> {
+    match parser.arg(args, position, name)? {
+        Value::Map(m) => Ok(m),
+        Value::List(v, ..) if v.is_empty() => Ok(SassMap::new()),
+        v => Err((
+            format!("${}: {} is not a map.", name, v.to_css_string(args.span())?),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+/// The value as a map, if it is one or is an empty list
+fn try_map(value: &Value) -> Option<SassMap> {
+    match value {
+        Value::Map(m) => Some(m.clone()),
+        Value::List(v, ..) if v.is_empty() => Some(SassMap::new()),
+        _ => None,
+    }
+}
+
+/// Replace the value found by following `keys` through nested maps with
+/// the result of `modify`, which is passed the old value or `null`
+///
+/// If `add_nesting` is true, any keys along the path that are missing or
+/// aren't maps are replaced by empty maps. Otherwise, `map` is returned
+/// unchanged when the path doesn't exist.
+fn modify_nested(
+    mut map: SassMap,
+    keys: &[Value],
+    add_nesting: bool,
+    modify: impl FnOnce(Value) -> Value,
+) -> SassMap {
+    let (key, rest) = match keys.split_first() {
+        Some(v) => v,
+        None => return map,
+    };
+
+    if rest.is_empty() {
+        let old = match map.get(key) {
+            Some(v) => v.clone(),
+            None if add_nesting => Value::Null,
+            None => return map,
+        };
+        map.insert(key.clone(), modify(old));
+        return map;
+    }
+
+    let nested = match map.get(key).and_then(try_map) {
+        Some(nested) => nested,
+        None if add_nesting => SassMap::new(),
+        None => return map,
+    };
+    map.insert(
+        key.clone(),
+        Value::Map(modify_nested(nested, rest, add_nesting, modify)),
+    );
+    map
+}
+
+/// Merge `map2` into `map1`, merging rather than replacing values that are
+/// maps in both
+fn deep_merge_maps(mut map1: SassMap, map2: SassMap) -> SassMap {
+    if map1.is_empty() {
+        return map2;
+    }
+    for (key, value) in map2 {
+        let merged = match (map1.get(&key).and_then(try_map), try_map(&value)) {
+            (Some(_), Some(nested2)) if nested2.is_empty() => continue,
+            (Some(nested1), Some(nested2)) => Value::Map(deep_merge_maps(nested1, nested2)),
+            _ => value,
+        };
+        map1.insert(key, merged);
+    }
+    map1
+}
+
 fn map_get(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
-    args.max_args(2)?;
     let key = parser.arg(&mut args, 1, "key")?;
-    let map = match parser.arg(&mut args, 0, "map")? {
-        Value::Map(m) => m,
-        Value::List(v, ..) if v.is_empty() => SassMap::new(),
-        v => {
-            return Err((
-                format!("$map: {} is not a map.", v.to_css_string(args.span())?),
-                args.span(),
-            )
-                .into())
-        }
-    };
-    Ok(map.get(&key, args.span(), parser)?.unwrap_or(Value::Null))
+    let map = map_arg(parser, &mut args, 0, "map")?;
+    let keys = parser.variadic_args(args)?;
+
+    let mut value = map.get(&key).cloned().unwrap_or(Value::Null);
+    for key in keys {
+        value = match value {
+            Value::Map(m) => m.get(&key.node).cloned().unwrap_or(Value::Null),
+            _ => return Ok(Value::Null),
+        };
+    }
+    Ok(value)
 }
 
 fn map_has_key(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
-    args.max_args(2)?;
-    let key = parser.arg(&mut args, 1, "key")?;
-    let map = match parser.arg(&mut args, 0, "map")? {
-        Value::Map(m) => m,
-        Value::List(v, ..) if v.is_empty() => SassMap::new(),
-        v => {
-            return Err((
-                format!("$map: {} is not a map.", v.to_css_string(args.span())?),
-                args.span(),
-            )
-                .into())
-        }
-    };
-    Ok(Value::bool(map.get(&key, args.span(), parser)?.is_some()))
+    let mut key = parser.arg(&mut args, 1, "key")?;
+    let mut map = map_arg(parser, &mut args, 0, "map")?;
+    let keys = parser.variadic_args(args)?;
+
+    for next in keys {
+        map = match map.get(&key) {
+            Some(Value::Map(m)) => m.clone(),
+            _ => return Ok(Value::False),
+        };
+        key = next.node;
+    }
+    Ok(Value::bool(map.contains_key(&key)))
 }
 
 fn map_keys(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
@@ -56,7 +137,7 @@ fn map_keys(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         }
     };
     Ok(Value::List(
-        map.keys(),
+        Rc::new(map.keys()),
         ListSeparator::Comma,
         Brackets::None,
     ))
@@ -76,38 +157,60 @@ fn map_values(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
         }
     };
     Ok(Value::List(
-        map.values(),
+        Rc::new(map.values()),
         ListSeparator::Comma,
         Brackets::None,
     ))
 }
 
 fn map_merge(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
-    args.max_args(2)?;
-    let mut map1 = match parser.arg(&mut args, 0, "map1")? {
-        Value::Map(m) => m,
-        Value::List(v, ..) if v.is_empty() => SassMap::new(),
-        v => {
-            return Err((
-                format!("$map1: {} is not a map.", v.to_css_string(args.span())?),
-                args.span(),
-            )
-                .into())
-        }
-    };
-    let map2 = match parser.arg(&mut args, 1, "map2")? {
-        Value::Map(m) => m,
-        Value::List(v, ..) if v.is_empty() => SassMap::new(),
-        v => {
-            return Err((
-                format!("$map2: {} is not a map.", v.to_css_string(args.span())?),
-                args.span(),
-            )
-                .into())
-        }
+    let mut map1 = map_arg(parser, &mut args, 0, "map1")?;
+
+    if args.len() <= 1 {
+        let map2 = map_arg(parser, &mut args, 1, "map2")?;
+        map1.merge(map2);
+        return Ok(Value::Map(map1));
+    }
+
+    let span = args.span();
+    let mut keys = parser
+        .variadic_args(args)?
+        .into_iter()
+        .map(|key| key.node)
+        .collect::<Vec<Value>>();
+    let map2 = match keys.pop() {
+        Some(v) => match try_map(&v) {
+            Some(m) => m,
+            None => {
+                return Err((
+                    format!("$map2: {} is not a map.", v.to_css_string(span)?),
+                    span,
+                )
+                    .into())
+            }
+        },
+        None => return Err(("Expected $args to contain a map.", span).into()),
     };
-    map1.merge(map2);
-    Ok(Value::Map(map1))
+
+    Ok(Value::Map(modify_nested(
+        map1,
+        &keys,
+        true,
+        |old| match try_map(&old) {
+            Some(mut nested) => {
+                nested.merge(map2);
+                Value::Map(nested)
+            }
+            None => Value::Map(map2),
+        },
+    )))
+}
+
+fn deep_merge(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(2)?;
+    let map1 = map_arg(parser, &mut args, 0, "map1")?;
+    let map2 = map_arg(parser, &mut args, 1, "map2")?;
+    Ok(Value::Map(deep_merge_maps(map1, map2)))
 }
 
 fn map_remove(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
@@ -129,6 +232,57 @@ fn map_remove(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
     Ok(Value::Map(map))
 }
 
+fn map_set(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    let map = map_arg(parser, &mut args, 0, "map")?;
+    let span = args.span();
+    let mut keys = parser
+        .variadic_args(args)?
+        .into_iter()
+        .map(|key| key.node)
+        .collect::<Vec<Value>>();
+    let value = match keys.pop() {
+        Some(v) => v,
+        None => return Err(("Expected $args to contain a key.", span).into()),
+    };
+    if keys.is_empty() {
+        return Err(("Expected $args to contain a value.", span).into());
+    }
+    Ok(Value::Map(modify_nested(map, &keys, true, |_| value)))
+}
+
+fn deep_remove(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    let mut map = map_arg(parser, &mut args, 0, "map")?;
+    let key = parser.arg(&mut args, 1, "key")?;
+    let mut keys = parser
+        .variadic_args(args)?
+        .into_iter()
+        .map(|key| key.node)
+        .collect::<Vec<Value>>();
+    let last = match keys.pop() {
+        Some(last) => {
+            keys.insert(0, key);
+            last
+        }
+        None => {
+            map.remove(&key);
+            return Ok(Value::Map(map));
+        }
+    };
+
+    Ok(Value::Map(modify_nested(
+        map,
+        &keys,
+        false,
+        |value| match value {
+            Value::Map(mut nested) => {
+                nested.remove(&last);
+                Value::Map(nested)
+            }
+            v => v,
+        },
+    )))
+}
+
 pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     f.insert("map-get", Builtin::new(map_get));
     f.insert("map-has-key", Builtin::new(map_has_key));
@@ -137,3 +291,15 @@ pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     f.insert("map-merge", Builtin::new(map_merge));
     f.insert("map-remove", Builtin::new(map_remove));
 }
+
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("deep-merge", deep_merge);
+    m.insert_builtin("deep-remove", deep_remove);
+    m.insert_builtin("get", map_get);
+    m.insert_builtin("has-key", map_has_key);
+    m.insert_builtin("keys", map_keys);
+    m.insert_builtin("merge", map_merge);
+    m.insert_builtin("remove", map_remove);
+    m.insert_builtin("set", map_set);
+    m.insert_builtin("values", map_values);
+}
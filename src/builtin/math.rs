@@ -1,14 +1,18 @@
-use super::{Builtin, GlobalFunctionMap};
+use std::f64::consts::{E, PI};
 
+use super::{meta, Builtin, GlobalFunctionMap, Module};
+
+use codemap::Span;
+use num_traits::Zero;
 #[cfg(feature = "random")]
-use num_traits::{One, Signed, ToPrimitive, Zero};
+use num_traits::{One, Signed, ToPrimitive};
 #[cfg(feature = "random")]
 use rand::Rng;
 
 use crate::{
     args::CallArgs,
     error::SassResult,
-    parse::{HigherIntermediateValue, Parser, ValueVisitor},
+    parse::{Parser, ValueVisitor},
     unit::Unit,
     value::{Number, Value},
 };
@@ -16,7 +20,7 @@ use crate::{
 fn percentage(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     let num = match parser.arg(&mut args, 0, "number")? {
-        Value::Dimension(n, Unit::None) => n * Number::from(100),
+        Value::Dimension(n, Unit::None, _) => n * Number::from(100),
         v @ Value::Dimension(..) => {
             return Err((
                 format!(
@@ -38,13 +42,13 @@ fn percentage(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
                 .into())
         }
     };
-    Ok(Value::Dimension(num, Unit::Percent))
+    Ok(Value::Dimension(num, Unit::Percent, None))
 }
 
 fn round(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "number")? {
-        Value::Dimension(n, u) => Ok(Value::Dimension(n.round(), u)),
+        Value::Dimension(n, u, _) => Ok(Value::Dimension(n.round(), u, None)),
         v => Err((
             format!(
                 "$number: {} is not a number.",
@@ -59,7 +63,7 @@ fn round(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 fn ceil(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "number")? {
-        Value::Dimension(n, u) => Ok(Value::Dimension(n.ceil(), u)),
+        Value::Dimension(n, u, _) => Ok(Value::Dimension(n.ceil(), u, None)),
         v => Err((
             format!(
                 "$number: {} is not a number.",
@@ -74,7 +78,7 @@ fn ceil(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 fn floor(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "number")? {
-        Value::Dimension(n, u) => Ok(Value::Dimension(n.floor(), u)),
+        Value::Dimension(n, u, _) => Ok(Value::Dimension(n.floor(), u, None)),
         v => Err((
             format!(
                 "$number: {} is not a number.",
@@ -89,7 +93,7 @@ fn floor(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 fn abs(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "number")? {
-        Value::Dimension(n, u) => Ok(Value::Dimension(n.abs(), u)),
+        Value::Dimension(n, u, _) => Ok(Value::Dimension(n.abs(), u, None)),
         v => Err((
             format!(
                 "$number: {} is not a number.",
@@ -104,7 +108,7 @@ fn abs(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
 fn comparable(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(2)?;
     let unit1 = match parser.arg(&mut args, 0, "number1")? {
-        Value::Dimension(_, u) => u,
+        Value::Dimension(_, u, _) => u,
         v => {
             return Err((
                 format!(
@@ -117,7 +121,7 @@ fn comparable(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
         }
     };
     let unit2 = match parser.arg(&mut args, 1, "number2")? {
-        Value::Dimension(_, u) => u,
+        Value::Dimension(_, u, _) => u,
         v => {
             return Err((
                 format!(
@@ -138,12 +142,12 @@ fn comparable(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
 fn random(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     let limit = match parser.default_arg(&mut args, 0, "limit", Value::Null)? {
-        Value::Dimension(n, _) => n,
+        Value::Dimension(n, _, _) => n,
         Value::Null => {
-            let mut rng = rand::thread_rng();
             return Ok(Value::Dimension(
-                Number::from(rng.gen_range(0.0, 1.0)),
+                Number::from(parser.random.rng().gen_range(0.0, 1.0)),
                 Unit::None,
+                None,
             ));
         }
         v => {
@@ -156,7 +160,7 @@ fn random(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     };
 
     if limit.is_one() {
-        return Ok(Value::Dimension(Number::one(), Unit::None));
+        return Ok(Value::Dimension(Number::one(), Unit::None, None));
     }
 
     if limit.is_decimal() {
@@ -182,10 +186,10 @@ fn random(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         }
     };
 
-    let mut rng = rand::thread_rng();
     Ok(Value::Dimension(
-        Number::from(rng.gen_range(0, limit) + 1),
+        Number::from(parser.random.rng().gen_range(0, limit) + 1),
         Unit::None,
+        None,
     ))
 }
 
@@ -196,7 +200,7 @@ fn min(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         .variadic_args(args)?
         .into_iter()
         .map(|val| match val.node {
-            Value::Dimension(number, unit) => Ok((number, unit)),
+            Value::Dimension(number, unit, _) => Ok((number, unit)),
             v => Err((format!("{} is not a number.", v.inspect(span)?), span).into()),
         })
         .collect::<SassResult<Vec<(Number, Unit)>>>()?
@@ -206,17 +210,17 @@ fn min(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     let mut min = nums.next().unwrap();
 
     for num in nums {
-        if ValueVisitor::new(parser, span)
+        if ValueVisitor::new(span)
             .less_than(
-                HigherIntermediateValue::Literal(Value::Dimension(num.0.clone(), num.1.clone())),
-                HigherIntermediateValue::Literal(Value::Dimension(min.0.clone(), min.1.clone())),
+                Value::Dimension(num.0.clone(), num.1.clone(), None),
+                Value::Dimension(min.0.clone(), min.1.clone(), None),
             )?
             .is_true()
         {
             min = num;
         }
     }
-    Ok(Value::Dimension(min.0, min.1))
+    Ok(Value::Dimension(min.0, min.1, None))
 }
 
 fn max(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
@@ -226,7 +230,7 @@ fn max(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         .variadic_args(args)?
         .into_iter()
         .map(|val| match val.node {
-            Value::Dimension(number, unit) => Ok((number, unit)),
+            Value::Dimension(number, unit, _) => Ok((number, unit)),
             v => Err((format!("{} is not a number.", v.inspect(span)?), span).into()),
         })
         .collect::<SassResult<Vec<(Number, Unit)>>>()?
@@ -236,17 +240,283 @@ fn max(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     let mut max = nums.next().unwrap();
 
     for num in nums {
-        if ValueVisitor::new(parser, span)
+        if ValueVisitor::new(span)
             .greater_than(
-                HigherIntermediateValue::Literal(Value::Dimension(num.0.clone(), num.1.clone())),
-                HigherIntermediateValue::Literal(Value::Dimension(max.0.clone(), max.1.clone())),
+                Value::Dimension(num.0.clone(), num.1.clone(), None),
+                Value::Dimension(max.0.clone(), max.1.clone(), None),
             )?
             .is_true()
         {
             max = num;
         }
     }
-    Ok(Value::Dimension(max.0, max.1))
+    Ok(Value::Dimension(max.0, max.1, None))
+}
+
+/// Convert the result of a computation on `f64`s back to a number, which
+/// can't represent `NaN` or infinity
+fn from_f64(num: f64, span: Span) -> SassResult<Number> {
+    if num.is_nan() {
+        Err(("The result is NaN, which can't be represented.", span).into())
+    } else if num.is_infinite() {
+        Err(("The result is infinite, which can't be represented.", span).into())
+    } else {
+        Ok(Number::from(num))
+    }
+}
+
+fn expect_number(value: Value, name: &str, span: Span) -> SassResult<(Number, Unit)> {
+    match value {
+        Value::Dimension(n, u, _) => Ok((n, u)),
+        v => Err((
+            format!("${}: {} is not a number.", name, v.inspect(span)?),
+            span,
+        )
+            .into()),
+    }
+}
+
+fn expect_unitless(value: Value, name: &str, span: Span) -> SassResult<f64> {
+    match expect_number(value, name, span)? {
+        (n, Unit::None) => Ok(n.to_f64()),
+        (n, u) => Err((
+            format!("${}: Expected {}{} to have no units.", name, n, u),
+            span,
+        )
+            .into()),
+    }
+}
+
+fn number_arg(
+    args: &mut CallArgs,
+    parser: &mut Parser<'_>,
+    position: usize,
+    name: &'static str,
+) -> SassResult<(Number, Unit)> {
+    let value = parser.arg(args, position, name)?;
+    expect_number(value, name, args.span())
+}
+
+fn unitless_arg(
+    args: &mut CallArgs,
+    parser: &mut Parser<'_>,
+    position: usize,
+    name: &'static str,
+) -> SassResult<f64> {
+    let value = parser.arg(args, position, name)?;
+    expect_unitless(value, name, args.span())
+}
+
+/// The value of an angle in radians, treating unitless numbers as radians
+fn angle_arg(
+    args: &mut CallArgs,
+    parser: &mut Parser<'_>,
+    position: usize,
+    name: &'static str,
+) -> SassResult<f64> {
+    let (n, u) = number_arg(args, parser, position, name)?;
+    if u == Unit::None {
+        return Ok(n.to_f64());
+    }
+    match u.conversion_factor(&Unit::Rad) {
+        Some(factor) => Ok((n * factor).to_f64()),
+        None => Err((
+            format!("${}: Expected {}{} to be an angle.", name, n, u),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+/// Convert `num` to the unit `to`, requiring that both units are compatible
+/// and that either both or neither of them are unitless
+fn convert_to_match(
+    (num, unit): (Number, &Unit),
+    (to_num, to): (&Number, &Unit),
+    name: &str,
+    to_name: &str,
+    span: Span,
+) -> SassResult<Number> {
+    if (*unit == Unit::None) != (*to == Unit::None) {
+        return Err((
+            format!(
+                "${}: {}{} and ${}: {}{} have incompatible units (one has units and the other doesn't).",
+                name, num, unit, to_name, to_num, to
+            ),
+            span,
+        )
+            .into());
+    }
+    match unit.conversion_factor(to) {
+        Some(factor) => Ok(num * factor),
+        None => Err((format!("Incompatible units {} and {}.", unit, to), span).into()),
+    }
+}
+
+fn clamp(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(3)?;
+    let span = args.span();
+    let min = number_arg(&mut args, parser, 0, "min")?;
+    let number = number_arg(&mut args, parser, 1, "number")?;
+    let max = number_arg(&mut args, parser, 2, "max")?;
+
+    let number_value = convert_to_match(
+        (number.0.clone(), &number.1),
+        (&min.0, &min.1),
+        "number",
+        "min",
+        span,
+    )?;
+    let max_value = convert_to_match(
+        (max.0.clone(), &max.1),
+        (&min.0, &min.1),
+        "max",
+        "min",
+        span,
+    )?;
+
+    Ok(if min.0 >= max_value || min.0 >= number_value {
+        Value::Dimension(min.0, min.1, None)
+    } else if number_value >= max_value {
+        Value::Dimension(max.0, max.1, None)
+    } else {
+        Value::Dimension(number.0, number.1, None)
+    })
+}
+
+fn hypot(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.min_args(1)?;
+    let span = args.span();
+    let nums = parser
+        .variadic_args(args)?
+        .into_iter()
+        .map(|val| match val.node {
+            Value::Dimension(number, unit, _) => Ok((number, unit)),
+            v => Err((format!("{} is not a number.", v.inspect(span)?), span).into()),
+        })
+        .collect::<SassResult<Vec<(Number, Unit)>>>()?;
+
+    let unit = nums[0].1.clone();
+    if let Some(idx) = nums
+        .iter()
+        .position(|(_, u)| (*u == Unit::None) != (unit == Unit::None))
+    {
+        let (unitless, with_unit) = if unit == Unit::None {
+            (1, idx + 1)
+        } else {
+            (idx + 1, 1)
+        };
+        return Err((
+            format!(
+                "Argument {} is unitless but argument {} has unit {}. Arguments must all have units or all be unitless.",
+                unitless, with_unit, nums[with_unit - 1].1
+            ),
+            span,
+        )
+            .into());
+    }
+
+    let mut sum = 0.0;
+    for (num, u) in nums {
+        let num = match u.conversion_factor(&unit) {
+            Some(factor) => (num * factor).to_f64(),
+            None => return Err((format!("Incompatible units {} and {}.", u, unit), span).into()),
+        };
+        sum += num * num;
+    }
+
+    Ok(Value::Dimension(from_f64(sum.sqrt(), span)?, unit, None))
+}
+
+fn log(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(2)?;
+    let span = args.span();
+    let number = unitless_arg(&mut args, parser, 0, "number")?;
+    let result = match parser.default_arg(&mut args, 1, "base", Value::Null)? {
+        Value::Null => number.ln(),
+        base => number.log(expect_unitless(base, "base", span)?),
+    };
+    Ok(Value::Dimension(from_f64(result, span)?, Unit::None, None))
+}
+
+fn pow(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(2)?;
+    let span = args.span();
+    let base = unitless_arg(&mut args, parser, 0, "base")?;
+    let exponent = unitless_arg(&mut args, parser, 1, "exponent")?;
+    Ok(Value::Dimension(
+        from_f64(base.powf(exponent), span)?,
+        Unit::None,
+        None,
+    ))
+}
+
+fn sqrt(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    let number = unitless_arg(&mut args, parser, 0, "number")?;
+    Ok(Value::Dimension(
+        from_f64(number.sqrt(), args.span())?,
+        Unit::None,
+        None,
+    ))
+}
+
+macro_rules! trig_fn {
+    ($name:ident, $method:ident) => {
+        fn $name(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+            args.max_args(1)?;
+            let number = angle_arg(&mut args, parser, 0, "number")?;
+            Ok(Value::Dimension(
+                from_f64(number.$method(), args.span())?,
+                Unit::None,
+                None,
+            ))
+        }
+    };
+}
+
+trig_fn!(cos, cos);
+trig_fn!(sin, sin);
+trig_fn!(tan, tan);
+
+macro_rules! inverse_trig_fn {
+    ($name:ident, $method:ident) => {
+        fn $name(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+            args.max_args(1)?;
+            let number = unitless_arg(&mut args, parser, 0, "number")?;
+            Ok(Value::Dimension(
+                from_f64(number.$method().to_degrees(), args.span())?,
+                Unit::Deg,
+                None,
+            ))
+        }
+    };
+}
+
+inverse_trig_fn!(acos, acos);
+inverse_trig_fn!(asin, asin);
+inverse_trig_fn!(atan, atan);
+
+fn atan2(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(2)?;
+    let span = args.span();
+    let (y, y_unit) = number_arg(&mut args, parser, 0, "y")?;
+    let x = number_arg(&mut args, parser, 1, "x")?;
+    let x = convert_to_match((x.0, &x.1), (&y, &y_unit), "x", "y", span)?;
+    Ok(Value::Dimension(
+        from_f64(y.to_f64().atan2(x.to_f64()).to_degrees(), span)?,
+        Unit::Deg,
+        None,
+    ))
+}
+
+fn div(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(2)?;
+    let span = args.span();
+    let number1 = parser.arg(&mut args, 0, "number1")?;
+    let number2 = parser.arg(&mut args, 1, "number2")?;
+
+    ValueVisitor::new(span).div(number1, number2)
 }
 
 pub(crate) fn declare(f: &mut GlobalFunctionMap) {
@@ -261,3 +531,47 @@ pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     #[cfg(feature = "random")]
     f.insert("random", Builtin::new(random));
 }
+
+/// Declare the members of the `sass:math` module
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("ceil", ceil);
+    m.insert_builtin("clamp", clamp);
+    m.insert_builtin("floor", floor);
+    m.insert_builtin("max", max);
+    m.insert_builtin("min", min);
+    m.insert_builtin("round", round);
+    m.insert_builtin("abs", abs);
+    m.insert_builtin("hypot", hypot);
+    m.insert_builtin("log", log);
+    m.insert_builtin("pow", pow);
+    m.insert_builtin("sqrt", sqrt);
+    m.insert_builtin("cos", cos);
+    m.insert_builtin("sin", sin);
+    m.insert_builtin("tan", tan);
+    m.insert_builtin("acos", acos);
+    m.insert_builtin("asin", asin);
+    m.insert_builtin("atan", atan);
+    m.insert_builtin("atan2", atan2);
+    m.insert_builtin("compatible", comparable);
+    m.insert_builtin("is-unitless", meta::unitless);
+    m.insert_builtin("unit", meta::unit);
+    m.insert_builtin("percentage", percentage);
+    m.insert_builtin("div", div);
+    #[cfg(feature = "random")]
+    m.insert_builtin("random", random);
+
+    m.insert_builtin_var("e", Value::Dimension(Number::from(E), Unit::None, None));
+    m.insert_builtin_var("pi", Value::Dimension(Number::from(PI), Unit::None, None));
+    m.insert_builtin_var(
+        "epsilon",
+        Value::Dimension(Number::from(f64::EPSILON), Unit::None, None),
+    );
+    m.insert_builtin_var(
+        "max-safe-integer",
+        Value::Dimension(Number::from(9_007_199_254_740_991_i64), Unit::None, None),
+    );
+    m.insert_builtin_var(
+        "min-safe-integer",
+        Value::Dimension(Number::from(-9_007_199_254_740_991_i64), Unit::None, None),
+    );
+}
@@ -1,14 +1,17 @@
-use super::{Builtin, GlobalFunctionMap, GLOBAL_FUNCTIONS};
+use std::rc::Rc;
+
+use super::{Builtin, GlobalFunctionMap, Module, GLOBAL_FUNCTIONS};
 
 use codemap::Spanned;
 
 use crate::{
-    args::CallArgs,
-    common::QuoteKind,
+    args::{CallArg, CallArgs},
+    atrule::Content,
+    common::{Brackets, Identifier, ListSeparator, QuoteKind},
     error::SassResult,
-    parse::Parser,
+    parse::{Parser, Stmt},
     unit::Unit,
-    value::{SassFunction, Value},
+    value::{SassFunction, SassMap, SassMixin, Value},
 };
 
 fn if_(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
@@ -53,10 +56,10 @@ fn feature_exists(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Val
     }
 }
 
-fn unit(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+pub(super) fn unit(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     let unit = match parser.arg(&mut args, 0, "number")? {
-        Value::Dimension(_, u) => u.to_string(),
+        Value::Dimension(_, u, _) => u.to_string(),
         v => {
             return Err((
                 format!(
@@ -77,12 +80,12 @@ fn type_of(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     Ok(Value::String(value.kind().to_owned(), QuoteKind::None))
 }
 
-fn unitless(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+pub(super) fn unitless(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     #[allow(clippy::match_same_arms)]
     Ok(match parser.arg(&mut args, 0, "number")? {
-        Value::Dimension(_, Unit::None) => Value::True,
-        Value::Dimension(_, _) => Value::False,
+        Value::Dimension(_, Unit::None, _) => Value::True,
+        Value::Dimension(_, _, _) => Value::False,
         _ => Value::True,
     })
 }
@@ -102,10 +105,7 @@ fn variable_exists(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Va
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "name")? {
         Value::String(s, _) => Ok(Value::bool(
-            parser
-                .scopes
-                .last()
-                .var_exists(&s.into(), parser.global_scope),
+            parser.scopes.var_exists(s, parser.global_scope),
         )),
         v => Err((
             format!("$name: {} is not a string.", v.to_css_string(args.span())?),
@@ -118,9 +118,7 @@ fn variable_exists(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Va
 fn global_variable_exists(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(1)?;
     match parser.arg(&mut args, 0, "name")? {
-        Value::String(s, _) => Ok(Value::bool(
-            parser.global_scope.var_exists_no_global(&s.into()),
-        )),
+        Value::String(s, _) => Ok(Value::bool(parser.global_scope.var_exists(s.into()))),
         v => Err((
             format!("$name: {} is not a string.", v.to_css_string(args.span())?),
             args.span(),
@@ -133,7 +131,7 @@ fn mixin_exists(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value
     args.max_args(2)?;
     match parser.arg(&mut args, 0, "name")? {
         Value::String(s, _) => Ok(Value::bool(
-            parser.scopes.last().mixin_exists(&s, parser.global_scope),
+            parser.scopes.mixin_exists(&s, parser.global_scope),
         )),
         v => Err((
             format!("$name: {} is not a string.", v.to_css_string(args.span())?),
@@ -147,7 +145,7 @@ fn function_exists(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Va
     args.max_args(2)?;
     match parser.arg(&mut args, 0, "name")? {
         Value::String(s, _) => Ok(Value::bool(
-            parser.scopes.last().fn_exists(&s, parser.global_scope),
+            parser.scopes.fn_exists(&s, parser.global_scope),
         )),
         v => Err((
             format!("$name: {} is not a string.", v.to_css_string(args.span())?),
@@ -195,7 +193,7 @@ fn get_function(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value
             .into());
     }
 
-    let func = match parser.scopes.last().get_fn(
+    let func = match parser.scopes.get_fn(
         Spanned {
             node: &name,
             span: args.span(),
@@ -244,6 +242,204 @@ fn content_exists(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
     ))
 }
 
+fn keywords(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    match parser.arg(&mut args, 0, "args")? {
+        Value::ArgList(arg_list) => Ok(Value::Map(arg_list.keywords_map())),
+        v => Err((
+            format!(
+                "$args: {} is not an argument list.",
+                v.inspect(args.span())?
+            ),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+fn module_name_arg(args: &mut CallArgs, parser: &mut Parser<'_>) -> SassResult<Identifier> {
+    match parser.arg(args, 0, "module")? {
+        Value::String(s, ..) => Ok(Identifier::from(s)),
+        v => Err((
+            format!("$module: {} is not a string.", v.inspect(args.span())?),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+fn module_variables(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    let name = module_name_arg(&mut args, parser)?;
+    Ok(Value::Map(
+        parser.modules.get(name, args.span())?.variables(),
+    ))
+}
+
+fn module_functions(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    let name = module_name_arg(&mut args, parser)?;
+    Ok(Value::Map(
+        parser.modules.get(name, args.span())?.functions(),
+    ))
+}
+
+fn calc_name(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    match parser.arg(&mut args, 0, "calc")? {
+        Value::Calculation(calc) => Ok(Value::String(
+            calc.name.as_str().to_owned(),
+            QuoteKind::Quoted,
+        )),
+        v => Err((
+            format!("$calc: {} is not a calculation.", v.inspect(args.span())?),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+fn calc_args(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    match parser.arg(&mut args, 0, "calc")? {
+        Value::Calculation(calc) => Ok(Value::List(
+            Rc::new(calc.args.into_iter().map(|arg| arg.into_value()).collect()),
+            ListSeparator::Comma,
+            Brackets::None,
+        )),
+        v => Err((
+            format!("$calc: {} is not a calculation.", v.inspect(args.span())?),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+fn load_css(mut args: CallArgs, _: Content, parser: &mut Parser<'_>) -> SassResult<Vec<Stmt>> {
+    args.max_args(2)?;
+    let span = args.span();
+    let url = match parser.arg(&mut args, 0, "url")? {
+        Value::String(s, ..) => s,
+        v => return Err((format!("$url: {} is not a string.", v.inspect(span)?), span).into()),
+    };
+    let with = match parser.default_arg(&mut args, 1, "with", Value::Null)? {
+        Value::Null => None,
+        Value::Map(map) => Some(map),
+        v => return Err((format!("$with: {} is not a map.", v.inspect(span)?), span).into()),
+    };
+
+    if let Some(name) = url.strip_prefix("sass:") {
+        if with.is_some() {
+            return Err(("Built-in modules can't be configured.", span).into());
+        }
+        // built-in modules don't contain any CSS
+        return match Module::builtin(name) {
+            Some(..) => Ok(Vec::new()),
+            None => Err(("Can't find stylesheet to import.", span).into()),
+        };
+    }
+
+    let mut config = Vec::new();
+    for (name, value) in with.map_or_else(Vec::new, SassMap::entries) {
+        let name = match name {
+            Value::String(s, ..) => s,
+            v => {
+                return Err((
+                    format!("$with key: {} is not a string.", v.inspect(span)?),
+                    span,
+                )
+                    .into())
+            }
+        };
+        config.push((name.into(), value));
+    }
+
+    parser.load_css(&url, config, span)
+}
+
+fn get_mixin(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(2)?;
+    let name = match parser.arg(&mut args, 0, "name")? {
+        Value::String(s, _) => s,
+        v => {
+            return Err((
+                format!("$name: {} is not a string.", v.inspect(args.span())?),
+                args.span(),
+            )
+                .into())
+        }
+    };
+    let module = match parser.default_arg(&mut args, 1, "module", Value::Null)? {
+        Value::String(s, ..) => Some(s),
+        Value::Null => None,
+        v => {
+            return Err((
+                format!("$module: {} is not a string.", v.inspect(args.span())?),
+                args.span(),
+            )
+                .into())
+        }
+    };
+
+    let ident = Identifier::from(&name);
+
+    let mixin = match module {
+        Some(module) => parser
+            .modules
+            .get(Identifier::from(module), args.span())?
+            .get_mixin(ident),
+        None => match parser.scopes.get_mixin(
+            Spanned {
+                node: ident,
+                span: args.span(),
+            },
+            parser.global_scope,
+        ) {
+            Ok(mixin) => Some(SassMixin::UserDefined(Box::new(mixin), ident)),
+            Err(..) => parser.modules.get_global_mixin(ident),
+        },
+    };
+
+    match mixin {
+        Some(mixin) => Ok(Value::MixinRef(mixin)),
+        None => Err((format!("Mixin not found: {}", name), args.span()).into()),
+    }
+}
+
+fn mixin_arg(args: &mut CallArgs, parser: &mut Parser<'_>) -> SassResult<SassMixin> {
+    match parser.arg(args, 0, "mixin")? {
+        Value::MixinRef(mixin) => Ok(mixin),
+        v => Err((
+            format!(
+                "$mixin: {} is not a mixin reference.",
+                v.inspect(args.span())?
+            ),
+            args.span(),
+        )
+            .into()),
+    }
+}
+
+fn accepts_content(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(1)?;
+    Ok(Value::bool(mixin_arg(&mut args, parser)?.accepts_content()))
+}
+
+fn apply(mut args: CallArgs, content: Content, parser: &mut Parser<'_>) -> SassResult<Vec<Stmt>> {
+    let mixin = mixin_arg(&mut args, parser)?;
+    if content.content.is_some() && !mixin.accepts_content() {
+        return Err(("Mixin doesn't accept a content block.", args.span()).into());
+    }
+    // the remaining positional arguments start at 1, unless `$mixin` was
+    // passed by name
+    let args = if args.0.contains_key(&CallArg::Positional(0)) {
+        args
+    } else {
+        args.decrement()
+    };
+    mixin.include(args, content, parser)
+}
+
 pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     f.insert("if", Builtin::new(if_));
     f.insert("feature-exists", Builtin::new(feature_exists));
@@ -261,4 +457,28 @@ pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     f.insert("get-function", Builtin::new(get_function));
     f.insert("call", Builtin::new(call));
     f.insert("content-exists", Builtin::new(content_exists));
+    f.insert("keywords", Builtin::new(keywords));
+}
+
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("accepts-content", accepts_content);
+    m.insert_builtin("calc-args", calc_args);
+    m.insert_builtin("calc-name", calc_name);
+    m.insert_builtin("call", call);
+    m.insert_builtin("content-exists", content_exists);
+    m.insert_builtin("feature-exists", feature_exists);
+    m.insert_builtin("function-exists", function_exists);
+    m.insert_builtin("get-function", get_function);
+    m.insert_builtin("get-mixin", get_mixin);
+    m.insert_builtin("global-variable-exists", global_variable_exists);
+    m.insert_builtin("inspect", inspect);
+    m.insert_builtin("keywords", keywords);
+    m.insert_builtin("mixin-exists", mixin_exists);
+    m.insert_builtin("module-functions", module_functions);
+    m.insert_builtin("module-variables", module_variables);
+    m.insert_builtin("type-of", type_of);
+    m.insert_builtin("variable-exists", variable_exists);
+
+    m.insert_builtin_mixin("apply", apply, true);
+    m.insert_builtin_mixin("load-css", load_css, false);
 }
@@ -15,9 +15,14 @@ mod list;
 mod map;
 mod math;
 mod meta;
+mod modules;
+mod random;
 mod selector;
 mod string;
 
+pub(crate) use modules::{BuiltinMixin, Module, Modules};
+pub(crate) use random::Random;
+
 pub(crate) type GlobalFunctionMap = HashMap<&'static str, Builtin>;
 
 static FUNCTION_COUNT: AtomicUsize = AtomicUsize::new(0);
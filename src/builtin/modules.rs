@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use codemap::{Span, Spanned};
+
+use indexmap::IndexMap;
+
+use crate::{
+    args::CallArgs,
+    atrule::Content,
+    common::{Identifier, QuoteKind},
+    error::SassResult,
+    parse::{Parser, Stmt},
+    value::{SassFunction, SassMap, SassMixin, Value},
+};
+
+use super::{color, list, map, math, meta, string, Builtin};
+
+/// A mixin implemented in rust, such as `meta.load-css()`, which is passed
+/// the content block of the `@include`, if any
+pub(crate) type BuiltinMixin = fn(CallArgs, Content, &mut Parser<'_>) -> SassResult<Vec<Stmt>>;
+
+/// The members of a module loaded with `@use`, in the order they were
+/// declared
+#[derive(Clone, Default)]
+pub(crate) struct Module {
+    vars: IndexMap<Identifier, Value>,
+    functions: IndexMap<Identifier, SassFunction>,
+    mixins: IndexMap<Identifier, SassMixin>,
+}
+
+impl Module {
+    /// Load a builtin module such as `sass:math`, given the part of its URL
+    /// after `sass:`
+    pub fn builtin(name: &str) -> Option<Self> {
+        let mut module = Self::default();
+        match name {
+            "color" => color::declare_module(&mut module),
+            "list" => list::declare_module(&mut module),
+            "map" => map::declare_module(&mut module),
+            "math" => math::declare_module(&mut module),
+            "meta" => meta::declare_module(&mut module),
+            "string" => string::declare_module(&mut module),
+            _ => return None,
+        }
+        Some(module)
+    }
+
+    pub fn get_var(&self, name: Spanned<Identifier>) -> SassResult<&Value> {
+        match self.vars.get(&name.node) {
+            Some(v) => Ok(v),
+            None => Err(("Undefined variable.", name.span).into()),
+        }
+    }
+
+    pub fn get_fn(&self, name: Identifier) -> Option<SassFunction> {
+        self.functions.get(&name).cloned()
+    }
+
+    pub fn get_mixin(&self, name: Identifier) -> Option<SassMixin> {
+        self.mixins.get(&name).cloned()
+    }
+
+    /// The variables of this module as a map from their names, without `$`,
+    /// to their values, as returned by `meta.module-variables()`
+    pub fn variables(&self) -> SassMap {
+        let mut map = SassMap::new();
+        for (name, value) in &self.vars {
+            map.insert(
+                Value::String(name.to_string(), QuoteKind::None),
+                value.clone(),
+            );
+        }
+        map
+    }
+
+    /// The functions of this module as a map from their names to references
+    /// to them, as returned by `meta.module-functions()`
+    pub fn functions(&self) -> SassMap {
+        let mut map = SassMap::new();
+        for (name, function) in &self.functions {
+            map.insert(
+                Value::String(name.to_string(), QuoteKind::None),
+                Value::FunctionRef(function.clone()),
+            );
+        }
+        map
+    }
+
+    pub fn insert_builtin_var(&mut self, name: &'static str, value: Value) {
+        self.vars.insert(name.into(), value);
+    }
+
+    pub fn insert_builtin(
+        &mut self,
+        name: &'static str,
+        function: fn(CallArgs, &mut Parser<'_>) -> SassResult<Value>,
+    ) {
+        let ident = name.into();
+        self.functions
+            .insert(ident, SassFunction::Builtin(Builtin::new(function), ident));
+    }
+
+    pub fn insert_builtin_mixin(
+        &mut self,
+        name: &'static str,
+        mixin: BuiltinMixin,
+        accepts_content: bool,
+    ) {
+        let ident = name.into();
+        self.mixins
+            .insert(ident, SassMixin::Builtin(mixin, ident, accepts_content));
+    }
+}
+
+/// The modules loaded by `@use`, either under a namespace or, for modules
+/// loaded `as *`, accessible without one
+#[derive(Default)]
+pub(crate) struct Modules {
+    namespaced: HashMap<Identifier, Module>,
+    global: Vec<Module>,
+}
+
+impl Modules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: Identifier, module: Module, span: Span) -> SassResult<()> {
+        if self.namespaced.contains_key(&name) {
+            return Err((
+                format!("There's already a module with namespace \"{}\".", name),
+                span,
+            )
+                .into());
+        }
+        self.namespaced.insert(name, module);
+        Ok(())
+    }
+
+    pub fn insert_global(&mut self, module: Module) {
+        self.global.push(module);
+    }
+
+    pub fn get(&self, name: Identifier, span: Span) -> SassResult<&Module> {
+        match self.namespaced.get(&name) {
+            Some(module) => Ok(module),
+            None => Err((
+                format!("There is no module with the namespace \"{}\".", name),
+                span,
+            )
+                .into()),
+        }
+    }
+
+    /// Find a variable declared by a module loaded `as *`
+    pub fn get_global_var(&self, name: Identifier) -> Option<&Value> {
+        self.global.iter().find_map(|module| module.vars.get(&name))
+    }
+
+    /// Find a function declared by a module loaded `as *`
+    pub fn get_global_fn(&self, name: Identifier) -> Option<SassFunction> {
+        self.global.iter().find_map(|module| module.get_fn(name))
+    }
+
+    /// Find a mixin declared by a module loaded `as *`
+    pub fn get_global_mixin(&self, name: Identifier) -> Option<SassMixin> {
+        self.global.iter().find_map(|module| module.get_mixin(name))
+    }
+}
@@ -0,0 +1,37 @@
+#[cfg(feature = "random")]
+use rand::SeedableRng;
+#[cfg(feature = "random")]
+use rand_chacha::ChaCha8Rng;
+
+/// The source of randomness for `random()` and `unique-id()`, shared by the
+/// whole of a compilation
+///
+/// When created with a seed, every compilation using that seed produces the
+/// same values in the same order. ChaCha8 is used rather than `StdRng`, whose
+/// algorithm may change between versions of `rand`.
+#[derive(Debug)]
+pub(crate) struct Random {
+    #[cfg(feature = "random")]
+    rng: ChaCha8Rng,
+}
+
+impl Random {
+    #[cfg(feature = "random")]
+    pub fn new(seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        };
+        Self { rng }
+    }
+
+    #[cfg(not(feature = "random"))]
+    pub fn new(_: Option<u64>) -> Self {
+        Self {}
+    }
+
+    #[cfg(feature = "random")]
+    pub fn rng(&mut self) -> &mut ChaCha8Rng {
+        &mut self.rng
+    }
+}
@@ -1,5 +1,7 @@
 use super::{Builtin, GlobalFunctionMap};
 
+use std::rc::Rc;
+
 use crate::{
     args::CallArgs,
     common::{Brackets, ListSeparator, QuoteKind},
@@ -30,24 +32,25 @@ fn simple_selectors(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<V
         .arg(&mut args, 0, "selector")?
         .to_selector(parser, "selector", false)?;
 
-    if selector.0.components.len() != 1 {
+    if selector.0.components.len() != 1 || selector.0.components[0].components.len() != 1 {
         return Err(("$selector: expected selector.", args.span()).into());
     }
 
-    let compound = if let Some(ComplexSelectorComponent::Compound(compound)) =
-        selector.0.components[0].components.get(0).cloned()
-    {
-        compound
-    } else {
-        todo!()
+    let compound = match selector.0.components[0].components.get(0).cloned() {
+        Some(ComplexSelectorComponent::Compound(compound)) => compound,
+        Some(ComplexSelectorComponent::Combinator(..)) | None => {
+            return Err(("$selector: expected selector.", args.span()).into())
+        }
     };
 
     Ok(Value::List(
-        compound
-            .components
-            .into_iter()
-            .map(|simple| Value::String(simple.to_string(), QuoteKind::None))
-            .collect(),
+        Rc::new(
+            compound
+                .components
+                .into_iter()
+                .map(|simple| Value::String(simple.to_string(), QuoteKind::None))
+                .collect(),
+        ),
         ListSeparator::Comma,
         Brackets::None,
     ))
@@ -1,14 +1,15 @@
-use super::{Builtin, GlobalFunctionMap};
+use super::{Builtin, GlobalFunctionMap, Module};
 
-use num_bigint::BigInt;
-use num_traits::{Signed, ToPrimitive, Zero};
+use std::rc::Rc;
+
+use num_traits::{One, Signed, ToPrimitive};
 
 #[cfg(feature = "random")]
-use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use rand::{distributions::Alphanumeric, Rng};
 
 use crate::{
     args::CallArgs,
-    common::QuoteKind,
+    common::{Brackets, ListSeparator, QuoteKind},
     error::SassResult,
     parse::Parser,
     unit::Unit,
@@ -57,6 +58,7 @@ fn str_length(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
         Value::String(i, _) => Ok(Value::Dimension(
             Number::from(i.chars().count()),
             Unit::None,
+            None,
         )),
         v => Err((
             format!(
@@ -99,6 +101,42 @@ fn unquote(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     }
 }
 
+/// Convert an integer to an `isize`, saturating if it is out of range
+fn saturating_isize(n: &Number) -> isize {
+    n.to_integer().to_isize().unwrap_or(if n.is_positive() {
+        isize::MAX
+    } else {
+        isize::MIN
+    })
+}
+
+/// Convert a 1-based Sass string index, which counts from the end of the
+/// string if it is negative, into a 0-based code point index
+///
+/// Indices past either end of the string are clamped to it, unless
+/// `allow_negative` is true, in which case indices before the start of the
+/// string remain negative.
+fn codepoint_for_index(index: isize, len: usize, allow_negative: bool) -> isize {
+    let len = len as isize;
+    if index == 0 {
+        return 0;
+    }
+    if index > 0 {
+        return (index - 1).min(len);
+    }
+    let result = len.saturating_add(index);
+    if result < 0 && !allow_negative {
+        return 0;
+    }
+    result
+}
+
+/// The byte offset of the code point at `index`, or the length of `s` if it
+/// is past the end
+fn byte_index(s: &str, index: usize) -> usize {
+    s.char_indices().nth(index).map_or(s.len(), |(i, _)| i)
+}
+
 fn str_slice(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(3)?;
     let (string, quotes) = match parser.arg(&mut args, 0, "string")? {
@@ -114,19 +152,11 @@ fn str_slice(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
                 .into())
         }
     };
-    let str_len = string.chars().count();
     let start = match parser.arg(&mut args, 1, "start-at")? {
-        Value::Dimension(n, Unit::None) if n.is_decimal() => {
+        Value::Dimension(n, Unit::None, _) if n.is_decimal() => {
             return Err((format!("{} is not an int.", n), args.span()).into())
         }
-        Value::Dimension(n, Unit::None) if n.is_positive() => {
-            n.to_integer().to_usize().unwrap_or(str_len + 1)
-        }
-        Value::Dimension(n, Unit::None) if n.is_zero() => 1_usize,
-        Value::Dimension(n, Unit::None) if n < -Number::from(str_len) => 1_usize,
-        Value::Dimension(n, Unit::None) => (n.to_integer() + BigInt::from(str_len + 1))
-            .to_usize()
-            .unwrap(),
+        Value::Dimension(n, Unit::None, _) => saturating_isize(&n),
         v @ Value::Dimension(..) => {
             return Err((
                 format!(
@@ -148,18 +178,11 @@ fn str_slice(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
                 .into())
         }
     };
-    let mut end = match parser.default_arg(&mut args, 2, "end-at", Value::Null)? {
-        Value::Dimension(n, Unit::None) if n.is_decimal() => {
+    let end = match parser.default_arg(&mut args, 2, "end-at", Value::Null)? {
+        Value::Dimension(n, Unit::None, _) if n.is_decimal() => {
             return Err((format!("{} is not an int.", n), args.span()).into())
         }
-        Value::Dimension(n, Unit::None) if n.is_positive() => {
-            n.to_integer().to_usize().unwrap_or(str_len + 1)
-        }
-        Value::Dimension(n, Unit::None) if n.is_zero() => 0_usize,
-        Value::Dimension(n, Unit::None) if n < -Number::from(str_len) => 0_usize,
-        Value::Dimension(n, Unit::None) => (n.to_integer() + BigInt::from(str_len + 1))
-            .to_usize()
-            .unwrap_or(str_len + 1),
+        Value::Dimension(n, Unit::None, _) => saturating_isize(&n),
         v @ Value::Dimension(..) => {
             return Err((
                 format!(
@@ -170,7 +193,7 @@ fn str_slice(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
             )
                 .into())
         }
-        Value::Null => str_len,
+        Value::Null => -1,
         v => {
             return Err((
                 format!(
@@ -183,22 +206,26 @@ fn str_slice(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
         }
     };
 
-    if end > str_len {
-        end = str_len;
+    let str_len = string.chars().count();
+    if end == 0 || str_len == 0 {
+        return Ok(Value::String(String::new(), quotes));
     }
 
-    if start > end || start > str_len {
-        Ok(Value::String(String::new(), quotes))
-    } else {
-        Ok(Value::String(
-            string
-                .chars()
-                .skip(start - 1)
-                .take(end - start + 1)
-                .collect(),
-            quotes,
-        ))
+    let start = codepoint_for_index(start, str_len, false);
+    let mut end = codepoint_for_index(end, str_len, true);
+    if end == str_len as isize {
+        end -= 1;
     }
+
+    if end < start {
+        return Ok(Value::String(String::new(), quotes));
+    }
+
+    Ok(Value::String(
+        string[byte_index(&string, start as usize)..byte_index(&string, end as usize + 1)]
+            .to_owned(),
+        quotes,
+    ))
 }
 
 fn str_index(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
@@ -232,14 +259,14 @@ fn str_index(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     };
 
     Ok(match s1.find(&substr) {
-        Some(v) => Value::Dimension(Number::from(v + 1), Unit::None),
+        Some(v) => Value::Dimension(Number::from(s1[..v].chars().count() + 1), Unit::None, None),
         None => Value::Null,
     })
 }
 
 fn str_insert(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(3)?;
-    let (s1, quotes) = match parser.arg(&mut args, 0, "string")? {
+    let (mut s1, quotes) = match parser.arg(&mut args, 0, "string")? {
         Value::String(i, q) => (i, q),
         v => {
             return Err((
@@ -268,10 +295,10 @@ fn str_insert(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
     };
 
     let index = match parser.arg(&mut args, 2, "index")? {
-        Value::Dimension(n, Unit::None) if n.is_decimal() => {
+        Value::Dimension(n, Unit::None, _) if n.is_decimal() => {
             return Err((format!("$index: {} is not an int.", n), args.span()).into())
         }
-        Value::Dimension(n, Unit::None) => n,
+        Value::Dimension(n, Unit::None, _) => saturating_isize(&n),
         v @ Value::Dimension(..) => {
             return Err((
                 format!(
@@ -291,58 +318,102 @@ fn str_insert(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value>
         }
     };
 
-    if s1.is_empty() {
-        return Ok(Value::String(substr, quotes));
-    }
-
     let len = s1.chars().count();
 
-    // Insert substring at char position, rather than byte position
-    let insert = |idx, s1: String, s2| {
-        s1.chars()
-            .enumerate()
-            .map(|(i, c)| {
-                if i + 1 == idx {
-                    c.to_string() + s2
-                } else if idx == 0 && i == 0 {
-                    s2.to_string() + &c.to_string()
-                } else {
-                    c.to_string()
-                }
-            })
-            .collect::<String>()
+    // `$insert` ends up at `$index` in the result, so it is inserted before
+    // a positive index and after a negative one
+    let index = if index < 0 {
+        index.saturating_add(len as isize + 2)
+    } else {
+        index
     };
 
-    let string = if index.is_positive() {
-        insert(
-            index
-                .to_integer()
-                .to_usize()
-                .unwrap_or(len + 1)
-                .min(len + 1)
-                - 1,
-            s1,
-            &substr,
-        )
-    } else if index.is_zero() {
-        insert(0, s1, &substr)
+    let codepoint = codepoint_for_index(index, len, false) as usize;
+    s1.insert_str(byte_index(&s1, codepoint), &substr);
+
+    Ok(Value::String(s1, quotes))
+}
+
+fn split(mut args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
+    args.max_args(3)?;
+    let (string, quotes) = match parser.arg(&mut args, 0, "string")? {
+        Value::String(s, q) => (s, q),
+        v => {
+            return Err((
+                format!(
+                    "$string: {} is not a string.",
+                    v.to_css_string(args.span())?
+                ),
+                args.span(),
+            )
+                .into())
+        }
+    };
+    let separator = match parser.arg(&mut args, 1, "separator")? {
+        Value::String(s, _) => s,
+        v => {
+            return Err((
+                format!(
+                    "$separator: {} is not a string.",
+                    v.to_css_string(args.span())?
+                ),
+                args.span(),
+            )
+                .into())
+        }
+    };
+    let limit = match parser.default_arg(&mut args, 2, "limit", Value::Null)? {
+        Value::Null => None,
+        Value::Dimension(n, _, _) if n.is_decimal() => {
+            return Err((format!("$limit: {} is not an int.", n), args.span()).into())
+        }
+        Value::Dimension(n, _, _) if n < Number::one() => {
+            return Err((
+                format!("$limit: Expected limit {} to be 1 or greater.", n),
+                args.span(),
+            )
+                .into())
+        }
+        Value::Dimension(n, _, _) => Some(n.to_integer().to_usize().unwrap_or(usize::MAX)),
+        v => {
+            return Err((
+                format!("$limit: {} is not a number.", v.to_css_string(args.span())?),
+                args.span(),
+            )
+                .into())
+        }
+    };
+
+    let pieces: Vec<Value> = if string.is_empty() {
+        Vec::new()
+    } else if separator.is_empty() {
+        string
+            .chars()
+            .map(|c| Value::String(c.to_string(), quotes))
+            .collect()
     } else {
-        let idx = index.abs().to_integer().to_usize().unwrap_or(len + 1);
-        if idx > len {
-            insert(0, s1, &substr)
-        } else {
-            insert(len - idx + 1, s1, &substr)
+        let to_value = |piece: &str| Value::String(piece.to_owned(), quotes);
+        match limit {
+            Some(limit) => string
+                .splitn(limit.saturating_add(1), separator.as_str())
+                .map(to_value)
+                .collect(),
+            None => string.split(separator.as_str()).map(to_value).collect(),
         }
     };
 
-    Ok(Value::String(string, quotes))
+    Ok(Value::List(
+        Rc::new(pieces),
+        ListSeparator::Comma,
+        Brackets::Bracketed,
+    ))
 }
 
 #[cfg(feature = "random")]
 #[allow(clippy::needless_pass_by_value)]
-fn unique_id(args: CallArgs, _: &mut Parser<'_>) -> SassResult<Value> {
+fn unique_id(args: CallArgs, parser: &mut Parser<'_>) -> SassResult<Value> {
     args.max_args(0)?;
-    let mut rng = thread_rng();
+    let rng = parser.random.rng();
     let string = std::iter::repeat(())
         .map(|()| rng.sample(Alphanumeric))
         .take(7)
@@ -362,3 +433,17 @@ pub(crate) fn declare(f: &mut GlobalFunctionMap) {
     #[cfg(feature = "random")]
     f.insert("unique-id", Builtin::new(unique_id));
 }
+
+pub(crate) fn declare_module(m: &mut Module) {
+    m.insert_builtin("index", str_index);
+    m.insert_builtin("insert", str_insert);
+    m.insert_builtin("length", str_length);
+    m.insert_builtin("quote", quote);
+    m.insert_builtin("slice", str_slice);
+    m.insert_builtin("split", split);
+    m.insert_builtin("to-lower-case", to_lower_case);
+    m.insert_builtin("to-upper-case", to_upper_case);
+    #[cfg(feature = "random")]
+    m.insert_builtin("unique-id", unique_id);
+    m.insert_builtin("unquote", unquote);
+}
@@ -1,5 +1,9 @@
 //! A color is internally represented as either RGBA or HSLA.
 //!
+//! Colors in the non-legacy color spaces of CSS Color Level 4, such as `lab`
+//! or `display-p3`, additionally keep their channels in that space, along
+//! with an RGBA approximation used by the legacy color functions.
+//!
 //! Colors can be constructed in Sass through names (e.g. red, blue, aqua)
 //! or the builtin functions `rgb()`, `rgba()`, `hsl()`, and `hsla()`,
 //! all of which can accept 1-4 arguments.
@@ -22,15 +26,18 @@ use std::{
 
 use crate::value::Number;
 pub(crate) use name::NAMED_COLORS;
+pub(crate) use space::{Channels, ColorSpace};
 
 use num_traits::{One, Signed, ToPrimitive, Zero};
 
 mod name;
+mod space;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) struct Color {
     rgba: Rgba,
     hsla: Option<Hsla>,
+    space: Option<Box<SpaceChannels>>,
     repr: String,
 }
 
@@ -45,6 +52,7 @@ impl Color {
         Color {
             rgba: Rgba::new(red, green, blue, alpha),
             hsla: None,
+            space: None,
             repr,
         }
     }
@@ -60,6 +68,7 @@ impl Color {
         Color {
             rgba: Rgba::new(red, green, blue, alpha),
             hsla: Some(hsla),
+            space: None,
             repr,
         }
     }
@@ -88,6 +97,13 @@ impl Rgba {
     }
 }
 
+/// The channels of a color in a non-legacy color space
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct SpaceChannels {
+    space: ColorSpace,
+    channels: [Option<Number>; 3],
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 struct Hsla {
     hue: Number,
@@ -129,6 +145,7 @@ impl Color {
         Color {
             rgba: Rgba::new(red.into(), green.into(), blue.into(), alpha.into()),
             hsla: None,
+            space: None,
             repr,
         }
     }
@@ -451,21 +468,299 @@ impl Color {
 
     /// Change `alpha` to value given
     pub fn with_alpha(self, alpha: Number) -> Self {
-        Color::from_rgba(self.red(), self.green(), self.blue(), alpha)
+        match self.space {
+            Some(space) => Color::new_in_space(space.space, space.channels, alpha),
+            None => Color::from_rgba(self.red(), self.green(), self.blue(), alpha),
+        }
     }
 
     /// Makes a color more opaque.
     /// Takes a color and a number between 0 and 1,
     /// and returns a color with the opacity increased by that amount.
     pub fn fade_in(self, amount: Number) -> Self {
-        Color::from_rgba(self.red(), self.green(), self.blue(), self.alpha() + amount)
+        let alpha = self.alpha() + amount;
+        self.with_alpha(alpha)
     }
 
     /// Makes a color more transparent.
     /// Takes a color and a number between 0 and 1,
     /// and returns a color with the opacity decreased by that amount.
     pub fn fade_out(self, amount: Number) -> Self {
-        Color::from_rgba(self.red(), self.green(), self.blue(), self.alpha() - amount)
+        let alpha = self.alpha() - amount;
+        self.with_alpha(alpha)
+    }
+}
+
+/// Color space functions
+impl Color {
+    /// Create a color in `space` from channels which may be missing
+    ///
+    /// Colors in the legacy `rgb`, `hsl` and `hwb` spaces are represented as
+    /// RGBA or HSLA, as if they were created by `rgb()` or `hsl()`.
+    pub fn new_in_space(space: ColorSpace, channels: [Option<Number>; 3], alpha: Number) -> Self {
+        let alpha = alpha.clamp(0, 1);
+        let filled = |i: usize| channels[i].clone().unwrap_or_else(Number::zero);
+        match space {
+            ColorSpace::Rgb => return Color::from_rgba(filled(0), filled(1), filled(2), alpha),
+            ColorSpace::Hsl => {
+                return Color::from_hsla(
+                    filled(0),
+                    filled(1) / Number::from(100),
+                    filled(2) / Number::from(100),
+                    alpha,
+                )
+            }
+            _ => {}
+        }
+
+        let [red, green, blue] = space.convert_filled(
+            ColorSpace::Rgb,
+            [filled(0).to_f64(), filled(1).to_f64(), filled(2).to_f64()],
+        );
+        let rgb_channel = |c: f64| Number::from(c.max(0.0).min(255.0));
+
+        if space == ColorSpace::Hwb {
+            return Color::from_rgba(
+                rgb_channel(red),
+                rgb_channel(green),
+                rgb_channel(blue),
+                alpha,
+            );
+        }
+
+        let repr = space_repr(space, &channels, &alpha);
+        Color {
+            rgba: Rgba::new(
+                rgb_channel(red),
+                rgb_channel(green),
+                rgb_channel(blue),
+                alpha,
+            ),
+            hsla: None,
+            space: Some(Box::new(SpaceChannels { space, channels })),
+            repr,
+        }
+    }
+
+    /// Create a color in `space` from channels computed as floating point
+    /// numbers
+    pub fn from_space(space: ColorSpace, channels: Channels, alpha: Number) -> Self {
+        let to_number = |c: Option<f64>| c.filter(|c| c.is_finite()).map(Number::from);
+        Color::new_in_space(
+            space,
+            [
+                to_number(channels[0]),
+                to_number(channels[1]),
+                to_number(channels[2]),
+            ],
+            alpha,
+        )
+    }
+
+    /// The space this color was written in, or converted to
+    pub fn space(&self) -> ColorSpace {
+        match (&self.space, &self.hsla) {
+            (Some(space), _) => space.space,
+            (None, Some(..)) => ColorSpace::Hsl,
+            (None, None) => ColorSpace::Rgb,
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.space.is_none()
+    }
+
+    /// The channels of this color in its own space
+    pub fn channels(&self) -> Channels {
+        if let Some(space) = &self.space {
+            let channel = |i: usize| space.channels[i].as_ref().map(Number::to_f64);
+            return [channel(0), channel(1), channel(2)];
+        }
+
+        if let Some(hsla) = &self.hsla {
+            return [
+                Some(hsla.hue().to_f64()),
+                Some(hsla.saturation().to_f64() * 100.0),
+                Some(hsla.luminance().to_f64() * 100.0),
+            ];
+        }
+
+        [
+            Some(self.rgba.red.to_f64()),
+            Some(self.rgba.green.to_f64()),
+            Some(self.rgba.blue.to_f64()),
+        ]
+    }
+
+    /// The channels of this color converted to `space`
+    pub fn channels_in(&self, space: ColorSpace) -> Channels {
+        self.space().convert(space, self.channels())
+    }
+
+    /// Convert this color to `space`
+    pub fn to_space(&self, space: ColorSpace) -> Self {
+        if space == self.space() {
+            return self.clone();
+        }
+        Color::from_space(space, self.channels_in(space), self.alpha())
+    }
+
+    /// Whether this color is within the gamut of `space`
+    pub fn is_in_gamut(&self, space: ColorSpace) -> bool {
+        space::is_in_gamut(space, filled(self.channels_in(space)))
+    }
+
+    /// Map this color into the gamut of `space`, returning a color in this
+    /// color's own space
+    pub fn to_gamut(&self, space: ColorSpace, method: GamutMapMethod) -> Self {
+        let channels = self.channels_in(space);
+        let mapped = match method {
+            GamutMapMethod::Clip => space::clip(space, filled(channels)),
+            GamutMapMethod::LocalMinde => space::local_minde(space, filled(channels)),
+        };
+        // channels that were missing stay missing
+        let mapped = [
+            channels[0].and(Some(mapped[0])),
+            channels[1].and(Some(mapped[1])),
+            channels[2].and(Some(mapped[2])),
+        ];
+        Color::from_space(space, mapped, self.alpha()).to_space(self.space())
+    }
+
+    /// Mix two colors by interpolating between them in `method.space`, as
+    /// described by <https://www.w3.org/TR/css-color-4/#interpolation>
+    ///
+    /// `weight` is the proportion of `self` in the result, from 0 to 1. The
+    /// result is in the same space as `self`.
+    pub fn interpolate(&self, other: &Color, method: InterpolationMethod, weight: f64) -> Self {
+        let space = method.space;
+        let channels1 = self.channels_in(space);
+        let channels2 = other.channels_in(space);
+        let alpha1 = self.alpha().to_f64();
+        let alpha2 = other.alpha().to_f64();
+        let hue_index = space.hue_index();
+
+        let premultiply = |channels: Channels, alpha: f64| {
+            let mut result = channels;
+            for (i, c) in result.iter_mut().enumerate() {
+                if Some(i) != hue_index {
+                    *c = c.map(|c| c * alpha);
+                }
+            }
+            result
+        };
+
+        let channels1 = premultiply(channels1, alpha1);
+        let channels2 = premultiply(channels2, alpha2);
+        let alpha = alpha1 * weight + alpha2 * (1.0 - weight);
+
+        let mut mixed = [None; 3];
+        for (i, mixed) in mixed.iter_mut().enumerate() {
+            *mixed = match (channels1[i], channels2[i]) {
+                (None, None) => None,
+                (Some(c), None) | (None, Some(c)) => Some(c),
+                (Some(c1), Some(c2)) if Some(i) == hue_index => {
+                    let (c1, c2) = method.hue.fixup(c1.rem_euclid(360.0), c2.rem_euclid(360.0));
+                    Some((c1 * weight + c2 * (1.0 - weight)).rem_euclid(360.0))
+                }
+                (Some(c1), Some(c2)) => {
+                    let c = c1 * weight + c2 * (1.0 - weight);
+                    Some(if alpha == 0.0 { c } else { c / alpha })
+                }
+            };
+        }
+
+        Color::from_space(space, mixed, Number::from(alpha)).to_space(self.space())
+    }
+}
+
+/// Fill in missing channels with zero
+fn filled(channels: Channels) -> [f64; 3] {
+    [
+        channels[0].unwrap_or(0.0),
+        channels[1].unwrap_or(0.0),
+        channels[2].unwrap_or(0.0),
+    ]
+}
+
+/// The method used by `color.to-gamut()` to map a color into a gamut
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GamutMapMethod {
+    /// Clamp each channel to the gamut
+    Clip,
+    /// Reduce chroma in Oklch until clipping is imperceptible, as specified
+    /// by CSS Color Level 4
+    LocalMinde,
+}
+
+impl GamutMapMethod {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clip" => Some(Self::Clip),
+            "local-minde" => Some(Self::LocalMinde),
+            _ => None,
+        }
+    }
+}
+
+/// The color space and hue interpolation method used to mix colors, as
+/// written in the `$method` argument to `mix()`, e.g. `oklch longer hue`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct InterpolationMethod {
+    pub space: ColorSpace,
+    pub hue: HueInterpolationMethod,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HueInterpolationMethod {
+    Shorter,
+    Longer,
+    Increasing,
+    Decreasing,
+}
+
+impl HueInterpolationMethod {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "shorter" => Some(Self::Shorter),
+            "longer" => Some(Self::Longer),
+            "increasing" => Some(Self::Increasing),
+            "decreasing" => Some(Self::Decreasing),
+            _ => None,
+        }
+    }
+
+    /// Adjust two hues, each from 0 to 360, so that interpolating linearly
+    /// between them goes the right way around the hue circle
+    fn fixup(self, mut hue1: f64, mut hue2: f64) -> (f64, f64) {
+        let difference = hue2 - hue1;
+        match self {
+            Self::Shorter => {
+                if difference > 180.0 {
+                    hue1 += 360.0;
+                } else if difference < -180.0 {
+                    hue2 += 360.0;
+                }
+            }
+            Self::Longer => {
+                if 0.0 < difference && difference < 180.0 {
+                    hue1 += 360.0;
+                } else if -180.0 < difference && difference <= 0.0 {
+                    hue2 += 360.0;
+                }
+            }
+            Self::Increasing => {
+                if hue2 < hue1 {
+                    hue2 += 360.0;
+                }
+            }
+            Self::Decreasing => {
+                if hue1 < hue2 {
+                    hue1 += 360.0;
+                }
+            }
+        }
+        (hue1, hue2)
     }
 }
 
@@ -507,6 +802,54 @@ fn repr(red: &Number, green: &Number, blue: &Number, alpha: &Number) -> String {
     }
 }
 
+/// Get the representation of a color in a non-legacy space, e.g.
+/// `oklch(70% 0.1 200deg)` or `color(display-p3 1 0.5 0 / 0.8)`
+fn space_repr(space: ColorSpace, channels: &[Option<Number>; 3], alpha: &Number) -> String {
+    let channel = |i: usize, unit: &str| match &channels[i] {
+        Some(n) => format!("{}{}", n, unit),
+        None => "none".to_owned(),
+    };
+
+    let alpha = if alpha < &Number::one() {
+        format!(" / {}", alpha)
+    } else {
+        String::new()
+    };
+
+    match space {
+        ColorSpace::Lab | ColorSpace::Lch | ColorSpace::Oklab | ColorSpace::Oklch => {
+            let lightness = match &channels[0] {
+                Some(n) if space == ColorSpace::Oklab || space == ColorSpace::Oklch => {
+                    format!("{}%", n.clone() * Number::from(100))
+                }
+                Some(n) => format!("{}%", n),
+                None => "none".to_owned(),
+            };
+            let hue_unit = if space.hue_index().is_some() {
+                "deg"
+            } else {
+                ""
+            };
+            format!(
+                "{}({} {} {}{})",
+                space.name(),
+                lightness,
+                channel(1, ""),
+                channel(2, hue_unit),
+                alpha
+            )
+        }
+        _ => format!(
+            "color({} {} {} {}{})",
+            space.name(),
+            channel(0, ""),
+            channel(1, ""),
+            channel(2, ""),
+            alpha
+        ),
+    }
+}
+
 impl Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.repr)
@@ -0,0 +1,901 @@
+//! The color spaces defined by CSS Color Level 4, and conversions between them
+//!
+//! Conversions go through CIE XYZ with a D65 white point, using the matrices
+//! and transfer functions from the sample code in the specification:
+//! <https://www.w3.org/TR/css-color-4/#color-conversion-code>
+//!
+//! Channels are given in the units they are written in CSS, e.g. 0-255 for
+//! `rgb`, 0-100 for the saturation and lightness of `hsl`, 0-1 for the
+//! channels of `display-p3`. A channel is `None` if it's missing, i.e. was
+//! written as `none`.
+
+use std::f64::consts::PI;
+
+pub(crate) type Channels = [Option<f64>; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ColorSpace {
+    Rgb,
+    Hsl,
+    Hwb,
+    Srgb,
+    SrgbLinear,
+    DisplayP3,
+    A98Rgb,
+    ProphotoRgb,
+    Rec2020,
+    XyzD65,
+    XyzD50,
+    Lab,
+    Lch,
+    Oklab,
+    Oklch,
+}
+
+/// The kind of a channel, used to carry missing channels forward to
+/// analogous channels in another space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChannelKind {
+    Red,
+    Green,
+    Blue,
+    Lightness,
+    Colorfulness,
+    Hue,
+    OpponentA,
+    OpponentB,
+    Other,
+}
+
+impl ColorSpace {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "rgb" => Self::Rgb,
+            "hsl" => Self::Hsl,
+            "hwb" => Self::Hwb,
+            "srgb" => Self::Srgb,
+            "srgb-linear" => Self::SrgbLinear,
+            "display-p3" => Self::DisplayP3,
+            "a98-rgb" => Self::A98Rgb,
+            "prophoto-rgb" => Self::ProphotoRgb,
+            "rec2020" => Self::Rec2020,
+            "xyz" | "xyz-d65" => Self::XyzD65,
+            "xyz-d50" => Self::XyzD50,
+            "lab" => Self::Lab,
+            "lch" => Self::Lch,
+            "oklab" => Self::Oklab,
+            "oklch" => Self::Oklch,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rgb => "rgb",
+            Self::Hsl => "hsl",
+            Self::Hwb => "hwb",
+            Self::Srgb => "srgb",
+            Self::SrgbLinear => "srgb-linear",
+            Self::DisplayP3 => "display-p3",
+            Self::A98Rgb => "a98-rgb",
+            Self::ProphotoRgb => "prophoto-rgb",
+            Self::Rec2020 => "rec2020",
+            Self::XyzD65 => "xyz",
+            Self::XyzD50 => "xyz-d50",
+            Self::Lab => "lab",
+            Self::Lch => "lch",
+            Self::Oklab => "oklab",
+            Self::Oklch => "oklch",
+        }
+    }
+
+    pub fn channel_names(self) -> [&'static str; 3] {
+        match self {
+            Self::Rgb
+            | Self::Srgb
+            | Self::SrgbLinear
+            | Self::DisplayP3
+            | Self::A98Rgb
+            | Self::ProphotoRgb
+            | Self::Rec2020 => ["red", "green", "blue"],
+            Self::Hsl => ["hue", "saturation", "lightness"],
+            Self::Hwb => ["hue", "whiteness", "blackness"],
+            Self::XyzD65 | Self::XyzD50 => ["x", "y", "z"],
+            Self::Lab | Self::Oklab => ["lightness", "a", "b"],
+            Self::Lch | Self::Oklch => ["lightness", "chroma", "hue"],
+        }
+    }
+
+    /// The index of the channel with the given name, if this space has one
+    pub fn channel_index(self, name: &str) -> Option<usize> {
+        self.channel_names().iter().position(|c| *c == name)
+    }
+
+    /// Whether colors in this space are serialized using the `rgb()` and
+    /// `hsl()` syntaxes, and can be manipulated by the older color functions
+    pub fn is_legacy(self) -> bool {
+        matches!(self, Self::Rgb | Self::Hsl | Self::Hwb)
+    }
+
+    /// The index of the hue channel, if this is a polar space
+    pub fn hue_index(self) -> Option<usize> {
+        match self {
+            Self::Hsl | Self::Hwb => Some(0),
+            Self::Lch | Self::Oklch => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Whether this space has a limited gamut
+    pub fn is_bounded(self) -> bool {
+        !matches!(
+            self,
+            Self::XyzD65 | Self::XyzD50 | Self::Lab | Self::Lch | Self::Oklab | Self::Oklch
+        )
+    }
+
+    /// The range of a channel, which is the gamut of bounded spaces and the
+    /// reference range that percentages are relative to otherwise
+    pub fn channel_range(self, index: usize) -> (f64, f64) {
+        match (self, index) {
+            (Self::Rgb, _) => (0.0, 255.0),
+            (Self::Hsl, 0) | (Self::Hwb, 0) | (Self::Lch, 2) | (Self::Oklch, 2) => (0.0, 360.0),
+            (Self::Hsl, _) | (Self::Hwb, _) | (Self::Lab, 0) | (Self::Lch, 0) => (0.0, 100.0),
+            (Self::Lab, _) => (-125.0, 125.0),
+            (Self::Lch, _) => (0.0, 150.0),
+            (Self::Oklab, 0) | (Self::Oklch, 0) => (0.0, 1.0),
+            (Self::Oklab, _) => (-0.4, 0.4),
+            (Self::Oklch, _) => (0.0, 0.4),
+            _ => (0.0, 1.0),
+        }
+    }
+
+    /// Whether a channel is conventionally written as a percentage, and so
+    /// is returned with the unit `%` by `color.channel()`
+    pub fn is_percent_channel(self, index: usize) -> bool {
+        matches!(
+            (self, index),
+            (Self::Hsl, 1..=2) | (Self::Hwb, 1..=2) | (Self::Oklab, 0) | (Self::Oklch, 0)
+        )
+    }
+
+    fn channel_kind(self, index: usize) -> ChannelKind {
+        match (self, self.channel_names()[index]) {
+            (_, "hue") => ChannelKind::Hue,
+            (_, "lightness") => ChannelKind::Lightness,
+            (_, "saturation") | (_, "chroma") => ChannelKind::Colorfulness,
+            (_, "red") | (_, "x") => ChannelKind::Red,
+            (_, "green") | (_, "y") => ChannelKind::Green,
+            (_, "blue") | (_, "z") => ChannelKind::Blue,
+            (Self::Lab, "a") | (Self::Oklab, "a") => ChannelKind::OpponentA,
+            (Self::Lab, "b") | (Self::Oklab, "b") => ChannelKind::OpponentB,
+            _ => ChannelKind::Other,
+        }
+    }
+
+    /// Convert channels in this space to channels in `to`
+    ///
+    /// Missing channels are treated as zero, unless `to` has an analogous
+    /// channel, in which case that channel is missing in the result as well.
+    pub fn convert(self, to: ColorSpace, channels: Channels) -> Channels {
+        if self == to {
+            return channels;
+        }
+
+        let filled = [
+            channels[0].unwrap_or(0.0),
+            channels[1].unwrap_or(0.0),
+            channels[2].unwrap_or(0.0),
+        ];
+
+        let converted = match (self, to) {
+            (Self::Lab, Self::Lch) | (Self::Oklab, Self::Oklch) => to_polar(filled),
+            (Self::Lch, Self::Lab) | (Self::Oklch, Self::Oklab) => from_polar(filled),
+            (Self::Rgb, Self::Srgb) => rgb_to_srgb(filled),
+            (Self::Srgb, Self::Rgb) => srgb_to_rgb(filled),
+            (from, to) if from.is_srgb_based() && to.is_srgb_based() => {
+                to.from_srgb(from.to_srgb(filled))
+            }
+            (from, to) => to.from_xyz(from.to_xyz(filled)),
+        };
+
+        let mut result = [Some(converted[0]), Some(converted[1]), Some(converted[2])];
+
+        for (i, channel) in channels.iter().enumerate() {
+            if channel.is_some() {
+                continue;
+            }
+            let kind = self.channel_kind(i);
+            if kind == ChannelKind::Other {
+                continue;
+            }
+            if let Some(j) = (0..3).find(|&j| to.channel_kind(j) == kind) {
+                result[j] = None;
+            }
+        }
+
+        if let Some(hue) = to.hue_index() {
+            if is_powerless_hue(to, &converted) {
+                result[hue] = None;
+            }
+        }
+
+        result
+    }
+
+    fn is_srgb_based(self) -> bool {
+        matches!(self, Self::Rgb | Self::Hsl | Self::Hwb | Self::Srgb)
+    }
+
+    /// Convert channels in a space based on sRGB to gamma-encoded sRGB
+    /// channels from 0 to 1
+    fn to_srgb(self, channels: [f64; 3]) -> [f64; 3] {
+        match self {
+            Self::Rgb => rgb_to_srgb(channels),
+            Self::Hsl => hsl_to_srgb(channels),
+            Self::Hwb => hwb_to_srgb(channels),
+            _ => channels,
+        }
+    }
+
+    fn from_srgb(self, channels: [f64; 3]) -> [f64; 3] {
+        match self {
+            Self::Rgb => srgb_to_rgb(channels),
+            Self::Hsl => srgb_to_hsl(channels),
+            Self::Hwb => srgb_to_hwb(channels),
+            _ => channels,
+        }
+    }
+
+    fn to_xyz(self, channels: [f64; 3]) -> [f64; 3] {
+        match self {
+            Self::Rgb | Self::Hsl | Self::Hwb | Self::Srgb => multiply(
+                &LINEAR_SRGB_TO_XYZ,
+                map(self.to_srgb(channels), srgb_to_linear),
+            ),
+            Self::SrgbLinear => multiply(&LINEAR_SRGB_TO_XYZ, channels),
+            Self::DisplayP3 => multiply(&LINEAR_P3_TO_XYZ, map(channels, srgb_to_linear)),
+            Self::A98Rgb => multiply(&LINEAR_A98_TO_XYZ, map(channels, a98_to_linear)),
+            Self::ProphotoRgb => multiply(
+                &D50_TO_D65,
+                multiply(
+                    &LINEAR_PROPHOTO_TO_XYZ_D50,
+                    map(channels, prophoto_to_linear),
+                ),
+            ),
+            Self::Rec2020 => multiply(&LINEAR_REC2020_TO_XYZ, map(channels, rec2020_to_linear)),
+            Self::XyzD65 => channels,
+            Self::XyzD50 => multiply(&D50_TO_D65, channels),
+            Self::Lab => multiply(&D50_TO_D65, lab_to_xyz_d50(channels)),
+            Self::Lch => multiply(&D50_TO_D65, lab_to_xyz_d50(from_polar(channels))),
+            Self::Oklab => oklab_to_xyz(channels),
+            Self::Oklch => oklab_to_xyz(from_polar(channels)),
+        }
+    }
+
+    fn from_xyz(self, xyz: [f64; 3]) -> [f64; 3] {
+        match self {
+            Self::Rgb | Self::Hsl | Self::Hwb | Self::Srgb => {
+                self.from_srgb(map(multiply(&XYZ_TO_LINEAR_SRGB, xyz), linear_to_srgb))
+            }
+            Self::SrgbLinear => multiply(&XYZ_TO_LINEAR_SRGB, xyz),
+            Self::DisplayP3 => map(multiply(&XYZ_TO_LINEAR_P3, xyz), linear_to_srgb),
+            Self::A98Rgb => map(multiply(&XYZ_TO_LINEAR_A98, xyz), linear_to_a98),
+            Self::ProphotoRgb => map(
+                multiply(&XYZ_D50_TO_LINEAR_PROPHOTO, multiply(&D65_TO_D50, xyz)),
+                linear_to_prophoto,
+            ),
+            Self::Rec2020 => map(multiply(&XYZ_TO_LINEAR_REC2020, xyz), linear_to_rec2020),
+            Self::XyzD65 => xyz,
+            Self::XyzD50 => multiply(&D65_TO_D50, xyz),
+            Self::Lab => xyz_d50_to_lab(multiply(&D65_TO_D50, xyz)),
+            Self::Lch => to_polar(xyz_d50_to_lab(multiply(&D65_TO_D50, xyz))),
+            Self::Oklab => xyz_to_oklab(xyz),
+            Self::Oklch => to_polar(xyz_to_oklab(xyz)),
+        }
+    }
+}
+
+/// Whether the hue of a color converted to a polar space has no effect on
+/// the color, because it has no chroma or saturation
+fn is_powerless_hue(space: ColorSpace, channels: &[f64; 3]) -> bool {
+    const EPSILON: f64 = 1e-6;
+    match space {
+        ColorSpace::Hsl => channels[1].abs() < EPSILON,
+        ColorSpace::Hwb => channels[1] + channels[2] >= 100.0 - EPSILON,
+        ColorSpace::Lch => channels[1].abs() < EPSILON * 100.0,
+        ColorSpace::Oklch => channels[1].abs() < EPSILON,
+        _ => false,
+    }
+}
+
+/// The distance between two colors in Oklab, which is used as the measure of
+/// how perceptually different they are when mapping into a gamut
+fn delta_eok(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let a = from_polar(a);
+    let b = from_polar(b);
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// Whether channels in `space` are within its gamut
+pub(crate) fn is_in_gamut(space: ColorSpace, channels: [f64; 3]) -> bool {
+    const EPSILON: f64 = 1e-6;
+    if !space.is_bounded() {
+        return true;
+    }
+    let channels = match space {
+        ColorSpace::Hsl | ColorSpace::Hwb => space.convert_filled(ColorSpace::Rgb, channels),
+        _ => channels,
+    };
+    let space = match space {
+        ColorSpace::Hsl | ColorSpace::Hwb => ColorSpace::Rgb,
+        _ => space,
+    };
+    channels.iter().enumerate().all(|(i, c)| {
+        let (min, max) = space.channel_range(i);
+        let epsilon = (max - min) * EPSILON;
+        *c >= min - epsilon && *c <= max + epsilon
+    })
+}
+
+/// Map channels in `space` into its gamut by clamping each channel
+pub(crate) fn clip(space: ColorSpace, channels: [f64; 3]) -> [f64; 3] {
+    match space {
+        ColorSpace::Hsl | ColorSpace::Hwb => ColorSpace::Rgb.convert_filled(
+            space,
+            clip(
+                ColorSpace::Rgb,
+                space.convert_filled(ColorSpace::Rgb, channels),
+            ),
+        ),
+        _ if !space.is_bounded() => channels,
+        _ => {
+            let mut result = channels;
+            for (i, c) in result.iter_mut().enumerate() {
+                let (min, max) = space.channel_range(i);
+                *c = c.max(min).min(max);
+            }
+            result
+        }
+    }
+}
+
+/// Map channels in `space` into its gamut by reducing their chroma in Oklch
+/// until clipping them produces a color that is close enough to be
+/// indistinguishable
+///
+/// This is the algorithm from
+/// <https://www.w3.org/TR/css-color-4/#css-gamut-mapping>
+pub(crate) fn local_minde(space: ColorSpace, channels: [f64; 3]) -> [f64; 3] {
+    const JND: f64 = 0.02;
+    const EPSILON: f64 = 0.0001;
+
+    if !space.is_bounded() {
+        return channels;
+    }
+
+    let origin = space.convert_filled(ColorSpace::Oklch, channels);
+
+    if origin[0] >= 1.0 {
+        return ColorSpace::Oklch.convert_filled(space, [1.0, 0.0, 0.0]);
+    } else if origin[0] <= 0.0 {
+        return ColorSpace::Oklch.convert_filled(space, [0.0, 0.0, 0.0]);
+    }
+
+    if is_in_gamut(space, channels) {
+        return channels;
+    }
+
+    let in_space = |oklch: [f64; 3]| ColorSpace::Oklch.convert_filled(space, oklch);
+    let clipped_oklch = |oklch: [f64; 3]| {
+        let clipped = clip(space, in_space(oklch));
+        (clipped, space.convert_filled(ColorSpace::Oklch, clipped))
+    };
+
+    let mut current = origin;
+    let (mut clipped, clipped_as_oklch) = clipped_oklch(current);
+    if delta_eok(clipped_as_oklch, current) < JND {
+        return clipped;
+    }
+
+    let mut min = 0.0;
+    let mut max = origin[1];
+    let mut min_in_gamut = true;
+
+    while max - min > EPSILON {
+        let chroma = (min + max) / 2.0;
+        current[1] = chroma;
+
+        if min_in_gamut && is_in_gamut(space, in_space(current)) {
+            min = chroma;
+            continue;
+        }
+
+        let (next, next_as_oklch) = clipped_oklch(current);
+        clipped = next;
+        let delta = delta_eok(next_as_oklch, current);
+        if delta < JND {
+            if JND - delta < EPSILON {
+                return clipped;
+            }
+            min_in_gamut = false;
+            min = chroma;
+        } else {
+            max = chroma;
+        }
+    }
+
+    clipped
+}
+
+impl ColorSpace {
+    /// Convert channels none of which are missing
+    pub fn convert_filled(self, to: ColorSpace, channels: [f64; 3]) -> [f64; 3] {
+        let converted = self.convert(
+            to,
+            [Some(channels[0]), Some(channels[1]), Some(channels[2])],
+        );
+        [
+            converted[0].unwrap_or(0.0),
+            converted[1].unwrap_or(0.0),
+            converted[2].unwrap_or(0.0),
+        ]
+    }
+}
+
+fn map(channels: [f64; 3], f: impl Fn(f64) -> f64) -> [f64; 3] {
+    [f(channels[0]), f(channels[1]), f(channels[2])]
+}
+
+fn multiply(matrix: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    let row = |r: &[f64; 3]| r[0] * v[0] + r[1] * v[1] + r[2] * v[2];
+    [row(&matrix[0]), row(&matrix[1]), row(&matrix[2])]
+}
+
+fn rgb_to_srgb(channels: [f64; 3]) -> [f64; 3] {
+    map(channels, |c| c / 255.0)
+}
+
+fn srgb_to_rgb(channels: [f64; 3]) -> [f64; 3] {
+    map(channels, |c| c * 255.0)
+}
+
+fn hsl_to_srgb([hue, saturation, lightness]: [f64; 3]) -> [f64; 3] {
+    let hue = hue.rem_euclid(360.0);
+    let saturation = saturation / 100.0;
+    let lightness = lightness / 100.0;
+
+    let f = |n: f64| {
+        let k = (n + hue / 30.0) % 12.0;
+        let a = saturation * lightness.min(1.0 - lightness);
+        lightness - a * (k - 3.0).min(9.0 - k).min(1.0).max(-1.0)
+    };
+
+    [f(0.0), f(8.0), f(4.0)]
+}
+
+fn srgb_to_hsl([red, green, blue]: [f64; 3]) -> [f64; 3] {
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let delta = max - min;
+    let lightness = (min + max) / 2.0;
+
+    let saturation = if lightness == 0.0 || lightness == 1.0 {
+        0.0
+    } else {
+        (max - lightness) / lightness.min(1.0 - lightness)
+    };
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == red {
+        (green - blue) / delta + if green < blue { 6.0 } else { 0.0 }
+    } else if max == green {
+        (blue - red) / delta + 2.0
+    } else {
+        (red - green) / delta + 4.0
+    };
+
+    [hue * 60.0, saturation * 100.0, lightness * 100.0]
+}
+
+fn hwb_to_srgb([hue, whiteness, blackness]: [f64; 3]) -> [f64; 3] {
+    let whiteness = whiteness / 100.0;
+    let blackness = blackness / 100.0;
+    if whiteness + blackness >= 1.0 {
+        let gray = whiteness / (whiteness + blackness);
+        return [gray, gray, gray];
+    }
+    map(hsl_to_srgb([hue, 100.0, 50.0]), |c| {
+        c * (1.0 - whiteness - blackness) + whiteness
+    })
+}
+
+fn srgb_to_hwb(channels: [f64; 3]) -> [f64; 3] {
+    let hue = srgb_to_hsl(channels)[0];
+    let whiteness = channels[0].min(channels[1]).min(channels[2]);
+    let blackness = 1.0 - channels[0].max(channels[1]).max(channels[2]);
+    [hue, whiteness * 100.0, blackness * 100.0]
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    if c.abs() <= 0.04045 {
+        c / 12.92
+    } else {
+        c.signum() * ((c.abs() + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f64) -> f64 {
+    if c.abs() > 0.003_130_8 {
+        c.signum() * (1.055 * c.abs().powf(1.0 / 2.4) - 0.055)
+    } else {
+        12.92 * c
+    }
+}
+
+fn a98_to_linear(c: f64) -> f64 {
+    c.signum() * c.abs().powf(563.0 / 256.0)
+}
+
+fn linear_to_a98(c: f64) -> f64 {
+    c.signum() * c.abs().powf(256.0 / 563.0)
+}
+
+fn prophoto_to_linear(c: f64) -> f64 {
+    if c.abs() <= 16.0 / 512.0 {
+        c / 16.0
+    } else {
+        c.signum() * c.abs().powf(1.8)
+    }
+}
+
+fn linear_to_prophoto(c: f64) -> f64 {
+    if c.abs() >= 1.0 / 512.0 {
+        c.signum() * c.abs().powf(1.0 / 1.8)
+    } else {
+        16.0 * c
+    }
+}
+
+const REC2020_ALPHA: f64 = 1.099_296_826_809_44;
+const REC2020_BETA: f64 = 0.018_053_968_510_807;
+
+fn rec2020_to_linear(c: f64) -> f64 {
+    if c.abs() < REC2020_BETA * 4.5 {
+        c / 4.5
+    } else {
+        c.signum() * ((c.abs() + REC2020_ALPHA - 1.0) / REC2020_ALPHA).powf(1.0 / 0.45)
+    }
+}
+
+fn linear_to_rec2020(c: f64) -> f64 {
+    if c.abs() > REC2020_BETA {
+        c.signum() * (REC2020_ALPHA * c.abs().powf(0.45) - (REC2020_ALPHA - 1.0))
+    } else {
+        4.5 * c
+    }
+}
+
+/// The D50 white point used by Lab and LCH
+const D50_WHITE: [f64; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+const LAB_KAPPA: f64 = 24389.0 / 27.0;
+const LAB_EPSILON: f64 = 216.0 / 24389.0;
+
+fn xyz_d50_to_lab(xyz: [f64; 3]) -> [f64; 3] {
+    let f = |i: usize| {
+        let v = xyz[i] / D50_WHITE[i];
+        if v > LAB_EPSILON {
+            v.cbrt()
+        } else {
+            (LAB_KAPPA * v + 16.0) / 116.0
+        }
+    };
+    let (f0, f1, f2) = (f(0), f(1), f(2));
+    [116.0 * f1 - 16.0, 500.0 * (f0 - f1), 200.0 * (f1 - f2)]
+}
+
+fn lab_to_xyz_d50([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    let f1 = (lightness + 16.0) / 116.0;
+    let f0 = a / 500.0 + f1;
+    let f2 = f1 - b / 200.0;
+    let inverse = |f: f64| {
+        if f.powi(3) > LAB_EPSILON {
+            f.powi(3)
+        } else {
+            (116.0 * f - 16.0) / LAB_KAPPA
+        }
+    };
+    let y = if lightness > LAB_KAPPA * LAB_EPSILON {
+        f1.powi(3)
+    } else {
+        lightness / LAB_KAPPA
+    };
+    [
+        inverse(f0) * D50_WHITE[0],
+        y * D50_WHITE[1],
+        inverse(f2) * D50_WHITE[2],
+    ]
+}
+
+fn xyz_to_oklab(xyz: [f64; 3]) -> [f64; 3] {
+    multiply(&LMS_TO_OKLAB, map(multiply(&XYZ_TO_LMS, xyz), f64::cbrt))
+}
+
+fn oklab_to_xyz(oklab: [f64; 3]) -> [f64; 3] {
+    multiply(
+        &LMS_TO_XYZ,
+        map(multiply(&OKLAB_TO_LMS, oklab), |c| c.powi(3)),
+    )
+}
+
+/// Convert rectangular `L a b` channels to polar `L C H` channels
+fn to_polar([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    let chroma = (a * a + b * b).sqrt();
+    let hue = (b.atan2(a) * 180.0 / PI).rem_euclid(360.0);
+    [lightness, chroma, hue]
+}
+
+/// Convert polar `L C H` channels to rectangular `L a b` channels
+fn from_polar([lightness, chroma, hue]: [f64; 3]) -> [f64; 3] {
+    let hue = hue * PI / 180.0;
+    [lightness, chroma * hue.cos(), chroma * hue.sin()]
+}
+
+const LINEAR_SRGB_TO_XYZ: [[f64; 3]; 3] = [
+    [
+        506_752.0 / 1_228_815.0,
+        87_881.0 / 245_763.0,
+        12_673.0 / 70_218.0,
+    ],
+    [
+        87_098.0 / 409_605.0,
+        175_762.0 / 245_763.0,
+        12_673.0 / 175_545.0,
+    ],
+    [
+        7_918.0 / 409_605.0,
+        87_881.0 / 737_289.0,
+        1_001_167.0 / 1_053_648.0,
+    ],
+];
+
+const XYZ_TO_LINEAR_SRGB: [[f64; 3]; 3] = [
+    [12_831.0 / 3_959.0, -329.0 / 214.0, -1_974.0 / 3_959.0],
+    [
+        -851_781.0 / 878_810.0,
+        1_648_619.0 / 878_810.0,
+        36_519.0 / 878_810.0,
+    ],
+    [705.0 / 12_673.0, -2_585.0 / 12_673.0, 705.0 / 667.0],
+];
+
+const LINEAR_P3_TO_XYZ: [[f64; 3]; 3] = [
+    [
+        608_311.0 / 1_250_200.0,
+        189_793.0 / 714_400.0,
+        198_249.0 / 1_000_160.0,
+    ],
+    [
+        35_783.0 / 156_275.0,
+        247_089.0 / 357_200.0,
+        198_249.0 / 2_500_400.0,
+    ],
+    [0.0, 32_229.0 / 714_400.0, 5_220_557.0 / 5_000_800.0],
+];
+
+const XYZ_TO_LINEAR_P3: [[f64; 3]; 3] = [
+    [
+        446_124.0 / 178_915.0,
+        -333_277.0 / 357_830.0,
+        -72_051.0 / 178_915.0,
+    ],
+    [-14_852.0 / 17_905.0, 63_121.0 / 35_810.0, 423.0 / 17_905.0],
+    [
+        11_844.0 / 330_415.0,
+        -50_337.0 / 660_830.0,
+        316_169.0 / 330_415.0,
+    ],
+];
+
+const LINEAR_A98_TO_XYZ: [[f64; 3]; 3] = [
+    [
+        573_536.0 / 994_567.0,
+        263_643.0 / 1_420_810.0,
+        187_206.0 / 994_567.0,
+    ],
+    [
+        591_459.0 / 1_989_134.0,
+        6_239_551.0 / 9_945_670.0,
+        374_412.0 / 4_972_835.0,
+    ],
+    [
+        53_769.0 / 1_989_134.0,
+        351_524.0 / 4_972_835.0,
+        4_929_758.0 / 4_972_835.0,
+    ],
+];
+
+const XYZ_TO_LINEAR_A98: [[f64; 3]; 3] = [
+    [
+        1_829_569.0 / 896_150.0,
+        -506_331.0 / 896_150.0,
+        -308_931.0 / 896_150.0,
+    ],
+    [
+        -851_781.0 / 878_810.0,
+        1_648_619.0 / 878_810.0,
+        36_519.0 / 878_810.0,
+    ],
+    [
+        16_779.0 / 1_248_040.0,
+        -147_721.0 / 1_248_040.0,
+        1_266_979.0 / 1_248_040.0,
+    ],
+];
+
+const LINEAR_PROPHOTO_TO_XYZ_D50: [[f64; 3]; 3] = [
+    [
+        0.797_766_644_900_642_3,
+        0.135_181_297_400_533_08,
+        0.031_347_734_128_392_2,
+    ],
+    [
+        0.288_074_828_819_401_3,
+        0.711_835_234_241_873,
+        0.000_089_936_938_725_64,
+    ],
+    [0.0, 0.0, 0.825_104_602_510_460_2],
+];
+
+const XYZ_D50_TO_LINEAR_PROPHOTO: [[f64; 3]; 3] = [
+    [
+        1.345_786_881_647_158_3,
+        -0.255_572_087_379_794_64,
+        -0.051_101_864_975_545_26,
+    ],
+    [
+        -0.544_630_705_124_901_9,
+        1.508_247_742_845_146_8,
+        0.020_527_447_436_421_39,
+    ],
+    [0.0, 0.0, 1.211_967_545_638_945_2],
+];
+
+const LINEAR_REC2020_TO_XYZ: [[f64; 3]; 3] = [
+    [
+        63_426_534.0 / 99_577_255.0,
+        20_160_776.0 / 139_408_157.0,
+        47_086_771.0 / 278_816_314.0,
+    ],
+    [
+        26_158_966.0 / 99_577_255.0,
+        472_592_308.0 / 697_040_785.0,
+        8_267_143.0 / 139_408_157.0,
+    ],
+    [
+        0.0,
+        19_567_812.0 / 697_040_785.0,
+        295_819_943.0 / 278_816_314.0,
+    ],
+];
+
+const XYZ_TO_LINEAR_REC2020: [[f64; 3]; 3] = [
+    [
+        30_757_411.0 / 17_917_100.0,
+        -6_372_589.0 / 17_917_100.0,
+        -4_539_589.0 / 17_917_100.0,
+    ],
+    [
+        -19_765_991.0 / 29_648_200.0,
+        47_925_759.0 / 29_648_200.0,
+        467_509.0 / 29_648_200.0,
+    ],
+    [
+        792_561.0 / 44_930_125.0,
+        -1_921_689.0 / 44_930_125.0,
+        42_328_811.0 / 44_930_125.0,
+    ],
+];
+
+/// Bradford chromatic adaptation from a D65 to a D50 white point
+const D65_TO_D50: [[f64; 3]; 3] = [
+    [
+        1.047_929_792_544_997,
+        0.022_946_870_601_609_652,
+        -0.050_192_266_289_205_24,
+    ],
+    [
+        0.029_627_808_770_055_99,
+        0.990_434_426_753_879_9,
+        -0.017_073_799_063_418_826,
+    ],
+    [
+        -0.009_243_040_646_204_504,
+        0.015_055_191_490_298_152,
+        0.751_874_281_428_137_1,
+    ],
+];
+
+const D50_TO_D65: [[f64; 3]; 3] = [
+    [
+        0.955_473_421_488_075,
+        -0.023_098_454_948_764_71,
+        0.063_259_243_200_570_72,
+    ],
+    [
+        -0.028_369_709_333_863_7,
+        1.009_995_398_081_304_1,
+        0.021_041_441_191_917_323,
+    ],
+    [
+        0.012_314_014_864_481_998,
+        -0.020_507_649_298_898_964,
+        1.330_365_926_242_124,
+    ],
+];
+
+/// The matrices between XYZ and the LMS cone responses used by Oklab are
+/// derived from the linear sRGB to LMS matrix in the original definition of
+/// Oklab, with its rows normalized so that white has equal cone responses.
+/// This keeps them consistent with the sRGB matrices above, so that grays
+/// have no chroma in Oklab.
+const XYZ_TO_LMS: [[f64; 3]; 3] = [
+    [
+        0.819_019_997_568_793_5,
+        0.361_915_223_654_794_6,
+        -0.128_920_249_917_283_18,
+    ],
+    [
+        0.032_984_353_121_459_8,
+        0.929_284_331_948_989_5,
+        0.036_157_700_656_173_86,
+    ],
+    [
+        0.048_189_222_451_634_23,
+        0.264_195_441_030_404_43,
+        0.633_776_260_639_869_5,
+    ],
+];
+
+const LMS_TO_OKLAB: [[f64; 3]; 3] = [
+    [
+        0.210_454_268_309_314,
+        0.793_617_774_702_305_4,
+        -0.004_072_043_011_619_3,
+    ],
+    [
+        1.977_998_532_431_168_4,
+        -2.428_592_242_048_579_9,
+        0.450_593_709_617_411,
+    ],
+    [
+        0.025_904_042_465_547_8,
+        0.782_771_712_457_529_6,
+        -0.808_675_754_923_077_4,
+    ],
+];
+
+const OKLAB_TO_LMS: [[f64; 3]; 3] = [
+    [1.0, 0.396_337_777_376_174_9, 0.215_803_757_309_913_6],
+    [1.0, -0.105_561_345_815_658_6, -0.063_854_172_825_813_3],
+    [1.0, -0.089_484_177_529_811_9, -1.291_485_548_019_409_2],
+];
+
+const LMS_TO_XYZ: [[f64; 3]; 3] = [
+    [
+        1.226_879_873_374_156_4,
+        -0.557_814_996_499_700_6,
+        0.281_391_050_177_215_94,
+    ],
+    [
+        -0.040_575_762_624_313_14,
+        1.112_286_829_285_830_1,
+        -0.071_711_066_661_516_93,
+    ],
+    [
+        -0.076_371_518_852_594_5,
+        -0.421_253_453_376_040_25,
+        1.586_341_716_168_825_4,
+    ],
+];
@@ -1,4 +1,9 @@
-use std::fmt::{self, Display, Write};
+use std::{
+    fmt::{self, Display, Write},
+    sync::Arc,
+};
+
+use crate::interner::InternedString;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Op {
@@ -48,15 +53,12 @@ impl Op {
     /// If precedence is equal, the leftmost operation is evaluated first
     pub fn precedence(self) -> usize {
         match self {
-            Self::And | Self::Or | Self::Not => 0,
-            Self::Equal
-            | Self::NotEqual
-            | Self::GreaterThan
-            | Self::GreaterThanEqual
-            | Self::LessThan
-            | Self::LessThanEqual => 1,
-            Self::Plus | Self::Minus => 2,
-            Self::Mul | Self::Div | Self::Rem => 3,
+            Self::Or | Self::Not => 0,
+            Self::And => 1,
+            Self::Equal | Self::NotEqual => 2,
+            Self::GreaterThan | Self::GreaterThanEqual | Self::LessThan | Self::LessThanEqual => 3,
+            Self::Plus | Self::Minus => 4,
+            Self::Mul | Self::Div | Self::Rem => 5,
         }
     }
 }
@@ -77,16 +79,17 @@ impl Display for QuoteKind {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) enum Brackets {
     None,
     Bracketed,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) enum ListSeparator {
     Space,
     Comma,
+    Slash,
 }
 
 impl ListSeparator {
@@ -94,6 +97,7 @@ impl ListSeparator {
         match self {
             Self::Space => " ",
             Self::Comma => ", ",
+            Self::Slash => " / ",
         }
     }
 
@@ -101,28 +105,48 @@ impl ListSeparator {
         match self {
             Self::Space => "space",
             Self::Comma => "comma",
+            Self::Slash => "slash",
         }
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub(crate) struct Identifier(String);
+/// The name of a variable, function, mixin or argument
+///
+/// Identifiers are interned, so they are cheap to copy, compare and hash.
+/// Underscores and hyphens are considered the same, so they are normalized to
+/// hyphens when the identifier is created.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub(crate) struct Identifier(InternedString);
+
+impl Identifier {
+    fn intern(s: &str) -> Identifier {
+        if s.contains('_') {
+            Identifier(InternedString::get_or_intern(&s.replace('_', "-")))
+        } else {
+            Identifier(InternedString::get_or_intern(s))
+        }
+    }
+
+    pub fn resolve(self) -> Arc<str> {
+        self.0.resolve()
+    }
+}
 
 impl From<String> for Identifier {
     fn from(s: String) -> Identifier {
-        Identifier(s.replace('_', "-"))
+        Identifier::intern(&s)
     }
 }
 
 impl From<&String> for Identifier {
     fn from(s: &String) -> Identifier {
-        Identifier(s.replace('_', "-"))
+        Identifier::intern(s)
     }
 }
 
 impl From<&str> for Identifier {
     fn from(s: &str) -> Identifier {
-        Identifier(s.replace('_', "-"))
+        Identifier::intern(s)
     }
 }
 
@@ -134,14 +158,7 @@ impl Display for Identifier {
 
 impl Default for Identifier {
     fn default() -> Self {
-        Self(String::new())
-    }
-}
-
-impl Identifier {
-    #[allow(clippy::missing_const_for_fn)]
-    pub fn into_inner(self) -> String {
-        self.0
+        Identifier::intern("")
     }
 }
 
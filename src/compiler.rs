@@ -0,0 +1,485 @@
+//! A reusable compiler that caches imported files between compilations
+//!
+//! Every call to `grass::from_path` or `grass::from_string` starts from
+//! scratch, reading and parsing every imported partial again. `Compiler`
+//! instead holds on to the contents and syntax tree of every file it has
+//! loaded, keyed by canonical path, so that partials shared between many entry
+//! points (or reloaded in watch mode) are only read from disk and parsed when
+//! they actually change.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use codemap::{CodeMap, File};
+use indexmap::IndexMap;
+
+use crate::{
+    ast::AstStmt,
+    builtin::{Modules, Random},
+    error::SassResult,
+    interner::{Interner, Session},
+    lexer::Lexer,
+    logger::Logger,
+    output::Css,
+    parse::{
+        common::{ContextFlags, NeverEmptyVec},
+        Parser,
+    },
+    raw_to_parse_error,
+    scope::{Scope, Scopes},
+    selector::{Extender, Selector},
+    Result,
+};
+
+/// A file that has already been read from disk
+#[derive(Debug)]
+struct CachedFile {
+    /// The modification time of the file when it was last read
+    modified: Option<SystemTime>,
+    /// A hash of the contents of the file, used to avoid adding a file to the
+    /// `CodeMap` again if it was touched but its contents did not change
+    hash: u64,
+    file: Arc<File>,
+    /// The syntax tree of the file, once it has been parsed
+    ast: Option<ParsedFile>,
+}
+
+/// The syntax tree of a cached file
+#[derive(Debug)]
+struct ParsedFile(Rc<Vec<AstStmt>>);
+
+// SAFETY: the tree is only shared with the compilations of the `Compiler`
+// that owns the cache, which borrow it mutably and drop every clone of the
+// `Rc`s in the tree before they return. The identifiers in the tree belong to
+// the compiler's `Interner`, which moves along with it.
+unsafe impl Send for ParsedFile {}
+
+/// Files read from disk, keyed by their canonical path
+///
+/// A cache entry is invalidated when the modification time of the file on
+/// disk changes *and* the contents of the file no longer hash to the same
+/// value.
+///
+/// Every file that passes through the cache is also recorded as having been
+/// loaded by the current compilation, whether or not it was already cached.
+#[derive(Debug, Default)]
+pub(crate) struct FileCache {
+    files: HashMap<PathBuf, CachedFile>,
+    /// The files loaded by the current compilation, in the order they were
+    /// first loaded
+    ///
+    /// This maps canonical paths to the path as it was resolved by `@import`
+    loaded: IndexMap<PathBuf, PathBuf>,
+    /// The canonical paths of the files loaded by the current compilation,
+    /// keyed by the path as it was resolved by `@import`
+    ///
+    /// A file is only checked for changes the first time a compilation loads
+    /// it.
+    resolved: HashMap<PathBuf, PathBuf>,
+    /// The number of times a file has been parsed and its syntax tree cached
+    parse_count: usize,
+    /// The total length of the sources in the `CodeMap` that are no longer
+    /// referenced by the cache, such as old versions of changed files
+    ///
+    /// A `CodeMap` can't release individual files, so once this outgrows the
+    /// cached sources the map is rebuilt from the cache alone.
+    stale_len: u64,
+}
+
+impl FileCache {
+    pub fn new() -> Self {
+        Self {
+            files: HashMap::new(),
+            loaded: IndexMap::new(),
+            resolved: HashMap::new(),
+            parse_count: 0,
+            stale_len: 0,
+        }
+    }
+
+    /// Read the file at `path`, reusing the result of a previous load if the
+    /// file has not changed since
+    pub fn load(&mut self, path: &Path, map: &mut CodeMap) -> SassResult<Arc<File>> {
+        if let Some(cached) = self.resolved.get(path).and_then(|key| self.files.get(key)) {
+            return Ok(Arc::clone(&cached.file));
+        }
+
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.resolved.insert(path.to_path_buf(), key.clone());
+        self.loaded
+            .entry(key.clone())
+            .or_insert_with(|| path.to_path_buf());
+        let modified = fs::metadata(&key).and_then(|m| m.modified()).ok();
+
+        if let Some(cached) = self.files.get(&key) {
+            if modified.is_some() && cached.modified == modified {
+                return Ok(Arc::clone(&cached.file));
+            }
+        }
+
+        let contents = String::from_utf8(fs::read(path)?)?;
+        let hash = hash_contents(&contents);
+
+        if let Some(cached) = self.files.get_mut(&key) {
+            if cached.hash == hash {
+                cached.modified = modified;
+                return Ok(Arc::clone(&cached.file));
+            }
+        }
+
+        let file = map.add_file(path.to_string_lossy().into(), contents);
+
+        if let Some(old) = self.files.insert(
+            key,
+            CachedFile {
+                modified,
+                hash,
+                file: Arc::clone(&file),
+                ast: None,
+            },
+        ) {
+            self.mark_stale(&old.file);
+        }
+
+        Ok(file)
+    }
+
+    /// The syntax tree of the file at `path`, which must already have been
+    /// loaded by the current compilation, if it has been parsed since it last
+    /// changed
+    pub fn parsed(&self, path: &Path) -> Option<Rc<Vec<AstStmt>>> {
+        let cached = self.files.get(self.resolved.get(path)?)?;
+        cached.ast.as_ref().map(|ast| Rc::clone(&ast.0))
+    }
+
+    /// Cache the syntax tree of the file at `path`, which must already have
+    /// been loaded by the current compilation
+    pub fn insert_parsed(&mut self, path: &Path, ast: Rc<Vec<AstStmt>>) {
+        let files = &mut self.files;
+        if let Some(cached) = self.resolved.get(path).and_then(|key| files.get_mut(key)) {
+            cached.ast = Some(ParsedFile(ast));
+            self.parse_count += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn parse_count(&self) -> usize {
+        self.parse_count
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+        self.resolved.clear();
+        self.stale_len = 0;
+    }
+
+    /// Record that `file` is still in the `CodeMap`, but is no longer needed
+    pub fn mark_stale(&mut self, file: &File) {
+        self.stale_len += file.span.len();
+    }
+
+    /// The total length of the sources of every cached file
+    fn cached_len(&self) -> u64 {
+        self.files
+            .values()
+            .map(|cached| cached.file.span.len())
+            .sum()
+    }
+
+    /// The total length of every source in the `CodeMap`, stale or not
+    pub fn source_len(&self) -> u64 {
+        self.cached_len() + self.stale_len
+    }
+
+    /// Replace `map` with one containing only the cached files, if more of it
+    /// is taken up by stale files than by cached ones
+    pub fn compact(&mut self, map: &mut CodeMap) {
+        if self.stale_len == 0 || self.stale_len < self.cached_len() {
+            return;
+        }
+
+        // the spans in syntax trees point into the old map, so the files
+        // will have to be parsed again. this happens at most once for every
+        // time the stale sources, which were also parsed, outgrow the cached
+        // ones
+        *map = CodeMap::new();
+        for cached in self.files.values_mut() {
+            cached.file = map.add_file(
+                cached.file.name().to_owned(),
+                cached.file.source().to_owned(),
+            );
+            cached.ast = None;
+        }
+        self.stale_len = 0;
+    }
+
+    /// Take the list of files loaded since this was last called
+    ///
+    /// Files loaded after this are checked for changes again.
+    pub fn take_loaded(&mut self) -> Vec<PathBuf> {
+        self.resolved.clear();
+        self.loaded.drain(..).map(|(_, path)| path).collect()
+    }
+}
+
+fn hash_contents(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The result of a successful compilation
+#[derive(Debug, Clone)]
+pub struct CompileResult {
+    css: String,
+    loaded_files: Vec<PathBuf>,
+}
+
+impl CompileResult {
+    /// The compiled CSS
+    #[must_use]
+    pub fn css(&self) -> &str {
+        &self.css
+    }
+
+    /// Every file read during compilation, including the entry point if it
+    /// was compiled from a path
+    ///
+    /// Files are listed in the order they were first loaded, and each file is
+    /// listed only once. This is useful for build systems that need to know
+    /// when a stylesheet should be recompiled.
+    #[must_use]
+    pub fn loaded_files(&self) -> &[PathBuf] {
+        &self.loaded_files
+    }
+
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn into_css(self) -> String {
+        self.css
+    }
+}
+
+/// A long-lived Sass compiler
+///
+/// Files loaded by `@import` (and entry points compiled with
+/// `Compiler::compile_path`) are cached between compilations, so the same
+/// partials are not read from disk again for every entry point that uses them.
+/// Imported files are also only parsed once, until they change.
+///
+/// ```
+/// fn main() -> Result<(), Box<grass::Error>> {
+///     let mut compiler = grass::Compiler::new();
+///     let sass = compiler.compile_string("a { b { color: &; } }".to_string())?;
+///     assert_eq!(sass, "a b {\n  color: a b;\n}\n");
+///     Ok(())
+/// }
+/// ```
+///
+/// `Compiler` is `Send`, so a separate compiler may be used on each thread of
+/// a thread pool.
+///
+/// The sources of changed files and of compiled strings are released once
+/// they take up more memory than the files still cached.
+#[derive(Debug, Default)]
+pub struct Compiler {
+    map: CodeMap,
+    cache: FileCache,
+    /// The seed for `random()` and `unique-id()`, if they should be
+    /// deterministic
+    seed: Option<u64>,
+    /// Whether to silence `@debug`, `@warn` and deprecation warnings
+    quiet: bool,
+    /// The identifiers of every compilation, which are referred to by the
+    /// syntax trees in `cache`
+    interner: Interner,
+}
+
+impl Compiler {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            map: CodeMap::new(),
+            cache: FileCache::new(),
+            seed: None,
+            quiet: false,
+            interner: Interner::new(),
+        }
+    }
+
+    /// Seed the random number generator used by `random()` and `unique-id()`
+    ///
+    /// The generator is reseeded at the start of every compilation, so the
+    /// same stylesheet always compiles to the same CSS.
+    ///
+    /// ```
+    /// fn main() -> Result<(), Box<grass::Error>> {
+    ///     let input = "a { color: random(); }";
+    ///     let first = grass::Compiler::new()
+    ///         .with_random_seed(42)
+    ///         .compile_string(input.to_string())?;
+    ///     let second = grass::Compiler::new()
+    ///         .with_random_seed(42)
+    ///         .compile_string(input.to_string())?;
+    ///     assert_eq!(first, second);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "random")]
+    #[must_use]
+    pub fn with_random_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Don't print `@debug`, `@warn` or deprecation warnings to stderr
+    ///
+    /// ```
+    /// fn main() -> Result<(), Box<grass::Error>> {
+    ///     let css = grass::Compiler::new()
+    ///         .quiet(true)
+    ///         .compile_string("@warn \"ignored\";".to_string())?;
+    ///     assert_eq!(css, "");
+    ///     Ok(())
+    /// }
+    /// ```
+    #[must_use]
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Compile CSS from a path
+    ///
+    /// (grass does not currently allow files or paths that are not valid UTF-8)
+    pub fn compile_path(&mut self, p: &str) -> Result<String> {
+        self.compile_path_to_result(p).map(CompileResult::into_css)
+    }
+
+    /// Compile CSS from a string
+    ///
+    /// Imports are resolved relative to the current working directory
+    pub fn compile_string(&mut self, input: String) -> Result<String> {
+        self.compile_string_to_result(input)
+            .map(CompileResult::into_css)
+    }
+
+    /// Compile CSS from a path, additionally returning the files that were
+    /// loaded in the process
+    ///
+    /// ```
+    /// fn main() -> Result<(), Box<grass::Error>> {
+    ///     let result = grass::Compiler::new().compile_path_to_result("input.scss")?;
+    ///     assert_eq!(result.loaded_files(), &[std::path::PathBuf::from("input.scss")]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn compile_path_to_result(&mut self, p: &str) -> Result<CompileResult> {
+        self.cache.take_loaded();
+        self.cache.compact(&mut self.map);
+        let file = self.cache.load(p.as_ref(), &mut self.map)?;
+        self.compile(&file, p.as_ref())
+    }
+
+    /// Compile CSS from a string, additionally returning the files that were
+    /// loaded in the process
+    pub fn compile_string_to_result(&mut self, input: String) -> Result<CompileResult> {
+        self.cache.take_loaded();
+        self.cache.compact(&mut self.map);
+        let file = self.map.add_file("stdin".into(), input);
+        let result = self.compile(&file, Path::new(""));
+        self.cache.mark_stale(&file);
+        result
+    }
+
+    /// The number of files currently cached
+    #[must_use]
+    pub fn cached_files(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// The number of times a file loaded by `@import` or `meta.load-css()` has
+    /// been parsed
+    ///
+    /// The syntax tree of each file is cached along with its source, so this
+    /// only grows when a file is imported for the first time or has changed
+    /// since it was last parsed.
+    #[must_use]
+    pub fn parse_count(&self) -> usize {
+        self.cache.parse_count()
+    }
+
+    /// The total length of every source held in memory, including those that
+    /// are no longer needed but haven't yet been released
+    #[must_use]
+    pub fn source_len(&self) -> u64 {
+        self.cache.source_len()
+    }
+
+    /// Forget every cached file
+    ///
+    /// This also releases the memory held by the sources and syntax trees of
+    /// all previously compiled files, and by the names they use.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+        self.map = CodeMap::new();
+        self.interner = Interner::new();
+    }
+
+    fn compile(&mut self, file: &Arc<File>, path: &Path) -> Result<CompileResult> {
+        let _session = Session::start(&mut self.interner);
+        let empty_span = file.span.subspan(0, 0);
+        let logger = Logger::new(self.quiet);
+
+        let mut parser = Parser {
+            toks: &mut Lexer::new(file),
+            map: &mut self.map,
+            path,
+            scopes: &mut Scopes::new(),
+            global_scope: &mut Scope::new(),
+            super_selectors: &mut NeverEmptyVec::new(Selector::new(empty_span)),
+            span_before: empty_span,
+            content: &mut Vec::new(),
+            flags: ContextFlags::empty(),
+            at_root: true,
+            at_root_has_selector: false,
+            extender: &mut Extender::new(empty_span),
+            cache: &mut self.cache,
+            modules: &mut Modules::new(),
+            random: &mut Random::new(self.seed),
+            logger: &logger,
+        };
+
+        let stmts = parser.eval_stylesheet();
+        parser.extender.apply_pending_extensions();
+        logger.summarize();
+        let map = &self.map;
+        let stmts = stmts.map_err(|e| raw_to_parse_error(map, *e))?;
+
+        let css = Css::from_stmts(stmts, false)
+            .map_err(|e| raw_to_parse_error(map, *e))?
+            .pretty_print(map)
+            .map_err(|e| raw_to_parse_error(map, *e))?;
+
+        Ok(CompileResult {
+            css,
+            loaded_files: self.cache.take_loaded(),
+        })
+    }
+}
+
+#[allow(dead_code)]
+fn assert_send() {
+    fn assert_send<T: Send>() {}
+    assert_send::<Compiler>();
+}
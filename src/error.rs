@@ -2,8 +2,8 @@ use std::{
     error::Error,
     fmt::{self, Display},
     io,
-    rc::Rc,
     string::FromUtf8Error,
+    sync::Arc,
 };
 
 use codemap::{Span, SpanLoc};
@@ -31,6 +31,9 @@ pub type SassResult<T> = Result<T, Box<SassError>>;
 /// directly to the error by simply clicking the file name.
 ///
 /// Note that this is a deviation from the Sass specification.
+///
+/// `SassError` is both `Send` and `Sync`, so compilation results may be
+/// freely passed between threads.
 #[derive(Debug, Clone)]
 pub struct SassError {
     kind: SassErrorKind,
@@ -61,9 +64,9 @@ enum SassErrorKind {
         message: String,
         loc: SpanLoc,
     },
-    // we put IoErrors in an `Rc` to allow it to be
-    // cloneable
-    IoError(Rc<io::Error>),
+    // we put IoErrors in an `Arc` to allow it to be
+    // cloneable while keeping `SassError` `Send` and `Sync`
+    IoError(Arc<io::Error>),
     FromUtf8Error(String),
 }
 
@@ -106,7 +109,7 @@ impl From<io::Error> for Box<SassError> {
     #[inline]
     fn from(error: io::Error) -> Box<SassError> {
         Box::new(SassError {
-            kind: SassErrorKind::IoError(Rc::new(error)),
+            kind: SassErrorKind::IoError(Arc::new(error)),
         })
     }
 }
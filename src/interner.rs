@@ -0,0 +1,131 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{self, Display},
+    marker::PhantomData,
+    mem,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+thread_local! {
+    static STRINGS: RefCell<Interner> = RefCell::new(Interner::new());
+}
+
+/// Distinguishes the symbol tables of different threads and compilers, so
+/// that an `InternedString` is never resolved against the wrong table
+static GENERATION: AtomicU32 = AtomicU32::new(0);
+
+/// A table mapping every distinct string to a unique integer
+///
+/// Each `Compiler` owns a table, which lives as long as the compiler does so
+/// that the syntax trees of cached files remain valid between compilations.
+/// It is only used while one of its compilations is running, see `Session`.
+#[derive(Debug)]
+pub(crate) struct Interner {
+    generation: u32,
+    ids: HashMap<Arc<str>, u32>,
+    strings: Vec<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self {
+            generation: GENERATION.fetch_add(1, Ordering::Relaxed),
+            ids: HashMap::new(),
+            strings: Vec::new(),
+        }
+    }
+
+    fn get_or_intern(&mut self, s: &str) -> u32 {
+        if let Some(&id) = self.ids.get(s) {
+            return id;
+        }
+        let s: Arc<str> = Arc::from(s);
+        let id = self.strings.len() as u32;
+        self.strings.push(Arc::clone(&s));
+        self.ids.insert(s, id);
+        id
+    }
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Makes `Interner` the thread's symbol table for the duration of a
+/// compilation
+///
+/// The thread's previous table is restored once the session ends, so
+/// compilations may be nested.
+pub(crate) struct Session<'a> {
+    interner: &'a mut Interner,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<'a> Session<'a> {
+    pub fn start(interner: &'a mut Interner) -> Self {
+        STRINGS.with(|strings| mem::swap(&mut *strings.borrow_mut(), interner));
+        Self {
+            interner,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for Session<'_> {
+    fn drop(&mut self) {
+        STRINGS.with(|strings| mem::swap(&mut *strings.borrow_mut(), self.interner));
+    }
+}
+
+/// A string stored in the thread's symbol table
+///
+/// Comparing and hashing an `InternedString` only touches integers. It can
+/// only be resolved while the table it was created in is the thread's table.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub(crate) struct InternedString {
+    id: u32,
+    generation: u32,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl InternedString {
+    pub fn get_or_intern(s: &str) -> Self {
+        STRINGS.with(|interner| {
+            let mut interner = interner.borrow_mut();
+            Self {
+                id: interner.get_or_intern(s),
+                generation: interner.generation,
+                _not_send: PhantomData,
+            }
+        })
+    }
+
+    pub fn resolve(self) -> Arc<str> {
+        STRINGS.with(|interner| {
+            let interner = interner.borrow();
+            assert_eq!(
+                interner.generation, self.generation,
+                "interned string used outside of the compiler that created it"
+            );
+            Arc::clone(&interner.strings[self.id as usize])
+        })
+    }
+}
+
+impl fmt::Debug for InternedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.resolve())
+    }
+}
+
+impl Display for InternedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.resolve())
+    }
+}
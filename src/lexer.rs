@@ -1,48 +1,348 @@
-use std::{iter::Peekable, str::Chars, sync::Arc};
+use std::sync::Arc;
 
-use codemap::File;
+use codemap::{File, Span, Spanned};
 
-use crate::Token;
+use crate::{Token, TokenKind};
 
-const FORM_FEED: char = '\x0C';
+/// Splits a stylesheet into identifiers, numbers, strings, whitespace and
+/// symbols as the parser asks for them
+///
+/// The parser drives the lexer: `peek` lexes the token at the current
+/// position and `next` moves past it. As what a piece of text means depends
+/// on where it appears, the parser may also move back to an earlier position
+/// to read the text there differently, or read the raw text at the current
+/// position, such as the contents of a comment. Nothing is lexed ahead of
+/// the parser and no tokens are stored, so lexing takes no memory beyond the
+/// source itself.
+///
+/// The source is read byte by byte, and only non-ASCII characters, which can
+/// only appear in identifiers, strings and comments, need to be decoded.
+#[derive(Debug)]
+pub(crate) struct Lexer {
+    source: Source,
+    /// The byte offset of the next token
+    cursor: usize,
+    /// The token at `cursor`, if it has already been lexed
+    peeked: Option<Token>,
+}
 
-#[derive(Debug, Clone)]
-pub(crate) struct Lexer<'a> {
-    buf: Peekable<Chars<'a>>,
-    pos: usize,
-    file: &'a Arc<File>,
+#[derive(Debug)]
+enum Source {
+    File(Arc<File>),
+    /// Text produced at runtime, such as a selector once its interpolation
+    /// has been evaluated, all of which is reported at the given span
+    Text(String, Span),
 }
 
-impl<'a> Iterator for Lexer<'a> {
-    type Item = Token;
-    fn next(&mut self) -> Option<Self::Item> {
-        let kind = match self.buf.next()? {
-            FORM_FEED => '\n',
-            '\r' => {
-                if self.buf.peek() == Some(&'\n') {
-                    self.pos += 1;
-                    self.buf.next();
-                }
-                '\n'
+impl Lexer {
+    pub fn new(file: &Arc<File>) -> Lexer {
+        Lexer {
+            source: Source::File(Arc::clone(file)),
+            cursor: 0,
+            peeked: None,
+        }
+    }
+
+    /// Lex text produced at runtime, reporting everything within it at `span`
+    pub fn from_string(text: String, span: Span) -> Lexer {
+        Lexer {
+            source: Source::Text(text, span),
+            cursor: 0,
+            peeked: None,
+        }
+    }
+
+    fn source(&self) -> &str {
+        match &self.source {
+            Source::File(file) => file.source(),
+            Source::Text(text, ..) => text,
+        }
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        match &self.source {
+            Source::File(file) => file.span.subspan(start as u64, end as u64),
+            Source::Text(_, span) => *span,
+        }
+    }
+
+    fn byte_at(&self, idx: usize) -> Option<u8> {
+        self.source().as_bytes().get(idx).copied()
+    }
+
+    /// The byte offset of the next token, which may be passed to
+    /// `set_cursor` to read the text from there again
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+    }
+
+    /// The text of a token
+    pub fn text(&self, tok: Token) -> &str {
+        &self.source()[tok.start..tok.end]
+    }
+
+    /// The text from `start` up to the cursor
+    pub fn text_from(&self, start: usize) -> &str {
+        &self.source()[start..self.cursor]
+    }
+
+    /// The span from `start` up to the cursor
+    pub fn span_from(&self, start: usize) -> Span {
+        self.span(start, self.cursor)
+    }
+
+    pub fn peek(&mut self) -> Option<Token> {
+        match self.peeked {
+            Some(tok) if tok.start == self.cursor => Some(tok),
+            _ => {
+                let tok = self.lex(self.cursor);
+                self.peeked = tok;
+                tok
+            }
+        }
+    }
+
+    /// The token following the one returned by `peek`
+    pub fn peek_next(&mut self) -> Option<Token> {
+        let end = self.peek()?.end;
+        self.lex(end)
+    }
+
+    pub fn next(&mut self) -> Option<Token> {
+        let tok = self.peek()?;
+        self.cursor = tok.end;
+        Some(tok)
+    }
+
+    /// Whether the text at the cursor begins with `s`
+    pub fn starts_with(&self, s: &str) -> bool {
+        self.source().as_bytes()[self.cursor..].starts_with(s.as_bytes())
+    }
+
+    /// Consume `s` if the text at the cursor begins with it
+    pub fn scan_str(&mut self, s: &str) -> bool {
+        if self.starts_with(s) {
+            self.cursor += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consume the character at the cursor, whatever token it begins
+    pub fn next_char(&mut self) -> Option<Spanned<char>> {
+        let start = self.cursor;
+        let c = self.peek_char()?;
+        self.cursor += c.len_utf8();
+        Some(Spanned {
+            node: c,
+            span: self.span(start, self.cursor),
+        })
+    }
+
+    pub fn peek_char(&self) -> Option<char> {
+        let byte = self.byte_at(self.cursor)?;
+        if byte.is_ascii() {
+            Some(char::from(byte))
+        } else {
+            self.source()[self.cursor..].chars().next()
+        }
+    }
+
+    /// Consume the run of letters, digits, `_`, `-` and non-ASCII characters
+    /// at the cursor, such as the body of an identifier or the digits of a hex
+    /// color, which may begin with a digit or `-`
+    pub fn next_name(&mut self) -> Option<Token> {
+        let end = self.scan(self.cursor, is_name_byte);
+        self.take(TokenKind::Ident, end)
+    }
+
+    /// Like `next_name`, but stops before a `-` that is followed by a digit or
+    /// `.`, which begins a number being subtracted from this one
+    pub fn next_unit(&mut self) -> Option<Token> {
+        let bytes = self.source().as_bytes();
+        let mut end = self.cursor;
+        while let Some(&b) = bytes.get(end) {
+            if !is_name_byte(b)
+                || (b == b'-'
+                    && !matches!(bytes.get(end + 1), Some(&n) if n != b'.' && !n.is_ascii_digit()))
+            {
+                break;
+            }
+            end += 1;
+        }
+        self.take(TokenKind::Ident, end)
+    }
+
+    /// Consume the run of digits at the cursor, without any fractional part
+    pub fn next_digits(&mut self) -> Option<Token> {
+        let end = self.scan(self.cursor, |b| b.is_ascii_digit());
+        self.take(TokenKind::Number, end)
+    }
+
+    /// Consume the number at the cursor, which may begin with its decimal
+    /// point
+    pub fn next_number(&mut self) -> Option<Token> {
+        let end = self.number_end(self.cursor);
+        self.take(TokenKind::Number, end)
+    }
+
+    /// Consume raw text up to, but not including, the first byte for which
+    /// `stop` returns `true`
+    ///
+    /// `stop` must only stop at ASCII characters, so that the text is never
+    /// split in the middle of a character.
+    pub fn next_text(&mut self, stop: impl Fn(u8) -> bool) -> Option<Spanned<&str>> {
+        let start = self.cursor;
+        let end = self.scan(start, |b| !stop(b));
+        if end == start {
+            return None;
+        }
+        self.cursor = end;
+        Some(Spanned {
+            node: &self.source()[start..end],
+            span: self.span(start, end),
+        })
+    }
+
+    /// Consume everything up to and including the next newline, as in a
+    /// silent comment
+    pub fn skip_line(&mut self) {
+        self.cursor = self.scan(self.cursor, |b| !is_newline_byte(b));
+        if let Some(b) = self.byte_at(self.cursor) {
+            self.cursor += 1;
+            if b == b'\r' && self.byte_at(self.cursor) == Some(b'\n') {
+                self.cursor += 1;
+            }
+        }
+    }
+
+    fn take(&mut self, kind: TokenKind, end: usize) -> Option<Token> {
+        let start = self.cursor;
+        if end == start {
+            return None;
+        }
+        self.cursor = end;
+        Some(Token {
+            kind,
+            pos: self.span(start, end),
+            start,
+            end,
+        })
+    }
+
+    /// The end of the run of bytes starting at `start` for which `f` returns
+    /// `true`
+    fn scan(&self, start: usize, f: impl Fn(u8) -> bool) -> usize {
+        let bytes = self.source().as_bytes();
+        match bytes[start..].iter().position(|&b| !f(b)) {
+            Some(len) => start + len,
+            None => bytes.len(),
+        }
+    }
+
+    fn lex(&self, start: usize) -> Option<Token> {
+        let bytes = self.source().as_bytes();
+        let byte = *bytes.get(start)?;
+        let (kind, end) = match byte {
+            b' ' | b'\t' | b'\n' | b'\r' | b'\x0C' => (
+                TokenKind::Whitespace,
+                self.scan(start, |b| {
+                    matches!(b, b' ' | b'\t' | b'\n' | b'\r' | b'\x0C')
+                }),
+            ),
+            b'0'..=b'9' => (TokenKind::Number, self.number_end(start)),
+            b'-' if matches!(bytes.get(start + 1), Some(&b) if b == b'-' || is_name_start_byte(b)) => {
+                (TokenKind::Ident, self.scan(start, is_name_byte))
             }
-            c => c,
+            b'"' | b'\'' => (TokenKind::String, self.string_end(start)),
+            b if is_name_start_byte(b) => (TokenKind::Ident, self.scan(start, is_name_byte)),
+            b => (TokenKind::Symbol(char::from(b)), start + 1),
         };
-        let len = kind.len_utf8();
-        let pos = self
-            .file
-            .span
-            .subspan(self.pos as u64, (self.pos + len) as u64);
-        self.pos += len;
-        Some(Token { kind, pos })
+        Some(Token {
+            kind,
+            pos: self.span(start, end),
+            start,
+            end,
+        })
     }
-}
 
-impl<'a> Lexer<'a> {
-    pub fn new(file: &'a Arc<File>) -> Lexer<'a> {
-        Lexer {
-            buf: file.source().chars().peekable(),
-            pos: 0,
-            file,
+    /// The end of the number starting at `start`
+    ///
+    /// A `.` is only part of the number if it's followed by a digit. An
+    /// exponent that has a `-` but no digits is included, so that the parser
+    /// can report it.
+    fn number_end(&self, start: usize) -> usize {
+        let bytes = self.source().as_bytes();
+        let is_digit = |idx: usize| matches!(bytes.get(idx), Some(b) if b.is_ascii_digit());
+        let mut end = self.scan(start, |b| b.is_ascii_digit());
+        if bytes.get(end) == Some(&b'.') && is_digit(end + 1) {
+            end = self.scan(end + 1, |b| b.is_ascii_digit());
+        }
+        if end > start && matches!(bytes.get(end), Some(b'e') | Some(b'E')) {
+            if is_digit(end + 1) {
+                end = self.scan(end + 1, |b| b.is_ascii_digit());
+            } else if bytes.get(end + 1) == Some(&b'-') {
+                end = self.scan(end + 2, |b| b.is_ascii_digit());
+            }
+        }
+        end
+    }
+
+    /// The end of the quoted string starting at `start`, skipping over any
+    /// escapes and interpolation within it
+    fn string_end(&self, start: usize) -> usize {
+        let bytes = self.source().as_bytes();
+        let quote = bytes[start];
+        let mut idx = start + 1;
+        while let Some(&b) = bytes.get(idx) {
+            match b {
+                b'\\' => idx += 2,
+                b'#' if bytes.get(idx + 1) == Some(&b'{') => idx = self.interpolation_end(idx + 2),
+                b if b == quote => return idx + 1,
+                b if is_newline_byte(b) => return idx,
+                _ => idx += 1,
+            }
         }
+        bytes.len()
     }
+
+    /// The end of the interpolation whose `#{` ends just before `start`
+    fn interpolation_end(&self, start: usize) -> usize {
+        let bytes = self.source().as_bytes();
+        let mut nesting = 0;
+        let mut idx = start;
+        while let Some(&b) = bytes.get(idx) {
+            match b {
+                b'{' => nesting += 1,
+                b'}' if nesting == 0 => return idx + 1,
+                b'}' => nesting -= 1,
+                b'"' | b'\'' => {
+                    idx = self.string_end(idx);
+                    continue;
+                }
+                _ => {}
+            }
+            idx += 1;
+        }
+        bytes.len()
+    }
+}
+
+/// Whether `b` may begin an identifier, or be part of a non-ASCII character,
+/// which always may
+pub(crate) fn is_name_start_byte(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || !b.is_ascii()
+}
+
+pub(crate) fn is_name_byte(b: u8) -> bool {
+    is_name_start_byte(b) || b.is_ascii_digit() || b == b'-'
+}
+
+pub(crate) fn is_newline_byte(b: u8) -> bool {
+    matches!(b, b'\n' | b'\r' | b'\x0C')
 }
@@ -78,8 +78,6 @@ grass input.scss
 )]
 #![cfg_attr(feature = "nightly", feature(track_caller))]
 #![cfg_attr(feature = "profiling", inline(never))]
-use std::{fs, path::Path};
-
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
@@ -87,28 +85,21 @@ pub(crate) use beef::lean::Cow;
 
 use codemap::CodeMap;
 
-use peekmore::PeekMore;
-
+pub use crate::compiler::{CompileResult, Compiler};
 pub use crate::error::{SassError as Error, SassResult as Result};
-pub(crate) use crate::token::Token;
-use crate::{
-    lexer::Lexer,
-    output::Css,
-    parse::{
-        common::{ContextFlags, NeverEmptyVec},
-        Parser,
-    },
-    scope::Scope,
-    selector::{Extender, Selector},
-};
+pub(crate) use crate::token::{Token, TokenKind};
 
 mod args;
+mod ast;
 mod atrule;
 mod builtin;
 mod color;
 mod common;
+mod compiler;
 mod error;
+mod interner;
 mod lexer;
+mod logger;
 mod output;
 mod parse;
 mod scope;
@@ -133,38 +124,13 @@ fn raw_to_parse_error(map: &CodeMap, err: Error) -> Box<Error> {
 /// }
 /// ```
 /// (grass does not currently allow files or paths that are not valid UTF-8)
+///
+/// To reuse imported files between many compilations, see `Compiler`
 #[cfg_attr(feature = "profiling", inline(never))]
 #[cfg_attr(not(feature = "profiling"), inline)]
 #[cfg(not(feature = "wasm"))]
 pub fn from_path(p: &str) -> Result<String> {
-    let mut map = CodeMap::new();
-    let file = map.add_file(p.into(), String::from_utf8(fs::read(p)?)?);
-    let empty_span = file.span.subspan(0, 0);
-
-    let stmts = Parser {
-        toks: &mut Lexer::new(&file)
-            .collect::<Vec<Token>>()
-            .into_iter()
-            .peekmore(),
-        map: &mut map,
-        path: p.as_ref(),
-        scopes: &mut NeverEmptyVec::new(Scope::new()),
-        global_scope: &mut Scope::new(),
-        super_selectors: &mut NeverEmptyVec::new(Selector::new(empty_span)),
-        span_before: empty_span,
-        content: &mut Vec::new(),
-        flags: ContextFlags::empty(),
-        at_root: true,
-        at_root_has_selector: false,
-        extender: &mut Extender::new(empty_span),
-    }
-    .parse()
-    .map_err(|e| raw_to_parse_error(&map, *e))?;
-
-    Css::from_stmts(stmts, false)
-        .map_err(|e| raw_to_parse_error(&map, *e))?
-        .pretty_print(&map)
-        .map_err(|e| raw_to_parse_error(&map, *e))
+    Compiler::new().compile_path(p)
 }
 
 /// Compile CSS from a string
@@ -180,64 +146,13 @@ pub fn from_path(p: &str) -> Result<String> {
 #[cfg_attr(not(feature = "profiling"), inline)]
 #[cfg(not(feature = "wasm"))]
 pub fn from_string(p: String) -> Result<String> {
-    let mut map = CodeMap::new();
-    let file = map.add_file("stdin".into(), p);
-    let empty_span = file.span.subspan(0, 0);
-    let stmts = Parser {
-        toks: &mut Lexer::new(&file)
-            .collect::<Vec<Token>>()
-            .into_iter()
-            .peekmore(),
-        map: &mut map,
-        path: Path::new(""),
-        scopes: &mut NeverEmptyVec::new(Scope::new()),
-        global_scope: &mut Scope::new(),
-        super_selectors: &mut NeverEmptyVec::new(Selector::new(empty_span)),
-        span_before: empty_span,
-        content: &mut Vec::new(),
-        flags: ContextFlags::empty(),
-        at_root: true,
-        at_root_has_selector: false,
-        extender: &mut Extender::new(empty_span),
-    }
-    .parse()
-    .map_err(|e| raw_to_parse_error(&map, *e))?;
-
-    Css::from_stmts(stmts, false)
-        .map_err(|e| raw_to_parse_error(&map, *e))?
-        .pretty_print(&map)
-        .map_err(|e| raw_to_parse_error(&map, *e))
+    Compiler::new().compile_string(p)
 }
 
 #[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn from_string(p: String) -> std::result::Result<String, JsValue> {
-    let mut map = CodeMap::new();
-    let file = map.add_file("stdin".into(), p);
-    let empty_span = file.span.subspan(0, 0);
-
-    let stmts = Parser {
-        toks: &mut Lexer::new(&file)
-            .collect::<Vec<Token>>()
-            .into_iter()
-            .peekmore(),
-        map: &mut map,
-        path: Path::new(""),
-        scopes: &mut NeverEmptyVec::new(Scope::new()),
-        global_scope: &mut Scope::new(),
-        super_selectors: &mut NeverEmptyVec::new(Selector::new(empty_span)),
-        span_before: empty_span,
-        content: &mut Vec::new(),
-        flags: ContextFlags::empty(),
-        at_root: true,
-        at_root_has_selector: false,
-        extender: &mut Extender::new(empty_span),
-    }
-    .parse()
-    .map_err(|e| raw_to_parse_error(&map, *e).to_string())?;
-
-    Ok(Css::from_stmts(stmts, false)
-        .map_err(|e| raw_to_parse_error(&map, *e).to_string())?
-        .pretty_print(&map)
-        .map_err(|e| raw_to_parse_error(&map, *e).to_string())?)
+    Ok(Compiler::new()
+        .compile_string(p)
+        .map_err(|e| e.to_string())?)
 }
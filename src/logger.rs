@@ -0,0 +1,101 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+};
+
+use codemap::{CodeMap, Span};
+
+use crate::error::SassResult;
+
+/// The number of deprecation warnings printed before the rest are counted
+/// rather than printed, as in dart-sass
+const MAX_DEPRECATION_WARNINGS: usize = 5;
+
+/// Prints `@debug`, `@warn` and deprecation warnings to stderr
+///
+/// A deprecation warning is printed at most once for each place in the
+/// source, no matter how many times the code there is evaluated, and only
+/// the first few are printed at all.
+#[derive(Debug, Default)]
+pub(crate) struct Logger {
+    quiet: bool,
+    /// The places that have already had a deprecation warning printed
+    deprecations: RefCell<HashSet<Span>>,
+    /// The number of deprecation warnings that were not printed because
+    /// `MAX_DEPRECATION_WARNINGS` had been reached
+    omitted: Cell<usize>,
+}
+
+impl Logger {
+    pub fn new(quiet: bool) -> Self {
+        Self {
+            quiet,
+            ..Self::default()
+        }
+    }
+
+    pub fn debug(&self, map: &CodeMap, message: &str, span: Span) {
+        if self.quiet {
+            return;
+        }
+        let loc = map.look_up_span(span);
+        eprintln!(
+            "{}:{} Debug: {}",
+            loc.file.name(),
+            loc.begin.line + 1,
+            message
+        );
+    }
+
+    pub fn warn(&self, map: &CodeMap, message: &str, span: Span) {
+        if self.quiet {
+            return;
+        }
+        let loc = map.look_up_span(span);
+        eprintln!(
+            "Warning: {}\n    {} {}:{}  root stylesheet",
+            message,
+            loc.file.name(),
+            loc.begin.line + 1,
+            loc.begin.column + 1
+        );
+    }
+
+    /// Warn about behavior that will change in a future version of Sass
+    ///
+    /// `message` is only built if the warning will be printed.
+    pub fn deprecation(
+        &self,
+        map: &CodeMap,
+        message: impl FnOnce() -> SassResult<String>,
+        span: Span,
+    ) -> SassResult<()> {
+        if self.quiet || !self.deprecations.borrow_mut().insert(span) {
+            return Ok(());
+        }
+        if self.deprecations.borrow().len() > MAX_DEPRECATION_WARNINGS {
+            self.omitted.set(self.omitted.get() + 1);
+            return Ok(());
+        }
+        let loc = map.look_up_span(span);
+        eprintln!(
+            "DEPRECATION WARNING: {}\n    {} {}:{}",
+            message()?,
+            loc.file.name(),
+            loc.begin.line + 1,
+            loc.begin.column + 1
+        );
+        Ok(())
+    }
+
+    /// Report the number of deprecation warnings that weren't printed, once
+    /// compilation has finished
+    pub fn summarize(&self) {
+        if self.omitted.get() > 0 {
+            eprintln!(
+                "\n{} repetitive deprecation warnings omitted.",
+                self.omitted.get()
+            );
+        }
+    }
+}
@@ -1,12 +1,18 @@
 use std::{
     fs::File,
-    io::{stdout, BufWriter, Write},
+    io::{self, stdout, BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
 };
 
 use clap::{arg_enum, App, Arg};
 
 #[cfg(not(feature = "wasm"))]
-use grass::from_path;
+use grass::Compiler;
 
 arg_enum! {
     #[derive(PartialEq, Debug)]
@@ -146,13 +152,28 @@ fn main() -> std::io::Result<()> {
                 .help("Don't print warnings."),
         )
         .arg(
-            Arg::with_name("INPUT")
-                .required(true)
-                .help("SCSS files"),
+            Arg::with_name("DEPFILE")
+                .long("depfile")
+                .help("Write a Makefile-style `.d` file listing every file the compilation loaded next to each output file.")
+        )
+        .arg(
+            Arg::with_name("JOBS")
+                .short("j")
+                .long("jobs")
+                .help("The number of threads to use when compiling many stylesheets at once. Defaults to the number of available cores.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("SEED")
+                .long("seed")
+                .help("Seed the random number generator, so that `random()` and `unique-id()` give the same results on every compilation.")
+                .takes_value(true)
         )
         .arg(
-            Arg::with_name("OUTPUT")
-                .help("Output SCSS file")
+            Arg::with_name("INPUT")
+                .required(true)
+                .multiple(true)
+                .help("SCSS file followed by an optional output file, or many `input.scss:output.css` pairs")
         )
 
         // Hidden, legacy arguments
@@ -164,28 +185,190 @@ fn main() -> std::io::Result<()> {
         )
         .get_matches();
 
-    if let Some(name) = matches.value_of("INPUT") {
-        if let Some(path) = matches.value_of("OUTPUT") {
-            let mut buf = BufWriter::new(File::open(path).unwrap_or(File::create(path)?));
-            buf.write_all(
-                from_path(name)
-                    .unwrap_or_else(|e| {
-                        eprintln!("{}", e);
-                        std::process::exit(1)
-                    })
-                    .as_bytes(),
-            )?;
-        } else {
-            let mut stdout = BufWriter::new(stdout());
-            stdout.write_all(
-                from_path(name)
-                    .unwrap_or_else(|e| {
-                        eprintln!("{}", e);
-                        std::process::exit(1)
-                    })
-                    .as_bytes(),
-            )?;
+    let inputs: Vec<&str> = matches.values_of("INPUT").unwrap_or_default().collect();
+    let depfile = matches.is_present("DEPFILE");
+    let quiet = matches.is_present("QUIET");
+    let seed = match matches.value_of("SEED").map(str::parse::<u64>) {
+        Some(Ok(..)) if cfg!(not(feature = "random")) => {
+            eprintln!("Error: --seed requires grass to be built with the `random` feature.");
+            std::process::exit(1)
+        }
+        Some(Ok(seed)) => Some(seed),
+        Some(Err(..)) => {
+            eprintln!("Error: --seed must be a non-negative integer.");
+            std::process::exit(1)
+        }
+        None => None,
+    };
+
+    if inputs.iter().any(|input| split_batch_arg(input).is_some()) {
+        let pairs = inputs
+            .iter()
+            .map(|input| match split_batch_arg(input) {
+                Some((input, output)) => (input.to_owned(), output.to_owned()),
+                None => {
+                    eprintln!(
+                        "Error: {} is not an `input.scss:output.css` pair. When compiling many stylesheets at once, every argument must be a pair.",
+                        input
+                    );
+                    std::process::exit(1)
+                }
+            })
+            .collect();
+        let jobs = match matches.value_of("JOBS").map(str::parse::<usize>) {
+            Some(Ok(jobs)) if jobs > 0 => jobs,
+            Some(..) => {
+                eprintln!("Error: --jobs must be a positive integer.");
+                std::process::exit(1)
+            }
+            None => thread::available_parallelism().map_or(1, usize::from),
+        };
+        if !compile_batch(pairs, jobs, depfile, seed, quiet) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let name = inputs[0];
+    let output = match inputs.as_slice() {
+        [_] => None,
+        [_, output] => Some(*output),
+        _ => {
+            eprintln!("Error: Only one input and one output file may be passed. To compile many stylesheets at once, use `input.scss:output.css` pairs.");
+            std::process::exit(1)
+        }
+    };
+
+    if depfile && output.is_none() {
+        eprintln!("Error: --depfile requires an output file.");
+        std::process::exit(1)
+    }
+
+    let result = new_compiler(seed, quiet)
+        .compile_path_to_result(name)
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        });
+
+    if let Some(path) = output {
+        let mut buf = BufWriter::new(File::open(path).unwrap_or(File::create(path)?));
+        buf.write_all(result.css().as_bytes())?;
+        if depfile {
+            write_depfile(path, result.loaded_files())?;
         }
+    } else {
+        let mut stdout = BufWriter::new(stdout());
+        stdout.write_all(result.css().as_bytes())?;
     }
     Ok(())
 }
+
+/// Create a compiler, seeding `random()` and `unique-id()` if a seed was
+/// passed
+///
+/// A seed is only ever passed when the `random` feature is enabled.
+#[cfg(not(feature = "wasm"))]
+fn new_compiler(seed: Option<u64>, quiet: bool) -> Compiler {
+    let compiler = Compiler::new().quiet(quiet);
+    match seed {
+        #[cfg(feature = "random")]
+        Some(seed) => compiler.with_random_seed(seed),
+        _ => compiler,
+    }
+}
+
+/// Write a Makefile-style depfile to `{output}.d`, declaring that `output`
+/// depends on every file in `loaded_files`
+#[cfg(not(feature = "wasm"))]
+fn write_depfile(output: &str, loaded_files: &[PathBuf]) -> io::Result<()> {
+    fn escape(path: &str) -> String {
+        path.replace('$', "$$")
+            .replace('#', "\\#")
+            .replace(' ', "\\ ")
+    }
+
+    let mut buf = BufWriter::new(File::create(format!("{}.d", output))?);
+    write!(buf, "{}:", escape(output))?;
+    for file in loaded_files {
+        write!(buf, " \\\n  {}", escape(&file.to_string_lossy()))?;
+    }
+    writeln!(buf)?;
+    buf.flush()
+}
+
+/// Split a many-to-many argument of the form `input.scss:output.css`
+///
+/// A colon in the second position is assumed to belong to a Windows drive
+/// letter rather than separate the input from the output
+#[cfg(not(feature = "wasm"))]
+fn split_batch_arg(arg: &str) -> Option<(&str, &str)> {
+    let idx = arg
+        .char_indices()
+        .find(|&(idx, c)| c == ':' && idx != 1)
+        .map(|(idx, _)| idx)?;
+    let (input, output) = (&arg[..idx], &arg[idx + 1..]);
+    if input.is_empty() || output.is_empty() {
+        return None;
+    }
+    Some((input, output))
+}
+
+/// Compile independent entry points in parallel, spreading them across
+/// `jobs` threads
+///
+/// Errors are printed as they are encountered. Returns whether or not
+/// every stylesheet compiled successfully.
+#[cfg(not(feature = "wasm"))]
+fn compile_batch(
+    pairs: Vec<(String, String)>,
+    jobs: usize,
+    depfile: bool,
+    seed: Option<u64>,
+    quiet: bool,
+) -> bool {
+    let pairs = Arc::new(pairs);
+    let next = Arc::new(AtomicUsize::new(0));
+    let succeeded = Arc::new(AtomicBool::new(true));
+
+    let handles = (0..jobs.min(pairs.len()))
+        .map(|_| {
+            let pairs = Arc::clone(&pairs);
+            let next = Arc::clone(&next);
+            let succeeded = Arc::clone(&succeeded);
+            thread::spawn(move || {
+                // partials shared between entry points are only read once per thread
+                let mut compiler = new_compiler(seed, quiet);
+                while let Some((input, output)) = pairs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let result = compiler
+                        .compile_path_to_result(input)
+                        .map_err(|e| e.to_string())
+                        .and_then(|result| {
+                            File::create(output)
+                                .and_then(|mut file| file.write_all(result.css().as_bytes()))
+                                .and_then(|()| {
+                                    if depfile {
+                                        write_depfile(output, result.loaded_files())
+                                    } else {
+                                        Ok(())
+                                    }
+                                })
+                                .map_err(|e| format!("Error: {}: {}", output, e))
+                        });
+                    if let Err(e) = result {
+                        eprintln!("{}", e);
+                        succeeded.store(false, Ordering::Relaxed);
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        if handle.join().is_err() {
+            succeeded.store(false, Ordering::Relaxed);
+        }
+    }
+
+    succeeded.load(Ordering::Relaxed)
+}
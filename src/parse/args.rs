@@ -1,293 +1,281 @@
-use std::{collections::HashMap, mem};
+use std::{collections::HashMap, mem, rc::Rc};
 
 use codemap::{Span, Spanned};
 
 use crate::{
     args::{CallArg, CallArgs, FuncArg, FuncArgs},
+    ast::{Argument, ArgumentInvocation, AstExpr},
+    common::Identifier,
     error::SassResult,
-    scope::Scope,
-    utils::{read_until_closing_paren, read_until_closing_quote, read_until_closing_square_brace},
-    value::Value,
-    Token,
+    scope::Scopes,
+    value::{ArgList, Value},
+    Token, TokenKind,
 };
 
-use super::Parser;
+use super::{value::ExprEnd, Parser};
 
 impl<'a> Parser<'a> {
     pub(super) fn parse_func_args(&mut self) -> SassResult<FuncArgs> {
         let mut args: Vec<FuncArg> = Vec::new();
         let mut close_paren_span: Span = match self.toks.peek() {
-            Some(Token { pos, .. }) => *pos,
+            Some(Token { pos, .. }) => pos,
             None => return Err(("expected \")\".", self.span_before).into()),
         };
 
-        self.whitespace();
-        while let Some(Token { kind, pos }) = self.toks.next() {
+        self.whitespace_or_comment();
+        while let Some(Token { kind, pos, .. }) = self.toks.next() {
             let name = match kind {
-                '$' => self.parse_identifier_no_interpolation(false)?,
-                ')' => {
+                TokenKind::Symbol('$') => self.parse_identifier_no_interpolation(false)?,
+                TokenKind::Symbol(')') => {
                     close_paren_span = pos;
                     break;
                 }
                 _ => return Err(("expected \")\".", pos).into()),
             };
-            let mut default: Vec<Token> = Vec::new();
-            let mut is_variadic = false;
-            self.whitespace();
+            self.whitespace_or_comment();
             let (kind, span) = match self.toks.next() {
-                Some(Token { kind, pos }) => (kind, pos),
+                Some(Token { kind, pos, .. }) => (kind, pos),
                 None => return Err(("expected \")\".", pos).into()),
             };
             match kind {
-                ':' => {
-                    self.whitespace();
-                    while let Some(tok) = self.toks.peek() {
-                        match &tok.kind {
-                            ',' => {
-                                self.toks.next();
-                                args.push(FuncArg {
-                                    name: name.node.into(),
-                                    default: Some(default),
-                                    is_variadic,
-                                });
-                                break;
-                            }
-                            ')' => {
-                                args.push(FuncArg {
-                                    name: name.node.into(),
-                                    default: Some(default),
-                                    is_variadic,
-                                });
-                                close_paren_span = tok.pos();
-                                break;
-                            }
-                            '(' => {
-                                default.push(self.toks.next().unwrap());
-                                default.extend(read_until_closing_paren(self.toks)?);
-                            }
-                            _ => default.push(self.toks.next().unwrap()),
+                TokenKind::Symbol(':') => {
+                    self.whitespace_or_comment();
+                    let default = self.parse_space_list(ExprEnd::Statement)?;
+                    self.whitespace_or_comment();
+                    args.push(FuncArg {
+                        name: name.node.into(),
+                        default: Some(default),
+                        is_variadic: false,
+                    });
+                    match self.toks.next() {
+                        Some(tok) if tok.is_symbol(',') => {}
+                        Some(tok) if tok.is_symbol(')') => {
+                            close_paren_span = tok.pos;
+                            break;
                         }
+                        Some(Token { pos, .. }) => return Err(("expected \")\".", pos).into()),
+                        None => return Err(("expected \")\".", span).into()),
                     }
                 }
-                '.' => {
+                TokenKind::Symbol('.') => {
                     let next = self.toks.next().ok_or(("expected \".\".", span))?;
-                    if next.kind != '.' {
+                    if !next.is_symbol('.') {
                         return Err(("expected \".\".", next.pos()).into());
                     }
                     let next = self.toks.next().ok_or(("expected \".\".", next.pos()))?;
-                    if next.kind != '.' {
+                    if !next.is_symbol('.') {
                         return Err(("expected \".\".", next.pos()).into());
                     }
-                    self.whitespace();
+                    self.whitespace_or_comment();
                     let next = self.toks.next().ok_or(("expected \")\".", next.pos()))?;
-                    if next.kind != ')' {
+                    if !next.is_symbol(')') {
                         return Err(("expected \")\".", next.pos()).into());
                     }
 
-                    is_variadic = true;
-
+                    close_paren_span = next.pos();
                     args.push(FuncArg {
                         name: name.node.into(),
-                        default: Some(default),
-                        is_variadic,
+                        default: None,
+                        is_variadic: true,
                     });
                     break;
                 }
-                ')' => {
+                TokenKind::Symbol(')') => {
                     close_paren_span = span;
                     args.push(FuncArg {
                         name: name.node.into(),
-                        default: if default.is_empty() {
-                            None
-                        } else {
-                            Some(default)
-                        },
-                        is_variadic,
+                        default: None,
+                        is_variadic: false,
                     });
                     break;
                 }
-                ',' => args.push(FuncArg {
+                TokenKind::Symbol(',') => args.push(FuncArg {
                     name: name.node.into(),
                     default: None,
-                    is_variadic,
+                    is_variadic: false,
                 }),
-                _ => {}
+                _ => return Err(("expected \")\".", span).into()),
             }
-            self.whitespace();
+            self.whitespace_or_comment();
         }
-        self.whitespace();
+        self.whitespace_or_comment();
         // TODO: this should NOT eat the opening curly brace
         match self.toks.next() {
-            Some(v) if v.kind == '{' => {}
+            Some(tok) if tok.is_symbol('{') => {}
             Some(..) | None => return Err(("expected \"{\".", close_paren_span).into()),
         };
         Ok(FuncArgs(args))
     }
 
-    pub(super) fn parse_call_args(&mut self) -> SassResult<CallArgs> {
-        let mut args = HashMap::new();
+    /// Parse the arguments passed to a function or mixin, after the opening
+    /// parenthesis
+    pub(super) fn parse_call_args(&mut self) -> SassResult<ArgumentInvocation> {
+        let mut args = Vec::new();
         self.whitespace_or_comment();
-        let mut name = String::new();
-        let mut val: Vec<Token> = Vec::new();
         let mut span = self
             .toks
             .peek()
             .ok_or(("expected \")\".", self.span_before))?
             .pos();
         loop {
-            match self.toks.peek().cloned() {
-                Some(Token { kind: '$', pos }) => {
-                    span = span.merge(pos);
-                    self.toks.next();
-                    let v = self.parse_identifier_no_interpolation(false)?;
-                    let whitespace = self.whitespace_or_comment();
-                    if let Some(Token { kind: ':', .. }) = self.toks.peek() {
-                        self.toks.next();
-                        name = v.node;
-                    } else {
-                        val.push(Token::new(pos, '$'));
-                        let mut current_pos = 0;
-                        val.extend(v.chars().map(|x| {
-                            let len = x.len_utf8() as u64;
-                            let tok = Token::new(v.span.subspan(current_pos, current_pos + len), x);
-                            current_pos += len;
-                            tok
-                        }));
-                        if whitespace {
-                            val.push(Token::new(pos, ' '));
-                        }
-                        name.clear();
-                    }
-                }
-                Some(Token { kind: ')', .. }) => {
+            match self.toks.peek() {
+                Some(tok) if tok.is_symbol(')') => {
+                    span = span.merge(tok.pos);
                     self.toks.next();
-                    return Ok(CallArgs(args, span));
+                    return Ok(ArgumentInvocation { args, span });
                 }
-                Some(..) | None => name.clear(),
+                // the end of the statement
+                Some(Token {
+                    kind: TokenKind::Symbol('{' | ';' | '}'),
+                    pos,
+                    ..
+                }) => return Err(("expected \")\".", pos).into()),
+                Some(..) => {}
+                None => return Err(("expected \")\".", span).into()),
             }
+
+            let mut value = self.parse_space_list(ExprEnd::Statement)?;
             self.whitespace_or_comment();
 
-            let mut is_splat = false;
+            // `$name: value` is a keyword argument
+            let name = match value.node {
+                AstExpr::Variable {
+                    namespace: None,
+                    name,
+                } if self.scan_char(':') => {
+                    self.whitespace_or_comment();
+                    value = self.parse_space_list(ExprEnd::Statement)?;
+                    self.whitespace_or_comment();
+                    Some(name.node)
+                }
+                _ => None,
+            };
+            span = span.merge(value.span);
 
-            while let Some(tok) = self.toks.next() {
-                match tok.kind {
-                    ')' => {
-                        args.insert(
-                            if name.is_empty() {
-                                CallArg::Positional(args.len())
-                            } else {
-                                CallArg::Named(name.into())
-                            },
-                            self.parse_value_from_vec(val),
-                        );
-                        span = span.merge(tok.pos());
-                        return Ok(CallArgs(args, span));
-                    }
-                    ',' => break,
-                    '[' => {
-                        val.push(tok);
-                        val.extend(read_until_closing_square_brace(self.toks)?);
-                    }
-                    '(' => {
-                        val.push(tok);
-                        val.extend(read_until_closing_paren(self.toks)?);
-                    }
-                    '"' | '\'' => {
-                        val.push(tok);
-                        val.extend(read_until_closing_quote(self.toks, tok.kind)?);
-                    }
-                    '.' => {
-                        if let Some(Token { kind: '.', pos }) = self.toks.peek().cloned() {
-                            if !name.is_empty() {
-                                return Err(("expected \")\".", pos).into());
-                            }
-                            self.toks.next();
-                            if let Some(Token { kind: '.', .. }) = self.toks.peek() {
-                                self.toks.next();
-                                is_splat = true;
-                                break;
-                            } else {
-                                return Err(("expected \".\".", pos).into());
-                            }
-                        } else {
-                            val.push(tok);
+            if let Some(Token { pos, .. }) = self.toks.peek().filter(|tok| tok.is_symbol('.')) {
+                self.toks.next();
+                for _ in 0..2 {
+                    match self.toks.next() {
+                        Some(tok) if tok.is_symbol('.') && name.is_some() => {
+                            return Err(("expected \")\".", tok.pos).into())
                         }
+                        Some(tok) if tok.is_symbol('.') => {}
+                        Some(Token { pos, .. }) => return Err(("expected \".\".", pos).into()),
+                        None => return Err(("expected \".\".", pos).into()),
                     }
-                    _ => val.push(tok),
                 }
+                args.push(Argument::Rest(value));
+                self.whitespace_or_comment();
+            } else {
+                args.push(match name {
+                    Some(name) => Argument::Named(name, value),
+                    None => Argument::Positional(value),
+                });
             }
 
-            if is_splat {
-                let val = self.parse_value_from_vec(mem::take(&mut val))?;
-                match val.node {
-                    Value::ArgList(v) => {
-                        for arg in v {
-                            args.insert(CallArg::Positional(args.len()), Ok(arg));
+            match self.toks.next() {
+                Some(tok) if tok.is_symbol(',') => self.whitespace_or_comment(),
+                Some(tok) if tok.is_symbol(')') => {
+                    span = span.merge(tok.pos);
+                    return Ok(ArgumentInvocation { args, span });
+                }
+                Some(Token { pos, .. }) => return Err(("expected \")\".", pos).into()),
+                None => return Err(("expected \")\".", span).into()),
+            };
+        }
+    }
+
+    /// Evaluate the arguments passed to a function or mixin
+    ///
+    /// Like dart-sass, an error in an argument is only reported once the
+    /// argument is used.
+    pub(crate) fn eval_call_args(
+        &mut self,
+        invocation: &ArgumentInvocation,
+    ) -> SassResult<CallArgs> {
+        self.eval_remaining_args(HashMap::new(), &invocation.args, invocation.span)
+    }
+
+    /// Evaluate `arguments`, which follow the already evaluated `args`
+    pub(super) fn eval_remaining_args(
+        &mut self,
+        mut args: HashMap<CallArg, SassResult<Spanned<Value>>>,
+        arguments: &[Argument],
+        span: Span,
+    ) -> SassResult<CallArgs> {
+        for arg in arguments {
+            match arg {
+                Argument::Positional(expr) => {
+                    let val = self.eval_expr(expr);
+                    args.insert(CallArg::Positional(args.len()), val);
+                }
+                Argument::Named(name, expr) => {
+                    let val = self.eval_expr(expr);
+                    args.insert(CallArg::Named(*name), val);
+                }
+                Argument::Rest(expr) => {
+                    let val = self.eval_expr(expr)?;
+                    match val.node {
+                        Value::ArgList(v) => {
+                            for arg in &v.elems {
+                                args.insert(CallArg::Positional(args.len()), Ok(arg.clone()));
+                            }
+                            for (name, arg) in v.keywords() {
+                                args.insert(CallArg::Named(*name), Ok(arg.clone()));
+                            }
                         }
-                    }
-                    Value::List(v, ..) => {
-                        for arg in v {
-                            args.insert(CallArg::Positional(args.len()), Ok(arg.span(val.span)));
+                        Value::List(v, ..) => {
+                            for arg in v.iter() {
+                                args.insert(
+                                    CallArg::Positional(args.len()),
+                                    Ok(arg.clone().span(val.span)),
+                                );
+                            }
                         }
-                    }
-                    Value::Map(v) => {
-                        // NOTE: we clone the map here because it is used
-                        // later for error reporting. perhaps there is
-                        // some way around this?
-                        for (name, arg) in v.clone().entries() {
-                            let name = match name {
-                                Value::String(s, ..) => s,
-                                _ => {
-                                    return Err((
-                                        format!(
-                                            "{} is not a string in {}.",
-                                            name.inspect(val.span)?,
-                                            Value::Map(v).inspect(val.span)?
-                                        ),
-                                        val.span,
-                                    )
-                                        .into())
-                                }
-                            };
-                            args.insert(CallArg::Named(name.into()), Ok(arg.span(val.span)));
+                        Value::Map(v) => {
+                            // NOTE: we clone the map here because it is used
+                            // later for error reporting. perhaps there is
+                            // some way around this?
+                            for (name, arg) in v.clone().entries() {
+                                let name = match name {
+                                    Value::String(s, ..) => s,
+                                    _ => {
+                                        return Err((
+                                            format!(
+                                                "{} is not a string in {}.",
+                                                name.inspect(val.span)?,
+                                                Value::Map(v).inspect(val.span)?
+                                            ),
+                                            val.span,
+                                        )
+                                            .into())
+                                    }
+                                };
+                                args.insert(CallArg::Named(name.into()), Ok(arg.span(val.span)));
+                            }
+                        }
+                        _ => {
+                            args.insert(CallArg::Positional(args.len()), Ok(val));
                         }
-                    }
-                    _ => {
-                        args.insert(CallArg::Positional(args.len()), Ok(val));
                     }
                 }
-            } else {
-                args.insert(
-                    if name.is_empty() {
-                        CallArg::Positional(args.len())
-                    } else {
-                        CallArg::Named(name.as_str().into())
-                    },
-                    self.parse_value_from_vec(mem::take(&mut val)),
-                );
-            }
-
-            self.whitespace();
-
-            if self.toks.peek().is_none() {
-                return Ok(CallArgs(args, span));
             }
         }
+        Ok(CallArgs(args, span))
     }
 }
 
 impl<'a> Parser<'a> {
-    #[allow(clippy::unused_self)]
     pub fn arg(
         &self,
         args: &mut CallArgs,
         position: usize,
         name: &'static str,
     ) -> SassResult<Value> {
-        Ok(args.get_err(position, name)?.node)
+        let val = args.get_err(position, name)?;
+        self.without_slash(val.node, val.span)
     }
 
-    #[allow(clippy::unused_self)]
     pub fn default_arg(
         &self,
         args: &mut CallArgs,
@@ -296,7 +284,10 @@ impl<'a> Parser<'a> {
         default: Value,
     ) -> SassResult<Value> {
         Ok(match args.get(position, name) {
-            Some(val) => val?.node,
+            Some(val) => {
+                let val = val?;
+                self.without_slash(val.node, val.span)?
+            }
             None => default,
         })
     }
@@ -332,50 +323,81 @@ impl<'a> Parser<'a> {
         })
     }
 
-    #[allow(clippy::unused_self)]
     pub fn variadic_args(&self, args: CallArgs) -> SassResult<Vec<Spanned<Value>>> {
-        let mut vals = Vec::new();
-        let mut args = match args
-            .0
-            .into_iter()
-            .map(|(a, v)| Ok((a.position()?, v)))
-            .collect::<Result<Vec<(usize, SassResult<Spanned<Value>>)>, String>>()
-        {
-            Ok(v) => v,
-            Err(e) => return Err((format!("No argument named ${}.", e), args.1).into()),
-        };
-        args.sort_by(|(a1, _), (a2, _)| a1.cmp(a2));
-        for arg in args {
-            vals.push(arg.1?);
-        }
-        Ok(vals)
+        self.without_slash_all(args.into_positional()?)
+    }
+
+    fn without_slash_all(&self, args: Vec<Spanned<Value>>) -> SassResult<Vec<Spanned<Value>>> {
+        args.into_iter()
+            .map(|val| {
+                Ok(Spanned {
+                    node: self.without_slash(val.node, val.span)?,
+                    span: val.span,
+                })
+            })
+            .collect()
     }
 
+    /// Bind the arguments of a call to the parameters of a mixin, function
+    /// or content block, in a new scope added to `scopes`
+    ///
+    /// Default values are evaluated in that new scope, so that they can refer
+    /// to earlier parameters and to the variables the callable closes over.
+    ///
+    /// Returns the value bound to the rest parameter, if there is one, so that
+    /// the caller can check that its keywords were used once the callable has
+    /// been evaluated.
     pub(super) fn eval_args(
         &mut self,
-        mut fn_args: FuncArgs,
+        fn_args: &FuncArgs,
+        args: CallArgs,
+        scopes: &mut Scopes,
+    ) -> SassResult<Option<Rc<ArgList>>> {
+        scopes.enter_new_scope();
+        mem::swap(self.scopes, scopes);
+        let result = self.bind_args(fn_args, args);
+        mem::swap(self.scopes, scopes);
+        result
+    }
+
+    fn bind_args(
+        &mut self,
+        fn_args: &FuncArgs,
         mut args: CallArgs,
-        scope: &mut Scope,
-    ) -> SassResult<()> {
-        self.scopes.push(self.scopes.last().clone());
-        for (idx, arg) in fn_args.0.iter_mut().enumerate() {
+    ) -> SassResult<Option<Rc<ArgList>>> {
+        for (idx, arg) in fn_args.0.iter().enumerate() {
             if arg.is_variadic {
                 let span = args.span();
-                // todo: does this get the most recent scope?
-                let arg_list = Value::ArgList(self.variadic_args(args)?);
-                scope.insert_var(
-                    arg.name.clone(),
+                let (positional, named) = args.into_positional_and_named()?;
+                let arg_list = Rc::new(ArgList::new(
+                    self.without_slash_all(positional)?,
+                    named
+                        .into_iter()
+                        .map(|(name, val)| {
+                            Ok((
+                                name,
+                                Spanned {
+                                    node: self.without_slash(val.node, val.span)?,
+                                    span: val.span,
+                                },
+                            ))
+                        })
+                        .collect::<SassResult<Vec<(Identifier, Spanned<Value>)>>>()?,
+                ));
+                self.scopes.insert_var(
+                    arg.name,
                     Spanned {
-                        node: arg_list,
+                        node: Value::ArgList(Rc::clone(&arg_list)),
                         span,
                     },
+                    self.global_scope,
                 );
-                break;
+                return Ok(Some(arg_list));
             }
-            let val = match args.get(idx, arg.name.clone()) {
+            let val = match args.get(idx, arg.name) {
                 Some(v) => v,
-                None => match arg.default.as_mut() {
-                    Some(v) => self.parse_value_from_vec(mem::take(v)),
+                None => match &arg.default {
+                    Some(v) => self.eval_expr(v),
                     None => {
                         return Err(
                             (format!("Missing argument ${}.", &arg.name), args.span()).into()
@@ -383,12 +405,12 @@ impl<'a> Parser<'a> {
                     }
                 },
             }?;
-            self.scopes
-                .last_mut()
-                .insert_var(arg.name.clone(), val.clone());
-            scope.insert_var(mem::take(&mut arg.name), val);
+            let val = Spanned {
+                node: self.without_slash(val.node, val.span)?,
+                span: val.span,
+            };
+            self.scopes.insert_var(arg.name, val, self.global_scope);
         }
-        self.scopes.pop();
-        Ok(())
+        Ok(None)
     }
 }
@@ -0,0 +1,584 @@
+use std::convert::TryFrom;
+
+use codemap::{Span, Spanned};
+
+use crate::{
+    ast::{AstStmt, Branch, Interpolation, SelectorCache},
+    atrule::AtRuleKind,
+    error::SassResult,
+    Token, TokenKind,
+};
+
+use super::{common::SelectorOrStyle, value::ExprEnd, Parser};
+
+impl<'a> Parser<'a> {
+    /// Parse an entire stylesheet into an unevaluated tree
+    pub fn parse_stylesheet(&mut self) -> SassResult<Vec<AstStmt>> {
+        let mut stmts = Vec::new();
+        while self.toks.peek().is_some() {
+            stmts.append(&mut self.parse_ast()?);
+        }
+        Ok(stmts)
+    }
+
+    /// Parse statements into an unevaluated tree until the end of input, or
+    /// until a `}` closing the current block
+    pub(super) fn parse_ast(&mut self) -> SassResult<Vec<AstStmt>> {
+        Ok(self.parse_statements()?.0)
+    }
+
+    /// Parse the statements of a block whose `{` has already been consumed,
+    /// which must be closed by a `}`
+    pub(super) fn parse_block(&mut self) -> SassResult<Vec<AstStmt>> {
+        let (stmts, closed) = self.parse_statements()?;
+        if !closed {
+            return Err(("expected \"}\".", self.span_before).into());
+        }
+        Ok(stmts)
+    }
+
+    /// Parse statements until the end of input or a closing `}`, returning
+    /// whether the `}` was found
+    fn parse_statements(&mut self) -> SassResult<(Vec<AstStmt>, bool)> {
+        let mut stmts = Vec::new();
+        while self.toks.peek().is_some() {
+            if self.parse_statement(&mut stmts)? {
+                return Ok((stmts, true));
+            }
+        }
+        Ok((stmts, false))
+    }
+
+    /// Parse the next statement into `stmts`, returning whether it was a `}`
+    /// closing the current block instead
+    ///
+    /// Whitespace, silent comments and rules such as `@charset` don't add
+    /// anything to `stmts`.
+    pub(super) fn parse_statement(&mut self, stmts: &mut Vec<AstStmt>) -> SassResult<bool> {
+        if let Some(Token { kind, pos, .. }) = self.toks.peek() {
+            self.span_before = pos;
+            match kind {
+                TokenKind::Symbol('@') => {
+                    if let Some(stmt) = self.parse_at_rule()? {
+                        stmts.push(stmt);
+                    }
+                }
+                TokenKind::Symbol('$') => stmts.push(self.parse_variable_declaration()?),
+                TokenKind::Whitespace | TokenKind::Symbol(';') => {
+                    self.toks.next();
+                }
+                TokenKind::Symbol('/') => {
+                    self.toks.next();
+                    if self.scan_char('*') {
+                        let text = self.parse_loud_comment(pos)?;
+                        stmts.push(AstStmt::Comment {
+                            span: text.span,
+                            text,
+                        });
+                    } else if self.scan_char('/') {
+                        self.toks.skip_line();
+                    } else {
+                        return Err(("expected selector.", pos).into());
+                    }
+                    self.whitespace();
+                }
+                TokenKind::Symbol('\u{0}'..='\u{8}' | '\u{b}'..='\u{1f}') => {
+                    return Err(("expected selector.", pos).into())
+                }
+                TokenKind::Symbol('}') => {
+                    self.toks.next();
+                    return Ok(true);
+                }
+                // dart-sass seems to special-case the error message here?
+                TokenKind::Symbol('!' | '{') => return Err(("expected \"}\".", pos).into()),
+                _ => match self.is_selector_or_style()? {
+                    SelectorOrStyle::Style(property, value) => {
+                        stmts.push(self.parse_style(property, value)?)
+                    }
+                    SelectorOrStyle::Selector(init) => {
+                        let selector = self.parse_selector_interpolation(init)?;
+                        let body = self.parse_ast()?;
+                        stmts.push(AstStmt::RuleSet {
+                            span: selector.span,
+                            selector,
+                            parsed: SelectorCache::default(),
+                            body,
+                        });
+                    }
+                },
+            }
+        }
+        Ok(false)
+    }
+
+    /// Parse an at-rule, returning `None` for rules that have no effect once
+    /// parsed, such as `@charset`
+    fn parse_at_rule(&mut self) -> SassResult<Option<AstStmt>> {
+        self.toks.next();
+        let Spanned {
+            node: name,
+            span: name_span,
+        } = self.parse_interpolated_identifier()?;
+        self.span_before = name_span;
+
+        let kind = match name.as_plain() {
+            Some(plain) => AtRuleKind::try_from(&Spanned {
+                node: plain.to_owned(),
+                span: name_span,
+            })?,
+            None => return Ok(Some(self.parse_unknown_at_rule(name)?)),
+        };
+
+        Ok(Some(match kind {
+            AtRuleKind::Import => self.parse_import()?,
+            AtRuleKind::Mixin => self.parse_mixin()?,
+            AtRuleKind::Content => self.parse_content_rule()?,
+            AtRuleKind::Include => self.parse_include()?,
+            AtRuleKind::Function => self.parse_function()?,
+            AtRuleKind::Return => {
+                let value = self.parse_expression()?;
+                self.scan_semicolon();
+                AstStmt::Return {
+                    value,
+                    span: name_span,
+                }
+            }
+            AtRuleKind::AtRoot => self.parse_at_root()?,
+            AtRuleKind::Error => {
+                let message = self.parse_expression()?;
+                self.scan_semicolon();
+                AstStmt::Error {
+                    message,
+                    span: name_span,
+                }
+            }
+            AtRuleKind::Warn => {
+                let message = self.parse_expression()?;
+                self.scan_semicolon();
+                AstStmt::Warn {
+                    message,
+                    span: name_span,
+                }
+            }
+            AtRuleKind::Debug => {
+                let message = self.parse_expression()?;
+                self.scan_semicolon();
+                AstStmt::Debug {
+                    message,
+                    span: name_span,
+                }
+            }
+            AtRuleKind::If => self.parse_if()?,
+            AtRuleKind::Each => self.parse_each()?,
+            AtRuleKind::For => self.parse_for()?,
+            AtRuleKind::While => self.parse_while()?,
+            AtRuleKind::Charset => {
+                self.parse_expression()?;
+                self.scan_semicolon();
+                return Ok(None);
+            }
+            AtRuleKind::Media => self.parse_media()?,
+            AtRuleKind::Unknown(..) => self.parse_unknown_at_rule(name)?,
+            AtRuleKind::Use => self.parse_module_use()?,
+            AtRuleKind::Forward => todo!("@forward not yet implemented"),
+            AtRuleKind::Extend => self.parse_extend()?,
+            AtRuleKind::Supports => self.parse_supports()?,
+            AtRuleKind::Keyframes => self.parse_keyframes()?,
+        }))
+    }
+
+    /// Consume the `;` ending a statement, if there is one
+    fn scan_semicolon(&mut self) {
+        self.whitespace_or_comment();
+        self.scan_char(';');
+    }
+
+    /// Parse the contents of a loud comment, assuming the opening `/*` has
+    /// already been consumed
+    fn parse_loud_comment(&mut self, span: Span) -> SassResult<Interpolation> {
+        let start = self.toks.cursor();
+        let mut comment = Interpolation::new(span);
+        loop {
+            if let Some(text) = self
+                .toks
+                .next_text(|b| matches!(b, b'*' | b'#' | b'\r' | b'\x0C'))
+            {
+                comment.push_str(text.node);
+            }
+            if self.toks.scan_str("*/") {
+                break;
+            }
+            if self.toks.scan_str("#{") {
+                comment.push_expr(self.parse_interpolation_expr()?);
+                continue;
+            }
+            match self.toks.next_char() {
+                // newlines are written as `\n`, whatever they are in the source
+                Some(Spanned { node: '\r', .. }) => {
+                    self.toks.scan_str("\n");
+                    comment.push_char('\n');
+                }
+                Some(Spanned { node: '\x0C', .. }) => comment.push_char('\n'),
+                Some(Spanned { node: c, .. }) => comment.push_char(c),
+                None => break,
+            }
+        }
+        comment.span = span.merge(self.toks.span_from(start));
+        Ok(comment)
+    }
+
+    /// Read the rest of a selector up to and including the `{` that ends it,
+    /// starting with the text in `init` that was read while determining that
+    /// this is a selector
+    ///
+    /// Comments are replaced by a space, and the selector itself isn't parsed
+    /// until its interpolation has been evaluated.
+    pub(super) fn parse_selector_interpolation(
+        &mut self,
+        mut selector: Interpolation,
+    ) -> SassResult<Interpolation> {
+        let mut span = match self.toks.peek() {
+            Some(tok) => selector.span.merge(tok.pos),
+            None => return Err(("expected \"{\".", self.span_before).into()),
+        };
+
+        while let Some(tok) = self.toks.peek() {
+            span = span.merge(tok.pos);
+            match tok.kind {
+                TokenKind::Symbol('#') => {
+                    self.toks.next();
+                    if self.scan_char('{') {
+                        selector.push_expr(self.parse_interpolation_expr()?);
+                    } else {
+                        selector.push_char('#');
+                    }
+                }
+                TokenKind::Symbol('/') => {
+                    self.toks.next();
+                    if self.toks.peek().is_none() {
+                        return Err(("Expected selector.", tok.pos).into());
+                    }
+                    self.span_before = tok.pos;
+                    self.skip_comment()?;
+                    selector.push_char(' ');
+                }
+                TokenKind::Symbol('{') => {
+                    self.toks.next();
+                    selector.span = span;
+                    self.span_before = span;
+                    return Ok(selector);
+                }
+                TokenKind::String => self.parse_raw_string(&mut selector)?,
+                _ => {
+                    self.toks.next();
+                    selector.push_str(self.toks.text(tok));
+                }
+            }
+        }
+
+        Err(("expected \"{\".", span).into())
+    }
+
+    /// Read plain text that may contain interpolation up to, but not
+    /// including, the `{`, `;` or `}` that ends it, collapsing whitespace
+    ///
+    /// This is used for the parameters of at-rules that are emitted as
+    /// written, such as `@supports`.
+    fn parse_at_rule_params(&mut self) -> SassResult<Interpolation> {
+        let mut params = Interpolation::new(self.span_before);
+        self.whitespace();
+        while let Some(tok) = self.toks.peek() {
+            match tok.kind {
+                TokenKind::Symbol('{' | ';' | '}') => break,
+                TokenKind::Symbol('#') => {
+                    self.toks.next();
+                    if self.scan_char('{') {
+                        params.push_expr(self.parse_interpolation_expr()?);
+                    } else {
+                        params.push_char('#');
+                    }
+                }
+                TokenKind::Whitespace => {
+                    self.toks.next();
+                    params.push_char(' ');
+                }
+                TokenKind::String => self.parse_raw_string(&mut params)?,
+                _ => {
+                    self.toks.next();
+                    params.push_str(self.toks.text(tok));
+                }
+            }
+            params.span = params.span.merge(tok.pos);
+        }
+        Ok(params)
+    }
+
+    fn parse_unknown_at_rule(&mut self, name: Interpolation) -> SassResult<AstStmt> {
+        let span = self.span_before;
+        let params = self.parse_at_rule_params()?;
+        let body = if self.scan_char('{') {
+            Some(self.parse_ast()?)
+        } else {
+            self.scan_char(';');
+            None
+        };
+        Ok(AstStmt::UnknownAtRule {
+            name,
+            params,
+            body,
+            span,
+        })
+    }
+
+    fn parse_supports(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
+        let params = self.parse_at_rule_params()?;
+        if params.is_empty() {
+            return Err(("Expected \"not\".", self.span_before).into());
+        }
+        self.toks.next();
+        let body = self.parse_ast()?;
+        Ok(AstStmt::Supports { params, body, span })
+    }
+
+    fn parse_media(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
+        let query = self.parse_media_query_list()?;
+        self.whitespace();
+        if !self.scan_char('{') {
+            return Err(("expected \"{\".", self.span_before).into());
+        }
+        let body = self.parse_ast()?;
+        Ok(AstStmt::Media { query, body, span })
+    }
+
+    fn parse_at_root(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
+        self.whitespace();
+        let selector = if self.scan_char('{') {
+            None
+        } else {
+            Some(self.parse_selector_interpolation(Interpolation::new(self.span_before))?)
+        };
+        self.whitespace();
+        let body = self.parse_block()?;
+        self.whitespace();
+        Ok(AstStmt::AtRoot {
+            selector,
+            body,
+            span,
+        })
+    }
+
+    fn parse_extend(&mut self) -> SassResult<AstStmt> {
+        self.whitespace();
+        let span = self.span_before;
+        let mut selector = Interpolation::new(span);
+        while let Some(tok) = self.toks.peek() {
+            match tok.kind {
+                TokenKind::Symbol(';' | '}' | '!') => break,
+                TokenKind::Symbol('#') => {
+                    self.toks.next();
+                    if self.scan_char('{') {
+                        selector.push_expr(self.parse_interpolation_expr()?);
+                    } else {
+                        selector.push_char('#');
+                    }
+                }
+                TokenKind::Symbol('/') => {
+                    self.toks.next();
+                    self.skip_comment()?;
+                    selector.push_char(' ');
+                }
+                TokenKind::String => self.parse_raw_string(&mut selector)?,
+                _ => {
+                    self.toks.next();
+                    selector.push_str(self.toks.text(tok));
+                }
+            }
+            selector.span = selector.span.merge(tok.pos);
+        }
+
+        let is_optional = if self.scan_char('!') {
+            let flag = self.parse_identifier_no_interpolation(false)?;
+            if flag.node != "optional" {
+                return Err(("Expected \"optional\".", flag.span).into());
+            }
+            true
+        } else {
+            false
+        };
+
+        self.scan_semicolon();
+
+        Ok(AstStmt::Extend {
+            selector,
+            is_optional,
+            span,
+        })
+    }
+
+    fn parse_if(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
+        self.whitespace_or_comment();
+        let mut branches = Vec::new();
+
+        let cond = self.parse_expression()?;
+        self.expect_char('{')?;
+        branches.push(Branch::new(cond, self.parse_block()?));
+        self.whitespace_or_comment();
+
+        let mut else_ = Vec::new();
+
+        while matches!(self.toks.peek(), Some(tok) if tok.is_symbol('@')) {
+            let start = self.toks.cursor();
+            self.toks.next();
+            match self.parse_identifier_no_interpolation(false) {
+                Ok(ident) if ident.node == "else" => {}
+                Ok(..) | Err(..) => {
+                    self.toks.set_cursor(start);
+                    break;
+                }
+            }
+            self.whitespace_or_comment();
+
+            let start = self.toks.cursor();
+            let is_else_if = match self.parse_identifier_no_interpolation(false) {
+                Ok(ident) => ident.node.eq_ignore_ascii_case("if"),
+                Err(..) => false,
+            };
+
+            if is_else_if {
+                self.whitespace_or_comment();
+                let cond = self.parse_expression()?;
+                self.expect_char('{')?;
+                branches.push(Branch::new(cond, self.parse_block()?));
+                self.whitespace_or_comment();
+            } else {
+                self.toks.set_cursor(start);
+                match self.toks.next() {
+                    Some(tok) if tok.is_symbol('{') => {
+                        else_ = self.parse_block()?;
+                        break;
+                    }
+                    Some(tok) => return Err(("expected \"{\".", tok.pos).into()),
+                    None => return Err(("expected \"{\".", self.span_before).into()),
+                }
+            }
+        }
+        self.whitespace();
+
+        Ok(AstStmt::If {
+            branches,
+            else_,
+            span,
+        })
+    }
+
+    fn parse_for(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
+        self.whitespace();
+        if !self.scan_char('$') {
+            return Err(("expected \"$\".", self.span_before).into());
+        }
+        let var = self.parse_identifier_no_interpolation(false)?;
+        self.whitespace();
+        self.span_before = match self.toks.peek() {
+            Some(tok) => tok.pos,
+            None => return Err(("Expected \"from\".", var.span).into()),
+        };
+        if self.parse_identifier()?.node.to_ascii_lowercase() != "from" {
+            return Err(("Expected \"from\".", var.span).into());
+        }
+        self.whitespace();
+
+        let from = self.parse_expression_until(ExprEnd::ForBound)?;
+
+        let through = match self.toks.peek() {
+            Some(tok) if tok.is_symbol('{') => {
+                return Err(("Expected \"to\" or \"through\".", tok.pos).into())
+            }
+            Some(tok) => {
+                self.span_before = tok.pos;
+                let ident = self.parse_identifier_no_interpolation(false)?;
+                match ident.node.to_ascii_lowercase().as_str() {
+                    "through" => true,
+                    "to" => false,
+                    _ => return Err(("Expected \"to\" or \"through\".", ident.span).into()),
+                }
+            }
+            None => return Err(("Expected \"to\" or \"through\".", from.span).into()),
+        };
+
+        let to = self.parse_expression()?;
+        self.expect_char('{')?;
+        let body = self.parse_block()?;
+        self.whitespace();
+
+        Ok(AstStmt::For {
+            var: var.map_node(Into::into),
+            from,
+            to,
+            through,
+            body,
+            span,
+        })
+    }
+
+    fn parse_while(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
+        self.whitespace();
+        let cond = self.parse_expression()?;
+        self.expect_char('{')?;
+        let body = self.parse_block()?;
+        self.whitespace();
+        Ok(AstStmt::While { cond, body, span })
+    }
+
+    fn parse_each(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
+        self.whitespace();
+        let mut vars = Vec::new();
+
+        loop {
+            match self.toks.next() {
+                Some(tok) if tok.is_symbol('$') => vars.push(
+                    self.parse_identifier_no_interpolation(false)?
+                        .map_node(Into::into),
+                ),
+                Some(tok) => return Err(("expected \"$\".", tok.pos).into()),
+                None => return Err(("expected \"$\".", self.span_before).into()),
+            }
+            self.whitespace();
+            match self.toks.peek() {
+                Some(tok) if tok.is_symbol(',') => {
+                    self.toks.next();
+                    self.whitespace();
+                }
+                Some(..) => break,
+                None => {
+                    let span = vars.last().map_or(self.span_before, |var| var.span);
+                    return Err(("expected \"$\".", span).into());
+                }
+            }
+        }
+
+        let i = self.parse_identifier()?;
+        if i.node.to_ascii_lowercase() != "in" {
+            return Err(("Expected \"in\".", i.span).into());
+        }
+        self.whitespace();
+
+        let list = self.parse_expression()?;
+        self.expect_char('{')?;
+        let body = self.parse_block()?;
+        self.whitespace();
+
+        Ok(AstStmt::Each {
+            vars,
+            list,
+            body,
+            span,
+        })
+    }
+}
@@ -1,11 +1,6 @@
-use std::{
-    ops::{BitAnd, BitOr},
-    slice::IterMut,
-};
+use std::ops::{BitAnd, BitOr};
 
-use codemap::Spanned;
-
-use crate::{value::Value, Token};
+use crate::ast::{Expr, Interpolation};
 
 #[derive(Debug, Clone)]
 pub(crate) struct NeverEmptyVec<T> {
@@ -25,18 +20,6 @@ impl<T> NeverEmptyVec<T> {
         self.rest.last().unwrap_or(&self.first)
     }
 
-    pub fn last_mut(&mut self) -> &mut T {
-        self.rest.last_mut().unwrap_or(&mut self.first)
-    }
-
-    pub fn first(&mut self) -> &T {
-        &self.first
-    }
-
-    pub fn first_mut(&mut self) -> &mut T {
-        &mut self.first
-    }
-
     pub fn push(&mut self, value: T) {
         self.rest.push(value)
     }
@@ -45,14 +28,6 @@ impl<T> NeverEmptyVec<T> {
         self.rest.pop()
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
-        self.rest.iter_mut()
-    }
-
-    pub fn len(&self) -> usize {
-        self.rest.len()
-    }
-
     pub fn is_empty(&self) -> bool {
         self.rest.is_empty()
     }
@@ -63,20 +38,8 @@ impl<T> NeverEmptyVec<T> {
 /// selector or a style.
 #[derive(Debug)]
 pub(super) enum SelectorOrStyle {
-    Selector(String),
-    Style(String, Option<Box<Spanned<Value>>>),
-}
-
-#[derive(Debug, Clone)]
-pub(super) struct Branch {
-    pub cond: Vec<Token>,
-    pub toks: Vec<Token>,
-}
-
-impl Branch {
-    pub fn new(cond: Vec<Token>, toks: Vec<Token>) -> Branch {
-        Branch { cond, toks }
-    }
+    Selector(Interpolation),
+    Style(Interpolation, Option<Expr>),
 }
 
 #[derive(Debug, Copy, Clone)]
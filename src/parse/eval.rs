@@ -0,0 +1,683 @@
+use std::rc::Rc;
+
+use codemap::{Span, Spanned};
+use num_traits::cast::ToPrimitive;
+
+use crate::{
+    ast::{AstStmt, Branch, Expr, Interpolation, InterpolationPart, SelectorCache},
+    atrule::{keyframes::KeyframesRuleSet, media::MediaRule, Mixin, SupportsRule, UnknownAtRule},
+    common::{Brackets, Identifier, ListSeparator},
+    error::SassResult,
+    selector::{ComplexSelectorComponent, ExtendRule, ExtendedSelector, Selector},
+    style::Style,
+    unit::Unit,
+    value::{Number, Value},
+};
+
+use super::{
+    common::{ContextFlags, NeverEmptyVec},
+    Parser, Stmt,
+};
+
+impl<'a> Parser<'a> {
+    /// Parse and evaluate an entire stylesheet, producing the statements that
+    /// make up its output
+    ///
+    /// Each top-level statement is evaluated as soon as it has been parsed and
+    /// freed before the next one is parsed, so the tree of the whole file is
+    /// never held in memory alongside its output.
+    pub fn eval_stylesheet(&mut self) -> SassResult<Vec<Stmt>> {
+        let mut stmts = Vec::new();
+        let mut ast = Vec::new();
+        while self.toks.peek().is_some() {
+            self.parse_statement(&mut ast)?;
+            stmts.append(&mut self.eval_ast(&ast)?);
+            ast.clear();
+        }
+        Ok(stmts)
+    }
+
+    /// Evaluate an unevaluated tree, producing the statements that make up
+    /// its output
+    pub fn eval_ast(&mut self, ast: &[AstStmt]) -> SassResult<Vec<Stmt>> {
+        let mut stmts = Vec::new();
+        for stmt in ast {
+            if self.flags.in_function() && !stmts.is_empty() {
+                return Ok(stmts);
+            }
+            // errors that don't have a more specific location point at the
+            // statement being evaluated
+            self.span_before = stmt.span();
+            match stmt {
+                AstStmt::RuleSet {
+                    selector,
+                    parsed,
+                    body,
+                    ..
+                } => stmts.push(self.eval_ruleset(selector, parsed, body)?),
+                AstStmt::Style {
+                    property,
+                    value,
+                    body,
+                    ..
+                } => self.eval_style(None, property, value.as_ref(), body, &mut stmts)?,
+                AstStmt::VariableDecl {
+                    name,
+                    value,
+                    global,
+                    default,
+                    ..
+                } => {
+                    let Spanned { node: value, span } =
+                        self.eval_variable_value(*name, value, *global, *default)?;
+                    let value = self.without_slash(value, span)?;
+                    self.declare_variable(*name, Spanned { node: value, span }, *global, *default);
+                }
+                AstStmt::If {
+                    branches, else_, ..
+                } => stmts.append(&mut self.eval_if(branches, else_)?),
+                AstStmt::Each {
+                    vars, list, body, ..
+                } => stmts.append(&mut self.eval_each(vars, list, body)?),
+                AstStmt::For {
+                    var,
+                    from,
+                    to,
+                    through,
+                    body,
+                    ..
+                } => stmts.append(&mut self.eval_for(var, from, to, *through, body)?),
+                AstStmt::While { cond, body, .. } => {
+                    stmts.append(&mut self.eval_while(cond, body)?)
+                }
+                AstStmt::Comment { text, .. } => {
+                    stmts.push(Stmt::Comment(self.eval_interpolation(text)?))
+                }
+                AstStmt::Return { value, span } => {
+                    if !self.flags.in_function() {
+                        return Err(("This at-rule is not allowed here.", *span).into());
+                    }
+                    let Spanned { node: value, span } = self.eval_expr(value)?;
+                    return Ok(vec![Stmt::Return(Box::new(
+                        self.without_slash(value, span)?,
+                    ))]);
+                }
+                AstStmt::Warn { message, .. } => {
+                    let Spanned {
+                        node: message,
+                        span,
+                    } = self.eval_expr(message)?;
+                    self.warn(&Spanned {
+                        node: message.to_css_string(span)?,
+                        span,
+                    })
+                }
+                AstStmt::Debug { message, .. } => {
+                    let Spanned {
+                        node: message,
+                        span,
+                    } = self.eval_expr(message)?;
+                    self.debug(&Spanned {
+                        node: message.inspect(span)?,
+                        span,
+                    })
+                }
+                AstStmt::Error { message, span } => {
+                    let Spanned {
+                        node: message,
+                        span: message_span,
+                    } = self.eval_expr(message)?;
+                    return Err((
+                        message.inspect(message_span)?.to_string(),
+                        message_span.merge(*span),
+                    )
+                        .into());
+                }
+                AstStmt::Mixin {
+                    name,
+                    args,
+                    body,
+                    accepts_content_block,
+                    ..
+                } => {
+                    let mixin = Mixin::new(
+                        self.scopes.clone(),
+                        args.clone(),
+                        Rc::clone(body),
+                        *accepts_content_block,
+                    );
+                    self.scopes.insert_mixin(*name, mixin, self.global_scope);
+                }
+                AstStmt::Function {
+                    name,
+                    args,
+                    body,
+                    span,
+                } => self.eval_function_decl(*name, args, body, *span)?,
+                AstStmt::Include {
+                    namespace,
+                    name,
+                    args,
+                    content,
+                    ..
+                } => stmts.append(&mut self.eval_include(*namespace, *name, args, content)?),
+                AstStmt::Content { args, span } => {
+                    stmts.append(&mut self.eval_content_rule(args, *span)?)
+                }
+                AstStmt::Import { url, .. } => stmts.append(&mut self.eval_import(url)?),
+                AstStmt::Use {
+                    url,
+                    namespace,
+                    span,
+                } => self.eval_use(url, namespace.as_deref(), *span)?,
+                AstStmt::Media { query, body, span } => {
+                    let query = self.eval_interpolation(query)?;
+                    let body = self.eval_at_rule_body(body)?;
+                    stmts.push(Stmt::Media(Box::new(MediaRule {
+                        super_selector: Selector::new(*span),
+                        query,
+                        body,
+                    })));
+                }
+                AstStmt::Supports { params, body, .. } => {
+                    let params = self.eval_interpolation(params)?.trim().to_owned();
+                    let body = self.eval_at_rule_body(body)?;
+                    stmts.push(Stmt::Supports(Box::new(SupportsRule { params, body })));
+                }
+                AstStmt::UnknownAtRule {
+                    name,
+                    params,
+                    body,
+                    span,
+                } => {
+                    let name = self.eval_interpolation(name)?;
+                    let params = self.eval_interpolation(params)?.trim().to_owned();
+                    let body = match body {
+                        Some(body) => self.eval_at_rule_body(body)?,
+                        None => Vec::new(),
+                    };
+                    stmts.push(Stmt::UnknownAtRule(Box::new(UnknownAtRule {
+                        name,
+                        super_selector: Selector::new(*span),
+                        params,
+                        body,
+                    })));
+                }
+                AstStmt::AtRoot { selector, body, .. } => {
+                    let mut body = self.eval_at_root(selector.as_ref(), body)?;
+                    if self.at_root {
+                        stmts.append(&mut body);
+                    } else {
+                        stmts.push(Stmt::AtRoot { body });
+                    }
+                }
+                AstStmt::Extend {
+                    selector,
+                    is_optional,
+                    span,
+                } => self.eval_extend(selector, *is_optional, *span)?,
+                AstStmt::Keyframes { name, body, .. } => {
+                    stmts.push(self.eval_keyframes(name, body)?)
+                }
+            }
+        }
+        Ok(stmts)
+    }
+
+    fn eval_ruleset(
+        &mut self,
+        selector: &Interpolation,
+        parsed: &SelectorCache,
+        body: &[AstStmt],
+    ) -> SassResult<Stmt> {
+        let span = selector.span;
+        self.span_before = span;
+
+        if self.flags.in_keyframes() {
+            let selector = self.eval_selector_interpolation(selector)?;
+            let selector = self.parse_keyframes_selector_from_string(selector, span)?;
+            self.scopes.enter_new_scope();
+            let body = self.eval_ast(body)?;
+            self.scopes.exit_scope();
+            return Ok(Stmt::KeyframesRuleSet(Box::new(KeyframesRuleSet {
+                selector,
+                body,
+            })));
+        }
+
+        let allows_parent = !self.super_selectors.is_empty();
+        let selector = if selector.as_plain().is_some() {
+            parsed
+                .get(allows_parent)
+                .get_or_try_init(|| {
+                    let selector = self.eval_selector_interpolation(selector)?;
+                    self.parse_selector_from_string(allows_parent, selector, span)
+                })?
+                .clone()
+        } else {
+            let selector = self.eval_selector_interpolation(selector)?;
+            self.parse_selector_from_string(allows_parent, selector, span)?
+        };
+
+        let at_root = self.at_root;
+        self.at_root = false;
+        let selector = selector.resolve_parent_selectors(
+            self.super_selectors.last(),
+            !at_root || self.at_root_has_selector,
+        )?;
+        self.scopes.enter_new_scope();
+        self.super_selectors.push(selector.clone());
+
+        let extended_selector = self.extender.add_selector(selector.0, None);
+
+        let body = self.eval_ast(body)?;
+        self.scopes.exit_scope();
+        self.super_selectors.pop();
+        self.at_root = self.super_selectors.is_empty();
+        Ok(Stmt::RuleSet {
+            selector: extended_selector,
+            body,
+        })
+    }
+
+    /// Evaluate the body of a control flow rule
+    fn eval_control_flow_body(&mut self, body: &[AstStmt]) -> SassResult<Vec<Stmt>> {
+        let flags = self.flags;
+        let at_root = self.at_root;
+        self.flags = self.flags | ContextFlags::IN_CONTROL_FLOW;
+        let stmts = self.eval_ast(body);
+        self.flags = flags;
+        self.at_root = at_root;
+        stmts
+    }
+
+    fn eval_if(&mut self, branches: &[Branch], else_: &[AstStmt]) -> SassResult<Vec<Stmt>> {
+        let mut body = else_;
+        for branch in branches {
+            if self.eval_expr(&branch.cond)?.node.is_true() {
+                body = &branch.body;
+                break;
+            }
+        }
+        self.scopes.enter_new_scope();
+        let stmts = self.eval_control_flow_body(body);
+        self.scopes.exit_scope();
+        stmts
+    }
+
+    fn eval_for(
+        &mut self,
+        var: &Spanned<Identifier>,
+        from: &Expr,
+        to: &Expr,
+        through: bool,
+        body: &[AstStmt],
+    ) -> SassResult<Vec<Stmt>> {
+        let from_val = self.eval_expr(from)?;
+        let from = match from_val.node {
+            Value::Dimension(n, _, _) => match n.to_integer().to_isize() {
+                Some(v) => v,
+                None => return Err((format!("{} is not a int.", n), from_val.span).into()),
+            },
+            v => {
+                return Err((
+                    format!("{} is not an integer.", v.inspect(from_val.span)?),
+                    from_val.span,
+                )
+                    .into())
+            }
+        };
+
+        let to_val = self.eval_expr(to)?;
+        let to = match to_val.node {
+            Value::Dimension(n, _, _) => match n.to_integer().to_isize() {
+                Some(v) => v,
+                None => return Err((format!("{} is not a int.", n), to_val.span).into()),
+            },
+            v => {
+                return Err((
+                    format!("{} is not an integer.", v.to_css_string(to_val.span)?),
+                    to_val.span,
+                )
+                    .into())
+            }
+        };
+
+        let through = if through { 1 } else { 0 };
+
+        let (mut x, mut y);
+        // we can't use an inclusive range here
+        #[allow(clippy::range_plus_one)]
+        let iter: &mut dyn Iterator<Item = isize> = if from < to {
+            x = from..(to + through);
+            &mut x
+        } else {
+            y = ((to - through)..(from + 1)).skip(1).rev();
+            &mut y
+        };
+
+        let mut stmts = Vec::new();
+
+        self.scopes.enter_new_scope();
+
+        for i in iter {
+            self.scopes.insert_var(
+                var.node,
+                Spanned {
+                    node: Value::Dimension(Number::from(i), Unit::None, None),
+                    span: var.span,
+                },
+                self.global_scope,
+            );
+            let mut these_stmts = self.eval_control_flow_body(body)?;
+            if self.flags.in_function() && !these_stmts.is_empty() {
+                self.scopes.exit_scope();
+                return Ok(these_stmts);
+            }
+            stmts.append(&mut these_stmts);
+        }
+
+        self.scopes.exit_scope();
+
+        Ok(stmts)
+    }
+
+    fn eval_while(&mut self, cond: &Expr, body: &[AstStmt]) -> SassResult<Vec<Stmt>> {
+        let mut stmts = Vec::new();
+        self.scopes.enter_new_scope();
+        while self.eval_expr(cond)?.node.is_true() {
+            let mut these_stmts = self.eval_control_flow_body(body)?;
+            if self.flags.in_function() && !these_stmts.is_empty() {
+                self.scopes.exit_scope();
+                return Ok(these_stmts);
+            }
+            stmts.append(&mut these_stmts);
+        }
+        self.scopes.exit_scope();
+
+        Ok(stmts)
+    }
+
+    fn eval_each(
+        &mut self,
+        vars: &[Spanned<Identifier>],
+        list: &Expr,
+        body: &[AstStmt],
+    ) -> SassResult<Vec<Stmt>> {
+        let Spanned {
+            node: list,
+            span: list_span,
+        } = self.eval_expr(list)?;
+        let iter = list.as_list();
+
+        let mut stmts = Vec::new();
+
+        self.scopes.enter_new_scope();
+
+        for row in iter {
+            let row = self.without_slash(row, list_span)?;
+            let this_iterator = row.clone().as_list();
+            if vars.len() == 1 {
+                let node = if this_iterator.len() == 1 {
+                    row
+                } else {
+                    Value::List(Rc::new(this_iterator), ListSeparator::Space, Brackets::None)
+                };
+                self.scopes.insert_var(
+                    vars[0].node,
+                    Spanned {
+                        node,
+                        span: vars[0].span,
+                    },
+                    self.global_scope,
+                );
+            } else {
+                for (var, val) in vars.iter().zip(
+                    this_iterator
+                        .into_iter()
+                        .chain(std::iter::once(Value::Null).cycle()),
+                ) {
+                    self.scopes.insert_var(
+                        var.node,
+                        Spanned {
+                            node: val,
+                            span: var.span,
+                        },
+                        self.global_scope,
+                    );
+                }
+            }
+
+            let mut these_stmts = self.eval_control_flow_body(body)?;
+            if self.flags.in_function() && !these_stmts.is_empty() {
+                self.scopes.exit_scope();
+                return Ok(these_stmts);
+            }
+            stmts.append(&mut these_stmts);
+        }
+
+        self.scopes.exit_scope();
+
+        Ok(stmts)
+    }
+
+    fn eval_style(
+        &mut self,
+        super_property: Option<&str>,
+        property: &Interpolation,
+        value: Option<&Expr>,
+        body: &[AstStmt],
+        stmts: &mut Vec<Stmt>,
+    ) -> SassResult<()> {
+        let mut property = self.eval_interpolation(property)?;
+        if let Some(super_property) = super_property {
+            property = format!("{}-{}", super_property, property);
+        }
+
+        if let Some(value) = value {
+            let value = self.eval_expr(value)?;
+            stmts.push(Stmt::Style(Style {
+                property: property.clone(),
+                value: Box::new(value),
+            }));
+        }
+
+        for stmt in body {
+            if let AstStmt::Style {
+                property: nested,
+                value,
+                body,
+                ..
+            } = stmt
+            {
+                self.eval_style(Some(&property), nested, value.as_ref(), body, stmts)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evaluate the body of an at-rule such as `@media`, wrapping any styles
+    /// directly within it in the current style rule
+    fn eval_at_rule_body(&mut self, body: &[AstStmt]) -> SassResult<Vec<Stmt>> {
+        let at_root = self.at_root;
+        self.at_root = false;
+        let raw_body = self.eval_ast(body);
+        self.at_root = at_root;
+
+        let mut rules = Vec::new();
+        let mut body = Vec::new();
+
+        for stmt in raw_body? {
+            match stmt {
+                Stmt::Style(..) => body.push(stmt),
+                _ => rules.push(stmt),
+            }
+        }
+
+        if !self.super_selectors.last().is_empty() {
+            body = vec![Stmt::RuleSet {
+                selector: ExtendedSelector::new(self.super_selectors.last().clone().0),
+                body,
+            }];
+        }
+
+        body.append(&mut rules);
+
+        Ok(body)
+    }
+
+    fn eval_at_root(
+        &mut self,
+        selector: Option<&Interpolation>,
+        body: &[AstStmt],
+    ) -> SassResult<Vec<Stmt>> {
+        let at_root_has_selector = selector.is_some();
+        let at_rule_selector = match selector {
+            Some(selector) => {
+                let span = selector.span;
+                let selector = self.eval_selector_interpolation(selector)?;
+                self.parse_selector_from_string(true, selector, span)?
+            }
+            None => self.super_selectors.last().clone(),
+        }
+        .resolve_parent_selectors(self.super_selectors.last(), false)?;
+
+        let mut styles = Vec::new();
+        let raw_stmts = Parser {
+            toks: self.toks,
+            map: self.map,
+            path: self.path,
+            scopes: self.scopes,
+            global_scope: self.global_scope,
+            super_selectors: &mut NeverEmptyVec::new(at_rule_selector.clone()),
+            span_before: self.span_before,
+            content: self.content,
+            flags: self.flags,
+            at_root: true,
+            at_root_has_selector,
+            extender: self.extender,
+            cache: self.cache,
+            modules: self.modules,
+            random: self.random,
+            logger: self.logger,
+        }
+        .eval_ast(body)?
+        .into_iter()
+        .filter_map(|s| match s {
+            Stmt::Style(..) => {
+                styles.push(s);
+                None
+            }
+            _ => Some(s),
+        })
+        .collect::<Vec<Stmt>>();
+
+        let mut stmts = vec![Stmt::RuleSet {
+            selector: ExtendedSelector::new(at_rule_selector.0),
+            body: styles,
+        }];
+        stmts.extend(raw_stmts);
+        Ok(stmts)
+    }
+
+    fn eval_extend(
+        &mut self,
+        selector: &Interpolation,
+        is_optional: bool,
+        span: Span,
+    ) -> SassResult<()> {
+        // todo: track when inside ruleset or `@content`
+        // if !self.in_style_rule && !self.in_mixin && !self.in_content_block {
+        //     return Err(("@extend may only be used within style rules.", span).into());
+        // }
+        self.span_before = span;
+        let selector_text = self.eval_selector_interpolation(selector)?;
+        let value = self.parse_selector_from_string(false, selector_text, selector.span)?;
+
+        let extend_rule = ExtendRule::new(value.clone(), is_optional, span);
+
+        let super_selector = self.super_selectors.last();
+
+        for complex in value.0.components {
+            if complex.components.len() != 1 || !complex.components.first().unwrap().is_compound() {
+                // If the selector was a compound selector but not a simple
+                // selector, emit a more explicit error.
+                return Err(("complex selectors may not be extended.", span).into());
+            }
+
+            let compound = match complex.components.first() {
+                Some(ComplexSelectorComponent::Compound(c)) => c.clone(),
+                Some(..) | None => todo!(),
+            };
+            if compound.components.len() != 1 {
+                return Err((
+                    format!(
+                        "compound selectors may no longer be extended.\nConsider `@extend {}` instead.\nSee http://bit.ly/ExtendCompound for details.\n",
+                        compound.components.into_iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", ")
+                    )
+                , span).into());
+            }
+
+            self.extender.add_extension(
+                super_selector.clone().0,
+                compound.components.first().unwrap(),
+                &extend_rule,
+                &None,
+                Some(span),
+            )
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn eval_interpolation(
+        &mut self,
+        interpolation: &Interpolation,
+    ) -> SassResult<String> {
+        let mut buf = String::new();
+        for part in &interpolation.parts {
+            match part {
+                InterpolationPart::Literal(s) => buf.push_str(s),
+                InterpolationPart::Expr(expr) => buf.push_str(&self.eval_interpolated_expr(expr)?),
+            }
+        }
+        Ok(buf)
+    }
+
+    /// Evaluate the interpolation in a selector, normalizing the whitespace
+    /// around commas in the same way as `Parser::parse_selector`
+    fn eval_selector_interpolation(&mut self, interpolation: &Interpolation) -> SassResult<String> {
+        let mut buf = String::new();
+        for part in &interpolation.parts {
+            match part {
+                InterpolationPart::Literal(s) => {
+                    for c in s.chars() {
+                        if c != ',' {
+                            buf.push(c);
+                            continue;
+                        }
+                        while let Some(c) = buf.pop() {
+                            if c == ' ' || c == ',' {
+                                continue;
+                            }
+                            buf.push(c);
+                            buf.push(',');
+                            break;
+                        }
+                    }
+                }
+                InterpolationPart::Expr(expr) => buf.push_str(&self.eval_interpolated_expr(expr)?),
+            }
+        }
+        Ok(buf)
+    }
+
+    fn eval_interpolated_expr(&mut self, expr: &Expr) -> SassResult<String> {
+        let Spanned { node, span } = self.eval_expr(expr)?;
+        Ok(match node.unquote() {
+            Value::String(s, ..) => s,
+            v => v.to_css_string(span)?.into_owned(),
+        })
+    }
+}
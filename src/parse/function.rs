@@ -1,72 +1,73 @@
-use codemap::Spanned;
-use peekmore::PeekMore;
+use std::rc::Rc;
+
+use codemap::{Span, Spanned};
 
 use crate::{
-    args::CallArgs,
+    args::{CallArgs, FuncArgs},
+    ast::AstStmt,
     atrule::Function,
-    common::unvendor,
+    common::{unvendor, Identifier},
     error::SassResult,
-    utils::{read_until_closing_curly_brace, read_until_semicolon_or_closing_curly_brace},
     value::Value,
     Token,
 };
 
-use super::{common::ContextFlags, NeverEmptyVec, Parser, Stmt};
+use super::{common::ContextFlags, Parser, Stmt};
 
 /// Names that functions are not allowed to have
 const FORBIDDEN_IDENTIFIERS: [&str; 7] =
     ["calc", "element", "expression", "url", "and", "or", "not"];
 
 impl<'a> Parser<'a> {
-    pub(super) fn parse_function(&mut self) -> SassResult<()> {
+    pub(super) fn parse_function(&mut self) -> SassResult<AstStmt> {
         self.whitespace_or_comment();
-        let Spanned { node: name, span } = self.parse_identifier()?;
+        let Spanned { node: name, span } = self.parse_identifier_no_interpolation(false)?;
 
+        // like dart-sass, this is an error as soon as the mixin is declared,
+        // rather than when it's included
         if self.flags.in_mixin() {
             return Err(("Mixins may not contain function declarations.", span).into());
         }
 
-        if self.flags.in_control_flow() {
-            return Err(("Functions may not be declared in control directives.", span).into());
-        }
-
         if FORBIDDEN_IDENTIFIERS.contains(&unvendor(&name)) {
             return Err(("Invalid function name.", span).into());
         }
 
         self.whitespace_or_comment();
         let args = match self.toks.next() {
-            Some(Token { kind: '(', .. }) => self.parse_func_args()?,
+            Some(tok) if tok.is_symbol('(') => self.parse_func_args()?,
             Some(Token { pos, .. }) => return Err(("expected \"(\".", pos).into()),
             None => return Err(("expected \"(\".", span).into()),
         };
 
         self.whitespace();
 
-        let mut body = read_until_closing_curly_brace(self.toks)?;
-        body.push(match self.toks.next() {
-            Some(tok) => tok,
-            None => return Err(("expected \"}\".", self.span_before).into()),
-        });
+        let body = self.parse_block()?;
         self.whitespace();
 
-        let function = Function::new(self.scopes.last().clone(), args, body, span);
-
-        if self.at_root {
-            self.global_scope.insert_fn(name, function);
-        } else {
-            self.scopes.last_mut().insert_fn(name, function);
-        }
-        Ok(())
+        Ok(AstStmt::Function {
+            name: Identifier::from(name),
+            args,
+            body: Rc::new(body),
+            span,
+        })
     }
 
-    pub(super) fn parse_return(&mut self) -> SassResult<Box<Value>> {
-        let toks = read_until_semicolon_or_closing_curly_brace(self.toks)?;
-        let v = self.parse_value_from_vec(toks)?;
-        if let Some(Token { kind: ';', .. }) = self.toks.peek() {
-            self.toks.next();
+    pub(super) fn eval_function_decl(
+        &mut self,
+        name: Identifier,
+        args: &FuncArgs,
+        body: &Rc<Vec<AstStmt>>,
+        span: Span,
+    ) -> SassResult<()> {
+        if self.flags.in_control_flow() {
+            return Err(("Functions may not be declared in control directives.", span).into());
         }
-        Ok(Box::new(v.node))
+
+        let function = Function::new(self.scopes.clone(), args.clone(), Rc::clone(body), span);
+
+        self.scopes.insert_fn(name, function, self.global_scope);
+        Ok(())
     }
 
     pub fn eval_function(&mut self, function: Function, args: CallArgs) -> SassResult<Value> {
@@ -77,13 +78,14 @@ impl<'a> Parser<'a> {
             ..
         } = function;
 
-        self.eval_args(fn_args, args, &mut scope)?;
+        let span = args.span();
+        let arg_list = self.eval_args(&fn_args, args, &mut scope)?;
 
         let mut return_value = Parser {
-            toks: &mut body.into_iter().peekmore(),
+            toks: self.toks,
             map: self.map,
             path: self.path,
-            scopes: &mut NeverEmptyVec::new(scope),
+            scopes: &mut scope,
             global_scope: self.global_scope,
             super_selectors: self.super_selectors,
             span_before: self.span_before,
@@ -92,8 +94,16 @@ impl<'a> Parser<'a> {
             at_root: false,
             at_root_has_selector: self.at_root_has_selector,
             extender: self.extender,
+            cache: self.cache,
+            modules: self.modules,
+            random: self.random,
+            logger: self.logger,
+        }
+        .eval_ast(&body)?;
+
+        if let Some(arg_list) = arg_list {
+            arg_list.verify_keywords_accessed(span)?;
         }
-        .parse()?;
 
         debug_assert!(return_value.len() <= 1);
         match return_value
@@ -1,112 +1,105 @@
-use std::{borrow::Borrow, iter::Iterator};
-
-use codemap::Spanned;
+use codemap::{Span, Spanned};
 
 use crate::{
+    ast::Interpolation,
     common::QuoteKind,
     error::SassResult,
     utils::{as_hex, hex_char_for, is_name, is_name_start},
     value::Value,
-    Token,
+    TokenKind,
 };
 
 use super::Parser;
 
 impl<'a> Parser<'a> {
     fn ident_body_no_interpolation(&mut self, unit: bool) -> SassResult<Spanned<String>> {
+        let start = self.toks.cursor();
         let mut text = String::new();
-        while let Some(tok) = self.toks.peek() {
-            self.span_before = self.span_before.merge(tok.pos());
-            if unit && tok.kind == '-' {
-                // Disallow `-` followed by a dot or a digit digit in units.
-                let second = match self.toks.peek_forward(1) {
-                    Some(v) => *v,
-                    None => break,
-                };
-
-                self.toks.peek_backward(1).unwrap();
-
-                if second.kind == '.' || second.kind.is_ascii_digit() {
-                    break;
-                }
-
-                self.toks.next();
-                text.push('-');
-            } else if is_name(tok.kind) {
-                text.push(self.toks.next().unwrap().kind);
-            } else if tok.kind == '\\' {
-                self.toks.next();
+        loop {
+            // Disallow `-` followed by a dot or a digit in units.
+            let run = if unit {
+                self.toks.next_unit()
+            } else {
+                self.toks.next_name()
+            };
+            if let Some(run) = run {
+                text.push_str(self.toks.text(run));
+            } else if self.toks.peek_char() == Some('\\') {
+                self.toks.next_char();
                 text.push_str(&self.escape(false)?);
             } else {
                 break;
             }
         }
+        if self.toks.cursor() > start {
+            self.span_before = self.span_before.merge(self.toks.span_from(start));
+        }
         Ok(Spanned {
             node: text,
             span: self.span_before,
         })
     }
 
-    fn interpolated_ident_body(&mut self, buf: &mut String) -> SassResult<()> {
-        while let Some(tok) = self.toks.peek() {
-            match tok.kind {
-                'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '\u{80}'..=std::char::MAX => {
-                    self.span_before = self.span_before.merge(tok.pos());
-                    buf.push(self.toks.next().unwrap().kind);
-                }
-                '\\' => {
-                    self.toks.next();
-                    buf.push_str(&self.escape(false)?);
-                }
-                '#' => {
-                    if let Some(Token { kind: '{', .. }) = self.toks.peek_forward(1).cloned() {
-                        self.toks.next();
-                        self.toks.next();
-                        // TODO: if ident, interpolate literally
-                        let interpolation = self.parse_interpolation()?;
-                        buf.push_str(&interpolation.node.to_css_string(interpolation.span)?);
-                    } else {
-                        self.toks.reset_cursor();
-                        break;
-                    }
-                }
-                _ => break,
+    fn interpolated_ident_body(&mut self, buf: &mut Interpolation) -> SassResult<()> {
+        loop {
+            let start = self.toks.cursor();
+            if let Some(run) = self.toks.next_name() {
+                self.span_before = self.span_before.merge(self.toks.span_from(start));
+                buf.push_str(self.toks.text(run));
+            } else if self.toks.peek_char() == Some('\\') {
+                self.toks.next_char();
+                buf.push_str(&self.escape(false)?);
+            } else if self.toks.scan_str("#{") {
+                buf.push_expr(self.parse_interpolation_expr()?);
+            } else {
+                break;
             }
         }
         Ok(())
     }
 
     fn escape(&mut self, identifier_start: bool) -> SassResult<String> {
+        let start = self.toks.cursor();
         let mut value = 0;
-        let first = match self.toks.peek() {
-            Some(t) => t,
+        let first = match self.toks.peek_char() {
+            Some(c) => c,
             None => return Ok(String::new()),
         };
-        let mut span = first.pos();
-        if first.kind == '\n' {
-            return Err(("Expected escape sequence.", span).into());
-        } else if first.kind.is_ascii_hexdigit() {
+        if matches!(first, '\n' | '\r' | '\x0C') {
+            self.toks.next_char();
+            return Err(("Expected escape sequence.", self.toks.span_from(start)).into());
+        } else if first.is_ascii_hexdigit() {
             for _ in 0..6 {
-                let next = match self.toks.peek() {
-                    Some(t) => t,
-                    None => break,
-                };
-                if !next.kind.is_ascii_hexdigit() {
-                    break;
+                match self.toks.peek_char() {
+                    Some(c) if c.is_ascii_hexdigit() => {
+                        self.toks.next_char();
+                        value = value * 16 + as_hex(c);
+                    }
+                    Some(..) | None => break,
                 }
-                value *= 16;
-                span = span.merge(next.pos());
-                value += as_hex(self.toks.next().unwrap().kind)
-            }
-            if self.toks.peek().is_some() && self.toks.peek().unwrap().kind.is_whitespace() {
-                self.toks.next();
             }
-        } else {
-            let next = self.toks.next().unwrap();
-            span = span.merge(next.pos());
-            value = next.kind as u32;
+            let span = self.toks.span_from(start);
+            self.skip_escape_whitespace(char::is_whitespace);
+            return self.escaped_char(value, identifier_start, span);
+        } else if let Some(c) = self.toks.next_char() {
+            value = c.node as u32;
         }
 
+        self.escaped_char(value, identifier_start, self.toks.span_from(start))
+    }
+
+    /// Skip the single whitespace character that may end a hex escape, where
+    /// `\r\n` counts as one character
+    fn skip_escape_whitespace(&mut self, is_whitespace: impl Fn(char) -> bool) {
+        if self.toks.scan_str("\r\n") {
+            return;
+        }
+        if matches!(self.toks.peek_char(), Some(c) if is_whitespace(c)) {
+            self.toks.next_char();
+        }
+    }
+
+    fn escaped_char(&self, value: u32, identifier_start: bool, span: Span) -> SassResult<String> {
         let c = std::char::from_u32(value).ok_or(("Invalid escape sequence.", span))?;
         if (identifier_start && is_name_start(c) && !c.is_digit(10))
             || (!identifier_start && is_name(c))
@@ -126,69 +119,62 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parse an identifier, evaluating any interpolation within it
+    ///
+    /// This is only used when parsing text that was produced at runtime, such
+    /// as a selector containing interpolation.
     pub(crate) fn parse_identifier(&mut self) -> SassResult<Spanned<String>> {
-        let Token { kind, pos } = self
+        let ident = self.parse_interpolated_identifier()?;
+        Ok(Spanned {
+            node: self.eval_interpolation(&ident.node)?,
+            span: ident.span,
+        })
+    }
+
+    pub(crate) fn parse_interpolated_identifier(&mut self) -> SassResult<Spanned<Interpolation>> {
+        let first = self
             .toks
             .peek()
-            .cloned()
             .ok_or(("Expected identifier.", self.span_before))?;
-        let mut text = String::new();
-        if kind == '-' {
-            self.toks.next();
-            text.push('-');
-            match self.toks.peek() {
-                Some(Token { kind: '-', .. }) => {
-                    self.toks.next();
-                    text.push('-');
-                    self.interpolated_ident_body(&mut text)?;
-                    return Ok(Spanned {
-                        node: text,
-                        span: pos,
-                    });
-                }
-                Some(..) => {}
-                None => {
-                    return Ok(Spanned {
-                        node: text,
-                        span: self.span_before,
-                    })
+        let mut text = Interpolation::new(first.pos);
+        match first.kind {
+            // unlike in dart-sass, identifiers may begin with a digit, so that
+            // selectors such as `#2foo` are supported
+            TokenKind::Ident | TokenKind::Number => {
+                if let Some(run) = self.toks.next_name() {
+                    text.push_str(self.toks.text(run));
                 }
+                self.span_before = first.pos;
             }
-        }
-
-        let Token { kind: first, pos } = match self.toks.peek() {
-            Some(v) => *v,
-            None => return Err(("Expected identifier.", self.span_before).into()),
-        };
+            kind => {
+                if kind == TokenKind::Symbol('-') {
+                    self.toks.next();
+                    text.push_char('-');
+                    if self.toks.peek().is_none() {
+                        text.span = self.span_before;
+                        return Ok(Spanned {
+                            node: text,
+                            span: self.span_before,
+                        });
+                    }
+                }
 
-        if is_name_start(first) {
-            text.push(self.toks.next().unwrap().kind);
-        } else if first == '\\' {
-            self.toks.next();
-            text.push_str(&self.escape(true)?);
-        // TODO: peekmore
-        // (first == '#' && scanner.peekChar(1) == $lbrace)
-        } else if first == '#' {
-            self.toks.next();
-            let Token { kind, pos } = if let Some(tok) = self.toks.peek() {
-                *tok
-            } else {
-                return Err(("Expected identifier.", pos).into());
-            };
-            if kind == '{' {
-                self.toks.next();
-                match self.parse_interpolation()?.node {
-                    Value::String(ref s, ..) => text.push_str(s),
-                    v => text.push_str(v.to_css_string(self.span_before)?.borrow()),
+                if let Some(run) = self.toks.next_name() {
+                    text.push_str(self.toks.text(run));
+                } else if self.toks.peek_char() == Some('\\') {
+                    self.toks.next_char();
+                    text.push_str(&self.escape(true)?);
+                } else if self.toks.scan_str("#{") {
+                    text.push_expr(self.parse_interpolation_expr()?);
+                } else {
+                    let pos = self.toks.peek().map_or(first.pos, |tok| tok.pos);
+                    return Err(("Expected identifier.", pos).into());
                 }
-            } else {
-                return Err(("Expected identifier.", pos).into());
             }
-        } else {
-            return Err(("Expected identifier.", pos).into());
         }
 
         self.interpolated_ident_body(&mut text)?;
+        text.span = self.span_before;
         Ok(Spanned {
             node: text,
             span: self.span_before,
@@ -199,41 +185,42 @@ impl<'a> Parser<'a> {
         &mut self,
         unit: bool,
     ) -> SassResult<Spanned<String>> {
-        let Token {
-            kind,
-            pos: mut span,
-        } = self
+        let first = self
             .toks
             .peek()
             .ok_or(("Expected identifier.", self.span_before))?;
+        let mut span = first.pos;
         let mut text = String::new();
-        if kind == &'-' {
-            self.toks.next();
-            text.push('-');
-
-            match self.toks.peek() {
-                Some(Token { kind: '-', .. }) => {
+        match first.kind {
+            TokenKind::Ident | TokenKind::Number => {
+                let run = if unit {
+                    self.toks.next_unit()
+                } else {
+                    self.toks.next_name()
+                };
+                if let Some(run) = run {
+                    text.push_str(self.toks.text(run));
+                }
+            }
+            kind => {
+                if kind == TokenKind::Symbol('-') {
                     self.toks.next();
                     text.push('-');
-                    text.push_str(&self.ident_body_no_interpolation(unit)?.node);
-                    return Ok(Spanned { node: text, span });
+                    if self.toks.peek().is_none() {
+                        return Ok(Spanned { node: text, span });
+                    }
                 }
-                Some(..) => {}
-                None => return Ok(Spanned { node: text, span }),
-            }
-        }
 
-        let first = match self.toks.next() {
-            Some(v) => v,
-            None => return Err(("Expected identifier.", span).into()),
-        };
-
-        if is_name_start(first.kind) {
-            text.push(first.kind);
-        } else if first.kind == '\\' {
-            text.push_str(&self.escape(true)?);
-        } else {
-            return Err(("Expected identifier.", first.pos).into());
+                if let Some(run) = self.toks.next_name() {
+                    text.push_str(self.toks.text(run));
+                } else {
+                    match self.toks.next_char() {
+                        Some(Spanned { node: '\\', .. }) => text.push_str(&self.escape(true)?),
+                        Some(c) => return Err(("Expected identifier.", c.span).into()),
+                        None => return Err(("Expected identifier.", span).into()),
+                    }
+                }
+            }
         }
 
         let body = self.ident_body_no_interpolation(unit)?;
@@ -242,85 +229,146 @@ impl<'a> Parser<'a> {
         Ok(Spanned { node: text, span })
     }
 
-    pub(crate) fn parse_quoted_string(&mut self, q: char) -> SassResult<Spanned<Value>> {
-        let mut s = String::new();
-        let mut span = self
-            .toks
-            .peek()
-            .ok_or((format!("Expected {}.", q), self.span_before))?
-            .pos();
-        while let Some(tok) = self.toks.next() {
-            span = span.merge(tok.pos());
-            match tok.kind {
-                '"' if q == '"' => break,
-                '\'' if q == '\'' => break,
+    /// Parse a quoted string, evaluating any interpolation within it
+    pub(crate) fn parse_quoted_string(&mut self) -> SassResult<Spanned<Value>> {
+        let string = self.parse_interpolated_string()?;
+        Ok(Spanned {
+            node: Value::String(self.eval_interpolation(&string.node)?, QuoteKind::Quoted),
+            span: string.span,
+        })
+    }
+
+    /// Parse the contents of the quoted string at the cursor, resolving any
+    /// escapes within it
+    pub(crate) fn parse_interpolated_string(&mut self) -> SassResult<Spanned<Interpolation>> {
+        let start = self.toks.cursor();
+        let q = match self.toks.next_char() {
+            Some(c) => c.node,
+            None => return Err(("Expected string.", self.span_before).into()),
+        };
+        let mut s = Interpolation::new(self.toks.span_from(start));
+        loop {
+            if let Some(text) = self
+                .toks
+                .next_text(|b| b == q as u8 || matches!(b, b'\\' | b'#' | b'\n' | b'\r' | b'\x0C'))
+            {
+                s.push_str(text.node);
+            }
+            let c = match self.toks.next_char() {
+                Some(c) => c,
+                None => return Err((format!("Expected {}.", q), self.toks.span_from(start)).into()),
+            };
+            match c.node {
+                c if c == q => break,
                 '#' => {
-                    if let Some(Token { kind: '{', pos }) = self.toks.peek() {
-                        self.span_before = self.span_before.merge(*pos);
-                        self.toks.next();
-                        let interpolation = self.parse_interpolation()?;
-                        match interpolation.node {
-                            Value::String(ref v, ..) => s.push_str(v),
-                            v => s.push_str(v.to_css_string(interpolation.span)?.borrow()),
-                        };
-                        continue;
+                    if self.toks.scan_str("{") {
+                        self.span_before = self.toks.span_from(start);
+                        s.push_expr(self.parse_interpolation_expr()?);
                     } else {
-                        s.push('#');
-                        continue;
+                        s.push_char('#');
                     }
                 }
-                '\n' => return Err(("Expected \".", tok.pos()).into()),
-                '\\' => {
-                    let first = match self.toks.peek() {
-                        Some(c) => c,
-                        None => {
-                            s.push('\u{FFFD}');
-                            continue;
-                        }
-                    };
+                '\\' => self.parse_string_escape(&mut s),
+                _ => return Err(("Expected \".", c.span).into()),
+            }
+        }
+        let span = self.toks.span_from(start);
+        s.span = span;
+        Ok(Spanned { node: s, span })
+    }
+
+    /// Resolve an escape within a quoted string, assuming the `\\` has already
+    /// been consumed
+    fn parse_string_escape(&mut self, s: &mut Interpolation) {
+        let first = match self.toks.peek_char() {
+            Some(c) => c,
+            None => {
+                s.push_char('\u{FFFD}');
+                return;
+            }
+        };
+
+        // an escaped newline continues the string on the next line
+        if matches!(first, '\n' | '\r' | '\x0C') {
+            if !self.toks.scan_str("\r\n") {
+                self.toks.next_char();
+            }
+            return;
+        }
 
-                    if first.kind == '\n' {
-                        self.toks.next();
-                        continue;
+        if first.is_ascii_hexdigit() {
+            let mut value = 0;
+            for _ in 0..6 {
+                match self.toks.peek_char() {
+                    Some(c) if c.is_ascii_hexdigit() => {
+                        self.toks.next_char();
+                        value = (value << 4) + as_hex(c);
                     }
+                    Some(..) | None => break,
+                }
+            }
 
-                    if first.kind.is_ascii_hexdigit() {
-                        let mut value = 0;
-                        for _ in 0..6 {
-                            let next = match self.toks.peek() {
-                                Some(c) => c,
-                                None => break,
-                            };
-                            if !next.kind.is_ascii_hexdigit() {
-                                break;
-                            }
-                            value = (value << 4) + as_hex(self.toks.next().unwrap().kind);
-                        }
+            self.skip_escape_whitespace(|c| c.is_ascii_whitespace());
 
-                        if self.toks.peek().is_some()
-                            && self.toks.peek().unwrap().kind.is_ascii_whitespace()
-                        {
-                            self.toks.next();
-                        }
+            if value == 0 || (value >= 0xD800 && value <= 0xDFFF) || value >= 0x0010_FFFF {
+                s.push_char('\u{FFFD}');
+            } else {
+                s.push_char(std::char::from_u32(value).unwrap());
+            }
+        } else if let Some(c) = self.toks.next_char() {
+            s.push_char(c.node);
+        }
+    }
 
-                        if value == 0
-                            || (value >= 0xD800 && value <= 0xDFFF)
-                            || value >= 0x0010_FFFF
-                        {
-                            s.push('\u{FFFD}');
-                        } else {
-                            s.push(std::char::from_u32(value).unwrap());
-                        }
-                    } else {
-                        s.push(self.toks.next().unwrap().kind);
+    /// Copy the quoted string at the cursor into `buf` as it's written, quotes
+    /// and escapes included, evaluating only its interpolation
+    ///
+    /// This is used where strings are passed through to the output, such as in
+    /// selectors and the parameters of unknown at-rules.
+    pub(crate) fn parse_raw_string(&mut self, buf: &mut Interpolation) -> SassResult<()> {
+        let tok = match self.toks.peek() {
+            Some(tok) => tok,
+            None => return Ok(()),
+        };
+        let text = self.toks.text(tok);
+        if !text.contains("#{") {
+            buf.push_str(text);
+            self.toks.next();
+            return Ok(());
+        }
+
+        let q = match self.toks.next_char() {
+            Some(c) => c.node,
+            None => return Ok(()),
+        };
+        buf.push_char(q);
+        loop {
+            if let Some(text) = self
+                .toks
+                .next_text(|b| b == q as u8 || matches!(b, b'\\' | b'#' | b'\n' | b'\r' | b'\x0C'))
+            {
+                buf.push_str(text.node);
+            }
+            if self.toks.scan_str("#{") {
+                buf.push_expr(self.parse_interpolation_expr()?);
+                continue;
+            }
+            // an unterminated string ends at the end of its line
+            if matches!(self.toks.peek_char(), Some('\n' | '\r' | '\x0C') | None) {
+                break;
+            }
+            if let Some(c) = self.toks.next_char() {
+                buf.push_char(c.node);
+                if c.node == q {
+                    break;
+                }
+                if c.node == '\\' {
+                    if let Some(c) = self.toks.next_char() {
+                        buf.push_char(c.node);
                     }
                 }
-                _ => s.push(tok.kind),
             }
         }
-        Ok(Spanned {
-            node: Value::String(s, QuoteKind::Quoted),
-            span,
-        })
+        Ok(())
     }
 }
@@ -1,28 +1,52 @@
-use std::{ffi::OsStr, fs, path::Path};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
-use codemap::Spanned;
-use peekmore::PeekMore;
+use codemap::{Span, Spanned};
 
-use crate::{common::QuoteKind, error::SassResult, lexer::Lexer, value::Value, Token};
+use crate::{
+    ast::{AstStmt, Expr},
+    builtin::Modules,
+    common::{Identifier, QuoteKind},
+    error::SassResult,
+    lexer::Lexer,
+    scope::{Scope, Scopes},
+    value::Value,
+    Token, TokenKind,
+};
 
 use super::{Parser, Stmt};
 
 impl<'a> Parser<'a> {
-    pub(super) fn import(&mut self) -> SassResult<Vec<Stmt>> {
+    pub(super) fn parse_import(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
         self.whitespace();
 
         match self.toks.peek() {
-            Some(Token { kind: '\'', .. })
-            | Some(Token { kind: '"', .. })
-            | Some(Token { kind: 'u', .. }) => {}
-            Some(Token { pos, .. }) => return Err(("Expected string.", *pos).into()),
+            Some(Token {
+                kind: TokenKind::String,
+                ..
+            }) => {}
+            Some(tok) if tok.kind == TokenKind::Ident && self.toks.text(tok).starts_with('u') => {}
+            Some(Token { pos, .. }) => return Err(("Expected string.", pos).into()),
             None => return Err(("expected more input.", self.span_before).into()),
         };
 
+        let url = self.parse_expression()?;
+        self.whitespace_or_comment();
+        self.scan_char(';');
+
+        Ok(AstStmt::Import { url, span })
+    }
+
+    pub(super) fn eval_import(&mut self, url: &Expr) -> SassResult<Vec<Stmt>> {
         let Spanned {
             node: file_name_as_value,
             span,
-        } = self.parse_value()?;
+        } = self.eval_expr(url)?;
         let file_name = match file_name_as_value {
             Value::String(s, QuoteKind::Quoted) => {
                 if s.ends_with(".css") || s.starts_with("http://") || s.starts_with("https://") {
@@ -41,10 +65,137 @@ impl<'a> Parser<'a> {
             _ => return Err(("Expected string.", span).into()),
         };
 
-        self.whitespace();
+        if let Some(name) = self.find_import(file_name.as_ref()) {
+            let file = self.cache.load(&name, self.map)?;
+
+            let mut parser = Parser {
+                toks: &mut Lexer::new(&file),
+                map: self.map,
+                path: &name,
+                scopes: self.scopes,
+                global_scope: self.global_scope,
+                super_selectors: self.super_selectors,
+                span_before: file.span.subspan(0, 0),
+                content: self.content,
+                flags: self.flags,
+                at_root: self.at_root,
+                at_root_has_selector: self.at_root_has_selector,
+                extender: self.extender,
+                cache: self.cache,
+                modules: self.modules,
+                random: self.random,
+                logger: self.logger,
+            };
+            let ast = parser.parse_file(&name)?;
+            return parser.eval_ast(&ast);
+        }
+
+        Err(("Can't find stylesheet to import.", span).into())
+    }
 
-        let path: &Path = file_name.as_ref();
+    /// Evaluate the stylesheet at `url` for `meta.load-css()`, emitting its
+    /// CSS where the mixin was included
+    ///
+    /// Unlike `@import`, the stylesheet gets its own global scope, which
+    /// starts out with the variables in `config` so that they override any
+    /// `!default` declarations.
+    pub(crate) fn load_css(
+        &mut self,
+        url: &str,
+        config: Vec<(Identifier, Value)>,
+        span: Span,
+    ) -> SassResult<Vec<Stmt>> {
+        let name = match self.find_import(url.as_ref()) {
+            Some(name) => name,
+            None => return Err(("Can't find stylesheet to import.", span).into()),
+        };
+
+        let file = self.cache.load(&name, self.map)?;
 
+        let mut global_scope = Scope::new();
+        for (name, value) in &config {
+            global_scope.insert_var(
+                *name,
+                Spanned {
+                    node: value.clone(),
+                    span,
+                },
+            );
+        }
+
+        let mut parser = Parser {
+            toks: &mut Lexer::new(&file),
+            map: self.map,
+            path: &name,
+            scopes: &mut Scopes::new(),
+            global_scope: &mut global_scope,
+            super_selectors: self.super_selectors,
+            span_before: file.span.subspan(0, 0),
+            content: &mut Vec::new(),
+            flags: self.flags,
+            at_root: self.at_root,
+            at_root_has_selector: self.at_root_has_selector,
+            extender: self.extender,
+            cache: self.cache,
+            modules: &mut Modules::new(),
+            random: self.random,
+            logger: self.logger,
+        };
+        let ast = parser.parse_file(&name)?;
+
+        // every configured variable must be used by a `!default` declaration
+        let mut declared = HashMap::new();
+        global_variable_decls(&ast, true, &mut declared);
+
+        let stmts = parser.eval_ast(&ast)?;
+
+        for (name, _) in config {
+            match declared.get(&name) {
+                Some(true) => {}
+                Some(false) => {
+                    return Err((
+                        "This variable was not declared with !default in the @used module.",
+                        span,
+                    )
+                        .into())
+                }
+                None => {
+                    return Err((
+                        format!(
+                            "${} was not declared with !default in the @used module.",
+                            name
+                        ),
+                        span,
+                    )
+                        .into())
+                }
+            }
+        }
+
+        Ok(stmts)
+    }
+
+    /// Parse the file at `path`, which has just been loaded, reusing its syntax
+    /// tree if it hasn't changed since it was last parsed
+    fn parse_file(&mut self, path: &Path) -> SassResult<Rc<Vec<AstStmt>>> {
+        // declaring a function is an error inside of a mixin, so the tree of a
+        // file imported by one may differ
+        if self.flags.in_mixin() {
+            return Ok(Rc::new(self.parse_stylesheet()?));
+        }
+
+        if let Some(ast) = self.cache.parsed(path) {
+            return Ok(ast);
+        }
+
+        let ast = Rc::new(self.parse_stylesheet()?);
+        self.cache.insert_parsed(path, Rc::clone(&ast));
+        Ok(ast)
+    }
+
+    /// Find the file that `@import` or `meta.load-css()` would load for `path`,
+    /// relative to the current file
+    fn find_import(&self, path: &Path) -> Option<PathBuf> {
         let path_buf = if path.is_absolute() {
             // todo: test for absolute path imports
             path.into()
@@ -67,34 +218,61 @@ impl<'a> Parser<'a> {
             path_buf.join("_index.scss"),
         ];
 
-        for name in &paths {
-            if name.is_file() {
-                let file = self.map.add_file(
-                    name.to_string_lossy().into(),
-                    String::from_utf8(fs::read(name)?)?,
-                );
-
-                return Parser {
-                    toks: &mut Lexer::new(&file)
-                        .collect::<Vec<Token>>()
-                        .into_iter()
-                        .peekmore(),
-                    map: self.map,
-                    path: name.as_ref(),
-                    scopes: self.scopes,
-                    global_scope: self.global_scope,
-                    super_selectors: self.super_selectors,
-                    span_before: file.span.subspan(0, 0),
-                    content: self.content,
-                    flags: self.flags,
-                    at_root: self.at_root,
-                    at_root_has_selector: self.at_root_has_selector,
-                    extender: self.extender,
+        paths.iter().find(|name| name.is_file()).cloned()
+    }
+}
+
+/// Record every variable declared in the global scope by `stmts`, along with
+/// whether any of its declarations were marked `!default`
+fn global_variable_decls(stmts: &[AstStmt], at_root: bool, decls: &mut HashMap<Identifier, bool>) {
+    for stmt in stmts {
+        match stmt {
+            AstStmt::VariableDecl {
+                name,
+                global,
+                default,
+                ..
+            } if at_root || *global => *decls.entry(*name).or_insert(false) |= *default,
+            AstStmt::RuleSet { body, .. }
+            | AstStmt::Media { body, .. }
+            | AstStmt::Supports { body, .. }
+            | AstStmt::AtRoot { body, .. }
+            | AstStmt::Keyframes { body, .. }
+            | AstStmt::UnknownAtRule {
+                body: Some(body), ..
+            } => global_variable_decls(body, false, decls),
+            AstStmt::Include {
+                content: Some(content),
+                ..
+            } => global_variable_decls(&content.body, false, decls),
+            AstStmt::If {
+                branches, else_, ..
+            } => {
+                for branch in branches {
+                    global_variable_decls(&branch.body, at_root, decls);
                 }
-                .parse();
+                global_variable_decls(else_, at_root, decls);
             }
+            AstStmt::Each { body, .. }
+            | AstStmt::For { body, .. }
+            | AstStmt::While { body, .. } => global_variable_decls(body, at_root, decls),
+            // the bodies of mixins and functions aren't evaluated when the
+            // stylesheet is loaded
+            AstStmt::VariableDecl { .. }
+            | AstStmt::Style { .. }
+            | AstStmt::Comment { .. }
+            | AstStmt::Return { .. }
+            | AstStmt::Warn { .. }
+            | AstStmt::Debug { .. }
+            | AstStmt::Error { .. }
+            | AstStmt::Mixin { .. }
+            | AstStmt::Function { .. }
+            | AstStmt::Include { .. }
+            | AstStmt::Content { .. }
+            | AstStmt::Import { .. }
+            | AstStmt::Use { .. }
+            | AstStmt::Extend { .. }
+            | AstStmt::UnknownAtRule { .. } => {}
         }
-
-        Err(("Can't find stylesheet to import.", span).into())
     }
 }
@@ -1,13 +1,14 @@
 use std::fmt;
 
-use peekmore::PeekMore;
+use codemap::Span;
 
 use crate::{
+    ast::{AstStmt, Interpolation},
     atrule::keyframes::{Keyframes, KeyframesSelector},
     error::SassResult,
+    lexer::Lexer,
     parse::Stmt,
-    utils::eat_whole_number,
-    Token,
+    TokenKind,
 };
 
 use super::{common::ContextFlags, Parser};
@@ -34,9 +35,15 @@ impl<'a, 'b> KeyframesSelectorParser<'a, 'b> {
     fn parse_keyframes_selector(&mut self) -> SassResult<Vec<KeyframesSelector>> {
         let mut selectors = Vec::new();
         self.parser.whitespace_or_comment();
-        while let Some(tok) = self.parser.toks.peek().cloned() {
-            match tok.kind {
-                't' | 'T' => {
+        while let Some(tok) = self.parser.toks.peek() {
+            let first = match tok.kind {
+                TokenKind::Ident => self.parser.toks.text(tok).as_bytes()[0],
+                TokenKind::Number => b'0',
+                TokenKind::Symbol(c) if c.is_ascii() => c as u8,
+                _ => 0,
+            };
+            match first {
+                b't' | b'T' => {
                     let mut ident = self.parser.parse_identifier()?;
                     ident.node.make_ascii_lowercase();
                     if ident.node == "to" {
@@ -45,7 +52,7 @@ impl<'a, 'b> KeyframesSelectorParser<'a, 'b> {
                         return Err(("Expected \"to\" or \"from\".", tok.pos).into());
                     }
                 }
-                'f' | 'F' => {
+                b'f' | b'F' => {
                     let mut ident = self.parser.parse_identifier()?;
                     ident.node.make_ascii_lowercase();
                     if ident.node == "from" {
@@ -54,20 +61,22 @@ impl<'a, 'b> KeyframesSelectorParser<'a, 'b> {
                         return Err(("Expected \"to\" or \"from\".", tok.pos).into());
                     }
                 }
-                '0'..='9' => {
-                    let num = eat_whole_number(self.parser.toks);
-                    if !matches!(self.parser.toks.next(), Some(Token { kind: '%', .. })) {
+                b'0'..=b'9' => {
+                    let num = match self.parser.toks.next_digits() {
+                        Some(num) => self.parser.toks.text(num).into(),
+                        None => return Err(("expected \"%\".", tok.pos).into()),
+                    };
+                    if !self.parser.scan_char('%') {
                         return Err(("expected \"%\".", tok.pos).into());
                     }
-                    selectors.push(KeyframesSelector::Percent(num.into_boxed_str()));
+                    selectors.push(KeyframesSelector::Percent(num));
                 }
-                '{' => break,
-                '\\' => todo!("escaped chars in @keyframes selector"),
+                b'{' => break,
+                b'\\' => todo!("escaped chars in @keyframes selector"),
                 _ => return Err(("Expected \"to\" or \"from\".", tok.pos).into()),
             }
             self.parser.whitespace_or_comment();
-            if let Some(Token { kind: ',', .. }) = self.parser.toks.peek() {
-                self.parser.toks.next();
+            if self.parser.scan_char(',') {
                 self.parser.whitespace_or_comment();
             } else {
                 break;
@@ -78,110 +87,40 @@ impl<'a, 'b> KeyframesSelectorParser<'a, 'b> {
 }
 
 impl<'a> Parser<'a> {
-    fn parse_keyframes_name(&mut self) -> SassResult<String> {
-        let mut name = String::new();
+    fn parse_keyframes_name(&mut self) -> SassResult<Interpolation> {
+        let mut name = Interpolation::new(self.span_before);
         self.whitespace_or_comment();
-        while let Some(tok) = self.toks.next() {
+        while let Some(tok) = self.toks.peek() {
+            if tok.kind == TokenKind::String {
+                self.parse_raw_string(&mut name)?;
+                continue;
+            }
+            self.toks.next();
             match tok.kind {
-                '#' => {
-                    if let Some(Token { kind: '{', .. }) = self.toks.peek() {
-                        self.toks.next();
-                        name.push_str(&self.parse_interpolation_as_string()?);
+                TokenKind::Symbol('#') => {
+                    if self.scan_char('{') {
+                        name.push_expr(self.parse_interpolation_expr()?);
                     } else {
-                        name.push('#');
+                        name.push_char('#');
                     }
                 }
-                ' ' | '\n' | '\t' => {
-                    self.whitespace();
-                    name.push(' ');
-                }
-                '{' => {
-                    // todo: we can avoid the reallocation by trimming before emitting
-                    // (in `output.rs`)
-                    return Ok(name.trim().to_string());
-                }
-                _ => name.push(tok.kind),
+                TokenKind::Whitespace => name.push_char(' '),
+                TokenKind::Symbol('{') => return Ok(name),
+                _ => name.push_str(self.toks.text(tok)),
             }
         }
         Err(("expected \"{\".", self.span_before).into())
     }
 
-    pub(super) fn parse_keyframes_selector(
+    /// Parse a keyframes selector whose interpolation has already been
+    /// resolved
+    pub(super) fn parse_keyframes_selector_from_string(
         &mut self,
-        mut string: String,
+        string: String,
+        span: Span,
     ) -> SassResult<Vec<KeyframesSelector>> {
-        let mut span = if let Some(tok) = self.toks.peek() {
-            tok.pos()
-        } else {
-            return Err(("expected \"{\".", self.span_before).into());
-        };
-
-        self.span_before = span;
-
-        while let Some(tok) = self.toks.next() {
-            span = span.merge(tok.pos());
-            match tok.kind {
-                '#' => {
-                    if let Some(Token { kind: '{', .. }) = self.toks.peek().cloned() {
-                        self.toks.next();
-                        string.push_str(&self.parse_interpolation()?.to_css_string(span)?);
-                    } else {
-                        string.push('#');
-                    }
-                }
-                ',' => {
-                    while let Some(c) = string.pop() {
-                        if c == ' ' || c == ',' {
-                            continue;
-                        }
-                        string.push(c);
-                        string.push(',');
-                        break;
-                    }
-                }
-                '/' => {
-                    if self.toks.peek().is_none() {
-                        return Err(("Expected selector.", tok.pos()).into());
-                    }
-                    self.parse_comment()?;
-                    self.whitespace();
-                    string.push(' ');
-                }
-                '{' => {
-                    let sel_toks: Vec<Token> =
-                        string.chars().map(|x| Token::new(span, x)).collect();
-
-                    let selector = KeyframesSelectorParser::new(&mut Parser {
-                        toks: &mut sel_toks.into_iter().peekmore(),
-                        map: self.map,
-                        path: self.path,
-                        scopes: self.scopes,
-                        global_scope: self.global_scope,
-                        super_selectors: self.super_selectors,
-                        span_before: self.span_before,
-                        content: self.content,
-                        flags: self.flags,
-                        at_root: self.at_root,
-                        at_root_has_selector: self.at_root_has_selector,
-                        extender: self.extender,
-                    })
-                    .parse_keyframes_selector()?;
-
-                    return Ok(selector);
-                }
-                c => string.push(c),
-            }
-        }
-        Err(("expected \"{\".", span).into())
-    }
-
-    pub(super) fn parse_keyframes(&mut self) -> SassResult<Stmt> {
-        let name = self.parse_keyframes_name()?;
-
-        self.whitespace();
-
-        let body = Parser {
-            toks: self.toks,
+        KeyframesSelectorParser::new(&mut Parser {
+            toks: &mut Lexer::from_string(string, span),
             map: self.map,
             path: self.path,
             scopes: self.scopes,
@@ -189,13 +128,46 @@ impl<'a> Parser<'a> {
             super_selectors: self.super_selectors,
             span_before: self.span_before,
             content: self.content,
-            flags: self.flags | ContextFlags::IN_KEYFRAMES,
-            at_root: false,
+            flags: self.flags,
+            at_root: self.at_root,
             at_root_has_selector: self.at_root_has_selector,
             extender: self.extender,
-        }
-        .parse_stmt()?;
+            cache: self.cache,
+            modules: self.modules,
+            random: self.random,
+            logger: self.logger,
+        })
+        .parse_keyframes_selector()
+    }
 
-        Ok(Stmt::Keyframes(Box::new(Keyframes { name, body })))
+    pub(super) fn parse_keyframes(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
+        let name = self.parse_keyframes_name()?;
+
+        self.whitespace();
+
+        let body = self.parse_ast()?;
+
+        Ok(AstStmt::Keyframes { name, body, span })
+    }
+
+    pub(super) fn eval_keyframes(
+        &mut self,
+        name: &Interpolation,
+        body: &[AstStmt],
+    ) -> SassResult<Stmt> {
+        // todo: we can avoid the reallocation by trimming before emitting
+        // (in `output.rs`)
+        let name = self.eval_interpolation(name)?.trim().to_owned();
+
+        let flags = self.flags;
+        let at_root = self.at_root;
+        self.flags = flags | ContextFlags::IN_KEYFRAMES;
+        self.at_root = false;
+        let body = self.eval_ast(body);
+        self.flags = flags;
+        self.at_root = at_root;
+
+        Ok(Stmt::Keyframes(Box::new(Keyframes { name, body: body? })))
     }
 }
@@ -1,188 +1,134 @@
-use crate::{
-    error::SassResult,
-    utils::{is_name_start, peek_ident_no_interpolation, read_until_closing_paren},
-    {Cow, Token},
-};
+use crate::{ast::Interpolation, error::SassResult, utils::is_name_start, TokenKind};
 
-use super::Parser;
+use super::{value::ExprEnd, Parser};
 
 impl<'a> Parser<'a> {
     pub fn scan_identifier(&mut self, ident: &str) -> SassResult<bool> {
-        let peeked_identifier =
-            match peek_ident_no_interpolation(self.toks, false, self.span_before) {
-                Ok(v) => v.node,
-                Err(..) => return Ok(false),
-            };
-        if peeked_identifier == ident {
-            self.toks.truncate_iterator_to_cursor();
-            self.toks.next();
-            return Ok(true);
+        let start = self.toks.cursor();
+        match self.parse_identifier_no_interpolation(false) {
+            Ok(v) if v.node == ident => Ok(true),
+            Ok(..) | Err(..) => {
+                self.toks.set_cursor(start);
+                Ok(false)
+            }
         }
-        self.toks.reset_cursor();
-        Ok(false)
     }
 
     pub fn expect_char(&mut self, c: char) -> SassResult<()> {
-        if let Some(Token { kind, .. }) = self.toks.peek() {
-            if *kind == c {
-                self.toks.next();
-                return Ok(());
-            }
+        if self.scan_char(c) {
+            return Ok(());
         }
         Err((format!("expected \"{}\".", c), self.span_before).into())
     }
 
     pub fn scan_char(&mut self, c: char) -> bool {
-        if let Some(Token { kind, .. }) = self.toks.peek() {
-            if *kind == c {
-                self.toks.next();
-                return true;
-            }
+        if matches!(self.toks.peek(), Some(tok) if tok.is_symbol(c)) {
+            self.toks.next();
+            return true;
         }
         false
     }
 
-    pub fn expression_until_comparison(&mut self) -> SassResult<Cow<'static, str>> {
-        let mut toks = Vec::new();
-        while let Some(tok) = self.toks.peek().cloned() {
-            match tok.kind {
-                '=' => {
-                    self.toks.advance_cursor();
-                    if matches!(self.toks.peek(), Some(Token { kind: '=', .. })) {
-                        self.toks.reset_cursor();
-                        break;
-                    }
-                    self.toks.reset_cursor();
-                    toks.push(tok);
-                    toks.push(tok);
-                    self.toks.next();
-                    self.toks.next();
-                }
-                '>' | '<' | ':' => {
-                    break;
-                }
-                _ => {
-                    toks.push(tok);
-                    self.toks.next();
-                }
-            }
-        }
-        self.parse_value_as_string_from_vec(toks)
-    }
-
-    pub(super) fn parse_media_query_list(&mut self) -> SassResult<String> {
-        let mut buf = String::new();
+    pub(super) fn parse_media_query_list(&mut self) -> SassResult<Interpolation> {
+        let mut buf = Interpolation::new(self.span_before);
         loop {
             self.whitespace();
-            buf.push_str(&self.parse_single_media_query()?);
+            self.parse_single_media_query(&mut buf)?;
             if !self.scan_char(',') {
                 break;
             }
-            buf.push(',');
-            buf.push(' ');
+            buf.push_str(", ");
         }
         Ok(buf)
     }
 
-    fn parse_media_feature(&mut self) -> SassResult<String> {
-        if let Some(Token { kind: '#', .. }) = self.toks.peek() {
-            if let Some(Token { kind: '{', .. }) = self.toks.peek_forward(1) {
-                self.toks.next();
-                self.toks.next();
-                return Ok(self.parse_interpolation_as_string()?.into_owned());
-            }
-            todo!()
+    fn parse_media_feature(&mut self, buf: &mut Interpolation) -> SassResult<()> {
+        if self.toks.scan_str("#{") {
+            buf.push_expr(self.parse_interpolation_expr()?);
+            return Ok(());
         }
-        let mut buf = String::with_capacity(2);
         self.expect_char('(')?;
-        buf.push('(');
+        buf.push_char('(');
         self.whitespace();
 
-        buf.push_str(&self.expression_until_comparison()?);
+        buf.push_expr(self.parse_expression_until(ExprEnd::Comparison)?);
 
-        if let Some(Token { kind: ':', .. }) = self.toks.peek() {
-            self.toks.next();
+        if self.scan_char(':') {
             self.whitespace();
 
-            buf.push(':');
-            buf.push(' ');
-            let mut toks = read_until_closing_paren(self.toks)?;
-            if let Some(tok) = toks.pop() {
-                if tok.kind != ')' {
-                    todo!()
-                }
-            }
-            buf.push_str(&self.parse_value_as_string_from_vec(toks)?);
+            buf.push_str(": ");
+            let value = self.parse_expression()?;
+            self.whitespace_or_comment();
+            self.expect_char(')')?;
+            buf.push_expr(value);
 
             self.whitespace();
-            buf.push(')');
-            return Ok(buf);
-        } else {
-            let next_tok = self.toks.peek().cloned();
-            let is_angle = next_tok.map_or(false, |t| t.kind == '<' || t.kind == '>');
-            if is_angle || matches!(next_tok, Some(Token { kind: '=', .. })) {
-                buf.push(' ');
-                // todo: remove this unwrap
-                buf.push(self.toks.next().unwrap().kind);
-                if is_angle && self.scan_char('=') {
-                    buf.push('=');
-                }
-                buf.push(' ');
-
-                self.whitespace();
+            buf.push_char(')');
+            return Ok(());
+        }
 
-                buf.push_str(&self.expression_until_comparison()?);
+        if let Some(TokenKind::Symbol(kind @ ('<' | '>' | '='))) =
+            self.toks.peek().map(|tok| tok.kind)
+        {
+            self.toks.next();
+            buf.push_char(' ');
+            buf.push_char(kind);
+            if kind != '=' && self.scan_char('=') {
+                buf.push_char('=');
             }
+            buf.push_char(' ');
+
+            self.whitespace();
+
+            buf.push_expr(self.parse_expression_until(ExprEnd::Comparison)?);
         }
 
         self.expect_char(')')?;
         self.whitespace();
-        buf.push(')');
-        Ok(buf)
+        buf.push_char(')');
+        Ok(())
     }
 
-    fn parse_single_media_query(&mut self) -> SassResult<String> {
-        let mut buf = String::new();
-
-        if !matches!(self.toks.peek(), Some(Token { kind: '(', .. })) {
-            buf.push_str(&self.parse_identifier()?);
+    fn parse_single_media_query(&mut self, buf: &mut Interpolation) -> SassResult<()> {
+        if !matches!(self.toks.peek(), Some(tok) if tok.is_symbol('(')) {
+            buf.append(self.parse_interpolated_identifier()?.node);
 
             self.whitespace();
 
-            if let Some(tok) = self.toks.peek() {
-                if !is_name_start(tok.kind) {
-                    return Ok(buf);
+            if let Some(c) = self.toks.peek_char() {
+                if !is_name_start(c) {
+                    return Ok(());
                 }
             }
 
-            buf.push(' ');
-            let ident = self.parse_identifier()?;
+            buf.push_char(' ');
+            let ident = self.parse_interpolated_identifier()?.node;
 
             self.whitespace();
 
-            if ident.to_ascii_lowercase() == "and" {
+            if matches!(ident.as_plain(), Some(ident) if ident.eq_ignore_ascii_case("and")) {
                 buf.push_str("and ");
             } else {
-                buf.push_str(&ident);
+                buf.append(ident);
 
                 if self.scan_identifier("and")? {
                     self.whitespace();
                     buf.push_str(" and ");
                 } else {
-                    return Ok(buf);
+                    return Ok(());
                 }
             }
         }
 
         loop {
             self.whitespace();
-            buf.push_str(&self.parse_media_feature()?);
+            self.parse_media_feature(buf)?;
             self.whitespace();
             if !self.scan_identifier("and")? {
                 break;
             }
             buf.push_str(" and ");
         }
-        Ok(buf)
+        Ok(())
     }
 }
@@ -1,77 +1,96 @@
-use codemap::Spanned;
+use std::rc::Rc;
 
-use peekmore::PeekMore;
+use codemap::{Span, Spanned};
 
 use crate::{
     args::{CallArgs, FuncArgs},
+    ast::{ArgumentInvocation, AstStmt, ContentBlock},
     atrule::{Content, Mixin},
+    common::Identifier,
     error::SassResult,
-    utils::read_until_closing_curly_brace,
-    Token,
+    value::SassMixin,
+    TokenKind,
 };
 
-use super::{common::ContextFlags, NeverEmptyVec, Parser, Stmt};
+use super::{common::ContextFlags, Parser, Stmt};
 
 impl<'a> Parser<'a> {
-    pub(super) fn parse_mixin(&mut self) -> SassResult<()> {
+    pub(super) fn parse_mixin(&mut self) -> SassResult<AstStmt> {
         self.whitespace();
-        let Spanned { node: name, span } = self.parse_identifier()?;
+        let Spanned { node: name, span } = self.parse_identifier_no_interpolation(false)?;
         self.whitespace();
         let args = match self.toks.next() {
-            Some(Token { kind: '(', .. }) => self.parse_func_args()?,
-            Some(Token { kind: '{', .. }) => FuncArgs::new(),
+            Some(tok) if tok.is_symbol('(') => self.parse_func_args()?,
+            Some(tok) if tok.is_symbol('{') => FuncArgs::new(),
             Some(t) => return Err(("expected \"{\".", t.pos()).into()),
             None => return Err(("expected \"{\".", span).into()),
         };
 
         self.whitespace();
 
-        let mut body = read_until_closing_curly_brace(self.toks)?;
-        body.push(match self.toks.next() {
-            Some(tok) => tok,
-            None => return Err(("expected \"}\".", self.span_before).into()),
-        });
+        let flags = self.flags;
+        self.flags = flags | ContextFlags::IN_MIXIN;
+        let body = self.parse_block();
+        self.flags = flags;
+        let body = body?;
 
-        // todo: `@include` can only give content when `@content` is present within the body
-        // if `@content` is *not* present and `@include` attempts to give a body, we throw an error
-        // `Error: Mixin doesn't accept a content block.`
-        //
-        // this is blocked on figuring out just how to check for this. presumably we could have a check
-        // not when parsing initially, but rather when `@include`ing to see if an `@content` was found.
+        // `@include` may only pass a content block if `@content` is present
+        // within the body
+        let accepts_content_block = body.iter().any(AstStmt::contains_content_rule);
 
-        let mixin = Mixin::new(self.scopes.last().clone(), args, body, false);
-
-        if self.at_root {
-            self.global_scope.insert_mixin(name, mixin);
-        } else {
-            self.scopes.last_mut().insert_mixin(name, mixin);
-        }
-        Ok(())
+        Ok(AstStmt::Mixin {
+            name: Identifier::from(name),
+            args,
+            body: Rc::new(body),
+            accepts_content_block,
+            span,
+        })
     }
 
-    pub(super) fn parse_include(&mut self) -> SassResult<Vec<Stmt>> {
+    pub(super) fn parse_include(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
         self.whitespace_or_comment();
-        let name = self.parse_identifier()?;
+        let name = self
+            .parse_identifier_no_interpolation(false)?
+            .map_node(Identifier::from);
+
+        let (namespace, name) = if self.scan_char('.') {
+            let member = self.parse_identifier_no_interpolation(false)?;
+            let span = name.span.merge(member.span);
+            (
+                Some(name),
+                Spanned {
+                    node: Identifier::from(member.node),
+                    span,
+                },
+            )
+        } else {
+            (None, name)
+        };
 
         self.whitespace_or_comment();
 
-        let args = if let Some(Token { kind: '(', .. }) = self.toks.peek() {
-            self.toks.next();
+        let args = if self.scan_char('(') {
             self.parse_call_args()?
         } else {
-            CallArgs::new(name.span)
+            ArgumentInvocation {
+                args: Vec::new(),
+                span: name.span,
+            }
         };
 
         self.whitespace_or_comment();
 
-        let content_args = if let Some(Token { kind: 'u', .. }) | Some(Token { kind: 'U', .. }) =
-            self.toks.peek()
-        {
+        let content_args = if matches!(
+            self.toks.peek(),
+            Some(tok) if tok.kind == TokenKind::Ident
+                && self.toks.text(tok).starts_with(|c| c == 'u' || c == 'U')
+        ) {
             let mut ident = self.parse_identifier_no_interpolation(false)?;
             ident.node.make_ascii_lowercase();
             if ident.node == "using" {
                 self.whitespace_or_comment();
-                if !matches!(self.toks.next(), Some(Token { kind: '(', .. })) {
+                if !self.scan_char('(') {
                     return Err(("expected \"(\".", ident.span).into());
                 }
 
@@ -85,45 +104,89 @@ impl<'a> Parser<'a> {
 
         self.whitespace_or_comment();
 
-        let content = if content_args.is_some()
-            || matches!(self.toks.peek(), Some(Token { kind: '{', .. }))
-        {
-            if matches!(self.toks.peek(), Some(Token { kind: '{', .. })) {
-                self.toks.next();
-            }
-            let mut toks = read_until_closing_curly_brace(self.toks)?;
-            if let Some(tok) = self.toks.peek() {
-                toks.push(*tok);
-                self.toks.next();
-            }
-            Some(toks)
+        // the `{` following `using (...)` has already been consumed
+        let content = if content_args.is_some() || self.scan_char('{') {
+            Some(Rc::new(ContentBlock {
+                args: content_args,
+                body: self.parse_ast()?,
+            }))
         } else {
             None
         };
 
-        if let Some(Token { kind: ';', .. }) = self.toks.peek() {
-            self.toks.next();
+        self.scan_char(';');
+
+        Ok(AstStmt::Include {
+            namespace,
+            name,
+            args,
+            content,
+            span,
+        })
+    }
+
+    pub(super) fn eval_include(
+        &mut self,
+        namespace: Option<Spanned<Identifier>>,
+        name: Spanned<Identifier>,
+        args: &ArgumentInvocation,
+        content: &Option<Rc<ContentBlock>>,
+    ) -> SassResult<Vec<Stmt>> {
+        let mixin = match namespace {
+            Some(namespace) => match self
+                .modules
+                .get(namespace.node, namespace.span)?
+                .get_mixin(name.node)
+            {
+                Some(mixin) => mixin,
+                None => return Err(("Undefined mixin.", name.span).into()),
+            },
+            None => match self.scopes.get_mixin(name, self.global_scope) {
+                Ok(mixin) => SassMixin::UserDefined(Box::new(mixin), name.node),
+                Err(e) => match self.modules.get_global_mixin(name.node) {
+                    Some(mixin) => mixin,
+                    None => return Err(e),
+                },
+            },
+        };
+
+        let args = self.eval_call_args(args)?;
+
+        if content.is_some() && !mixin.accepts_content() {
+            return Err(("Mixin doesn't accept a content block.", name.span).into());
         }
 
+        let content = Content {
+            content: content.clone(),
+            scope: self.scopes.clone(),
+        };
+
+        mixin.include(args, content, self)
+    }
+
+    /// Include a user-defined mixin, passing it `content` as its content block
+    pub(crate) fn include_mixin(
+        &mut self,
+        mixin: Mixin,
+        args: CallArgs,
+        content: Content,
+    ) -> SassResult<Vec<Stmt>> {
         let Mixin {
             mut scope,
             body,
             args: fn_args,
             ..
-        } = self.scopes.last().get_mixin(name, self.global_scope)?;
-        self.eval_args(fn_args, args, &mut scope)?;
+        } = mixin;
+        let span = args.span();
+        let arg_list = self.eval_args(&fn_args, args, &mut scope)?;
 
-        self.content.push(Content {
-            content,
-            content_args,
-            scope: self.scopes.last().clone(),
-        });
+        self.content.push(content);
 
         let body = Parser {
-            toks: &mut body.into_iter().peekmore(),
+            toks: self.toks,
             map: self.map,
             path: self.path,
-            scopes: &mut NeverEmptyVec::new(scope),
+            scopes: &mut scope,
             global_scope: self.global_scope,
             super_selectors: self.super_selectors,
             span_before: self.span_before,
@@ -132,67 +195,103 @@ impl<'a> Parser<'a> {
             at_root: false,
             at_root_has_selector: self.at_root_has_selector,
             extender: self.extender,
+            cache: self.cache,
+            modules: self.modules,
+            random: self.random,
+            logger: self.logger,
         }
-        .parse()?;
+        .eval_ast(&body)?;
 
         self.content.pop();
 
+        if let Some(arg_list) = arg_list {
+            arg_list.verify_keywords_accessed(span)?;
+        }
+
         Ok(body)
     }
 
-    pub(super) fn parse_content_rule(&mut self) -> SassResult<Vec<Stmt>> {
-        if self.flags.in_mixin() {
-            let mut scope = self
-                .content
-                .last()
-                .cloned()
-                .unwrap_or_else(Content::new)
-                .scope;
-            if let Some(Token { kind: '(', .. }) = self.toks.peek() {
-                self.toks.next();
-                let args = self.parse_call_args()?;
-                if let Some(Some(content_args)) =
-                    self.content.last().map(|v| v.content_args.clone())
-                {
-                    args.max_args(content_args.len())?;
-
-                    self.eval_args(content_args, args, &mut scope)?;
-                } else {
-                    args.max_args(0)?;
-                }
+    pub(super) fn parse_content_rule(&mut self) -> SassResult<AstStmt> {
+        let span = self.span_before;
+        let args = if self.scan_char('(') {
+            self.parse_call_args()?
+        } else {
+            ArgumentInvocation {
+                args: Vec::new(),
+                span,
             }
+        };
+        self.whitespace_or_comment();
+        self.scan_char(';');
+        Ok(AstStmt::Content { args, span })
+    }
 
-            Ok(if let Some(content) = &self.content.pop() {
-                let stmts = if let Some(body) = content.content.clone() {
-                    Parser {
-                        toks: &mut body.into_iter().peekmore(),
-                        map: self.map,
-                        path: self.path,
-                        scopes: &mut NeverEmptyVec::new(scope),
-                        global_scope: self.global_scope,
-                        super_selectors: self.super_selectors,
-                        span_before: self.span_before,
-                        flags: self.flags,
-                        content: self.content,
-                        at_root: self.at_root,
-                        at_root_has_selector: self.at_root_has_selector,
-                        extender: self.extender,
-                    }
-                    .parse()?
-                } else {
-                    Vec::new()
-                };
-                self.content.push(content.clone());
-                stmts
-            } else {
-                Vec::new()
-            })
+    pub(super) fn eval_content_rule(
+        &mut self,
+        args: &ArgumentInvocation,
+        span: Span,
+    ) -> SassResult<Vec<Stmt>> {
+        if !self.flags.in_mixin() {
+            return Err(("@content is only allowed within mixin declarations.", span).into());
+        }
+
+        let mut scope = self
+            .content
+            .last()
+            .cloned()
+            .unwrap_or_else(Content::new)
+            .scope;
+        let args = self.eval_call_args(args)?;
+        let args_span = args.span();
+        let content_args = self
+            .content
+            .last()
+            .and_then(|content| content.content.as_ref())
+            .and_then(|block| block.args.clone());
+        let arg_list = if let Some(content_args) = content_args {
+            args.max_args(content_args.len())?;
+
+            self.eval_args(&content_args, args, &mut scope)?
         } else {
-            Err((
-                "@content is only allowed within mixin declarations.",
-                self.span_before,
-            )
-                .into())
+            args.max_args(0)?;
+            scope.enter_new_scope();
+            None
+        };
+
+        let content = match self.content.pop() {
+            Some(content) => content,
+            None => return Ok(Vec::new()),
+        };
+
+        let stmts = match &content.content {
+            Some(block) => Parser {
+                toks: self.toks,
+                map: self.map,
+                path: self.path,
+                scopes: &mut scope,
+                global_scope: self.global_scope,
+                super_selectors: self.super_selectors,
+                span_before: self.span_before,
+                flags: self.flags,
+                content: self.content,
+                at_root: self.at_root,
+                at_root_has_selector: self.at_root_has_selector,
+                extender: self.extender,
+                cache: self.cache,
+                modules: self.modules,
+                random: self.random,
+                logger: self.logger,
+            }
+            .eval_ast(&block.body)?,
+            None => Vec::new(),
+        };
+
+        self.content.push(content);
+
+        if let Some(arg_list) = arg_list {
+            arg_list.verify_keywords_accessed(args_span)?;
         }
+
+        Ok(stmts)
     }
 }
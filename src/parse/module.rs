@@ -0,0 +1,99 @@
+use codemap::{Span, Spanned};
+
+use crate::{
+    ast::AstStmt, builtin::Module, common::Identifier, error::SassResult, value::Value, Token,
+    TokenKind,
+};
+
+use super::Parser;
+
+impl<'a> Parser<'a> {
+    /// Parse and load a `@use` rule, e.g. `@use "sass:math" as m;`
+    ///
+    /// Only builtin modules are supported.
+    pub(super) fn parse_module_use(&mut self) -> SassResult<AstStmt> {
+        self.whitespace_or_comment();
+
+        match self.toks.peek() {
+            Some(Token {
+                kind: TokenKind::String,
+                ..
+            }) => {}
+            Some(Token { pos, .. }) => return Err(("Expected string.", pos).into()),
+            None => return Err(("Expected string.", self.span_before).into()),
+        }
+
+        let Spanned { node: url, span } = self.parse_quoted_string()?;
+        let url = match url {
+            Value::String(s, ..) => s,
+            _ => unreachable!("parse_quoted_string always returns a string"),
+        };
+
+        self.whitespace_or_comment();
+
+        let namespace = if self.scan_identifier("as")? {
+            self.whitespace_or_comment();
+            if self.scan_char('*') {
+                None
+            } else {
+                Some(self.parse_identifier_no_interpolation(false)?.node)
+            }
+        } else {
+            Some(default_namespace(&url).to_owned())
+        };
+
+        self.whitespace_or_comment();
+
+        if self.scan_identifier("with")? {
+            return Err(("Built-in modules can't be configured.", span).into());
+        }
+
+        match self.toks.peek() {
+            Some(tok) if tok.is_symbol(';') => {
+                self.toks.next();
+            }
+            Some(tok) if tok.is_symbol('}') => {}
+            None => {}
+            Some(Token { pos, .. }) => return Err(("expected \";\".", pos).into()),
+        }
+
+        Ok(AstStmt::Use {
+            url,
+            namespace,
+            span,
+        })
+    }
+
+    /// Load the module of a `@use` rule
+    pub(super) fn eval_use(
+        &mut self,
+        url: &str,
+        namespace: Option<&str>,
+        span: Span,
+    ) -> SassResult<()> {
+        let module = match url.strip_prefix("sass:") {
+            Some(name) => match Module::builtin(name) {
+                Some(module) => module,
+                None => return Err(("Can't find stylesheet to import.", span).into()),
+            },
+            None => return Err(("Only built-in modules can be loaded with @use.", span).into()),
+        };
+
+        match namespace {
+            Some(namespace) => self
+                .modules
+                .insert(Identifier::from(namespace), module, span)?,
+            None => self.modules.insert_global(module),
+        }
+
+        Ok(())
+    }
+}
+
+/// The namespace a module is loaded under when no `as` clause is given,
+/// which is the last component of its URL without any extension
+fn default_namespace(url: &str) -> &str {
+    let name = url.rsplit(|c| c == '/' || c == ':').next().unwrap_or(url);
+    let name = name.split('.').next().unwrap_or(name);
+    name.strip_prefix('_').unwrap_or(name)
+}
@@ -1,11 +1,9 @@
 use codemap::Spanned;
 
 use crate::{
+    ast::{AstStmt, Expr, Interpolation},
     error::SassResult,
-    style::Style,
-    utils::{is_name, is_name_start},
-    value::Value,
-    Token,
+    Token, TokenKind,
 };
 
 use super::common::SelectorOrStyle;
@@ -13,54 +11,28 @@ use super::common::SelectorOrStyle;
 use super::Parser;
 
 impl<'a> Parser<'a> {
-    fn parse_style_value_when_no_space_after_semicolon(&mut self) -> Option<Vec<Token>> {
-        let mut toks = Vec::new();
-        while let Some(tok) = self.toks.peek() {
+    /// Find where the value following the `:` of what may be a declaration
+    /// ends, without consuming anything
+    ///
+    /// Returns `None` if a `{` is found first, in which case it's a selector.
+    fn style_value_end_when_no_space_after_semicolon(&mut self) -> Option<usize> {
+        let start = self.toks.cursor();
+        let mut nesting = 0;
+        let end = loop {
+            let tok = match self.toks.next() {
+                Some(tok) => tok,
+                None => break Some(self.toks.cursor()),
+            };
             match tok.kind {
-                ';' | '}' => {
-                    self.toks.reset_cursor();
-                    break;
-                }
-                '{' => {
-                    self.toks.reset_cursor();
-                    return None;
-                }
-                '(' => {
-                    toks.push(*tok);
-                    self.toks.peek_forward(1);
-                    let mut scope = 0;
-                    while let Some(tok) = self.toks.peek() {
-                        match tok.kind {
-                            ')' => {
-                                if scope == 0 {
-                                    toks.push(*tok);
-                                    self.toks.peek_forward(1);
-                                    break;
-                                } else {
-                                    scope -= 1;
-                                    toks.push(*tok);
-                                    self.toks.peek_forward(1);
-                                }
-                            }
-                            '(' => {
-                                toks.push(*tok);
-                                self.toks.peek_forward(1);
-                                scope += 1;
-                            }
-                            _ => {
-                                toks.push(*tok);
-                                self.toks.peek_forward(1);
-                            }
-                        }
-                    }
-                }
-                _ => {
-                    toks.push(*tok);
-                    self.toks.peek_forward(1);
-                }
+                TokenKind::Symbol(';' | '}') if nesting == 0 => break Some(tok.start),
+                TokenKind::Symbol('{') if nesting == 0 => break None,
+                TokenKind::Symbol('(') => nesting += 1,
+                TokenKind::Symbol(')') if nesting > 0 => nesting -= 1,
+                _ => {}
             }
-        }
-        Some(toks)
+        };
+        self.toks.set_cursor(start);
+        end
     }
 
     /// Determines whether the parser is looking at a style or a selector
@@ -95,45 +67,45 @@ impl<'a> Parser<'a> {
     // todo: potentially we read the property to a string already since properties
     // are more common than selectors? this seems to be annihilating our performance
     pub(super) fn is_selector_or_style(&mut self) -> SassResult<SelectorOrStyle> {
-        if let Some(first_char) = self.toks.peek() {
-            if first_char.kind == '#' {
-                if !matches!(self.toks.peek_forward(1), Some(Token { kind: '{', .. })) {
-                    self.toks.reset_cursor();
-                    return Ok(SelectorOrStyle::Selector(String::new()));
+        if let Some(first) = self.toks.peek() {
+            let is_property_start = match first.kind {
+                TokenKind::Ident | TokenKind::Number | TokenKind::Symbol('-' | '\\') => true,
+                TokenKind::Symbol('#') => {
+                    matches!(self.toks.peek_next(), Some(tok) if tok.is_symbol('{'))
                 }
-                self.toks.reset_cursor();
-            } else if !is_name_start(first_char.kind) && first_char.kind != '-' {
-                return Ok(SelectorOrStyle::Selector(String::new()));
+                _ => false,
+            };
+            if !is_property_start {
+                return Ok(SelectorOrStyle::Selector(Interpolation::new(
+                    self.span_before,
+                )));
             }
         }
 
-        let mut property = self.parse_identifier()?.node;
+        let mut property = self.parse_interpolated_identifier()?.node;
         let whitespace_after_property = self.whitespace();
 
-        if let Some(Token { kind: ':', .. }) = self.toks.peek() {
-            self.toks.next();
+        if self.scan_char(':') {
             if let Some(Token { kind, .. }) = self.toks.peek() {
                 return Ok(match kind {
-                    ':' => {
+                    TokenKind::Symbol(':') => {
                         if whitespace_after_property {
-                            property.push(' ');
+                            property.push_char(' ');
                         }
-                        property.push(':');
+                        property.push_char(':');
                         SelectorOrStyle::Selector(property)
                     }
-                    c if is_name(*c) => {
-                        if let Some(toks) = self.parse_style_value_when_no_space_after_semicolon() {
-                            let len = toks.len();
-                            if let Ok(val) = self.parse_value_from_vec(toks) {
-                                self.toks.take(len).for_each(drop);
-                                return Ok(SelectorOrStyle::Style(property, Some(Box::new(val))));
+                    TokenKind::Ident | TokenKind::Number | TokenKind::Symbol('-') => {
+                        if let Some(end) = self.style_value_end_when_no_space_after_semicolon() {
+                            if let Some(value) = self.try_parse_style_value(end) {
+                                return Ok(SelectorOrStyle::Style(property, Some(value)));
                             }
                         }
 
                         if whitespace_after_property {
-                            property.push(' ');
+                            property.push_char(' ');
                         }
-                        property.push(':');
+                        property.push_char(':');
                         return Ok(SelectorOrStyle::Selector(property));
                     }
                     _ => SelectorOrStyle::Style(property, None),
@@ -141,125 +113,144 @@ impl<'a> Parser<'a> {
             }
         } else {
             if whitespace_after_property {
-                property.push(' ');
+                property.push_char(' ');
             }
             return Ok(SelectorOrStyle::Selector(property));
         }
         Err(("expected \"{\".", self.span_before).into())
     }
 
-    fn parse_property(&mut self, mut super_property: String) -> SassResult<String> {
-        let property = self.parse_identifier()?;
+    /// Parse the value of a declaration up to `end`, returning `None`
+    /// without consuming anything if it isn't a single valid expression
+    fn try_parse_style_value(&mut self, end: usize) -> Option<Expr> {
+        let start = self.toks.cursor();
+        let value = self.parse_expression().ok();
         self.whitespace_or_comment();
-        if let Some(Token { kind: ':', .. }) = self.toks.peek() {
-            self.toks.next();
-            self.whitespace_or_comment();
-        } else {
-            return Err(("Expected \":\".", property.span).into());
+        if value.is_none() || self.toks.cursor() != end {
+            self.toks.set_cursor(start);
+            return None;
         }
+        value
+    }
 
-        if super_property.is_empty() {
-            Ok(property.node)
-        } else {
-            super_property.reserve(1 + property.node.len());
-            super_property.push('-');
-            super_property.push_str(&property.node);
-            Ok(super_property)
+    /// Parse a declaration whose property and `:` have already been consumed
+    pub(super) fn parse_style(
+        &mut self,
+        property: Interpolation,
+        value: Option<Expr>,
+    ) -> SassResult<AstStmt> {
+        let span = property.span;
+        if let Some(value) = value {
+            return Ok(AstStmt::Style {
+                property,
+                value: Some(value),
+                body: Vec::new(),
+                span,
+            });
+        }
+
+        self.whitespace();
+
+        if self.scan_char('{') {
+            return Ok(AstStmt::Style {
+                property,
+                value: None,
+                body: self.parse_nested_properties()?,
+                span,
+            });
         }
-    }
 
-    fn parse_style_value(&mut self) -> SassResult<Spanned<Value>> {
-        self.parse_value()
+        let value = self.parse_expression()?;
+        let body = match self.toks.peek() {
+            Some(tok) if tok.is_symbol(';') => {
+                self.toks.next();
+                self.whitespace();
+                Vec::new()
+            }
+            Some(tok) if tok.is_symbol('{') => {
+                self.toks.next();
+                self.parse_nested_properties()?
+            }
+            Some(tok) if tok.is_symbol('}') => Vec::new(),
+            Some(tok) if tok.is_symbol('!') => {
+                return Err(("Expected \"important\".", tok.pos).into())
+            }
+            Some(Token { pos, .. }) => return Err(("expected \";\".", pos).into()),
+            None => return Err(("expected more input.", value.span).into()),
+        };
+
+        Ok(AstStmt::Style {
+            property,
+            value: Some(value),
+            body,
+            span,
+        })
     }
 
-    pub(super) fn parse_style_group(&mut self, super_property: String) -> SassResult<Vec<Style>> {
-        let mut styles = Vec::new();
+    /// Parse the properties nested within a declaration, such as the
+    /// `family` in `font: { family: serif; }`, assuming the `{` has already
+    /// been consumed
+    ///
+    /// The names of the nested properties are relative to the declaration
+    /// they're nested within.
+    fn parse_nested_properties(&mut self) -> SassResult<Vec<AstStmt>> {
+        let mut body = Vec::new();
         self.whitespace();
-        while let Some(tok) = self.toks.peek().cloned() {
-            match tok.kind {
-                '{' => {
+        loop {
+            let property = self.parse_interpolated_identifier()?;
+            self.whitespace_or_comment();
+            if !self.scan_char(':') {
+                return Err(("Expected \":\".", property.span).into());
+            }
+            self.whitespace_or_comment();
+            let Spanned {
+                node: property,
+                span,
+            } = property;
+
+            if self.scan_char('{') {
+                body.push(AstStmt::Style {
+                    property,
+                    value: None,
+                    body: self.parse_nested_properties()?,
+                    span,
+                });
+                self.whitespace();
+                if self.scan_char('}') {
+                    self.whitespace();
+                    return Ok(body);
+                }
+                continue;
+            }
+
+            let value = self.parse_expression()?;
+            let nested = match self.toks.peek() {
+                Some(tok) if tok.is_symbol('}') => Vec::new(),
+                Some(tok) if tok.is_symbol(';') => {
                     self.toks.next();
                     self.whitespace();
-                    loop {
-                        let property = self.parse_property(super_property.clone())?;
-                        if let Some(tok) = self.toks.peek() {
-                            if tok.kind == '{' {
-                                styles.append(&mut self.parse_style_group(property)?);
-                                self.whitespace();
-                                if let Some(tok) = self.toks.peek() {
-                                    if tok.kind == '}' {
-                                        self.toks.next();
-                                        self.whitespace();
-                                        return Ok(styles);
-                                    } else {
-                                        continue;
-                                    }
-                                }
-                                continue;
-                            }
-                        }
-                        let value = Box::new(self.parse_style_value()?);
-                        match self.toks.peek() {
-                            Some(Token { kind: '}', .. }) => {
-                                styles.push(Style { property, value });
-                            }
-                            Some(Token { kind: ';', .. }) => {
-                                self.toks.next();
-                                self.whitespace();
-                                styles.push(Style { property, value });
-                            }
-                            Some(Token { kind: '{', .. }) => {
-                                styles.push(Style {
-                                    property: property.clone(),
-                                    value,
-                                });
-                                styles.append(&mut self.parse_style_group(property)?);
-                            }
-                            Some(..) | None => {
-                                self.whitespace();
-                                styles.push(Style { property, value });
-                            }
-                        }
-                        if let Some(tok) = self.toks.peek() {
-                            match tok.kind {
-                                '}' => {
-                                    self.toks.next();
-                                    self.whitespace();
-                                    return Ok(styles);
-                                }
-                                _ => continue,
-                            }
-                        }
-                    }
+                    Vec::new()
                 }
-                _ => {
-                    let value = self.parse_style_value()?;
-                    let t = self
-                        .toks
-                        .peek()
-                        .ok_or(("expected more input.", value.span))?;
-                    match t.kind {
-                        ';' => {
-                            self.toks.next();
-                            self.whitespace();
-                        }
-                        '{' => {
-                            let mut v = vec![Style {
-                                property: super_property.clone(),
-                                value: Box::new(value),
-                            }];
-                            v.append(&mut self.parse_style_group(super_property)?);
-                            return Ok(v);
-                        }
-                        _ => {}
-                    }
-                    return Ok(vec![Style {
-                        property: super_property,
-                        value: Box::new(value),
-                    }]);
+                Some(tok) if tok.is_symbol('{') => {
+                    self.toks.next();
+                    self.parse_nested_properties()?
                 }
+                Some(..) | None => {
+                    self.whitespace();
+                    Vec::new()
+                }
+            };
+            body.push(AstStmt::Style {
+                property,
+                value: Some(value),
+                body: nested,
+                span,
+            });
+
+            if self.scan_char('}') {
+                self.whitespace();
+                return Ok(body);
             }
         }
-        Ok(styles)
     }
 }
@@ -1,395 +1,377 @@
-use std::{borrow::Borrow, iter::Iterator};
-
-use codemap::Spanned;
-
 use crate::{
+    ast::{AstExpr, CalcArg, Interpolation},
+    common::{Identifier, Op},
     error::SassResult,
-    utils::{
-        as_hex, hex_char_for, is_name, peek_ident_no_interpolation, peek_until_closing_curly_brace,
-        peek_whitespace,
-    },
-    value::Value,
-    Token,
+    value::{CalculationName, Value},
+    Token, TokenKind,
 };
 
 use super::super::Parser;
 
 impl<'a> Parser<'a> {
-    pub(super) fn eat_calc_args(&mut self, buf: &mut String) -> SassResult<()> {
-        buf.reserve(2);
-        buf.push('(');
+    /// Read the arguments of a function such as `expression()` as plain text,
+    /// after its opening parenthesis
+    pub(super) fn eat_calc_args(&mut self, buf: &mut Interpolation) -> SassResult<()> {
+        buf.push_char('(');
+        self.eat_calc_args_inner(buf)?;
+        buf.push_char(')');
+        Ok(())
+    }
+
+    /// Read everything up to and including the closing parenthesis, which
+    /// isn't added to `buf`
+    fn eat_calc_args_inner(&mut self, buf: &mut Interpolation) -> SassResult<()> {
         let mut nesting = 0;
-        while let Some(tok) = self.toks.next() {
+        while let Some(tok) = self.toks.peek() {
+            if tok.kind == TokenKind::String {
+                self.parse_raw_string(buf)?;
+                continue;
+            }
+            self.toks.next();
             match tok.kind {
-                ' ' | '\t' | '\n' => {
-                    self.whitespace();
-                    buf.push(' ');
-                }
-                '#' => {
-                    if let Some(Token { kind: '{', pos }) = self.toks.peek() {
-                        self.span_before = *pos;
-                        self.toks.next();
-                        let interpolation = self.parse_interpolation()?;
-                        buf.push_str(&interpolation.node.to_css_string(interpolation.span)?);
+                TokenKind::Whitespace => buf.push_char(' '),
+                TokenKind::Symbol('#') => {
+                    if self.scan_char('{') {
+                        self.span_before = tok.pos;
+                        buf.push_expr(self.parse_interpolation_expr()?);
                     } else {
-                        buf.push('#');
+                        buf.push_char('#');
                     }
                 }
-                '(' => {
+                TokenKind::Symbol('(') => {
                     nesting += 1;
-                    buf.push('(');
+                    buf.push_char('(');
                 }
-                ')' => {
+                TokenKind::Symbol(')') => {
                     if nesting == 0 {
                         break;
                     } else {
                         nesting -= 1;
-                        buf.push(')');
+                        buf.push_char(')');
                     }
                 }
-                c => buf.push(c),
+                _ => buf.push_str(self.toks.text(tok)),
             }
         }
-        buf.push(')');
         Ok(())
     }
 
-    pub(super) fn eat_progid(&mut self) -> SassResult<String> {
-        let mut string = String::new();
+    pub(super) fn eat_progid(&mut self, buf: &mut Interpolation) -> SassResult<()> {
         let mut span = self.toks.peek().unwrap().pos();
         while let Some(tok) = self.toks.next() {
             span = span.merge(tok.pos());
             match tok.kind {
-                'a'..='z' | 'A'..='Z' | '.' => {
-                    string.push(tok.kind);
+                TokenKind::Ident
+                    if self.toks.text(tok).bytes().all(|b| b.is_ascii_alphabetic()) =>
+                {
+                    buf.push_str(self.toks.text(tok));
                 }
-                '(' => {
-                    self.eat_calc_args(&mut string)?;
+                TokenKind::Symbol('.') => buf.push_char('.'),
+                TokenKind::Symbol('(') => {
+                    self.eat_calc_args(buf)?;
                     break;
                 }
                 _ => return Err(("expected \"(\".", span).into()),
             }
         }
-        Ok(string)
+        Ok(())
     }
 
-    pub(super) fn try_eat_url(&mut self) -> SassResult<Option<String>> {
-        let mut buf = String::from("url(");
-        peek_whitespace(self.toks);
-        while let Some(tok) = self.toks.peek() {
-            let kind = tok.kind;
-            self.toks.advance_cursor();
-            if kind == '!'
-                || kind == '%'
-                || kind == '&'
-                || (kind >= '*' && kind <= '~')
-                || kind as u32 >= 0x0080
-            {
-                buf.push(kind);
-            } else if kind == '\\' {
-                buf.push_str(&self.peek_escape()?);
-            } else if kind == '#' {
-                if let Some(Token { kind: '{', .. }) = self.toks.peek() {
-                    self.toks.advance_cursor();
-                    let interpolation = self.peek_interpolation()?;
-                    match interpolation.node {
-                        Value::String(ref s, ..) => buf.push_str(s),
-                        v => buf.push_str(v.to_css_string(interpolation.span)?.borrow()),
-                    };
-                } else {
-                    buf.push('#');
+    /// Try to read the arguments of `url()` as an unquoted URL, after its
+    /// opening parenthesis
+    ///
+    /// If they aren't one, such as when the URL is quoted, this returns `None`
+    /// without consuming anything, and the arguments are parsed as those of a
+    /// regular function call.
+    pub(super) fn try_eat_url(&mut self) -> SassResult<Option<Interpolation>> {
+        let start = self.toks.cursor();
+        let mut buf = Interpolation::new(self.span_before);
+        buf.push_str("url(");
+        self.whitespace();
+        loop {
+            if let Some(text) = self.toks.next_text(|b| !is_url_byte(b)) {
+                buf.push_str(text.node);
+            }
+            if self.toks.scan_str("#{") {
+                buf.push_expr(self.parse_interpolation_expr()?);
+                continue;
+            }
+            match self.toks.peek() {
+                Some(tok) if tok.is_symbol('#') => {
+                    self.toks.next();
+                    buf.push_char('#');
                 }
-            } else if kind == ')' {
-                buf.push(')');
-                self.toks.truncate_iterator_to_cursor();
-                self.toks.next();
-                return Ok(Some(buf));
-            } else if kind.is_whitespace() {
-                peek_whitespace(self.toks);
-                let next = match self.toks.peek() {
-                    Some(v) => v,
-                    None => break,
-                };
-                if next.kind == ')' {
-                    buf.push(')');
-                    self.toks.truncate_iterator_to_cursor();
+                Some(tok) if tok.is_symbol(')') => {
                     self.toks.next();
+                    buf.push_char(')');
                     return Ok(Some(buf));
-                } else {
-                    break;
                 }
-            } else {
-                break;
+                Some(Token {
+                    kind: TokenKind::Whitespace,
+                    ..
+                }) => {
+                    self.whitespace();
+                    if !self.scan_char(')') {
+                        break;
+                    }
+                    buf.push_char(')');
+                    return Ok(Some(buf));
+                }
+                Some(..) | None => break,
             }
         }
-        self.toks.reset_cursor();
+        self.toks.set_cursor(start);
         Ok(None)
     }
 
-    pub(super) fn try_parse_min_max(
+    /// Try to parse the arguments of a calculation, after its opening
+    /// parenthesis
+    ///
+    /// `min()` and `max()` may also be calls to the global Sass functions of
+    /// the same name. If their arguments can't be parsed as a calculation,
+    /// this returns `None` without consuming anything.
+    pub(super) fn try_parse_calculation(
         &mut self,
-        fn_name: &str,
-        allow_comma: bool,
-    ) -> SassResult<Option<String>> {
-        let mut buf = if allow_comma {
-            format!("{}(", fn_name)
-        } else {
-            String::new()
-        };
-        peek_whitespace(self.toks);
-        while let Some(tok) = self.toks.peek() {
-            let kind = tok.kind;
-            match kind {
-                '+' | '-' | '0'..='9' => {
-                    self.toks.advance_cursor();
-                    if let Some(number) = self.peek_number()? {
-                        buf.push(kind);
-                        buf.push_str(&number);
-                    } else {
-                        return Ok(None);
-                    }
-                }
-                '#' => {
-                    self.toks.advance_cursor();
-                    if let Some(Token { kind: '{', .. }) = self.toks.peek() {
-                        self.toks.advance_cursor();
-                        let interpolation = self.peek_interpolation()?;
-                        match interpolation.node {
-                            Value::String(ref s, ..) => buf.push_str(s),
-                            v => buf.push_str(v.to_css_string(interpolation.span)?.borrow()),
-                        };
-                    } else {
-                        return Ok(None);
-                    }
-                }
-                'c' | 'C' => {
-                    if let Some(name) = self.try_parse_min_max_function("calc")? {
-                        buf.push_str(&name);
-                    } else {
-                        return Ok(None);
-                    }
-                }
-                'e' | 'E' => {
-                    if let Some(name) = self.try_parse_min_max_function("env")? {
-                        buf.push_str(&name);
-                    } else {
-                        return Ok(None);
-                    }
-                }
-                'v' | 'V' => {
-                    if let Some(name) = self.try_parse_min_max_function("var")? {
-                        buf.push_str(&name);
-                    } else {
-                        return Ok(None);
-                    }
-                }
-                '(' => {
-                    self.toks.advance_cursor();
-                    buf.push('(');
-                    if let Some(val) = self.try_parse_min_max(fn_name, false)? {
-                        buf.push_str(&val);
-                    } else {
-                        return Ok(None);
-                    }
-                }
-                'm' | 'M' => {
-                    self.toks.advance_cursor();
-                    match self.toks.peek() {
-                        Some(Token { kind: 'i', .. }) | Some(Token { kind: 'I', .. }) => {
-                            self.toks.advance_cursor();
-                            if !matches!(self.toks.peek(), Some(Token { kind: 'n', .. }) | Some(Token { kind: 'N', .. }))
-                            {
-                                return Ok(None);
-                            }
-                            buf.push_str("min(")
-                        }
-                        Some(Token { kind: 'a', .. }) | Some(Token { kind: 'A', .. }) => {
-                            self.toks.advance_cursor();
-                            if !matches!(self.toks.peek(), Some(Token { kind: 'x', .. }) | Some(Token { kind: 'X', .. }))
-                            {
-                                return Ok(None);
-                            }
-                            buf.push_str("max(")
-                        }
-                        _ => return Ok(None),
-                    }
+        name: CalculationName,
+    ) -> SassResult<Option<Vec<CalcArg>>> {
+        let start = self.toks.cursor();
+        match self.parse_calculation_args(name) {
+            Ok(args) => Ok(Some(args)),
+            Err(..) if matches!(name, CalculationName::Min | CalculationName::Max) => {
+                self.toks.set_cursor(start);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
 
-                    self.toks.advance_cursor();
+    fn parse_calculation_args(&mut self, name: CalculationName) -> SassResult<Vec<CalcArg>> {
+        if let Some(interpolation) = self.try_parse_calculation_interpolation()? {
+            return Ok(vec![interpolation]);
+        }
 
-                    if !matches!(self.toks.peek(), Some(Token { kind: '(', .. })) {
-                        return Ok(None);
-                    }
+        let max_args = match name {
+            CalculationName::Calc => 1,
+            CalculationName::Clamp => 3,
+            CalculationName::Min | CalculationName::Max => usize::MAX,
+        };
 
-                    if let Some(val) = self.try_parse_min_max(fn_name, false)? {
-                        buf.push_str(&val);
-                    } else {
-                        return Ok(None);
-                    }
-                }
-                _ => return Ok(None),
+        self.whitespace_or_comment();
+        let mut args = vec![self.parse_calculation_sum()?];
+        while args.len() < max_args {
+            if self.scan_char(',') {
+                self.whitespace_or_comment();
+                args.push(self.parse_calculation_sum()?);
+            } else {
+                break;
             }
+        }
 
-            peek_whitespace(self.toks);
+        match self.toks.next() {
+            Some(tok) if tok.is_symbol(')') => Ok(args),
+            Some(Token { pos, .. }) if args.len() == max_args => {
+                Err(("expected \"+\", \"-\", \"*\", \"/\", or \")\".", pos).into())
+            }
+            Some(Token { pos, .. }) => {
+                Err(("expected \"+\", \"-\", \"*\", \"/\", \",\", or \")\".", pos).into())
+            }
+            None => Err(("expected \")\".", self.span_before).into()),
+        }
+    }
 
-            let next = match self.toks.peek() {
-                Some(tok) => tok,
-                None => return Ok(None),
-            };
+    /// Interpolation directly within a calculation, or within parentheses in
+    /// a calculation, makes everything up to the closing parenthesis an
+    /// unevaluated string
+    fn try_parse_calculation_interpolation(&mut self) -> SassResult<Option<CalcArg>> {
+        if !self.contains_calculation_interpolation() {
+            return Ok(None);
+        }
+        let mut buf = Interpolation::new(self.span_before);
+        self.eat_calc_args_inner(&mut buf)?;
+        Ok(Some(CalcArg::Interpolation(buf)))
+    }
 
-            match next.kind {
-                ')' => {
-                    self.toks.advance_cursor();
-                    buf.push(')');
-                    return Ok(Some(buf));
+    fn contains_calculation_interpolation(&mut self) -> bool {
+        let start = self.toks.cursor();
+        let mut nesting = 0;
+        let mut found_interpolation = false;
+        while let Some(tok) = self.toks.next() {
+            match tok.kind {
+                TokenKind::Symbol('\\') => {
+                    self.toks.next_char();
                 }
-                '+' | '-' | '*' | '/' => {
-                    buf.push(' ');
-                    buf.push(next.kind);
-                    buf.push(' ');
-                    self.toks.advance_cursor();
+                TokenKind::Symbol('#') if nesting == 0 => {
+                    if matches!(self.toks.peek(), Some(tok) if tok.is_symbol('{')) {
+                        found_interpolation = true;
+                        break;
+                    }
                 }
-                ',' => {
-                    if !allow_comma {
-                        return Ok(None);
+                TokenKind::Symbol('(') => nesting += 1,
+                TokenKind::Symbol(')') => {
+                    if nesting == 0 {
+                        break;
                     }
-                    self.toks.advance_cursor();
-                    buf.push(',');
-                    buf.push(' ');
+                    nesting -= 1;
                 }
-                _ => return Ok(None),
+                _ => {}
             }
-
-            peek_whitespace(self.toks);
         }
-
-        Ok(Some(buf))
+        self.toks.set_cursor(start);
+        found_interpolation
     }
 
-    #[allow(dead_code, unused_mut, unused_variables, unused_assignments)]
-    fn try_parse_min_max_function(&mut self, fn_name: &'static str) -> SassResult<Option<String>> {
-        let mut ident = peek_ident_no_interpolation(self.toks, false, self.span_before)?.node;
-        ident.make_ascii_lowercase();
-        if ident != fn_name {
-            return Ok(None);
-        }
-        if !matches!(self.toks.peek(), Some(Token { kind: '(', .. })) {
-            return Ok(None);
+    fn parse_calculation_sum(&mut self) -> SassResult<CalcArg> {
+        let (mut sum, mut whitespace_before) = self.parse_calculation_product()?;
+        loop {
+            let (op, span, whitespace_after) = match self.toks.peek() {
+                Some(tok) if tok.is_symbol('+') || tok.is_symbol('-') => {
+                    self.toks.next();
+                    let op = if tok.is_symbol('+') {
+                        Op::Plus
+                    } else {
+                        Op::Minus
+                    };
+                    let whitespace_after = matches!(
+                        self.toks.peek(),
+                        Some(Token {
+                            kind: TokenKind::Whitespace,
+                            ..
+                        })
+                    );
+                    (op, tok.pos, whitespace_after)
+                }
+                // a `-` directly followed by an identifier
+                Some(tok)
+                    if tok.kind == TokenKind::Ident && self.toks.text(tok).starts_with('-') =>
+                {
+                    (Op::Minus, tok.pos, false)
+                }
+                _ => return Ok(sum),
+            };
+            if !whitespace_before || !whitespace_after {
+                return Err((
+                    "\"+\" and \"-\" must be surrounded by whitespace in calculations.",
+                    span,
+                )
+                    .into());
+            }
+            self.whitespace_or_comment();
+            let (rhs, whitespace) = self.parse_calculation_product()?;
+            sum = CalcArg::Operation(Box::new(sum), op, Box::new(rhs), span);
+            whitespace_before = whitespace;
         }
-        self.toks.advance_cursor();
-        ident.push('(');
-        todo!("special functions inside `min()` or `max()`")
     }
-}
-
-/// Methods required to do arbitrary lookahead
-impl<'a> Parser<'a> {
-    fn peek_number(&mut self) -> SassResult<Option<String>> {
-        let mut buf = String::new();
-
-        let num = self.peek_whole_number();
-        buf.push_str(&num);
 
-        self.toks.advance_cursor();
-
-        if let Some(Token { kind: '.', .. }) = self.toks.peek() {
-            self.toks.advance_cursor();
-            let num = self.peek_whole_number();
-            if num.is_empty() {
-                return Ok(None);
-            }
-            buf.push_str(&num);
-        } else {
-            self.toks.move_cursor_back().unwrap();
+    /// Returns the product, along with whether it was followed by whitespace
+    fn parse_calculation_product(&mut self) -> SassResult<(CalcArg, bool)> {
+        let mut product = self.parse_calculation_value()?;
+        loop {
+            let whitespace = self.whitespace_or_comment();
+            let (op, span) = match self.toks.peek() {
+                Some(tok) if tok.is_symbol('*') => (Op::Mul, tok.pos),
+                Some(tok) if tok.is_symbol('/') => (Op::Div, tok.pos),
+                _ => return Ok((product, whitespace)),
+            };
+            self.toks.next();
+            self.whitespace_or_comment();
+            let rhs = self.parse_calculation_value()?;
+            product = CalcArg::Operation(Box::new(product), op, Box::new(rhs), span);
         }
+    }
 
-        let next = match self.toks.peek() {
+    fn parse_calculation_value(&mut self) -> SassResult<CalcArg> {
+        let tok = match self.toks.peek() {
             Some(tok) => tok,
-            None => return Ok(Some(buf)),
+            None => {
+                return Err((
+                    "Expected number, variable, function, or calculation.",
+                    self.span_before,
+                )
+                    .into())
+            }
         };
+        let span = tok.pos;
+        self.span_before = span;
 
-        match next.kind {
-            'a'..='z' | 'A'..='Z' | '-' | '_' | '\\' => {
-                let unit = peek_ident_no_interpolation(self.toks, true, self.span_before)?.node;
-
-                buf.push_str(&unit);
+        match tok.kind {
+            // a `-` directly followed by an identifier
+            TokenKind::Ident if self.toks.text(tok).starts_with('-') => {
+                Err(("Expected digit.", span).into())
             }
-            '%' => {
-                self.toks.advance_cursor();
-                buf.push('%');
+            TokenKind::Symbol('+' | '-' | '.') | TokenKind::Number => {
+                let negate = tok.is_symbol('-');
+                if tok.is_symbol('+') || negate {
+                    self.toks.next();
+                    if !matches!(
+                        self.toks.peek(),
+                        Some(Token {
+                            kind: TokenKind::Number | TokenKind::Symbol('.'),
+                            ..
+                        })
+                    ) {
+                        return Err(("Expected digit.", span).into());
+                    }
+                }
+                let number = self.parse_dimension()?;
+                Ok(CalcArg::Expr(match number.node {
+                    Value::Dimension(num, unit, _) if negate => {
+                        AstExpr::Number(-num, unit).span(number.span)
+                    }
+                    Value::Dimension(num, unit, _) => AstExpr::Number(num, unit).span(number.span),
+                    v => AstExpr::Literal(v).span(number.span),
+                }))
             }
-            _ => {}
-        }
-
-        Ok(Some(buf))
-    }
-
-    fn peek_whole_number(&mut self) -> String {
-        let mut buf = String::new();
-        while let Some(tok) = self.toks.peek() {
-            if tok.kind.is_ascii_digit() {
-                buf.push(tok.kind);
-                self.toks.advance_cursor();
-            } else {
-                return buf;
+            TokenKind::Symbol('$') => {
+                self.toks.next();
+                let name = self.parse_identifier_no_interpolation(false)?;
+                let span = name.span;
+                Ok(CalcArg::Expr(
+                    AstExpr::Variable {
+                        namespace: None,
+                        name: name.map_node(Identifier::from),
+                    }
+                    .span(span),
+                ))
             }
-        }
-        buf
-    }
-
-    fn peek_interpolation(&mut self) -> SassResult<Spanned<Value>> {
-        let vec = peek_until_closing_curly_brace(self.toks)?;
-        self.toks.advance_cursor();
-        let val = self.parse_value_from_vec(vec)?;
-        Ok(Spanned {
-            node: val.node.unquote(),
-            span: val.span,
-        })
-    }
-
-    fn peek_escape(&mut self) -> SassResult<String> {
-        let mut value = 0;
-        let first = match self.toks.peek() {
-            Some(t) => *t,
-            None => return Ok(String::new()),
-        };
-        let mut span = first.pos;
-        if first.kind == '\n' {
-            return Err(("Expected escape sequence.", first.pos()).into());
-        } else if first.kind.is_ascii_hexdigit() {
-            for _ in 0..6 {
-                let next = match self.toks.peek() {
-                    Some(t) => t,
-                    None => break,
-                };
-                if !next.kind.is_ascii_hexdigit() {
-                    break;
+            TokenKind::Symbol('(') => {
+                self.toks.next();
+                if let Some(interpolation) = self.try_parse_calculation_interpolation()? {
+                    return Ok(interpolation);
                 }
-                value *= 16;
-                value += as_hex(next.kind);
-                span = span.merge(next.pos);
-                self.toks.peek_forward(1);
-            }
-            if self.toks.peek().is_some() && self.toks.peek().unwrap().kind.is_whitespace() {
-                self.toks.peek_forward(1);
+                self.whitespace_or_comment();
+                let value = self.parse_calculation_sum()?;
+                self.whitespace_or_comment();
+                match self.toks.next() {
+                    Some(tok) if tok.is_symbol(')') => {}
+                    Some(Token { pos, .. }) => return Err(("expected \")\".", pos).into()),
+                    None => return Err(("expected \")\".", span).into()),
+                }
+                Ok(CalcArg::Paren(Box::new(value)))
             }
-        } else {
-            value = self.toks.peek_forward(1).unwrap().kind as u32;
-        }
+            _ => {
+                let start = self.toks.cursor();
+                let ident = self.parse_identifier_no_interpolation(false);
+                let next = self.toks.peek();
+                self.toks.set_cursor(start);
+                let ident = match ident {
+                    Ok(ident) => ident,
+                    Err(..) => {
+                        return Err(
+                            ("Expected number, variable, function, or calculation.", span).into(),
+                        );
+                    }
+                };
+                if !matches!(next, Some(tok) if tok.is_symbol('(') || tok.is_symbol('.')) {
+                    return Err(("Expected \"(\" or \".\".", ident.span).into());
+                }
 
-        let c = std::char::from_u32(value).ok_or(("Invalid escape sequence.", span))?;
-        if is_name(c) {
-            Ok(c.to_string())
-        } else if value <= 0x1F || value == 0x7F {
-            let mut buf = String::with_capacity(4);
-            buf.push('\\');
-            if value > 0xF {
-                buf.push(hex_char_for(value >> 4));
+                Ok(CalcArg::Expr(self.parse_ident_expr()?))
             }
-            buf.push(hex_char_for(value & 0xF));
-            buf.push(' ');
-            Ok(buf)
-        } else {
-            Ok(format!("\\{}", c))
         }
     }
 }
+
+/// Whether `b` may appear unescaped in an unquoted URL
+fn is_url_byte(b: u8) -> bool {
+    matches!(b, b'!' | b'%' | b'&' | b'*'..=b'~') || !b.is_ascii()
+}
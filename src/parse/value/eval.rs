@@ -1,112 +1,70 @@
 #![allow(unused_variables)]
 
-use std::cmp::Ordering;
+use std::{cmp::Ordering, collections::HashMap, rc::Rc};
 
 use codemap::{Span, Spanned};
 
+use num_traits::Zero;
+
 use crate::{
-    args::CallArgs,
-    common::{Op, QuoteKind},
+    args::{CallArg, CallArgs},
+    ast::{AstExpr, CalcArg, Expr, FunctionCall},
+    builtin::GLOBAL_FUNCTIONS,
+    common::{Brackets, Identifier, Op, QuoteKind},
     error::SassResult,
-    unit::{Unit, UNIT_CONVERSION_TABLE},
-    value::{SassFunction, Value},
+    unit::Unit,
+    value::{
+        CalculationArg, CalculationName, Number, SassCalculation, SassFunction, SassMap, Value,
+    },
 };
 
 use super::super::Parser;
 
-#[derive(Clone, Debug)]
-pub(crate) enum HigherIntermediateValue {
-    Literal(Value),
-    /// A function that hasn't yet been evaluated
-    Function(SassFunction, CallArgs),
-    BinaryOp(Box<Self>, Op, Box<Self>),
-    UnaryOp(Op, Box<Self>),
-    Paren(Box<Self>),
-}
-
-impl HigherIntermediateValue {
-    pub const fn span(self, span: Span) -> Spanned<Self> {
-        Spanned { node: self, span }
-    }
-}
-
-impl<'a> Parser<'a> {
-    fn call_function(&mut self, function: SassFunction, args: CallArgs) -> SassResult<Value> {
-        function.call(args, self)
-    }
-}
-
-pub(crate) struct ValueVisitor<'a, 'b: 'a> {
-    parser: &'a mut Parser<'b>,
+/// Applies operators to values that have already been evaluated
+pub(crate) struct ValueVisitor {
     span: Span,
 }
 
-impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
-    pub fn new(parser: &'a mut Parser<'b>, span: Span) -> Self {
-        Self { parser, span }
-    }
-
-    pub fn eval(&mut self, value: HigherIntermediateValue) -> SassResult<Value> {
-        match value {
-            HigherIntermediateValue::Literal(v) => Ok(v),
-            HigherIntermediateValue::BinaryOp(v1, op, v2) => self.bin_op(*v1, op, *v2),
-            HigherIntermediateValue::UnaryOp(op, val) => self.unary_op(op, *val),
-            HigherIntermediateValue::Paren(val) => self.eval(*val),
-            HigherIntermediateValue::Function(function, args) => {
-                self.parser.call_function(function, args)
-            }
-        }
+impl ValueVisitor {
+    pub fn new(span: Span) -> Self {
+        Self { span }
     }
 
-    fn bin_op(
-        &mut self,
-        val1: HigherIntermediateValue,
-        op: Op,
-        val2: HigherIntermediateValue,
-    ) -> SassResult<Value> {
-        let mut val1 = self.paren_or_unary(val1)?;
-        let val2 = self.paren_or_unary(val2)?;
-
-        if let HigherIntermediateValue::BinaryOp(val1_1, op2, val1_2) = val1 {
-            if op2.precedence() >= op.precedence() {
-                val1 = HigherIntermediateValue::Literal(self.bin_op(*val1_1, op2, *val1_2)?);
-            } else {
-                let val2 = HigherIntermediateValue::Literal(self.bin_op(*val1_2, op, val2)?);
-                return self.bin_op(*val1_1, op2, val2);
-            }
-        }
-
+    /// Apply a binary operator other than `and` and `or`, which are
+    /// short-circuited by the caller
+    pub fn bin_op(&mut self, val1: Value, op: Op, val2: Value) -> SassResult<Value> {
         Ok(match op {
             Op::Plus => self.add(val1, val2)?,
             Op::Minus => self.sub(val1, val2)?,
             Op::Mul => self.mul(val1, val2)?,
             Op::Div => self.div(val1, val2)?,
             Op::Rem => self.rem(val1, val2)?,
-            Op::And => Self::and(val1, val2)?,
-            Op::Or => Self::or(val1, val2)?,
             Op::Equal => self.equal(val1, val2)?,
             Op::NotEqual => self.not_equal(val1, val2)?,
             Op::GreaterThan => self.greater_than(val1, val2)?,
             Op::GreaterThanEqual => self.greater_than_or_equal(val1, val2)?,
             Op::LessThan => self.less_than(val1, val2)?,
             Op::LessThanEqual => self.less_than_or_equal(val1, val2)?,
-            Op::Not => unreachable!(),
+            Op::And | Op::Or | Op::Not => unreachable!(),
         })
     }
 
-    fn unary_op(&mut self, op: Op, val: HigherIntermediateValue) -> SassResult<Value> {
-        let val = self.eval(val)?;
+    pub fn unary_op(&self, op: Op, val: Value) -> SassResult<Value> {
         match op {
             Op::Minus => self.unary_minus(val),
-            Op::Not => Self::unary_not(&val),
+            Op::Not => Ok(Value::bool(!val.is_true())),
             Op::Plus => self.unary_plus(val),
+            Op::Div => Ok(Value::String(
+                format!("/{}", val.to_css_string(self.span)?),
+                QuoteKind::None,
+            )),
             _ => unreachable!(),
         }
     }
 
     fn unary_minus(&self, val: Value) -> SassResult<Value> {
         Ok(match val {
-            Value::Dimension(n, u) => Value::Dimension(-n, u),
+            Value::Dimension(n, u, _) => Value::Dimension(-n, u, None),
             v => Value::String(format!("-{}", v.to_css_string(self.span)?), QuoteKind::None),
         })
     }
@@ -118,55 +76,44 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
         })
     }
 
-    fn unary_not(val: &Value) -> SassResult<Value> {
-        Ok(Value::bool(!val.is_true()))
-    }
-
-    fn paren(&mut self, val: HigherIntermediateValue) -> SassResult<HigherIntermediateValue> {
-        Ok(if let HigherIntermediateValue::Paren(v) = val {
-            HigherIntermediateValue::Literal(self.eval(*v)?)
-        } else {
-            val
-        })
-    }
-
-    fn paren_or_unary(
-        &mut self,
-        val: HigherIntermediateValue,
-    ) -> SassResult<HigherIntermediateValue> {
-        let val = self.paren(val)?;
-        Ok(match val {
-            HigherIntermediateValue::UnaryOp(op, val) => {
-                HigherIntermediateValue::Literal(self.unary_op(op, *val)?)
-            }
-            HigherIntermediateValue::Function(function, args) => {
-                HigherIntermediateValue::Literal(self.parser.call_function(function, args)?)
-            }
-            val => val,
-        })
+    /// Convert `num` from `unit` to `to`, so that it can be used in an
+    /// operation with a number in `to`
+    ///
+    /// Unitless numbers are compatible with any unit, and aren't converted.
+    fn coerce_units(&self, to: &Unit, num: Number, unit: &Unit) -> SassResult<Number> {
+        if to == unit || *to == Unit::None || *unit == Unit::None {
+            return Ok(num);
+        }
+        match unit.conversion_factor(to) {
+            Some(factor) => Ok(num * factor),
+            None => Err((
+                format!("Incompatible units {} and {}.", unit, to),
+                self.span,
+            )
+                .into()),
+        }
     }
 
-    fn add(
-        &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
-        let left = match left {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        let right = match right {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
+    fn add(&self, left: Value, right: Value) -> SassResult<Value> {
         Ok(match left {
-            Value::Map(..) | Value::FunctionRef(..) => {
+            Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                 return Err((
                     format!("{} isn't a valid CSS value.", left.inspect(self.span)?),
                     self.span,
                 )
                     .into())
             }
+            Value::Calculation(..) => {
+                return Err((
+                    format!(
+                        "Undefined operation \"{} + {}\".",
+                        left.inspect(self.span)?,
+                        right.inspect(self.span)?
+                    ),
+                    self.span,
+                )
+                    .into())
+            }
             Value::Important | Value::True | Value::False => match right {
                 Value::String(s, QuoteKind::Quoted) => Value::String(
                     format!("{}{}", left.to_css_string(self.span)?, s),
@@ -191,29 +138,13 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                     QuoteKind::None,
                 ),
             },
-            Value::Dimension(num, unit) => match right {
-                Value::Dimension(num2, unit2) => {
-                    if !unit.comparable(&unit2) {
-                        return Err((
-                            format!("Incompatible units {} and {}.", unit2, unit),
-                            self.span,
-                        )
-                            .into());
-                    }
-                    if unit == unit2 {
-                        Value::Dimension(num + num2, unit)
-                    } else if unit == Unit::None {
-                        Value::Dimension(num + num2, unit2)
-                    } else if unit2 == Unit::None {
-                        Value::Dimension(num + num2, unit)
+            Value::Dimension(num, unit, _) => match right {
+                Value::Dimension(num2, unit2, _) => {
+                    let num2 = self.coerce_units(&unit, num2, &unit2)?;
+                    if unit == Unit::None {
+                        Value::Dimension(num + num2, unit2, None)
                     } else {
-                        Value::Dimension(
-                            num + num2
-                                * UNIT_CONVERSION_TABLE[unit.to_string().as_str()]
-                                    [unit2.to_string().as_str()]
-                                .clone(),
-                            unit,
-                        )
+                        Value::Dimension(num + num2, unit, None)
                     }
                 }
                 Value::String(s, q) => Value::String(format!("{}{}{}", num, unit, s), q),
@@ -222,7 +153,7 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                     format!("{}{}{}", num, unit, right.to_css_string(self.span)?),
                     QuoteKind::None,
                 ),
-                Value::Map(..) | Value::FunctionRef(..) => {
+                Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                     return Err((
                         format!("{} isn't a valid CSS value.", right.inspect(self.span)?),
                         self.span,
@@ -281,47 +212,30 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
         })
     }
 
-    fn sub(
-        &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
-        let left = match left {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        let right = match right {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
+    fn sub(&self, left: Value, right: Value) -> SassResult<Value> {
         Ok(match left {
             Value::Null => Value::String(
                 format!("-{}", right.to_css_string(self.span)?),
                 QuoteKind::None,
             ),
-            Value::Dimension(num, unit) => match right {
-                Value::Dimension(num2, unit2) => {
-                    if !unit.comparable(&unit2) {
-                        return Err((
-                            format!("Incompatible units {} and {}.", unit2, unit),
-                            self.span,
-                        )
-                            .into());
-                    }
-                    if unit == unit2 {
-                        Value::Dimension(num - num2, unit)
-                    } else if unit == Unit::None {
-                        Value::Dimension(num - num2, unit2)
-                    } else if unit2 == Unit::None {
-                        Value::Dimension(num - num2, unit)
+            Value::Calculation(..) => {
+                return Err((
+                    format!(
+                        "Undefined operation \"{} - {}\".",
+                        left.inspect(self.span)?,
+                        right.inspect(self.span)?
+                    ),
+                    self.span,
+                )
+                    .into())
+            }
+            Value::Dimension(num, unit, _) => match right {
+                Value::Dimension(num2, unit2, _) => {
+                    let num2 = self.coerce_units(&unit, num2, &unit2)?;
+                    if unit == Unit::None {
+                        Value::Dimension(num - num2, unit2, None)
                     } else {
-                        Value::Dimension(
-                            num - num2
-                                * UNIT_CONVERSION_TABLE[unit.to_string().as_str()]
-                                    [unit2.to_string().as_str()]
-                                .clone(),
-                            unit,
-                        )
+                        Value::Dimension(num - num2, unit, None)
                     }
                 }
                 Value::List(..)
@@ -333,14 +247,14 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                     format!("{}{}-{}", num, unit, right.to_css_string(self.span)?),
                     QuoteKind::None,
                 ),
-                Value::Map(..) | Value::FunctionRef(..) => {
+                Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                     return Err((
                         format!("{} isn't a valid CSS value.", right.inspect(self.span)?),
                         self.span,
                     )
                         .into())
                 }
-                Value::Color(..) => {
+                Value::Color(..) | Value::Calculation(..) => {
                     return Err((
                         format!(
                             "Undefined operation \"{}{} - {}\".",
@@ -404,28 +318,17 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
         })
     }
 
-    fn mul(
-        &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
-        let left = match left {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        let right = match right {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
+    fn mul(&self, left: Value, right: Value) -> SassResult<Value> {
         Ok(match left {
-            Value::Dimension(num, unit) => match right {
-                Value::Dimension(num2, unit2) => {
+            Value::Dimension(num, unit, _) => match right {
+                Value::Dimension(num2, unit2, _) => {
                     if unit == Unit::None {
-                        Value::Dimension(num * num2, unit2)
+                        Value::Dimension(num * num2, unit2, None)
                     } else if unit2 == Unit::None {
-                        Value::Dimension(num * num2, unit)
+                        Value::Dimension(num * num2, unit, None)
                     } else {
-                        Value::Dimension(num * num2, unit * unit2)
+                        let (unit, factor) = unit.multiply(unit2);
+                        Value::Dimension(num * num2 * factor, unit, None)
                     }
                 }
                 _ => {
@@ -455,53 +358,42 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
         })
     }
 
-    fn div(
-        &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
-        let left = match left {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        let right = match right {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
+    pub fn div(&self, left: Value, right: Value) -> SassResult<Value> {
         Ok(match left {
             Value::Null => Value::String(
                 format!("/{}", right.to_css_string(self.span)?),
                 QuoteKind::None,
             ),
-            Value::Dimension(num, unit) => match right {
-                Value::Dimension(num2, unit2) => {
+            Value::Calculation(..) => {
+                return Err((
+                    format!(
+                        "Undefined operation \"{} / {}\".",
+                        left.inspect(self.span)?,
+                        right.inspect(self.span)?
+                    ),
+                    self.span,
+                )
+                    .into())
+            }
+            Value::Dimension(num, unit, _) => match right {
+                Value::Dimension(num2, ..) if num2.is_zero() => {
+                    return Err(("Division by zero isn't supported.", self.span).into())
+                }
+                Value::Dimension(num2, unit2, _) => {
                     // `unit(1em / 1em)` => `""`
                     if unit == unit2 {
-                        Value::Dimension(num / num2, Unit::None)
-
-                    // `unit(1 / 1em)` => `"em^-1"`
-                    } else if unit == Unit::None {
-                        Value::Dimension(num / num2, Unit::None / unit2)
+                        Value::Dimension(num / num2, Unit::None, None)
 
                     // `unit(1em / 1)` => `"em"`
                     } else if unit2 == Unit::None {
-                        Value::Dimension(num / num2, unit)
+                        Value::Dimension(num / num2, unit, None)
 
+                    // `unit(1 / 1em)` => `"em^-1"`
                     // `unit(1in / 1px)` => `""`
-                    } else if unit.comparable(&unit2) {
-                        Value::Dimension(
-                            num / (num2
-                                * UNIT_CONVERSION_TABLE[unit.to_string().as_str()]
-                                    [unit2.to_string().as_str()]
-                                .clone()),
-                            Unit::None,
-                        )
                     // `unit(1em / 1px)` => `"em/px"`
-                    // todo: this should probably be its own variant
-                    // within the `Value` enum
                     } else {
-                        // todo: remember to account for `Mul` and `Div`
-                        todo!("non-comparable inverse units")
+                        let (unit, factor) = unit.divide(unit2);
+                        Value::Dimension(num / num2 * factor, unit, None)
                     }
                 }
                 Value::String(s, q) => {
@@ -517,7 +409,19 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                     QuoteKind::None,
                 ),
                 Value::Null => Value::String(format!("{}{}/", num, unit), QuoteKind::None),
-                Value::Map(..) | Value::FunctionRef(..) => {
+                Value::Calculation(..) => {
+                    return Err((
+                        format!(
+                            "Undefined operation \"{}{} / {}\".",
+                            num,
+                            unit,
+                            right.inspect(self.span)?
+                        ),
+                        self.span,
+                    )
+                        .into())
+                }
+                Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                     return Err((
                         format!("{} isn't a valid CSS value.", right.inspect(self.span)?),
                         self.span,
@@ -557,12 +461,13 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
                 | Value::Dimension(..)
                 | Value::Color(..)
                 | Value::List(..)
-                | Value::ArgList(..) => Value::String(
+                | Value::ArgList(..)
+                | Value::Calculation(..) => Value::String(
                     format!("{}{}{}/{}", q1, s1, q1, right.to_css_string(self.span)?),
                     QuoteKind::None,
                 ),
                 Value::Null => Value::String(format!("{}{}{}/", q1, s1, q1), QuoteKind::None),
-                Value::Map(..) | Value::FunctionRef(..) => {
+                Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                     return Err((
                         format!("{} isn't a valid CSS value.", right.inspect(self.span)?),
                         self.span,
@@ -591,42 +496,22 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
         })
     }
 
-    fn rem(
-        &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
-        let left = match left {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        let right = match right {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
+    fn rem(&self, left: Value, right: Value) -> SassResult<Value> {
         Ok(match left {
-            Value::Dimension(n, u) => match right {
-                Value::Dimension(n2, u2) => {
-                    if !u.comparable(&u2) {
-                        return Err(
-                            (format!("Incompatible units {} and {}.", u2, u), self.span).into()
-                        );
-                    }
-                    if u == u2 {
-                        Value::Dimension(n % n2, u)
-                    } else if u == Unit::None {
-                        Value::Dimension(n % n2, u2)
-                    } else if u2 == Unit::None {
-                        Value::Dimension(n % n2, u)
+            Value::Dimension(n, u, _) => match right {
+                Value::Dimension(n2, u2, _) => {
+                    let n2 = self.coerce_units(&u, n2, &u2)?;
+                    if u == Unit::None {
+                        Value::Dimension(n % n2, u2, None)
                     } else {
-                        Value::Dimension(n, u)
+                        Value::Dimension(n % n2, u, None)
                     }
                 }
                 _ => {
                     return Err((
                         format!(
                             "Undefined operation \"{} % {}\".",
-                            Value::Dimension(n, u).inspect(self.span)?,
+                            Value::Dimension(n, u, None).inspect(self.span)?,
                             right.inspect(self.span)?
                         ),
                         self.span,
@@ -648,188 +533,19 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
         })
     }
 
-    fn and(left: HigherIntermediateValue, right: HigherIntermediateValue) -> SassResult<Value> {
-        let left = match left {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        let right = match right {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        Ok(if left.is_true() { right } else { left })
+    pub fn equal(&self, left: Value, right: Value) -> SassResult<Value> {
+        Ok(Value::bool(left.equals(&right)))
     }
 
-    fn or(left: HigherIntermediateValue, right: HigherIntermediateValue) -> SassResult<Value> {
-        let left = match left {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        let right = match right {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        Ok(if left.is_true() { left } else { right })
+    fn not_equal(&self, left: Value, right: Value) -> SassResult<Value> {
+        Ok(Value::bool(!left.equals(&right)))
     }
 
-    pub fn equal(
-        &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
-        let left = match left {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        let right = match right {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        Ok(Value::bool(match left {
-            Value::String(s1, ..) => match right {
-                Value::String(s2, ..) => s1 == s2,
-                _ => false,
-            },
-            Value::Dimension(n, unit) => match right {
-                Value::Dimension(n2, unit2) => {
-                    if !unit.comparable(&unit2) {
-                        false
-                    } else if unit == unit2 {
-                        n == n2
-                    } else if unit == Unit::None || unit2 == Unit::None {
-                        false
-                    } else {
-                        n == (n2
-                            * UNIT_CONVERSION_TABLE[unit.to_string().as_str()]
-                                [unit2.to_string().as_str()]
-                            .clone())
-                    }
-                }
-                _ => false,
-            },
-            Value::List(list1, sep1, brackets1) => match right {
-                Value::List(list2, sep2, brackets2) => {
-                    if sep1 != sep2 || brackets1 != brackets2 || list1.len() != list2.len() {
-                        false
-                    } else {
-                        let mut equals = true;
-                        for (a, b) in list1.into_iter().zip(list2) {
-                            if !self
-                                .equal(
-                                    HigherIntermediateValue::Literal(a),
-                                    HigherIntermediateValue::Literal(b),
-                                )?
-                                .is_true()
-                            {
-                                equals = false;
-                                break;
-                            }
-                        }
-                        equals
-                    }
-                }
-                _ => false,
-            },
-            s => s == right,
-        }))
-    }
-
-    fn not_equal(
-        &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
-        let left = match left {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        let right = match right {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        Ok(Value::bool(match left {
-            Value::String(s1, ..) => match right {
-                Value::String(s2, ..) => s1 != s2,
-                _ => true,
-            },
-            Value::Dimension(n, unit) => match right {
-                Value::Dimension(n2, unit2) => {
-                    if !unit.comparable(&unit2) {
-                        true
-                    } else if unit == unit2 {
-                        n != n2
-                    } else if unit == Unit::None || unit2 == Unit::None {
-                        true
-                    } else {
-                        n != (n2
-                            * UNIT_CONVERSION_TABLE[unit.to_string().as_str()]
-                                [unit2.to_string().as_str()]
-                            .clone())
-                    }
-                }
-                _ => true,
-            },
-            Value::List(list1, sep1, brackets1) => match right {
-                Value::List(list2, sep2, brackets2) => {
-                    if sep1 != sep2 || brackets1 != brackets2 || list1.len() != list2.len() {
-                        true
-                    } else {
-                        let mut equals = false;
-                        for (a, b) in list1.into_iter().zip(list2) {
-                            if self
-                                .not_equal(
-                                    HigherIntermediateValue::Literal(a),
-                                    HigherIntermediateValue::Literal(b),
-                                )?
-                                .is_true()
-                            {
-                                equals = true;
-                                break;
-                            }
-                        }
-                        equals
-                    }
-                }
-                _ => true,
-            },
-            s => s != right,
-        }))
-    }
-
-    fn cmp(
-        &self,
-        left: HigherIntermediateValue,
-        op: Op,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
-        let left = match left {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
-        let right = match right {
-            HigherIntermediateValue::Literal(v) => v,
-            v => panic!("{:?}", v),
-        };
+    fn cmp(&self, left: Value, op: Op, right: Value) -> SassResult<Value> {
         let ordering = match left {
-            Value::Dimension(num, unit) => match &right {
-                Value::Dimension(num2, unit2) => {
-                    if !unit.comparable(unit2) {
-                        return Err((
-                            format!("Incompatible units {} and {}.", unit2, unit),
-                            self.span,
-                        )
-                            .into());
-                    }
-                    if &unit == unit2 || unit == Unit::None || unit2 == &Unit::None {
-                        num.cmp(num2)
-                    } else {
-                        num.cmp(
-                            &(num2.clone()
-                                * UNIT_CONVERSION_TABLE[unit.to_string().as_str()]
-                                    [unit2.to_string().as_str()]
-                                .clone()),
-                        )
-                    }
+            Value::Dimension(num, unit, _) => match &right {
+                Value::Dimension(num2, unit2, _) => {
+                    num.cmp(&self.coerce_units(&unit, num2.clone(), unit2)?)
                 }
                 v => {
                     return Err((
@@ -878,35 +594,420 @@ impl<'a, 'b: 'a> ValueVisitor<'a, 'b> {
         })
     }
 
-    pub fn greater_than(
-        &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
+    pub fn greater_than(&self, left: Value, right: Value) -> SassResult<Value> {
         self.cmp(left, Op::GreaterThan, right)
     }
 
-    fn greater_than_or_equal(
-        &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
+    fn greater_than_or_equal(&self, left: Value, right: Value) -> SassResult<Value> {
         self.cmp(left, Op::GreaterThanEqual, right)
     }
 
-    pub fn less_than(
-        &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
-    ) -> SassResult<Value> {
+    pub fn less_than(&self, left: Value, right: Value) -> SassResult<Value> {
         self.cmp(left, Op::LessThan, right)
     }
 
-    fn less_than_or_equal(
+    fn less_than_or_equal(&self, left: Value, right: Value) -> SassResult<Value> {
+        self.cmp(left, Op::LessThanEqual, right)
+    }
+}
+
+/// Evaluation of the expressions produced by `Parser::parse_expression`
+impl<'a> Parser<'a> {
+    pub(crate) fn eval_expr(&mut self, expr: &Expr) -> SassResult<Spanned<Value>> {
+        let span = expr.span;
+        let value = match &expr.node {
+            AstExpr::Literal(value) => value.clone(),
+            AstExpr::Number(num, unit) => Value::Dimension(num.clone(), unit.clone(), None),
+            AstExpr::Variable {
+                namespace: Some(namespace),
+                name,
+            } => self
+                .modules
+                .get(namespace.node, namespace.span)?
+                .get_var(*name)?
+                .clone(),
+            AstExpr::Variable {
+                namespace: None,
+                name,
+            } => self.get_var_or_module_var(*name)?,
+            AstExpr::ParentSelector => {
+                if self.super_selectors.is_empty() && !self.at_root_has_selector && !self.at_root {
+                    Value::Null
+                } else {
+                    self.super_selectors.last().clone().into_value()
+                }
+            }
+            AstExpr::String(string, quotes) => {
+                Value::String(self.eval_interpolation(string)?, *quotes)
+            }
+            AstExpr::FunctionCall(call) => self.eval_function_call(call)?,
+            AstExpr::InterpolatedFunction(call) => {
+                let (name, args) = &**call;
+                let mut name = self.eval_interpolation(name)?;
+                let args = self.eval_call_args(args)?;
+                name.push_str(&args.to_css_string()?.node);
+                Value::String(name, QuoteKind::None)
+            }
+            AstExpr::Calculation { name, args } => self.eval_calculation(*name, args, span)?,
+            AstExpr::BinaryOp(lhs, op, rhs) => self.eval_binary_op(lhs, *op, rhs, span)?,
+            AstExpr::UnaryOp(op, operand) => {
+                let operand = self.eval_expr(operand)?.node;
+                ValueVisitor::new(span).unary_op(*op, operand)?
+            }
+            AstExpr::Paren(inner) => {
+                // a slash-separated number in parentheses is divided
+                let Spanned { node, span } = self.eval_expr(inner)?;
+                self.without_slash(node, span)?
+            }
+            AstExpr::List(elems, separator, brackets) => {
+                let elems = elems
+                    .iter()
+                    .map(|elem| Ok(self.eval_expr(elem)?.node))
+                    .collect::<SassResult<Vec<Value>>>()?;
+                // `[$list]` brackets the list rather than nesting it
+                if *brackets == Brackets::Bracketed && elems.len() == 1 {
+                    if let Value::List(inner, separator, Brackets::None) = &elems[0] {
+                        return Ok(
+                            Value::List(Rc::clone(inner), *separator, Brackets::Bracketed)
+                                .span(span),
+                        );
+                    }
+                }
+                Value::List(Rc::new(elems), *separator, *brackets)
+            }
+            AstExpr::Map(pairs) => {
+                let mut map = SassMap::new();
+                for (key, value) in pairs {
+                    let key = self.eval_expr(key)?;
+                    let value = self.eval_expr(value)?.node;
+                    if map.insert(key.node, value) {
+                        return Err(("Duplicate key.", key.span).into());
+                    }
+                }
+                Value::Map(map)
+            }
+        };
+        Ok(value.span(span))
+    }
+
+    /// Look up a variable, falling back to the variables of modules loaded
+    /// `as *`
+    pub(crate) fn get_var_or_module_var(&self, name: Spanned<Identifier>) -> SassResult<Value> {
+        match self.scopes.get_var(name, self.global_scope) {
+            Ok(v) => Ok(v.node),
+            Err(e) => match self.modules.get_global_var(name.node) {
+                Some(v) => Ok(v.clone()),
+                None => Err(e),
+            },
+        }
+    }
+
+    fn eval_binary_op(&mut self, lhs: &Expr, op: Op, rhs: &Expr, span: Span) -> SassResult<Value> {
+        match op {
+            Op::And => {
+                let lhs = self.eval_expr(lhs)?.node;
+                return if lhs.is_true() {
+                    Ok(self.eval_expr(rhs)?.node)
+                } else {
+                    Ok(lhs)
+                };
+            }
+            Op::Or => {
+                let lhs = self.eval_expr(lhs)?.node;
+                return if lhs.is_true() {
+                    Ok(lhs)
+                } else {
+                    Ok(self.eval_expr(rhs)?.node)
+                };
+            }
+            _ => {}
+        }
+
+        let lhs_val = self.eval_expr(lhs)?.node;
+        let rhs_val = self.eval_expr(rhs)?.node;
+
+        if op == Op::Div {
+            if let (Some(numer), Some(denom)) = (
+                slash_operand(&lhs.node, &lhs_val),
+                slash_operand(&rhs.node, &rhs_val),
+            ) {
+                return Ok(match denom {
+                    // `1px/0` can't be divided, but is still valid CSS
+                    Value::Dimension(ref n, ..) if n.is_zero() => Value::String(
+                        format!(
+                            "{}/{}",
+                            numer.to_css_string(span)?,
+                            denom.to_css_string(span)?
+                        ),
+                        QuoteKind::None,
+                    ),
+                    denom => {
+                        match ValueVisitor::new(span).div(numer.clone(), denom.clone())? {
+                            Value::Dimension(n, u, _) => {
+                                Value::Dimension(n, u, Some(Box::new((numer, denom))))
+                            }
+                            v => v,
+                        }
+                    }
+                });
+            }
+        }
+
+        if op == Op::Div {
+            self.warn_slash_division(&lhs_val, &rhs_val, span, || {
+                (
+                    self.expr_div_recommendation(lhs, rhs),
+                    self.source_text(span).to_owned(),
+                )
+            })?;
+        }
+
+        ValueVisitor::new(span).bin_op(lhs_val, op, rhs_val)
+    }
+
+    /// Warn that dividing two numbers with `/` is deprecated
+    ///
+    /// `recommendation` gives the `math.div()` call and the contents of the
+    /// `calc()` to use instead, written using the source of the operands.
+    fn warn_slash_division(
         &self,
-        left: HigherIntermediateValue,
-        right: HigherIntermediateValue,
+        lhs: &Value,
+        rhs: &Value,
+        span: Span,
+        recommendation: impl FnOnce() -> (String, String),
+    ) -> SassResult<()> {
+        if !matches!((lhs, rhs), (Value::Dimension(..), Value::Dimension(..))) {
+            return Ok(());
+        }
+        self.logger.deprecation(
+            self.map,
+            || {
+                let (math_div, calc) = recommendation();
+                Ok(format!(
+                    "Using / for division outside of calc() is deprecated and will be removed in a future release.\n\nRecommendation: {} or calc({})",
+                    math_div, calc
+                ))
+            },
+            span,
+        )
+    }
+
+    /// The `math.div()` equivalent of `lhs / rhs`
+    fn expr_div_recommendation(&self, lhs: &Expr, rhs: &Expr) -> String {
+        let operand = |expr: &Expr| match &expr.node {
+            AstExpr::BinaryOp(lhs, Op::Div, rhs) => self.expr_div_recommendation(lhs, rhs),
+            AstExpr::Paren(inner) => self.source_text(inner.span).to_owned(),
+            _ => self.source_text(expr.span).to_owned(),
+        };
+        format!("math.div({}, {})", operand(lhs), operand(rhs))
+    }
+
+    fn eval_function_call(&mut self, call: &FunctionCall) -> SassResult<Value> {
+        let FunctionCall {
+            namespace,
+            name,
+            css_name,
+            args,
+            span,
+        } = call;
+
+        if let Some(namespace) = namespace {
+            let function = match self
+                .modules
+                .get(namespace.node, namespace.span)?
+                .get_fn(*name)
+            {
+                Some(f) => f,
+                None => return Err(("Undefined function.", *span).into()),
+            };
+            let args = self.eval_call_args(args)?;
+            return function.call(args, self);
+        }
+
+        let function = match self.scopes.get_fn(
+            Spanned {
+                node: *name,
+                span: *span,
+            },
+            self.global_scope,
+        ) {
+            Ok(f) => Some(SassFunction::UserDefined(Box::new(f), *name)),
+            Err(..) => match self.modules.get_global_fn(*name) {
+                Some(f) => Some(f),
+                None => GLOBAL_FUNCTIONS
+                    .get(&*name.resolve())
+                    .map(|f| SassFunction::Builtin(f.clone(), *name)),
+            },
+        };
+
+        let args = self.eval_call_args(args)?;
+
+        match function {
+            Some(function) => function.call(args, self),
+            None => Ok(Value::String(
+                format!("{}{}", css_name, args.to_css_string()?.node),
+                QuoteKind::None,
+            )),
+        }
+    }
+
+    fn eval_calculation(
+        &mut self,
+        name: CalculationName,
+        args: &[CalcArg],
+        span: Span,
     ) -> SassResult<Value> {
-        self.cmp(left, Op::LessThanEqual, right)
+        let in_min_max = matches!(name, CalculationName::Min | CalculationName::Max);
+
+        let evaluated = args
+            .iter()
+            .map(|arg| self.eval_calc_arg(arg, in_min_max))
+            .collect::<SassResult<Vec<CalculationArg>>>();
+
+        let mut evaluated = match evaluated {
+            Ok(args) => args,
+            // `min()` and `max()` whose arguments can't be used in a
+            // calculation are calls to the Sass functions of the same name
+            Err(..) if in_min_max => return self.eval_min_max_fallback(name, args, span),
+            Err(e) => return Err(e),
+        };
+
+        match name {
+            CalculationName::Calc => Ok(SassCalculation::calc(evaluated.pop().unwrap())),
+            CalculationName::Min | CalculationName::Max => {
+                SassCalculation::min_max(name, evaluated, span)
+            }
+            CalculationName::Clamp => SassCalculation::clamp(evaluated, span),
+        }
+    }
+
+    fn eval_calc_arg(&mut self, arg: &CalcArg, in_min_max: bool) -> SassResult<CalculationArg> {
+        match arg {
+            CalcArg::Expr(expr) => {
+                let value = self.eval_expr(expr)?;
+                CalculationArg::from_value(value.node, value.span)
+            }
+            CalcArg::Interpolation(interpolation) => Ok(CalculationArg::Interpolation(
+                self.eval_interpolation(interpolation)?,
+            )),
+            CalcArg::Operation(lhs, op, rhs, span) => {
+                let lhs = self.eval_calc_arg(lhs, in_min_max)?;
+                let rhs = self.eval_calc_arg(rhs, in_min_max)?;
+                CalculationArg::operate(lhs, *op, rhs, in_min_max, *span)
+            }
+            // the contents of `var()` may contain operators
+            CalcArg::Paren(inner) => Ok(match self.eval_calc_arg(inner, in_min_max)? {
+                CalculationArg::String(s) => CalculationArg::String(format!("({})", s)),
+                arg => arg,
+            }),
+        }
+    }
+
+    /// Call the Sass function `min()` or `max()` with the arguments of what
+    /// was parsed as a calculation
+    fn eval_min_max_fallback(
+        &mut self,
+        name: CalculationName,
+        args: &[CalcArg],
+        span: Span,
+    ) -> SassResult<Value> {
+        let mut call_args = HashMap::new();
+        for arg in args {
+            let value = self.eval_calc_arg_as_value(arg);
+            call_args.insert(CallArg::Positional(call_args.len()), value);
+        }
+        let name = Identifier::from(name.as_str());
+        let function = match self
+            .scopes
+            .get_fn(Spanned { node: name, span }, self.global_scope)
+        {
+            Ok(f) => SassFunction::UserDefined(Box::new(f), name),
+            Err(..) => match self.modules.get_global_fn(name) {
+                Some(f) => f,
+                None => match GLOBAL_FUNCTIONS.get(&*name.resolve()) {
+                    Some(f) => SassFunction::Builtin(f.clone(), name),
+                    None => return Err(("Undefined function.", span).into()),
+                },
+            },
+        };
+        function.call(CallArgs(call_args, span), self)
+    }
+
+    /// Evaluate an argument of a calculation as a normal Sass expression
+    fn eval_calc_arg_as_value(&mut self, arg: &CalcArg) -> SassResult<Spanned<Value>> {
+        match arg {
+            CalcArg::Expr(expr) => self.eval_expr(expr),
+            CalcArg::Interpolation(interpolation) => Ok(Value::String(
+                self.eval_interpolation(interpolation)?,
+                QuoteKind::None,
+            )
+            .span(interpolation.span)),
+            CalcArg::Operation(lhs_arg, op, rhs_arg, span) => {
+                let lhs = self.eval_calc_arg_as_value(lhs_arg)?.node;
+                let rhs = self.eval_calc_arg_as_value(rhs_arg)?.node;
+                if *op == Op::Div {
+                    self.warn_slash_division(&lhs, &rhs, *span, || {
+                        (
+                            self.calc_div_recommendation(lhs_arg, rhs_arg),
+                            self.calc_arg_source(arg),
+                        )
+                    })?;
+                }
+                Ok(ValueVisitor::new(*span)
+                    .bin_op(lhs, *op, rhs)?
+                    .span(*span))
+            }
+            CalcArg::Paren(inner) => {
+                let Spanned { node, span } = self.eval_calc_arg_as_value(inner)?;
+                Ok(self.without_slash(node, span)?.span(span))
+            }
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// The source of a calculation argument, with whitespace normalized
+    fn calc_arg_source(&self, arg: &CalcArg) -> String {
+        match arg {
+            CalcArg::Expr(expr) => self.source_text(expr.span).to_owned(),
+            CalcArg::Interpolation(interpolation) => {
+                self.source_text(interpolation.span).to_owned()
+            }
+            CalcArg::Operation(lhs, op, rhs, _) => format!(
+                "{} {} {}",
+                self.calc_arg_source(lhs),
+                op,
+                self.calc_arg_source(rhs)
+            ),
+            CalcArg::Paren(inner) => format!("({})", self.calc_arg_source(inner)),
+        }
+    }
+
+    /// The `math.div()` equivalent of `lhs / rhs` in a calculation
+    fn calc_div_recommendation(&self, lhs: &CalcArg, rhs: &CalcArg) -> String {
+        let operand = |arg: &CalcArg| match arg {
+            CalcArg::Operation(lhs, Op::Div, rhs, _) => self.calc_div_recommendation(lhs, rhs),
+            CalcArg::Paren(inner) => self.calc_arg_source(inner),
+            arg => self.calc_arg_source(arg),
+        };
+        format!("math.div({}, {})", operand(lhs), operand(rhs))
+    }
+}
+
+/// The value of an operand of `/` if it may be one side of a slash-separated
+/// number such as `12px/1.5`, which is only the case for numbers written
+/// directly in the stylesheet and for other slash-separated numbers
+fn slash_operand(expr: &AstExpr, value: &Value) -> Option<Value> {
+    match (expr, value) {
+        (AstExpr::Number(..), Value::Dimension(..))
+        | (AstExpr::BinaryOp(_, Op::Div, _), Value::Dimension(_, _, Some(..))) => {
+            Some(value.clone())
+        }
+        (AstExpr::UnaryOp(Op::Minus, operand), Value::Dimension(..))
+            if matches!(operand.node, AstExpr::Number(..)) =>
+        {
+            Some(value.clone())
+        }
+        _ => None,
     }
 }
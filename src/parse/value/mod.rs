@@ -1,4 +1,5 @@
-pub(crate) use eval::{HigherIntermediateValue, ValueVisitor};
+pub(crate) use eval::ValueVisitor;
+pub(crate) use parse::ExprEnd;
 
 mod css_function;
 mod eval;
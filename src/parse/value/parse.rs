@@ -1,676 +1,686 @@
-use std::{iter::Iterator, mem};
-
 use num_bigint::BigInt;
 use num_rational::{BigRational, Rational64};
 use num_traits::{pow, One, ToPrimitive};
 
 use codemap::{Span, Spanned};
 
-use peekmore::PeekMore;
-
 use crate::{
-    builtin::GLOBAL_FUNCTIONS,
+    ast::{AstExpr, Expr, FunctionCall, Interpolation},
     color::{Color, NAMED_COLORS},
     common::{Brackets, Identifier, ListSeparator, Op, QuoteKind},
     error::SassResult,
     unit::Unit,
-    utils::{
-        devour_whitespace, eat_number, read_until_char, read_until_closing_paren,
-        read_until_closing_square_brace, IsWhitespace,
-    },
-    value::{Number, SassFunction, SassMap, Value},
-    Token,
+    utils::ParsedNumber,
+    value::{CalculationName, Number, Value},
+    Token, TokenKind,
 };
 
-use super::eval::{HigherIntermediateValue, ValueVisitor};
-
 use super::super::Parser;
 
-#[derive(Clone, Debug)]
-enum IntermediateValue {
-    Value(HigherIntermediateValue),
-    Op(Op),
-    Bracketed(Vec<Token>),
-    Paren(Vec<Token>),
-    Comma,
-    Whitespace,
+/// The context an expression is parsed in, which determines what may end it
+/// in addition to `;`, `{`, `}`, `,`, `:` and closing brackets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExprEnd {
+    Statement,
+    /// A media feature such as `width >= 10px`, which ends at `<`, `>` or `=`
+    Comparison,
+    /// The start of a `@for` range, which ends at `to` or `through`
+    ForBound,
 }
 
-impl IntermediateValue {
-    const fn span(self, span: Span) -> Spanned<Self> {
-        Spanned { node: self, span }
+impl<'a> Parser<'a> {
+    /// Parse an expression, which may be a comma separated list
+    pub(crate) fn parse_expression(&mut self) -> SassResult<Expr> {
+        self.parse_expression_until(ExprEnd::Statement)
     }
-}
 
-impl IsWhitespace for IntermediateValue {
-    fn is_whitespace(&self) -> bool {
-        if let IntermediateValue::Whitespace = self {
-            return true;
+    pub(crate) fn parse_expression_until(&mut self, end: ExprEnd) -> SassResult<Expr> {
+        self.whitespace_or_comment();
+        let first = self.parse_space_list(end)?;
+        if !matches!(self.toks.peek(), Some(tok) if tok.is_symbol(',')) {
+            return Ok(first);
         }
-        false
-    }
-}
 
-impl<'a> Parser<'a> {
-    pub(crate) fn parse_value(&mut self) -> SassResult<Spanned<Value>> {
-        self.whitespace();
-        let span = match self.toks.peek() {
-            Some(Token { pos, .. }) => *pos,
-            None => return Err(("Expected expression.", self.span_before).into()),
-        };
-        let mut last_was_whitespace = false;
-        let mut space_separated = Vec::new();
-        let mut comma_separated = Vec::new();
-        let mut iter = IntermediateValueIterator::new(self);
-        while let Some(val) = iter.next() {
-            let val = val?;
-            match val.node {
-                IntermediateValue::Value(v) => {
-                    last_was_whitespace = false;
-                    space_separated.push(v.span(val.span))
-                }
-                IntermediateValue::Op(op) => {
-                    iter.eat_op(
-                        Spanned {
-                            node: op,
-                            span: val.span,
-                        },
-                        &mut space_separated,
-                        last_was_whitespace,
-                    )?;
-                }
-                IntermediateValue::Whitespace => {
-                    last_was_whitespace = true;
-                    continue;
-                }
-                IntermediateValue::Comma => {
-                    last_was_whitespace = false;
-
-                    if space_separated.len() == 1 {
-                        comma_separated.push(space_separated.pop().unwrap());
-                    } else {
-                        let mut span = space_separated
-                            .get(0)
-                            .ok_or(("Expected expression.", val.span))?
-                            .span;
-                        comma_separated.push(
-                            HigherIntermediateValue::Literal(Value::List(
-                                mem::take(&mut space_separated)
-                                    .into_iter()
-                                    .map(move |a| {
-                                        span = span.merge(a.span);
-                                        a.node
-                                    })
-                                    .map(|a| ValueVisitor::new(iter.parser, span).eval(a))
-                                    .collect::<SassResult<Vec<Value>>>()?,
-                                ListSeparator::Space,
-                                Brackets::None,
-                            ))
-                            .span(span),
-                        );
-                    }
-                }
-                IntermediateValue::Bracketed(t) => {
-                    last_was_whitespace = false;
-                    space_separated.push(
-                        HigherIntermediateValue::Literal(
-                            match iter.parser.parse_value_from_vec(t)?.node {
-                                Value::List(v, sep, Brackets::None) => {
-                                    Value::List(v, sep, Brackets::Bracketed)
-                                }
-                                v => {
-                                    Value::List(vec![v], ListSeparator::Space, Brackets::Bracketed)
-                                }
-                            },
-                        )
-                        .span(val.span),
-                    )
-                }
-                IntermediateValue::Paren(t) => {
-                    last_was_whitespace = false;
-                    space_separated.push(iter.parse_paren(Spanned {
-                        node: t,
-                        span: val.span,
-                    })?);
-                }
+        let mut span = first.span;
+        let mut elems = vec![first];
+        while let Some(tok) = self.toks.peek().filter(|tok| tok.is_symbol(',')) {
+            self.toks.next();
+            self.whitespace_or_comment();
+            if self.toks.peek().map_or(false, |tok| tok.is_symbol(',')) {
+                return Err(("Expected expression.", tok.pos).into());
+            }
+            // a trailing comma is allowed
+            if self.at_expression_end(end) {
+                break;
             }
+            let elem = self.parse_space_list(end)?;
+            span = span.merge(elem.span);
+            elems.push(elem);
         }
 
-        Ok(if !comma_separated.is_empty() {
-            if space_separated.len() == 1 {
-                comma_separated.push(space_separated.pop().unwrap());
-            } else if !space_separated.is_empty() {
-                comma_separated.push(
-                    HigherIntermediateValue::Literal(Value::List(
-                        space_separated
-                            .into_iter()
-                            .map(|a| ValueVisitor::new(self, span).eval(a.node))
-                            .collect::<SassResult<Vec<Value>>>()?,
-                        ListSeparator::Space,
-                        Brackets::None,
-                    ))
-                    .span(span),
-                );
-            }
-            Value::List(
-                comma_separated
-                    .into_iter()
-                    .map(|a| ValueVisitor::new(self, span).eval(a.node))
-                    .collect::<SassResult<Vec<Value>>>()?,
-                ListSeparator::Comma,
-                Brackets::None,
-            )
-            .span(span)
-        } else if space_separated.len() == 1 {
-            ValueVisitor::new(self, span)
-                .eval(space_separated.pop().unwrap().node)?
-                .span(span)
-        } else {
-            Value::List(
-                space_separated
-                    .into_iter()
-                    .map(|a| ValueVisitor::new(self, span).eval(a.node))
-                    .collect::<SassResult<Vec<Value>>>()?,
-                ListSeparator::Space,
-                Brackets::None,
-            )
-            .span(span)
-        })
+        Ok(AstExpr::List(elems, ListSeparator::Comma, Brackets::None).span(span))
     }
 
-    pub(crate) fn parse_value_from_vec(&mut self, toks: Vec<Token>) -> SassResult<Spanned<Value>> {
-        Parser {
-            toks: &mut toks.into_iter().peekmore(),
-            map: self.map,
-            path: self.path,
-            scopes: self.scopes,
-            global_scope: self.global_scope,
-            super_selectors: self.super_selectors,
-            span_before: self.span_before,
-            content: self.content,
-            flags: self.flags,
-            at_root: self.at_root,
-            at_root_has_selector: self.at_root_has_selector,
-            extender: self.extender,
+    /// Parse the contents of `#{...}`, assuming the opening `#{` has already
+    /// been consumed
+    pub(crate) fn parse_interpolation_expr(&mut self) -> SassResult<Expr> {
+        let expr = self.parse_expression()?;
+        self.whitespace_or_comment();
+        match self.toks.next() {
+            Some(tok) if tok.is_symbol('}') => Ok(expr),
+            Some(..) | None => Err(("expected \"}\".", expr.span).into()),
         }
-        .parse_value()
     }
 
-    fn parse_ident_value(&mut self) -> SassResult<Spanned<IntermediateValue>> {
-        let Spanned { node: mut s, span } = self.parse_identifier()?;
-
-        self.span_before = span;
-
-        let lower = s.to_ascii_lowercase();
+    pub(in crate::parse) fn parse_space_list(&mut self, end: ExprEnd) -> SassResult<Expr> {
+        let (first, _) = self.parse_binary(0, end)?;
+        let mut span = first.span;
+        let mut elems = vec![first];
+        loop {
+            self.whitespace_or_comment();
+            if self.at_expression_end(end) {
+                break;
+            }
+            let (elem, _) = self.parse_binary(0, end)?;
+            span = span.merge(elem.span);
+            elems.push(elem);
+        }
 
-        if lower == "progid" && self.toks.peek().is_some() && self.toks.peek().unwrap().kind == ':'
-        {
-            s = lower;
-            self.toks.next();
-            s.push(':');
-            s.push_str(&self.eat_progid()?);
-            return Ok(Spanned {
-                node: IntermediateValue::Value(HigherIntermediateValue::Literal(Value::String(
-                    s,
-                    QuoteKind::None,
-                ))),
-                span,
-            });
+        if elems.len() == 1 {
+            return Ok(elems.pop().unwrap());
         }
 
-        if let Some(Token { kind: '(', .. }) = self.toks.peek() {
-            self.toks.next();
+        Ok(AstExpr::List(elems, ListSeparator::Space, Brackets::None).span(span))
+    }
 
-            if lower == "min" {
-                match self.try_parse_min_max("min", true)? {
-                    Some(val) => {
-                        self.toks.truncate_iterator_to_cursor();
-                        self.toks.next();
-                        return Ok(IntermediateValue::Value(HigherIntermediateValue::Literal(
-                            Value::String(val, QuoteKind::None),
-                        ))
-                        .span(span));
-                    }
-                    None => {
-                        self.toks.reset_cursor();
-                    }
-                }
-            } else if lower == "max" {
-                match self.try_parse_min_max("max", true)? {
-                    Some(val) => {
-                        self.toks.truncate_iterator_to_cursor();
-                        self.toks.next();
-                        return Ok(IntermediateValue::Value(HigherIntermediateValue::Literal(
-                            Value::String(val, QuoteKind::None),
-                        ))
-                        .span(span));
-                    }
-                    None => {
-                        self.toks.reset_cursor();
-                    }
-                }
+    /// Parse a binary operation whose operators all have a precedence of at
+    /// least `min_precedence`
+    ///
+    /// Also returns whether the operation was followed by whitespace, which
+    /// has already been consumed, as it determines whether a following `-`
+    /// is an operator or negates the next element of a space separated list.
+    fn parse_binary(&mut self, min_precedence: usize, end: ExprEnd) -> SassResult<(Expr, bool)> {
+        let mut lhs = self.parse_unary(end)?;
+        let mut whitespace = self.whitespace_or_comment();
+        while let Some((op, len)) = self.peek_binary_op(whitespace, end) {
+            if op.precedence() < min_precedence {
+                break;
             }
-
-            let as_ident = Identifier::from(&s);
-            let ident_as_string = as_ident.clone().into_inner();
-            let func = match self.scopes.last().get_fn(
-                Spanned {
-                    node: as_ident.clone(),
-                    span,
-                },
-                self.global_scope,
-            ) {
-                Ok(f) => f,
-                Err(_) => {
-                    if let Some(f) = GLOBAL_FUNCTIONS.get(ident_as_string.as_str()) {
-                        return Ok(IntermediateValue::Value(HigherIntermediateValue::Function(
-                            SassFunction::Builtin(f.clone(), as_ident),
-                            self.parse_call_args()?,
-                        ))
-                        .span(span));
-                    } else {
-                        // check for special cased CSS functions
-                        match lower.as_str() {
-                            "calc" | "element" | "expression" => {
-                                s = lower;
-                                self.eat_calc_args(&mut s)?;
-                            }
-                            "url" => match self.try_eat_url()? {
-                                Some(val) => s = val,
-                                None => s.push_str(&self.parse_call_args()?.to_css_string(self)?),
-                            },
-                            _ => s.push_str(&self.parse_call_args()?.to_css_string(self)?),
-                        }
-
-                        return Ok(IntermediateValue::Value(HigherIntermediateValue::Literal(
-                            Value::String(s, QuoteKind::None),
-                        ))
-                        .span(span));
-                    }
-                }
-            };
-
-            let call_args = self.parse_call_args()?;
-            return Ok(IntermediateValue::Value(HigherIntermediateValue::Function(
-                SassFunction::UserDefined(Box::new(func), as_ident),
-                call_args,
-            ))
-            .span(span));
+            for _ in 0..len {
+                self.toks.next();
+            }
+            self.whitespace_or_comment();
+            let (rhs, whitespace_after) = self.parse_binary(op.precedence() + 1, end)?;
+            let span = lhs.span.merge(rhs.span);
+            lhs = AstExpr::BinaryOp(Box::new(lhs), op, Box::new(rhs)).span(span);
+            whitespace = whitespace_after;
         }
+        Ok((lhs, whitespace))
+    }
 
-        // check for named colors
-        if let Some(c) = NAMED_COLORS.get_by_name(lower.as_str()) {
-            return Ok(
-                IntermediateValue::Value(HigherIntermediateValue::Literal(Value::Color(Box::new(
-                    Color::new(c[0], c[1], c[2], c[3], s),
-                ))))
-                .span(span),
-            );
-        }
+    /// The binary operator at the current position, if there is one, along
+    /// with the number of tokens it's made up of
+    fn peek_binary_op(&mut self, whitespace_before: bool, end: ExprEnd) -> Option<(Op, usize)> {
+        let tok = self.toks.peek()?;
+        let next = self.toks.peek_next().map(|tok| tok.kind);
+        let kind = match tok.kind {
+            TokenKind::Symbol(c) => c,
+            TokenKind::Ident => {
+                return match self.toks.text(tok) {
+                    "and" => self.peek_keyword_op(Op::And),
+                    "or" => self.peek_keyword_op(Op::Or),
+                    _ => None,
+                }
+            }
+            TokenKind::Number | TokenKind::String | TokenKind::Whitespace => return None,
+        };
+        let next = match next {
+            Some(TokenKind::Symbol(c)) => Some(c),
+            Some(TokenKind::Whitespace) => Some(' '),
+            Some(..) | None => None,
+        };
+        Some(match (kind, next) {
+            ('+', _) => (Op::Plus, 1),
+            // `a -b` is a list, and a `-` directly followed by an identifier
+            // begins that identifier, which is already lexed as one
+            ('-', next) => {
+                // a comment counts as whitespace on either side
+                if whitespace_before && !matches!(next, Some(' ' | '/')) {
+                    return None;
+                }
+                (Op::Minus, 1)
+            }
+            ('*', _) => (Op::Mul, 1),
+            ('/', _) => (Op::Div, 1),
+            ('%', _) => (Op::Rem, 1),
+            ('=', Some('=')) => (Op::Equal, 2),
+            ('!', Some('=')) => (Op::NotEqual, 2),
+            ('<' | '>', _) if end == ExprEnd::Comparison => return None,
+            ('<', Some('=')) => (Op::LessThanEqual, 2),
+            ('<', _) => (Op::LessThan, 1),
+            ('>', Some('=')) => (Op::GreaterThanEqual, 2),
+            ('>', _) => (Op::GreaterThan, 1),
+            _ => return None,
+        })
+    }
 
-        // check for keywords
-        Ok(match s.as_str() {
-            "true" => IntermediateValue::Value(HigherIntermediateValue::Literal(Value::True)),
-            "false" => IntermediateValue::Value(HigherIntermediateValue::Literal(Value::False)),
-            "null" => IntermediateValue::Value(HigherIntermediateValue::Literal(Value::Null)),
-            "not" => IntermediateValue::Op(Op::Not),
-            "and" => IntermediateValue::Op(Op::And),
-            "or" => IntermediateValue::Op(Op::Or),
-            _ => IntermediateValue::Value(HigherIntermediateValue::Literal(Value::String(
-                s,
-                QuoteKind::None,
-            ))),
+    /// `and` or `or`, unless it's the name of a function or is at the end of
+    /// the expression, in which case it's just an unquoted string
+    fn peek_keyword_op(&mut self, op: Op) -> Option<(Op, usize)> {
+        if !self.peek_keyword() {
+            return None;
+        }
+        let start = self.toks.cursor();
+        self.toks.next();
+        self.whitespace();
+        let is_op = !matches!(
+            self.toks.peek().map(|tok| tok.kind),
+            None | Some(TokenKind::Symbol(';' | '}' | ')' | ']' | ',' | '{'))
+        );
+        self.toks.set_cursor(start);
+        if is_op {
+            Some((op, 1))
+        } else {
+            None
         }
-        .span(span))
     }
 
-    fn next_is_hypen(&mut self) -> bool {
-        self.toks.peek_forward(1).is_some()
-            && matches!(self.toks.peek().unwrap().kind, '-' | '_' | 'a'..='z' | 'A'..='Z')
+    /// Whether the identifier at the cursor is a keyword, rather than the
+    /// start of a function call or of an identifier containing an escape
+    fn peek_keyword(&mut self) -> bool {
+        !matches!(
+            self.toks.peek_next(),
+            Some(tok) if tok.is_symbol('(') || tok.is_symbol('\\')
+        )
     }
 
-    fn parse_intermediate_value(&mut self) -> Option<SassResult<Spanned<IntermediateValue>>> {
-        let (kind, span) = match self.toks.peek() {
-            Some(v) => (v.kind, v.pos()),
-            None => return None,
+    fn parse_unary(&mut self, end: ExprEnd) -> SassResult<Expr> {
+        let tok = match self.toks.peek() {
+            Some(tok) => tok,
+            None => return Err(("Expected expression.", self.span_before).into()),
         };
+        let op = match tok.kind {
+            TokenKind::Symbol('+') => Op::Plus,
+            TokenKind::Symbol('-') => Op::Minus,
+            TokenKind::Symbol('/') => Op::Div,
+            TokenKind::Ident if self.toks.text(tok) == "not" && self.peek_keyword() => Op::Not,
+            _ => return self.parse_operand(end),
+        };
+        self.toks.next();
+        self.whitespace_or_comment();
+        let operand = self.parse_unary(end)?;
+        let span = tok.pos.merge(operand.span);
+        Ok(AstExpr::UnaryOp(op, Box::new(operand)).span(span))
+    }
 
-        self.span_before = span;
-
-        if self.whitespace() {
-            return Some(Ok(Spanned {
-                node: IntermediateValue::Whitespace,
-                span,
-            }));
+    /// Whether the expression being parsed ends at the current position
+    pub(super) fn at_expression_end(&mut self, end: ExprEnd) -> bool {
+        let tok = match self.toks.peek() {
+            Some(tok) => tok,
+            None => return true,
+        };
+        match tok.kind {
+            TokenKind::Symbol(';' | '}' | '{' | ')' | ']' | ',' | ':') => true,
+            // the `...` of a rest argument
+            TokenKind::Symbol('.') => {
+                matches!(self.toks.peek_next(), Some(tok) if tok.is_symbol('.'))
+            }
+            TokenKind::Symbol('!') => match self.toks.peek_next() {
+                Some(tok) if tok.is_symbol('=') => false,
+                Some(..) => !self.peek_important(),
+                None => true,
+            },
+            TokenKind::Symbol('<' | '>' | '=') => end == ExprEnd::Comparison,
+            TokenKind::Ident if end == ExprEnd::ForBound => {
+                let ident = self.toks.text(tok);
+                ident.eq_ignore_ascii_case("to") || ident.eq_ignore_ascii_case("through")
+            }
+            _ => false,
         }
+    }
 
-        Some(Ok(match kind {
-            _ if kind.is_ascii_alphabetic()
-                || kind == '_'
-                || kind == '\\'
-                || (!kind.is_ascii() && !kind.is_control())
-                || (kind == '-' && self.next_is_hypen()) =>
-            {
-                return Some(self.parse_ident_value());
-            }
-            '0'..='9' | '.' => {
-                let Spanned {
-                    node: val,
-                    mut span,
-                } = match eat_number(self.toks) {
-                    Ok(v) => v,
-                    Err(e) => return Some(Err(e)),
-                };
-                let unit = if let Some(tok) = self.toks.peek() {
-                    let Token { kind, .. } = *tok;
-                    match kind {
-                        'a'..='z' | 'A'..='Z' | '_' | '\\' | '\u{7f}'..=std::char::MAX => {
-                            let u = match self.parse_identifier_no_interpolation(true) {
-                                Ok(v) => v,
-                                Err(e) => return Some(Err(e)),
-                            };
-                            span = span.merge(u.span);
-                            Unit::from(u.node)
-                        }
-                        '%' => {
-                            span = span.merge(self.toks.next().unwrap().pos());
-                            Unit::Percent
-                        }
-                        _ => Unit::None,
-                    }
-                } else {
-                    Unit::None
-                };
+    /// Whether the cursor is at `!important`
+    fn peek_important(&mut self) -> bool {
+        let start = self.toks.cursor();
+        self.toks.next();
+        self.whitespace();
+        let is_important = match self.parse_identifier_no_interpolation(false) {
+            Ok(ident) => ident.node.eq_ignore_ascii_case("important"),
+            Err(..) => false,
+        };
+        self.toks.set_cursor(start);
+        is_important
+    }
 
-                let n = if val.dec_len == 0 {
-                    if val.num.len() <= 18 && val.times_ten.is_empty() {
-                        let n = Rational64::new_raw(parse_i64(&val.num), 1);
-                        return Some(Ok(IntermediateValue::Value(
-                            HigherIntermediateValue::Literal(Value::Dimension(
-                                Number::new_small(n),
-                                unit,
-                            )),
-                        )
-                        .span(span)));
-                    }
-                    BigRational::new_raw(val.num.parse::<BigInt>().unwrap(), BigInt::one())
-                } else {
-                    if val.num.len() <= 18 && val.times_ten.is_empty() {
-                        let n = Rational64::new(parse_i64(&val.num), pow(10, val.dec_len));
-                        return Some(Ok(IntermediateValue::Value(
-                            HigherIntermediateValue::Literal(Value::Dimension(
-                                Number::new_small(n),
-                                unit,
-                            )),
-                        )
-                        .span(span)));
-                    }
-                    BigRational::new(val.num.parse().unwrap(), pow(BigInt::from(10), val.dec_len))
-                };
+    fn parse_operand(&mut self, end: ExprEnd) -> SassResult<Expr> {
+        let Token {
+            kind, pos: span, ..
+        } = match self.toks.peek() {
+            Some(tok) => tok,
+            None => return Err(("Expected expression.", self.span_before).into()),
+        };
 
-                if val.times_ten.is_empty() {
-                    return Some(Ok(IntermediateValue::Value(
-                        HigherIntermediateValue::Literal(Value::Dimension(
-                            Number::new_big(n),
-                            unit,
-                        )),
-                    )
-                    .span(span)));
-                }
+        self.span_before = span;
 
-                let times_ten = pow(
-                    BigInt::from(10),
-                    match val
-                        .times_ten
-                        .parse::<BigInt>()
-                        .unwrap()
-                        .to_usize()
-                        .ok_or(("Exponent too large (expected usize).", span))
-                    {
-                        Ok(v) => v,
-                        Err(e) => return Some(Err(e.into())),
-                    },
-                );
+        let next = self.toks.peek_next().map(|tok| tok.kind);
 
-                let times_ten = if val.times_ten_is_postive {
-                    BigRational::new_raw(times_ten, BigInt::one())
-                } else {
-                    BigRational::new(BigInt::one(), times_ten)
-                };
+        let kind = match kind {
+            TokenKind::Ident => return self.parse_ident_expr(),
+            TokenKind::Number => '0',
+            TokenKind::String => '"',
+            TokenKind::Whitespace => ' ',
+            TokenKind::Symbol(c) => c,
+        };
 
-                IntermediateValue::Value(HigherIntermediateValue::Literal(Value::Dimension(
-                    Number::new_big(n * times_ten),
-                    unit,
-                )))
-                .span(span)
+        match kind {
+            // an escape must escape something, and `!=` is missing its left
+            // operand
+            '\\' if next.is_none() => Err(("Expected expression.", span).into()),
+            '!' if next == Some(TokenKind::Symbol('=')) => {
+                Err(("Expected expression.", span).into())
             }
-            '(' => {
-                let mut span = self.toks.next().unwrap().pos();
-                let mut inner = match read_until_closing_paren(self.toks) {
-                    Ok(v) => v,
-                    Err(e) => return Some(Err(e)),
-                };
-                // todo: the above shouldn't eat the closing paren
-                if let Some(last_tok) = inner.pop() {
-                    if last_tok.kind != ')' {
-                        return Some(Err(("expected \")\".", span).into()));
-                    }
-                    span = span.merge(last_tok.pos());
+            '\\' => self.parse_ident_expr(),
+            '0' | '.' => {
+                let Spanned { node, span } = self.parse_dimension()?;
+                Ok(match node {
+                    Value::Dimension(n, u, _) => AstExpr::Number(n, u),
+                    v => AstExpr::Literal(v),
                 }
-                IntermediateValue::Paren(inner).span(span)
+                .span(span))
             }
+            '(' => self.parse_paren_expr(),
+            '[' => self.parse_bracketed_list(),
             '&' => {
-                let span = self.toks.next().unwrap().pos();
-                if self.super_selectors.is_empty() && !self.at_root_has_selector && !self.at_root {
-                    IntermediateValue::Value(HigherIntermediateValue::Literal(Value::Null))
-                        .span(span)
-                } else {
-                    IntermediateValue::Value(HigherIntermediateValue::Literal(
-                        self.super_selectors.last().clone().into_value(),
-                    ))
-                    .span(span)
-                }
+                self.toks.next();
+                Ok(AstExpr::ParentSelector.span(span))
             }
             '#' => {
-                if let Some(Token { kind: '{', pos }) = self.toks.peek_forward(1) {
-                    self.span_before = *pos;
-                    self.toks.reset_cursor();
-                    return Some(self.parse_ident_value());
+                if next == Some(TokenKind::Symbol('{')) {
+                    return self.parse_ident_expr();
                 }
-                self.toks.reset_cursor();
                 self.toks.next();
-                let hex = match self.parse_hex() {
-                    Ok(v) => v,
-                    Err(e) => return Some(Err(e)),
-                };
-                IntermediateValue::Value(HigherIntermediateValue::Literal(hex.node)).span(hex.span)
-            }
-            q @ '"' | q @ '\'' => {
-                let span_start = self.toks.next().unwrap().pos();
-                let Spanned { node, span } = match self.parse_quoted_string(q) {
-                    Ok(v) => v,
-                    Err(e) => return Some(Err(e)),
-                };
-                IntermediateValue::Value(HigherIntermediateValue::Literal(node))
-                    .span(span_start.merge(span))
+                self.parse_hex()
             }
-            '[' => {
-                let mut span = self.toks.next().unwrap().pos();
-                self.whitespace_or_comment();
-                let mut inner = match read_until_closing_square_brace(self.toks) {
-                    Ok(v) => v,
-                    Err(e) => return Some(Err(e)),
-                };
-                if let Some(last_tok) = inner.pop() {
-                    if last_tok.kind != ']' {
-                        return Some(Err(("expected \"]\".", span).into()));
-                    }
-                    span = span.merge(last_tok.pos());
-                }
-                if inner.is_empty() {
-                    IntermediateValue::Value(HigherIntermediateValue::Literal(Value::List(
-                        Vec::new(),
-                        ListSeparator::Space,
-                        Brackets::Bracketed,
-                    )))
-                } else {
-                    IntermediateValue::Bracketed(inner)
+            '"' => {
+                let string = self.parse_interpolated_string()?;
+                let span = span.merge(string.span);
+                Ok(match string.node.as_plain() {
+                    Some(s) => AstExpr::Literal(Value::String(s.to_owned(), QuoteKind::Quoted)),
+                    None => AstExpr::String(string.node, QuoteKind::Quoted),
                 }
-                .span(span)
+                .span(span))
             }
             '$' => {
                 self.toks.next();
-                let val = match self.parse_identifier_no_interpolation(false) {
-                    Ok(v) => v,
-                    Err(e) => return Some(Err(e)),
-                };
-                let span = val.span;
-                IntermediateValue::Value(HigherIntermediateValue::Literal(
-                    match self.scopes.last().get_var(val, self.global_scope) {
-                        Ok(v) => v,
-                        Err(e) => return Some(Err(e)),
-                    }
-                    .node,
-                ))
-                .span(span)
-            }
-            '+' => {
-                let span = self.toks.next().unwrap().pos();
-                IntermediateValue::Op(Op::Plus).span(span)
-            }
-            '-' => {
-                let span = self.toks.next().unwrap().pos();
-                IntermediateValue::Op(Op::Minus).span(span)
-            }
-            '*' => {
-                let span = self.toks.next().unwrap().pos();
-                IntermediateValue::Op(Op::Mul).span(span)
-            }
-            '%' => {
-                let span = self.toks.next().unwrap().pos();
-                IntermediateValue::Op(Op::Rem).span(span)
+                let name = self.parse_identifier_no_interpolation(false)?;
+                let span = name.span;
+                Ok(AstExpr::Variable {
+                    namespace: None,
+                    name: name.map_node(Identifier::from),
+                }
+                .span(span))
             }
-            ',' => {
+            '!' => {
                 self.toks.next();
-                IntermediateValue::Comma.span(span)
-            }
-            q @ '>' | q @ '<' => {
-                let mut span = self.toks.next().unwrap().pos;
-                #[allow(clippy::eval_order_dependence)]
-                IntermediateValue::Op(if let Some(Token { kind: '=', .. }) = self.toks.peek() {
-                    span = span.merge(self.toks.next().unwrap().pos);
-                    match q {
-                        '>' => Op::GreaterThanEqual,
-                        '<' => Op::LessThanEqual,
-                        _ => unreachable!(),
-                    }
+                self.whitespace();
+                let ident = self.parse_identifier_no_interpolation(false)?;
+                let span = span.merge(ident.span);
+                if ident.node.eq_ignore_ascii_case("important") {
+                    Ok(AstExpr::Literal(Value::Important).span(span))
                 } else {
-                    match q {
-                        '>' => Op::GreaterThan,
-                        '<' => Op::LessThan,
-                        _ => unreachable!(),
-                    }
-                })
-                .span(span)
+                    Err(("Expected \"important\".", span).into())
+                }
             }
             '=' => {
-                let mut span = self.toks.next().unwrap().pos();
-                if let Some(Token { kind: '=', pos }) = self.toks.next() {
-                    span = span.merge(pos);
-                    IntermediateValue::Op(Op::Equal).span(span)
+                self.toks.next();
+                if self.scan_char('=') {
+                    Err(("Expected expression.", span).into())
                 } else {
-                    return Some(Err(("expected \"=\".", span).into()));
+                    Err(("expected \"=\".", span).into())
                 }
             }
-            '!' => {
-                let mut span = self.toks.next().unwrap().pos();
-                if let Some(Token { kind: '=', .. }) = self.toks.peek() {
-                    span = span.merge(self.toks.next().unwrap().pos());
-                    return Some(Ok(IntermediateValue::Op(Op::NotEqual).span(span)));
+            ':' | '?' | '@' | '^' | '|' => Err(("expected \";\".", span).into()),
+            '<' | '>' if end == ExprEnd::Comparison => Err(("Expected expression.", span).into()),
+            _ => Err(("Expected expression.", span).into()),
+        }
+    }
+
+    /// Parse an expression beginning with an identifier, such as a function
+    /// call, a named color, a keyword, or an unquoted string
+    pub(super) fn parse_ident_expr(&mut self) -> SassResult<Expr> {
+        let Spanned { node: ident, span } = self.parse_interpolated_identifier()?;
+
+        self.span_before = span;
+
+        let name = match ident.as_plain() {
+            Some(name) => name.to_owned(),
+            // a function whose name contains interpolation is always a plain
+            // CSS function
+            None => {
+                if self.scan_char('(') {
+                    let args = self.parse_call_args()?;
+                    let span = span.merge(args.span);
+                    return Ok(AstExpr::InterpolatedFunction(Box::new((ident, args))).span(span));
                 }
-                self.whitespace();
-                let v = match self.parse_identifier() {
-                    Ok(v) => v,
-                    Err(e) => return Some(Err(e)),
-                };
-                span = span.merge(v.span);
-                match v.node.to_ascii_lowercase().as_str() {
-                    "important" => {
-                        IntermediateValue::Value(HigherIntermediateValue::Literal(Value::Important))
-                            .span(span)
+                return Ok(AstExpr::String(ident, QuoteKind::None).span(span));
+            }
+        };
+
+        let lower = name.to_ascii_lowercase();
+
+        if lower == "progid" && self.scan_char(':') {
+            let mut buf = Interpolation::new(span);
+            buf.push_str("progid:");
+            self.eat_progid(&mut buf)?;
+            return Ok(string_expr(buf, span));
+        }
+
+        if self.is_module_member() {
+            return self.parse_module_member(Spanned { node: name, span });
+        }
+
+        if self.scan_char('(') {
+            if let Some(calculation) = CalculationName::from_name(&lower) {
+                if let Some(args) = self.try_parse_calculation(calculation)? {
+                    return Ok(AstExpr::Calculation {
+                        name: calculation,
+                        args,
                     }
-                    _ => return Some(Err(("Expected \"important\".", span).into())),
+                    .span(span));
                 }
             }
-            '/' => {
-                let span = self.toks.next().unwrap().pos();
-                match self.toks.peek() {
-                    Some(Token { kind: '/', .. }) | Some(Token { kind: '*', .. }) => {
-                        let span = match self.parse_comment() {
-                            Ok(c) => c.span,
-                            Err(e) => return Some(Err(e)),
-                        };
-                        IntermediateValue::Whitespace.span(span)
+
+            // these can't be the names of Sass functions
+            match lower.as_str() {
+                "element" | "expression" => {
+                    let mut buf = Interpolation::new(span);
+                    buf.push_str(&lower);
+                    self.eat_calc_args(&mut buf)?;
+                    return Ok(string_expr(buf, span));
+                }
+                "url" => {
+                    if let Some(buf) = self.try_eat_url()? {
+                        return Ok(string_expr(buf, span));
                     }
-                    Some(..) => IntermediateValue::Op(Op::Div).span(span),
-                    None => return Some(Err(("Expected expression.", span).into())),
                 }
+                _ => {}
             }
-            ';' | '}' | '{' => return None,
-            ':' | '?' | ')' | '@' | '^' | ']' | '|' => {
-                self.toks.next();
-                return Some(Err(("expected \";\".", span).into()));
+
+            let args = self.parse_call_args()?;
+            return Ok(AstExpr::FunctionCall(Box::new(FunctionCall {
+                namespace: None,
+                name: Identifier::from(&name),
+                css_name: name,
+                args,
+                span,
+            }))
+            .span(span));
+        }
+
+        if let Some(c) = NAMED_COLORS.get_by_name(lower.as_str()) {
+            return Ok(AstExpr::Literal(Value::Color(Box::new(Color::new(
+                c[0], c[1], c[2], c[3], name,
+            ))))
+            .span(span));
+        }
+
+        Ok(AstExpr::Literal(match name.as_str() {
+            "true" => Value::True,
+            "false" => Value::False,
+            "null" => Value::Null,
+            _ => Value::String(name, QuoteKind::None),
+        })
+        .span(span))
+    }
+
+    /// Whether the next tokens are a reference to a member of a module,
+    /// such as `.$pi` or `.div(1, 2)` following `math`
+    fn is_module_member(&mut self) -> bool {
+        if !matches!(self.toks.peek(), Some(tok) if tok.is_symbol('.')) {
+            return false;
+        }
+        match self.toks.peek_next() {
+            Some(tok) if tok.is_symbol('$') => true,
+            Some(tok) if tok.kind == TokenKind::Ident => {
+                let first = self.toks.text(tok).as_bytes()[0];
+                first == b'_' || first.is_ascii_alphabetic()
+            }
+            Some(..) | None => false,
+        }
+    }
+
+    /// Parse a reference to a variable or function of a module, starting at
+    /// the `.` following its namespace
+    fn parse_module_member(&mut self, namespace: Spanned<String>) -> SassResult<Expr> {
+        self.expect_char('.')?;
+        let namespace = namespace.map_node(Identifier::from);
+
+        if self.scan_char('$') {
+            let name = self.parse_identifier_no_interpolation(false)?;
+            let span = namespace.span.merge(name.span);
+            return Ok(AstExpr::Variable {
+                namespace: Some(namespace),
+                name: name.map_node(Identifier::from),
             }
-            '\u{0}'..='\u{8}' | '\u{b}'..='\u{1f}' | '\u{7f}'..=std::char::MAX | '`' | '~' => {
+            .span(span));
+        }
+
+        let name = self.parse_identifier_no_interpolation(false)?;
+        let span = namespace.span.merge(name.span);
+        self.span_before = span;
+        self.expect_char('(')?;
+
+        let args = self.parse_call_args()?;
+        Ok(AstExpr::FunctionCall(Box::new(FunctionCall {
+            namespace: Some(namespace),
+            name: Identifier::from(&name.node),
+            css_name: name.node,
+            args,
+            span,
+        }))
+        .span(span))
+    }
+
+    /// Parse an expression in parentheses, which may also be a map
+    fn parse_paren_expr(&mut self) -> SassResult<Expr> {
+        let mut span = self.toks.next().unwrap().pos();
+        self.whitespace_or_comment();
+
+        if let Some(Token { pos, .. }) = self.toks.peek().filter(|tok| tok.is_symbol(')')) {
+            self.toks.next();
+            return Ok(
+                AstExpr::List(Vec::new(), ListSeparator::Space, Brackets::None)
+                    .span(span.merge(pos)),
+            );
+        }
+
+        let first = self.parse_expression()?;
+        self.whitespace_or_comment();
+
+        if !self.scan_char(':') {
+            span = span.merge(self.expect_closing_paren(span)?);
+            return Ok(AstExpr::Paren(Box::new(first)).span(span));
+        }
+
+        let mut pairs = Vec::new();
+        let mut key = first;
+        loop {
+            self.whitespace_or_comment();
+            let value = self.parse_space_list(ExprEnd::Statement)?;
+            pairs.push((key, value));
+            self.whitespace_or_comment();
+            if !self.scan_char(',') {
+                break;
+            }
+            self.whitespace_or_comment();
+            if matches!(self.toks.peek(), Some(tok) if tok.is_symbol(')')) {
+                break;
+            }
+            key = self.parse_space_list(ExprEnd::Statement)?;
+            self.whitespace_or_comment();
+            if !self.scan_char(':') {
+                return Err(("expected \":\".", key.span).into());
+            }
+        }
+
+        span = span.merge(self.expect_closing_paren(span)?);
+        Ok(AstExpr::Map(pairs).span(span))
+    }
+
+    fn expect_closing_paren(&mut self, span: Span) -> SassResult<Span> {
+        match self.toks.next() {
+            Some(tok) if tok.is_symbol(')') => Ok(tok.pos),
+            Some(..) | None => Err(("expected \")\".", span).into()),
+        }
+    }
+
+    /// Parse a list in square brackets, such as `[a b]` or `[a, b]`
+    fn parse_bracketed_list(&mut self) -> SassResult<Expr> {
+        let mut span = self.toks.next().unwrap().pos();
+        self.whitespace_or_comment();
+
+        if let Some(Token { pos, .. }) = self.toks.peek().filter(|tok| tok.is_symbol(']')) {
+            self.toks.next();
+            return Ok(
+                AstExpr::List(Vec::new(), ListSeparator::Space, Brackets::Bracketed)
+                    .span(span.merge(pos)),
+            );
+        }
+
+        let inner = self.parse_expression()?;
+        self.whitespace_or_comment();
+
+        match self.toks.next() {
+            Some(tok) if tok.is_symbol(']') => span = span.merge(tok.pos),
+            Some(..) | None => return Err(("expected \"]\".", span).into()),
+        }
+
+        Ok(match inner.node {
+            AstExpr::List(elems, separator, Brackets::None) => {
+                AstExpr::List(elems, separator, Brackets::Bracketed)
+            }
+            _ => AstExpr::List(vec![inner], ListSeparator::Space, Brackets::Bracketed),
+        }
+        .span(span))
+    }
+
+    /// Parse a number, along with its unit if it has one
+    pub(super) fn parse_dimension(&mut self) -> SassResult<Spanned<Value>> {
+        let number = match self.toks.next_number() {
+            Some(tok) => tok,
+            // a `.` that isn't followed by a digit
+            None => {
+                let span = self.toks.peek().map_or(self.span_before, |tok| tok.pos);
+                return Err(("Expected digit.", span).into());
+            }
+        };
+        let mut span = number.pos;
+        let val = ParsedNumber::from_text(self.toks.text(number), span)?;
+        let unit = match self.toks.peek() {
+            Some(tok)
+                if (tok.kind == TokenKind::Ident && !self.toks.text(tok).starts_with('-'))
+                    || tok.is_symbol('\\') =>
+            {
+                let u = self.parse_identifier_no_interpolation(true)?;
+                span = span.merge(u.span);
+                Unit::from(u.node)
+            }
+            Some(tok) if tok.is_symbol('%') => {
                 self.toks.next();
-                return Some(Err(("Expected expression.", span).into()));
+                span = span.merge(tok.pos);
+                Unit::Percent
             }
-            ' ' | '\n' | '\t' => unreachable!("whitespace is checked prior to this match"),
-            'A'..='Z' | 'a'..='z' | '_' | '\\' => {
-                unreachable!("these chars are checked in an if stmt")
+            Some(..) | None => Unit::None,
+        };
+
+        let n = if val.dec_len == 0 {
+            if val.num.len() <= 18 && val.times_ten.is_empty() {
+                let n = Rational64::new_raw(parse_i64(&val.num), 1);
+                return Ok(Value::Dimension(Number::new_small(n), unit, None).span(span));
             }
-        }))
+            BigRational::new_raw(val.num.parse::<BigInt>().unwrap(), BigInt::one())
+        } else {
+            if val.num.len() <= 18 && val.times_ten.is_empty() {
+                let n = Rational64::new(parse_i64(&val.num), pow(10, val.dec_len));
+                return Ok(Value::Dimension(Number::new_small(n), unit, None).span(span));
+            }
+            BigRational::new(val.num.parse().unwrap(), pow(BigInt::from(10), val.dec_len))
+        };
+
+        if val.times_ten.is_empty() {
+            return Ok(Value::Dimension(Number::new_big(n), unit, None).span(span));
+        }
+
+        let times_ten = pow(
+            BigInt::from(10),
+            val.times_ten
+                .parse::<BigInt>()
+                .unwrap()
+                .to_usize()
+                .ok_or(("Exponent too large (expected usize).", span))?,
+        );
+
+        let times_ten = if val.times_ten_is_postive {
+            BigRational::new_raw(times_ten, BigInt::one())
+        } else {
+            BigRational::new(BigInt::one(), times_ten)
+        };
+
+        Ok(Value::Dimension(Number::new_big(n * times_ten), unit, None).span(span))
     }
 
-    fn parse_hex(&mut self) -> SassResult<Spanned<Value>> {
+    /// Parse a hex color such as `#fff`, or the unquoted string `#` followed
+    /// by an identifier, assuming the `#` has already been consumed
+    fn parse_hex(&mut self) -> SassResult<Expr> {
         let mut s = String::with_capacity(7);
         s.push('#');
         let first_char = self
             .toks
-            .peek()
-            .ok_or(("Expected identifier.", self.span_before))?
-            .kind;
+            .peek_char()
+            .ok_or(("Expected identifier.", self.span_before))?;
         let first_is_digit = first_char.is_ascii_digit();
         let first_is_hexdigit = first_char.is_ascii_hexdigit();
         if first_is_digit {
-            while let Some(c) = self.toks.peek() {
-                if !c.kind.is_ascii_hexdigit() || s.len() == 9 {
-                    break;
-                }
-                let tok = self.toks.next().unwrap();
-                self.span_before = self.span_before.merge(tok.pos());
-                s.push(tok.kind);
+            let start = self.toks.cursor();
+            if let Some(run) = self.toks.next_name() {
+                let text = self.toks.text(run);
+                let len = text
+                    .bytes()
+                    .take(8)
+                    .take_while(u8::is_ascii_hexdigit)
+                    .count();
+                s.push_str(&text[..len]);
+                self.toks.set_cursor(start + len);
+                self.span_before = self.span_before.merge(self.toks.span_from(start));
             }
         // this branch exists so that we can emit `#` combined with
         // identifiers. e.g. `#ooobar` should be emitted exactly as written;
         // that is, `#ooobar`.
         } else {
-            let ident = self.parse_identifier()?;
-            if first_is_hexdigit
-                && ident.node.chars().all(|c| c.is_ascii_hexdigit())
-                && matches!(ident.node.len(), 3 | 4 | 6 | 8)
-            {
-                s.push_str(&ident.node);
-            } else {
-                return Ok(Spanned {
-                    node: Value::String(format!("#{}", ident.node), QuoteKind::None),
-                    span: ident.span,
-                });
+            let Spanned { node: ident, span } = self.parse_interpolated_identifier()?;
+            let is_hex = match ident.as_plain() {
+                Some(ident) => {
+                    first_is_hexdigit
+                        && ident.chars().all(|c| c.is_ascii_hexdigit())
+                        && matches!(ident.len(), 3 | 4 | 6 | 8)
+                }
+                None => false,
+            };
+            let mut buf = Interpolation::new(span);
+            buf.push_char('#');
+            buf.append(ident);
+            if !is_hex {
+                return Ok(string_expr(buf, span));
             }
+            s = buf.as_plain().unwrap().to_owned();
         }
         let v = match u32::from_str_radix(&s[1..], 16) {
             Ok(a) => a,
-            Err(_) => return Ok(Value::String(s, QuoteKind::None).span(self.span_before)),
+            Err(_) => {
+                return Ok(
+                    AstExpr::Literal(Value::String(s, QuoteKind::None)).span(self.span_before)
+                )
+            }
         };
         let (red, green, blue, alpha) = match s.len().saturating_sub(1) {
             3 => (
@@ -700,415 +710,17 @@ impl<'a> Parser<'a> {
             _ => return Err(("Expected hex digit.", self.span_before).into()),
         };
         let color = Color::new(red, green, blue, alpha, s);
-        Ok(Value::Color(Box::new(color)).span(self.span_before))
-    }
-}
-
-struct IntermediateValueIterator<'a, 'b: 'a> {
-    parser: &'a mut Parser<'b>,
-    peek: Option<SassResult<Spanned<IntermediateValue>>>,
-}
-
-impl<'a, 'b: 'a> Iterator for IntermediateValueIterator<'a, 'b> {
-    type Item = SassResult<Spanned<IntermediateValue>>;
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.peek.is_some() {
-            self.peek.take()
-        } else {
-            self.parser.parse_intermediate_value()
-        }
-    }
-}
-
-impl<'a, 'b: 'a> IntermediateValueIterator<'a, 'b> {
-    pub fn new(parser: &'a mut Parser<'b>) -> Self {
-        Self { parser, peek: None }
-    }
-
-    fn peek(&mut self) -> &Option<SassResult<Spanned<IntermediateValue>>> {
-        self.peek = self.next();
-        &self.peek
-    }
-
-    fn whitespace(&mut self) -> bool {
-        let mut found_whitespace = false;
-        while let Some(w) = self.peek() {
-            if !w.is_whitespace() {
-                break;
-            }
-            found_whitespace = true;
-            self.next();
-        }
-        found_whitespace
-    }
-
-    fn eat_op(
-        &mut self,
-        op: Spanned<Op>,
-        space_separated: &mut Vec<Spanned<HigherIntermediateValue>>,
-        last_was_whitespace: bool,
-    ) -> SassResult<()> {
-        match op.node {
-            Op::Not => {
-                self.whitespace();
-                let right = self.single_value()?;
-                space_separated.push(Spanned {
-                    node: HigherIntermediateValue::UnaryOp(op.node, Box::new(right.node)),
-                    span: right.span,
-                });
-            }
-            Op::Div => {
-                self.whitespace();
-                let right = self.single_value()?;
-                if let Some(left) = space_separated.pop() {
-                    space_separated.push(Spanned {
-                        node: HigherIntermediateValue::BinaryOp(
-                            Box::new(left.node),
-                            op.node,
-                            Box::new(right.node),
-                        ),
-                        span: left.span.merge(right.span),
-                    });
-                } else {
-                    self.whitespace();
-                    space_separated.push(Spanned {
-                        node: HigherIntermediateValue::Literal(Value::String(
-                            format!(
-                                "/{}",
-                                ValueVisitor::new(self.parser, right.span)
-                                    .eval(right.node)?
-                                    .to_css_string(right.span)?
-                            ),
-                            QuoteKind::None,
-                        )),
-                        span: op.span.merge(right.span),
-                    });
-                }
-            }
-            Op::Plus => {
-                if let Some(left) = space_separated.pop() {
-                    self.whitespace();
-                    let right = self.single_value()?;
-                    space_separated.push(Spanned {
-                        node: HigherIntermediateValue::BinaryOp(
-                            Box::new(left.node),
-                            op.node,
-                            Box::new(right.node),
-                        ),
-                        span: left.span.merge(right.span),
-                    });
-                } else {
-                    self.whitespace();
-                    let right = self.single_value()?;
-                    space_separated.push(Spanned {
-                        node: HigherIntermediateValue::UnaryOp(op.node, Box::new(right.node)),
-                        span: right.span,
-                    });
-                }
-            }
-            Op::Minus => {
-                if self.whitespace() || !last_was_whitespace {
-                    let right = self.single_value()?;
-                    if let Some(left) = space_separated.pop() {
-                        space_separated.push(Spanned {
-                            node: HigherIntermediateValue::BinaryOp(
-                                Box::new(left.node),
-                                op.node,
-                                Box::new(right.node),
-                            ),
-                            span: left.span.merge(right.span),
-                        });
-                    } else {
-                        space_separated.push(
-                            right.map_node(|n| {
-                                HigherIntermediateValue::UnaryOp(op.node, Box::new(n))
-                            }),
-                        );
-                    }
-                } else {
-                    let right = self.single_value()?;
-                    space_separated.push(
-                        right.map_node(|n| HigherIntermediateValue::UnaryOp(op.node, Box::new(n))),
-                    );
-                }
-            }
-            Op::And => {
-                self.whitespace();
-                // special case when the value is literally "and"
-                if self.peek().is_none() {
-                    space_separated.push(
-                        HigherIntermediateValue::Literal(Value::String(
-                            op.to_string(),
-                            QuoteKind::None,
-                        ))
-                        .span(op.span),
-                    );
-                } else if let Some(left) = space_separated.pop() {
-                    self.whitespace();
-                    if ValueVisitor::new(self.parser, left.span)
-                        .eval(left.node.clone())?
-                        .is_true()
-                    {
-                        let right = self.single_value()?;
-                        space_separated.push(
-                            HigherIntermediateValue::BinaryOp(
-                                Box::new(left.node),
-                                op.node,
-                                Box::new(right.node),
-                            )
-                            .span(left.span.merge(right.span)),
-                        );
-                    } else {
-                        // we explicitly ignore errors here as a workaround for short circuiting
-                        while let Some(value) = self.peek() {
-                            if let Ok(Spanned {
-                                node: IntermediateValue::Comma,
-                                ..
-                            }) = value
-                            {
-                                break;
-                            }
-                            self.next();
-                        }
-                        space_separated.push(left);
-                    }
-                } else {
-                    return Err(("Expected expression.", op.span).into());
-                }
-            }
-            Op::Or => {
-                self.whitespace();
-                // special case when the value is literally "or"
-                if self.peek().is_none() {
-                    space_separated.push(
-                        HigherIntermediateValue::Literal(Value::String(
-                            op.to_string(),
-                            QuoteKind::None,
-                        ))
-                        .span(op.span),
-                    );
-                } else if let Some(left) = space_separated.pop() {
-                    self.whitespace();
-                    if ValueVisitor::new(self.parser, left.span)
-                        .eval(left.node.clone())?
-                        .is_true()
-                    {
-                        // we explicitly ignore errors here as a workaround for short circuiting
-                        while let Some(value) = self.peek() {
-                            match value {
-                                Ok(Spanned {
-                                    node: IntermediateValue::Comma,
-                                    ..
-                                }) => break,
-                                Ok(..) => {
-                                    self.next();
-                                }
-                                Err(..) => {
-                                    if let Some(v) = self.next() {
-                                        v?;
-                                    }
-                                }
-                            }
-                        }
-                        space_separated.push(left);
-                    } else {
-                        let right = self.single_value()?;
-                        space_separated.push(
-                            HigherIntermediateValue::BinaryOp(
-                                Box::new(left.node),
-                                op.node,
-                                Box::new(right.node),
-                            )
-                            .span(left.span.merge(right.span)),
-                        );
-                    }
-                } else {
-                    return Err(("Expected expression.", op.span).into());
-                }
-            }
-            _ => {
-                if let Some(left) = space_separated.pop() {
-                    self.whitespace();
-                    let right = self.single_value()?;
-                    space_separated.push(
-                        HigherIntermediateValue::BinaryOp(
-                            Box::new(left.node),
-                            op.node,
-                            Box::new(right.node),
-                        )
-                        .span(left.span.merge(right.span)),
-                    );
-                } else {
-                    return Err(("Expected expression.", op.span).into());
-                }
-            }
-        }
-        Ok(())
-    }
-
-    fn single_value(&mut self) -> SassResult<Spanned<HigherIntermediateValue>> {
-        let next = self
-            .next()
-            .ok_or(("Expected expression.", self.parser.span_before))??;
-        Ok(match next.node {
-            IntermediateValue::Value(v) => v.span(next.span),
-            IntermediateValue::Op(op) => match op {
-                Op::Minus => {
-                    self.whitespace();
-                    let val = self.single_value()?;
-                    Spanned {
-                        node: HigherIntermediateValue::UnaryOp(Op::Minus, Box::new(val.node)),
-                        span: next.span.merge(val.span),
-                    }
-                }
-                Op::Not => {
-                    self.whitespace();
-                    let val = self.single_value()?;
-                    Spanned {
-                        node: HigherIntermediateValue::UnaryOp(Op::Not, Box::new(val.node)),
-                        span: next.span.merge(val.span),
-                    }
-                }
-                Op::Plus => {
-                    self.whitespace();
-                    self.single_value()?
-                }
-                Op::Div => {
-                    self.whitespace();
-                    let val = self.single_value()?;
-                    Spanned {
-                        node: HigherIntermediateValue::Literal(Value::String(
-                            format!(
-                                "/{}",
-                                ValueVisitor::new(self.parser, val.span)
-                                    .eval(val.node)?
-                                    .to_css_string(val.span)?
-                            ),
-                            QuoteKind::None,
-                        )),
-                        span: next.span.merge(val.span),
-                    }
-                }
-                Op::And => Spanned {
-                    node: HigherIntermediateValue::Literal(Value::String(
-                        "and".into(),
-                        QuoteKind::None,
-                    )),
-                    span: next.span,
-                },
-                Op::Or => Spanned {
-                    node: HigherIntermediateValue::Literal(Value::String(
-                        "or".into(),
-                        QuoteKind::None,
-                    )),
-                    span: next.span,
-                },
-                _ => {
-                    return Err(("Expected expression.", next.span).into());
-                }
-            },
-            IntermediateValue::Whitespace => unreachable!(),
-            IntermediateValue::Comma => {
-                return Err(("Expected expression.", self.parser.span_before).into())
-            }
-            IntermediateValue::Bracketed(t) => {
-                let v = self.parser.parse_value_from_vec(t)?;
-                HigherIntermediateValue::Literal(match v.node {
-                    Value::List(v, sep, Brackets::None) => Value::List(v, sep, Brackets::Bracketed),
-                    v => Value::List(vec![v], ListSeparator::Space, Brackets::Bracketed),
-                })
-                .span(v.span)
-            }
-            IntermediateValue::Paren(t) => {
-                let val = self.parse_paren(Spanned {
-                    node: t,
-                    span: next.span,
-                })?;
-                Spanned {
-                    node: HigherIntermediateValue::Paren(Box::new(val.node)),
-                    span: val.span,
-                }
-            }
-        })
-    }
-
-    fn parse_paren(
-        &mut self,
-        t: Spanned<Vec<Token>>,
-    ) -> SassResult<Spanned<HigherIntermediateValue>> {
-        if t.is_empty() {
-            return Ok(HigherIntermediateValue::Literal(Value::List(
-                Vec::new(),
-                ListSeparator::Space,
-                Brackets::None,
-            ))
-            .span(t.span));
-        }
-
-        let paren_toks = &mut t.node.into_iter().peekmore();
-
-        let mut map = SassMap::new();
-        let key = self
-            .parser
-            .parse_value_from_vec(read_until_char(paren_toks, ':')?)?;
-
-        if paren_toks.peek().is_none() {
-            return Ok(Spanned {
-                node: HigherIntermediateValue::Paren(Box::new(HigherIntermediateValue::Literal(
-                    key.node,
-                ))),
-                span: key.span,
-            });
-        }
-
-        let val = self
-            .parser
-            .parse_value_from_vec(read_until_char(paren_toks, ',')?)?;
-
-        map.insert(key.node, val.node);
-
-        devour_whitespace(paren_toks);
-
-        if paren_toks.peek().is_none() {
-            return Ok(Spanned {
-                node: HigherIntermediateValue::Literal(Value::Map(map)),
-                span: key.span.merge(val.span),
-            });
-        }
-
-        let mut span = key.span;
-
-        loop {
-            let key = self
-                .parser
-                .parse_value_from_vec(read_until_char(paren_toks, ':')?)?;
-            devour_whitespace(paren_toks);
-            let val = self
-                .parser
-                .parse_value_from_vec(read_until_char(paren_toks, ',')?)?;
-            span = span.merge(val.span);
-            devour_whitespace(paren_toks);
-            if map.insert(key.node, val.node) {
-                return Err(("Duplicate key.", key.span).into());
-            }
-            if paren_toks.peek().is_none() {
-                break;
-            }
-        }
-        Ok(Spanned {
-            node: HigherIntermediateValue::Literal(Value::Map(map)),
-            span,
-        })
+        Ok(AstExpr::Literal(Value::Color(Box::new(color))).span(self.span_before))
     }
 }
 
-impl IsWhitespace for SassResult<Spanned<IntermediateValue>> {
-    fn is_whitespace(&self) -> bool {
-        match self {
-            Ok(v) => v.node.is_whitespace(),
-            _ => false,
-        }
+/// An unquoted string, which is only evaluated if it contains interpolation
+fn string_expr(string: Interpolation, span: Span) -> Expr {
+    match string.as_plain() {
+        Some(s) => AstExpr::Literal(Value::String(s.to_owned(), QuoteKind::None)),
+        None => AstExpr::String(string, QuoteKind::None),
     }
+    .span(span)
 }
 
 fn parse_i64(s: &str) -> i64 {
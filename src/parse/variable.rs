@@ -1,195 +1,217 @@
+use std::collections::HashMap;
+
 use codemap::Spanned;
 
 use crate::{
+    args::CallArg,
+    ast::{Argument, AstExpr, AstStmt, Expr},
+    builtin::GLOBAL_FUNCTIONS,
     common::Identifier,
     error::SassResult,
-    utils::{
-        peek_ident_no_interpolation, read_until_closing_paren, read_until_closing_quote,
-        read_until_newline,
-    },
     value::Value,
-    Token,
+    Token, TokenKind,
 };
 
 use super::Parser;
 
-#[derive(Debug)]
-struct VariableValue {
-    value: Spanned<Value>,
-    global: bool,
-    default: bool,
-}
+impl<'a> Parser<'a> {
+    pub(super) fn parse_variable_declaration(&mut self) -> SassResult<AstStmt> {
+        let Spanned { node: name, span } = self.parse_variable_name()?;
+        let value = self.parse_expression()?;
+
+        let mut default = false;
+        let mut global = false;
+
+        while let Some(Token { pos, .. }) = self.toks.peek().filter(|tok| tok.is_symbol('!')) {
+            self.toks.next();
+            // todo: it should not be possible to declare the same flag more than once
+            let mut flag = match self.toks.peek() {
+                Some(..) => self.parse_identifier_no_interpolation(false)?,
+                None => return Err(("Expected identifier.", pos).into()),
+            };
+            flag.node.make_ascii_lowercase();
+            match flag.node.as_str() {
+                "global" => global = true,
+                "default" => default = true,
+                _ => return Err(("Invalid flag name.", flag.span).into()),
+            }
+            self.whitespace_or_comment();
+        }
+
+        match self.toks.peek() {
+            Some(tok) if tok.is_symbol(';') => {
+                self.toks.next();
+            }
+            Some(Token {
+                kind: TokenKind::Symbol('}' | '{'),
+                ..
+            })
+            | None => {}
+            Some(Token { pos, .. }) => return Err(("expected \";\".", pos).into()),
+        }
 
-impl VariableValue {
-    pub const fn new(value: Spanned<Value>, global: bool, default: bool) -> Self {
-        Self {
+        Ok(AstStmt::VariableDecl {
+            name,
             value,
             global,
             default,
-        }
+            span,
+        })
     }
-}
 
-impl<'a> Parser<'a> {
-    pub(super) fn parse_variable_declaration(&mut self) -> SassResult<()> {
-        assert!(matches!(self.toks.next(), Some(Token { kind: '$', .. })));
-        let ident: Identifier = self.parse_identifier_no_interpolation(false)?.node.into();
+    /// Parse the name of a variable declaration, up to and including the `:`
+    ///
+    /// The span of the name includes its `$`.
+    pub(super) fn parse_variable_name(&mut self) -> SassResult<Spanned<Identifier>> {
+        let dollar = match self.toks.next() {
+            Some(tok) if tok.is_symbol('$') => tok.pos,
+            _ => unreachable!("variable declarations begin with `$`"),
+        };
+        let ident = self.parse_identifier_no_interpolation(false)?;
         self.whitespace();
-        if !matches!(self.toks.next(), Some(Token { kind: ':', .. })) {
+        if !self.scan_char(':') {
             return Err(("expected \":\".", self.span_before).into());
         }
-        let value = self.parse_variable_value()?;
+        Ok(Spanned {
+            node: ident.node.into(),
+            span: dollar.merge(ident.span),
+        })
+    }
 
-        if value.global && !value.default {
-            self.global_scope
-                .insert_var(ident.clone(), value.value.clone());
-        }
+    /// Assign an already evaluated value to a variable in the appropriate
+    /// scope
+    ///
+    /// An assignment at the root of the stylesheet, or one marked `!global`,
+    /// goes to the global scope. Otherwise it goes to the innermost local
+    /// scope that already defines the variable, or declares a new variable in
+    /// the innermost scope. A global variable is shadowed rather than
+    /// assigned to, unless we're in a control flow rule at the root of the
+    /// stylesheet.
+    pub(super) fn declare_variable(
+        &mut self,
+        ident: Identifier,
+        value: Spanned<Value>,
+        global: bool,
+        default: bool,
+    ) {
+        let global = global || self.scopes.is_empty();
 
-        if value.default {
-            if self.at_root && !self.flags.in_control_flow() {
-                if !self.global_scope.var_exists_no_global(&ident) {
-                    self.global_scope.insert_var(ident, value.value);
-                }
+        if default {
+            let existing = if global {
+                self.global_scope.get_var(ident).cloned()
             } else {
-                if value.global && !self.global_scope.var_exists_no_global(&ident) {
-                    self.global_scope
-                        .insert_var(ident.clone(), value.value.clone());
-                }
-                if !self.scopes.last().var_exists_no_global(&ident) {
-                    self.scopes.last_mut().insert_var(ident, value.value);
-                }
-            }
-        } else if self.at_root {
-            if self.flags.in_control_flow() {
-                if self.global_scope.var_exists_no_global(&ident) {
-                    self.global_scope.insert_var(ident, value.value);
-                } else {
-                    self.scopes.last_mut().insert_var(ident, value.value);
-                }
-            } else {
-                self.global_scope.insert_var(ident, value.value);
+                self.scopes.lookup_var(ident, self.global_scope)
+            };
+            if matches!(existing, Some(v) if v.node != Value::Null) {
+                return;
             }
+        }
+
+        if self.assigns_to_global(ident, global) {
+            self.global_scope.insert_var(ident, value);
         } else {
-            let len = self.scopes.len();
-            for (_, scope) in self
-                .scopes
-                .iter_mut()
-                .enumerate()
-                .filter(|(i, _)| *i != len)
-            {
-                if scope.var_exists_no_global(&ident) {
-                    scope.insert_var(ident.clone(), value.value.clone());
-                }
-            }
-            if self.scopes.first().var_exists_no_global(&ident) {
-                self.scopes
-                    .first_mut()
-                    .insert_var(ident.clone(), value.value.clone());
-            }
-            self.scopes.last_mut().insert_var(ident, value.value);
+            self.scopes.assign_var(ident, value, self.global_scope);
         }
-        Ok(())
     }
 
-    fn parse_variable_value(&mut self) -> SassResult<VariableValue> {
-        let mut default = false;
-        let mut global = false;
+    /// Whether an assignment to `$ident` goes to the global scope rather than
+    /// a local one
+    fn assigns_to_global(&self, ident: Identifier, global: bool) -> bool {
+        global
+            || self.scopes.is_empty()
+            || (self.at_root
+                && self.flags.in_control_flow()
+                && !self.scopes.local_var_exists(ident)
+                && self.global_scope.var_exists(ident))
+    }
+
+    /// Evaluate the value of a variable declaration
+    ///
+    /// For `$list: append($list, ...)` and the like, the current value of
+    /// `$list` is moved into the call rather than copied, so that `append`
+    /// may push to the list in place rather than copying it. Otherwise the
+    /// list would be shared with the variable, and building up a list in a
+    /// loop would take quadratic time.
+    pub(super) fn eval_variable_value(
+        &mut self,
+        ident: Identifier,
+        value: &Expr,
+        global: bool,
+        default: bool,
+    ) -> SassResult<Spanned<Value>> {
+        let call = match &value.node {
+            AstExpr::FunctionCall(call) if !default && call.namespace.is_none() => call,
+            _ => return self.eval_expr(value),
+        };
 
-        let mut val_toks = Vec::new();
-        let mut nesting = 0;
-        while let Some(tok) = self.toks.peek() {
-            match tok.kind {
-                ';' => {
-                    self.toks.next();
-                    break;
-                }
-                '\\' => {
-                    val_toks.push(self.toks.next().unwrap());
-                    if self.toks.peek().is_some() {
-                        val_toks.push(self.toks.next().unwrap());
-                    }
-                }
-                '"' | '\'' => {
-                    let quote = self.toks.next().unwrap();
-                    val_toks.push(quote);
-                    val_toks.extend(read_until_closing_quote(self.toks, quote.kind)?);
-                }
-                '#' => {
-                    val_toks.push(self.toks.next().unwrap());
-                    match self.toks.peek() {
-                        Some(Token { kind: '{', .. }) => nesting += 1,
-                        Some(Token { kind: ';', .. }) => break,
-                        Some(Token { kind: '}', .. }) => {
-                            if nesting == 0 {
-                                break;
-                            } else {
-                                nesting -= 1;
-                            }
-                        }
-                        Some(..) | None => {}
-                    }
-                    if let Some(tok) = self.toks.next() {
-                        val_toks.push(tok);
-                    }
-                }
-                '{' => break,
-                '}' => {
-                    if nesting == 0 {
-                        break;
-                    } else {
-                        nesting -= 1;
-                        val_toks.push(self.toks.next().unwrap());
-                    }
-                }
-                '/' => {
-                    let next = self.toks.next().unwrap();
-                    match self.toks.peek() {
-                        Some(Token { kind: '/', .. }) => read_until_newline(self.toks),
-                        Some(..) | None => val_toks.push(next),
-                    };
-                    continue;
-                }
-                '(' => {
-                    val_toks.push(self.toks.next().unwrap());
-                    val_toks.extend(read_until_closing_paren(self.toks)?);
-                }
-                '!' => {
-                    let pos = tok.pos();
-                    match self.toks.peek_forward(1) {
-                        Some(Token { kind: '=', .. }) => {
-                            self.toks.reset_cursor();
-                            val_toks.push(self.toks.next().unwrap());
-                            continue;
-                        }
-                        Some(..) => {}
-                        None => return Err(("Expected identifier.", pos).into()),
-                    }
-                    // todo: it should not be possible to declare the same flag more than once
-                    let mut ident = peek_ident_no_interpolation(self.toks, false, pos)?;
-                    ident.node.make_ascii_lowercase();
-                    match ident.node.as_str() {
-                        "global" => {
-                            self.toks.truncate_iterator_to_cursor();
-                            global = true;
-                        }
-                        "default" => {
-                            self.toks.truncate_iterator_to_cursor();
-                            default = true;
-                        }
-                        "important" => {
-                            self.toks.reset_cursor();
-                            val_toks.push(self.toks.next().unwrap());
-                            continue;
-                        }
-                        _ => {
-                            return Err(("Invalid flag name.", ident.span).into());
-                        }
-                    }
-                }
-                _ => val_toks.push(self.toks.next().unwrap()),
+        let fn_name = call.name.resolve();
+        let builtin = match GLOBAL_FUNCTIONS.get(&*fn_name) {
+            Some(f) if UPDATES_FIRST_ARG_IN_PLACE.contains(&&*fn_name) => f.clone(),
+            _ => return self.eval_expr(value),
+        };
+
+        let var_span = match call.args.args.split_first() {
+            Some((
+                Argument::Positional(Spanned {
+                    node:
+                        AstExpr::Variable {
+                            namespace: None,
+                            name,
+                        },
+                    span,
+                }),
+                rest,
+            )) if name.node == ident
+                && !rest.iter().any(|arg| arg.expr().node.may_read_var(ident))
+                && !self.scopes.user_fn_exists(call.name, self.global_scope)
+                && self.modules.get_global_fn(call.name).is_none() =>
+            {
+                *span
             }
-        }
-        let val = self.parse_value_from_vec(val_toks)?;
-        Ok(VariableValue::new(val, global, default))
+            _ => return self.eval_expr(value),
+        };
+
+        // The variable must be read from the same scope that the result is
+        // assigned to, or we'd clobber a variable that is only being shadowed
+        let taken = if self.assigns_to_global(ident, global) {
+            if self.scopes.local_var_exists(ident) {
+                None
+            } else {
+                self.global_scope.take_var(ident)
+            }
+        } else {
+            self.scopes.take_local_var(ident)
+        };
+
+        let taken = match taken {
+            Some(v) => v,
+            None => return self.eval_expr(value),
+        };
+
+        let mut args = HashMap::new();
+        args.insert(
+            CallArg::Positional(0),
+            Ok(Spanned {
+                node: taken.node,
+                span: var_span,
+            }),
+        );
+        let args = self.eval_remaining_args(args, &call.args.args[1..], call.args.span)?;
+
+        Ok(Spanned {
+            node: builtin.0(args, self)?,
+            span: value.span,
+        })
     }
 }
+
+/// Builtins that modify their first argument in place if it isn't shared,
+/// rather than copying it
+const UPDATES_FIRST_ARG_IN_PLACE: &[&str] = &[
+    "append",
+    "join",
+    "set-nth",
+    "map-merge",
+    "map-remove",
+    "map-set",
+];
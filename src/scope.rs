@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use codemap::Spanned;
 
@@ -17,7 +17,6 @@ pub(crate) struct Scope {
     functions: HashMap<Identifier, Function>,
 }
 
-// todo: separate struct for global scope?
 impl Scope {
     #[must_use]
     pub fn new() -> Self {
@@ -28,11 +27,95 @@ impl Scope {
         }
     }
 
-    fn get_var_no_global(&self, name: &Spanned<Identifier>) -> SassResult<Spanned<Value>> {
-        match self.vars.get(&name.node) {
-            Some(v) => Ok(v.clone()),
-            None => Err(("Undefined variable.", name.span).into()),
-        }
+    pub fn get_var(&self, name: Identifier) -> Option<&Spanned<Value>> {
+        self.vars.get(&name)
+    }
+
+    pub fn insert_var<T: Into<Identifier>>(
+        &mut self,
+        s: T,
+        v: Spanned<Value>,
+    ) -> Option<Spanned<Value>> {
+        self.vars.insert(s.into(), v)
+    }
+
+    pub fn var_exists(&self, name: Identifier) -> bool {
+        self.vars.contains_key(&name)
+    }
+
+    /// Move the value of a variable out of this scope, leaving `null` in its
+    /// place
+    pub fn take_var(&mut self, name: Identifier) -> Option<Spanned<Value>> {
+        let var = self.vars.get_mut(&name)?;
+        Some(Spanned {
+            node: std::mem::replace(&mut var.node, Value::Null),
+            span: var.span,
+        })
+    }
+
+    fn get_mixin(&self, name: Identifier) -> Option<&Mixin> {
+        self.mixins.get(&name)
+    }
+
+    pub fn insert_mixin<T: Into<Identifier>>(&mut self, s: T, v: Mixin) -> Option<Mixin> {
+        self.mixins.insert(s.into(), v)
+    }
+
+    fn mixin_exists(&self, name: Identifier) -> bool {
+        self.mixins.contains_key(&name)
+    }
+
+    fn get_fn(&self, name: Identifier) -> Option<&Function> {
+        self.functions.get(&name)
+    }
+
+    pub fn insert_fn<T: Into<Identifier>>(&mut self, s: T, v: Function) -> Option<Function> {
+        self.functions.insert(s.into(), v)
+    }
+
+    fn fn_exists(&self, name: Identifier) -> bool {
+        self.functions.contains_key(&name)
+    }
+}
+
+/// The local scopes visible at some point in a stylesheet, from outermost to
+/// innermost
+///
+/// The global scope is not included, as there is only ever one global scope,
+/// owned by the `Parser`.
+///
+/// Each scope is shared between every `Scopes` it appears in. Mixins,
+/// functions and content blocks capture the `Scopes` they are declared in,
+/// so they see later changes to the variables they close over, and their own
+/// assignments to those variables are visible outside of them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scopes(Vec<Rc<RefCell<Scope>>>);
+
+impl Scopes {
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Whether there are no local scopes, i.e. we're at the root of the
+    /// stylesheet
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn enter_new_scope(&mut self) {
+        self.0.push(Rc::new(RefCell::new(Scope::new())));
+    }
+
+    pub fn exit_scope(&mut self) {
+        self.0.pop();
+    }
+
+    /// Find the innermost scope defining a variable with this name
+    fn scope_with_var(&self, name: Identifier) -> Option<&Rc<RefCell<Scope>>> {
+        self.0
+            .iter()
+            .rev()
+            .find(|scope| scope.borrow().var_exists(name))
     }
 
     pub fn get_var<T: Into<Identifier>>(
@@ -41,34 +124,63 @@ impl Scope {
         global_scope: &Scope,
     ) -> SassResult<Spanned<Value>> {
         let name = name.map_node(Into::into);
-        match self.vars.get(&name.node) {
-            Some(v) => Ok(v.clone()),
-            None => global_scope.get_var_no_global(&name),
+        match self.lookup_var(name.node, global_scope) {
+            Some(v) => Ok(v),
+            None => Err(("Undefined variable.", name.span).into()),
+        }
+    }
+
+    /// Like `Scopes::get_var`, but returns `None` rather than an error if the
+    /// variable is undefined
+    pub fn lookup_var(&self, name: Identifier, global_scope: &Scope) -> Option<Spanned<Value>> {
+        for scope in self.0.iter().rev() {
+            if let Some(v) = scope.borrow().get_var(name) {
+                return Some(v.clone());
+            }
         }
+        global_scope.get_var(name).cloned()
     }
 
+    /// Declare a variable in the innermost scope, which is the global scope
+    /// if there are no local scopes
     pub fn insert_var<T: Into<Identifier>>(
         &mut self,
         s: T,
         v: Spanned<Value>,
+        global_scope: &mut Scope,
     ) -> Option<Spanned<Value>> {
-        self.vars.insert(s.into(), v)
+        match self.0.last() {
+            Some(scope) => scope.borrow_mut().insert_var(s, v),
+            None => global_scope.insert_var(s, v),
+        }
     }
 
-    pub fn var_exists_no_global(&self, name: &Identifier) -> bool {
-        self.vars.contains_key(name)
+    /// Assign to the innermost local scope defining a variable with this
+    /// name, or declare it in the innermost scope if none of them do
+    pub fn assign_var(&mut self, name: Identifier, v: Spanned<Value>, global_scope: &mut Scope) {
+        match self.scope_with_var(name) {
+            Some(scope) => {
+                scope.borrow_mut().insert_var(name, v);
+            }
+            None => {
+                self.insert_var(name, v, global_scope);
+            }
+        }
     }
 
-    pub fn var_exists<'a, T: Into<&'a Identifier>>(&self, v: T, global_scope: &Scope) -> bool {
-        let name = v.into();
-        self.vars.contains_key(name) || global_scope.var_exists_no_global(name)
+    /// Like `Scope::take_var`, but for the innermost local scope defining a
+    /// variable with this name
+    pub fn take_local_var(&mut self, name: Identifier) -> Option<Spanned<Value>> {
+        self.scope_with_var(name)?.borrow_mut().take_var(name)
     }
 
-    fn get_mixin_no_global(&self, name: &Spanned<Identifier>) -> SassResult<Mixin> {
-        match self.mixins.get(&name.node) {
-            Some(v) => Ok(v.clone()),
-            None => Err(("Undefined mixin.", name.span).into()),
-        }
+    pub fn local_var_exists(&self, name: Identifier) -> bool {
+        self.scope_with_var(name).is_some()
+    }
+
+    pub fn var_exists<T: Into<Identifier>>(&self, name: T, global_scope: &Scope) -> bool {
+        let name = name.into();
+        self.local_var_exists(name) || global_scope.var_exists(name)
     }
 
     pub fn get_mixin<T: Into<Identifier>>(
@@ -77,30 +189,35 @@ impl Scope {
         global_scope: &Scope,
     ) -> SassResult<Mixin> {
         let name = name.map_node(Into::into);
-        match self.mixins.get(&name.node) {
+        for scope in self.0.iter().rev() {
+            if let Some(mixin) = scope.borrow().get_mixin(name.node) {
+                return Ok(mixin.clone());
+            }
+        }
+        match global_scope.get_mixin(name.node) {
             Some(v) => Ok(v.clone()),
-            None => global_scope.get_mixin_no_global(&name),
+            None => Err(("Undefined mixin.", name.span).into()),
         }
     }
 
-    pub fn insert_mixin<T: Into<Identifier>>(&mut self, s: T, v: Mixin) -> Option<Mixin> {
-        self.mixins.insert(s.into(), v)
-    }
-
-    fn mixin_exists_no_global(&self, name: &Identifier) -> bool {
-        self.mixins.contains_key(name)
-    }
-
-    pub fn mixin_exists<T: Into<Identifier>>(&self, v: T, global_scope: &Scope) -> bool {
-        let name = v.into();
-        self.mixins.contains_key(&name) || global_scope.mixin_exists_no_global(&name)
+    /// Declare a mixin in the innermost scope, which is the global scope if
+    /// there are no local scopes
+    pub fn insert_mixin<T: Into<Identifier>>(
+        &mut self,
+        s: T,
+        v: Mixin,
+        global_scope: &mut Scope,
+    ) -> Option<Mixin> {
+        match self.0.last() {
+            Some(scope) => scope.borrow_mut().insert_mixin(s, v),
+            None => global_scope.insert_mixin(s, v),
+        }
     }
 
-    fn get_fn_no_global(&self, name: &Spanned<Identifier>) -> SassResult<Function> {
-        match self.functions.get(&name.node) {
-            Some(v) => Ok(v.clone()),
-            None => Err(("Undefined function.", name.span).into()),
-        }
+    pub fn mixin_exists<T: Into<Identifier>>(&self, name: T, global_scope: &Scope) -> bool {
+        let name = name.into();
+        self.0.iter().any(|scope| scope.borrow().mixin_exists(name))
+            || global_scope.mixin_exists(name)
     }
 
     pub fn get_fn<T: Into<Identifier>>(
@@ -109,24 +226,39 @@ impl Scope {
         global_scope: &Scope,
     ) -> SassResult<Function> {
         let name = name.map_node(Into::into);
-        match self.functions.get(&name.node) {
+        for scope in self.0.iter().rev() {
+            if let Some(function) = scope.borrow().get_fn(name.node) {
+                return Ok(function.clone());
+            }
+        }
+        match global_scope.get_fn(name.node) {
             Some(v) => Ok(v.clone()),
-            None => global_scope.get_fn_no_global(&name),
+            None => Err(("Undefined function.", name.span).into()),
         }
     }
 
-    pub fn insert_fn<T: Into<Identifier>>(&mut self, s: T, v: Function) -> Option<Function> {
-        self.functions.insert(s.into(), v)
+    /// Declare a function in the innermost scope, which is the global scope
+    /// if there are no local scopes
+    pub fn insert_fn<T: Into<Identifier>>(
+        &mut self,
+        s: T,
+        v: Function,
+        global_scope: &mut Scope,
+    ) -> Option<Function> {
+        match self.0.last() {
+            Some(scope) => scope.borrow_mut().insert_fn(s, v),
+            None => global_scope.insert_fn(s, v),
+        }
     }
 
-    fn fn_exists_no_global(&self, name: &Identifier) -> bool {
-        self.functions.contains_key(name)
+    pub fn fn_exists<T: Into<Identifier>>(&self, name: T, global_scope: &Scope) -> bool {
+        let name = name.into();
+        self.user_fn_exists(name, global_scope) || GLOBAL_FUNCTIONS.contains_key(&*name.resolve())
     }
 
-    pub fn fn_exists<T: Into<Identifier>>(&self, v: T, global_scope: &Scope) -> bool {
-        let name = v.into();
-        self.functions.contains_key(&name)
-            || global_scope.fn_exists_no_global(&name)
-            || GLOBAL_FUNCTIONS.contains_key(name.clone().into_inner().as_str())
+    /// Whether a function with this name has been declared with `@function`,
+    /// possibly shadowing a builtin
+    pub fn user_fn_exists(&self, name: Identifier, global_scope: &Scope) -> bool {
+        self.0.iter().any(|scope| scope.borrow().fn_exists(name)) || global_scope.fn_exists(name)
     }
 }
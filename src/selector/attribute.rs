@@ -5,7 +5,9 @@ use std::{
 
 use codemap::Span;
 
-use crate::{common::QuoteKind, error::SassResult, parse::Parser, utils::is_ident, value::Value};
+use crate::{
+    common::QuoteKind, error::SassResult, parse::Parser, utils::is_ident, value::Value, TokenKind,
+};
 
 use super::{Namespace, QualifiedName};
 
@@ -40,10 +42,15 @@ impl Hash for Attribute {
 
 fn attribute_name(parser: &mut Parser<'_>, start: Span) -> SassResult<QualifiedName> {
     let next = parser.toks.peek().ok_or(("Expected identifier.", start))?;
-    if next.kind == '*' {
+    if next.is_symbol('*') {
         let pos = next.pos;
         parser.toks.next();
-        if parser.toks.peek().ok_or(("expected \"|\".", pos))?.kind != '|' {
+        if !parser
+            .toks
+            .peek()
+            .ok_or(("expected \"|\".", pos))?
+            .is_symbol('|')
+        {
             return Err(("expected \"|\".", pos).into());
         }
 
@@ -58,7 +65,7 @@ fn attribute_name(parser: &mut Parser<'_>, start: Span) -> SassResult<QualifiedN
     parser.span_before = next.pos;
     let name_or_namespace = parser.parse_identifier()?;
     match parser.toks.peek() {
-        Some(v) if v.kind != '|' => {
+        Some(v) if !v.is_symbol('|') => {
             return Ok(QualifiedName {
                 ident: name_or_namespace.node,
                 namespace: Namespace::None,
@@ -67,17 +74,14 @@ fn attribute_name(parser: &mut Parser<'_>, start: Span) -> SassResult<QualifiedN
         Some(..) => {}
         None => return Err(("expected more input.", name_or_namespace.span).into()),
     }
-    match parser.toks.peek_forward(1) {
-        Some(v) if v.kind == '=' => {
-            parser.toks.reset_cursor();
+    match parser.toks.peek_next() {
+        Some(v) if v.is_symbol('=') => {
             return Ok(QualifiedName {
                 ident: name_or_namespace.node,
                 namespace: Namespace::None,
             });
         }
-        Some(..) => {
-            parser.toks.reset_cursor();
-        }
+        Some(..) => {}
         None => return Err(("expected more input.", name_or_namespace.span).into()),
     }
     parser.span_before = parser.toks.next().unwrap().pos();
@@ -91,15 +95,20 @@ fn attribute_name(parser: &mut Parser<'_>, start: Span) -> SassResult<QualifiedN
 fn attribute_operator(parser: &mut Parser<'_>) -> SassResult<AttributeOp> {
     let start = parser.span_before;
     let op = match parser.toks.next().ok_or(("Expected \"]\".", start))?.kind {
-        '=' => return Ok(AttributeOp::Equals),
-        '~' => AttributeOp::Include,
-        '|' => AttributeOp::Dash,
-        '^' => AttributeOp::Prefix,
-        '$' => AttributeOp::Suffix,
-        '*' => AttributeOp::Contains,
+        TokenKind::Symbol('=') => return Ok(AttributeOp::Equals),
+        TokenKind::Symbol('~') => AttributeOp::Include,
+        TokenKind::Symbol('|') => AttributeOp::Dash,
+        TokenKind::Symbol('^') => AttributeOp::Prefix,
+        TokenKind::Symbol('$') => AttributeOp::Suffix,
+        TokenKind::Symbol('*') => AttributeOp::Contains,
         _ => return Err(("Expected \"]\".", start).into()),
     };
-    if parser.toks.next().ok_or(("expected \"=\".", start))?.kind != '=' {
+    if !parser
+        .toks
+        .next()
+        .ok_or(("expected \"=\".", start))?
+        .is_symbol('=')
+    {
         return Err(("expected \"=\".", start).into());
     }
     Ok(op)
@@ -114,8 +123,7 @@ impl Attribute {
             .toks
             .peek()
             .ok_or(("expected more input.", start))?
-            .kind
-            == ']'
+            .is_symbol(']')
         {
             parser.toks.next();
             return Ok(Attribute {
@@ -134,32 +142,34 @@ impl Attribute {
         let peek = parser.toks.peek().ok_or(("expected more input.", start))?;
         parser.span_before = peek.pos;
         let value = match peek.kind {
-            q @ '\'' | q @ '"' => {
-                parser.toks.next();
-                match parser.parse_quoted_string(q)?.node {
-                    Value::String(s, ..) => s,
-                    _ => unreachable!(),
-                }
-            }
+            TokenKind::String => match parser.parse_quoted_string()?.node {
+                Value::String(s, ..) => s,
+                _ => unreachable!(),
+            },
             _ => parser.parse_identifier()?.node,
         };
         parser.whitespace();
 
         let peek = parser.toks.peek().ok_or(("expected more input.", start))?;
 
-        let modifier = match peek.kind {
-            c if c.is_alphabetic() => Some(c),
+        let modifier = match parser.toks.peek_char() {
+            Some(c) if c.is_alphabetic() => Some(c),
             _ => None,
         };
 
         let pos = peek.pos();
 
         if modifier.is_some() {
-            parser.toks.next();
+            parser.toks.next_char();
             parser.whitespace();
         }
 
-        if parser.toks.peek().ok_or(("expected \"]\".", pos))?.kind != ']' {
+        if !parser
+            .toks
+            .peek()
+            .ok_or(("expected \"]\".", pos))?
+            .is_symbol(']')
+        {
             return Err(("expected \"]\".", pos).into());
         }
 
@@ -289,7 +289,7 @@ impl ComplexSelectorComponent {
     ) -> SassResult<Option<Vec<ComplexSelector>>> {
         match self {
             Self::Compound(c) => c.resolve_parent_selectors(parent),
-            Self::Combinator(..) => todo!(),
+            Self::Combinator(..) => Ok(None),
         }
     }
 
@@ -13,21 +13,18 @@ pub(crate) struct ExtendedSelector(Rc<RefCell<SelectorList>>);
 
 impl PartialEq for ExtendedSelector {
     fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0
+        Rc::ptr_eq(&self.0, &other.0)
     }
 }
 
 impl Eq for ExtendedSelector {}
 
 impl Hash for ExtendedSelector {
-    // We hash the ptr here for efficiency.
-    // TODO: is this an issue? it probably is,
-    // but I haven't managed to find a test case
-    // that exhibits it.
+    // `ExtendedSelector`s are compared by identity, because the selector they
+    // contain changes as extensions are added, so we hash the ptr rather than
+    // the selector itself.
     fn hash<H: Hasher>(&self, state: &mut H) {
         ptr::hash(&*self.0, state)
-        // in case we need to hash the actual value:
-        // self.0.borrow().hash(state);
     }
 }
 
@@ -43,8 +40,10 @@ impl ExtendedSelector {
         })
     }
 
-    pub fn set_inner(&mut self, selector: SelectorList) {
-        self.0.replace(selector);
+    /// Calls `f` with a mutable reference to the current selector, allowing
+    /// it to be modified in place
+    pub fn with_selector_mut<T>(&mut self, f: impl FnOnce(&mut SelectorList) -> T) -> T {
+        f(&mut self.0.borrow_mut())
     }
 }
 
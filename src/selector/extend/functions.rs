@@ -547,7 +547,7 @@ fn merge_final_combinators(
             }
 
             result.push_front(vec![vec![
-                components_one.pop_back().unwrap(),
+                components_one.pop_back()?,
                 ComplexSelectorComponent::Combinator(*combinator_one),
             ]]);
 
@@ -569,12 +569,13 @@ fn merge_final_combinators(
             }
 
             result.push_front(vec![vec![
-                components_two.pop_back().unwrap(),
+                components_two.pop_back()?,
                 ComplexSelectorComponent::Combinator(*combinator_two),
             ]]);
             merge_final_combinators(components_one, components_two, Some(result))
         }
-        (None, None) => todo!("the above, but we dont have access to combinator_two"),
+        // Both lists ending without a combinator was handled by the early return above
+        (None, None) => Some(Vec::from(result)),
     }
 }
 
@@ -1,6 +1,7 @@
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     hash::Hash,
+    mem,
 };
 
 use codemap::Span;
@@ -101,12 +102,33 @@ pub(crate) struct Extender {
     /// [first law of extend]: https://github.com/sass/sass/issues/324#issuecomment-4607184
     originals: HashSet<ComplexSelector>,
 
+    /// Extensions that have been added but not yet applied to the selectors
+    /// that existed when they were added, in the order they were added.
+    ///
+    /// Selectors that are the target of many `@extend`s grow with each one,
+    /// so rather than rebuilding and re-trimming them once per extension,
+    /// extensions are applied to existing selectors in batches by
+    /// `Extender::apply_pending_extensions`.
+    pending: Vec<PendingExtension>,
+
+    /// The simple selectors in the extenders of `pending`, which may appear in
+    /// existing selectors once `pending` has been applied.
+    pending_extenders: HashSet<SimpleSelector>,
+
     /// The mode that controls this extender's behavior.
     mode: ExtendMode,
 
     span: Span,
 }
 
+/// Extensions added by a single `@extend`, along with the selectors that
+/// existed at the time that they apply to
+#[derive(Clone, Debug)]
+struct PendingExtension {
+    selectors: SelectorHashSet,
+    extensions: HashMap<SimpleSelector, IndexMap<ComplexSelector, Extension>>,
+}
+
 impl Extender {
     /// An `Extender` that contains no extensions and can have no extensions added.
     // TODO: empty extender
@@ -130,6 +152,8 @@ impl Extender {
             media_contexts: HashMap::new(),
             source_specificity: HashMap::new(),
             originals: HashSet::new(),
+            pending: Vec::new(),
+            pending_extenders: HashSet::new(),
             mode: ExtendMode::Normal,
             span,
         }
@@ -207,16 +231,36 @@ impl Extender {
     ) -> SelectorList {
         // This could be written more simply using Vec<Vec<T>>, but we want to avoid
         // any allocations in the common case where no extends apply.
+        match self.extend_components(&list.components, extensions, media_query_context) {
+            Some(components) => SelectorList {
+                components,
+                span: self.span,
+            },
+            None => list,
+        }
+    }
+
+    /// Extends the complex selectors that make up a `SelectorList`, returning
+    /// `None` if no extensions apply.
+    fn extend_components(
+        &mut self,
+        components: &[ComplexSelector],
+        extensions: Option<&HashMap<SimpleSelector, IndexMap<ComplexSelector, Extension>>>,
+        media_query_context: &Option<Vec<CssMediaQuery>>,
+    ) -> Option<Vec<ComplexSelector>> {
         let mut extended: Option<Vec<ComplexSelector>> = None;
-        for (i, complex) in list.components.iter().enumerate() {
-            if let Some(result) =
+        for (i, complex) in components.iter().enumerate() {
+            let result = if self.may_extend_complex(complex, extensions) {
                 self.extend_complex(complex.clone(), extensions, media_query_context)
-            {
+            } else {
+                None
+            };
+            if let Some(result) = result {
                 if extended.is_none() {
                     extended = Some(if i == 0 {
                         Vec::new()
                     } else {
-                        list.components[0..i].to_vec()
+                        components[0..i].to_vec()
                     });
                 }
                 match extended.as_mut() {
@@ -228,15 +272,39 @@ impl Extender {
             }
         }
 
-        let extended = match extended {
-            Some(v) => v,
-            None => return list,
-        };
+        Some(self.trim(extended?, |complex| self.originals.contains(complex)))
+    }
 
-        SelectorList {
-            components: self.trim(extended, |complex| self.originals.contains(complex)),
-            span: self.span,
-        }
+    /// Returns whether any simple selector in `complex`, including those nested
+    /// in selector pseudo-classes, has an extension.
+    ///
+    /// This is much cheaper than `Extender::extend_complex`, and lets us skip the
+    /// bulk of a large selector list when only a few of its complex selectors
+    /// are affected by a new extension.
+    fn may_extend_complex(
+        &self,
+        complex: &ComplexSelector,
+        extensions: Option<&HashMap<SimpleSelector, IndexMap<ComplexSelector, Extension>>>,
+    ) -> bool {
+        let extensions = extensions.unwrap_or(&self.extensions);
+        complex.components.iter().any(|component| match component {
+            ComplexSelectorComponent::Compound(compound) => {
+                compound.components.iter().any(|simple| {
+                    extensions.contains_key(simple)
+                        || match simple {
+                            SimpleSelector::Pseudo(Pseudo {
+                                selector: Some(selector),
+                                ..
+                            }) => selector
+                                .components
+                                .iter()
+                                .any(|complex| self.may_extend_complex(complex, Some(extensions))),
+                            _ => false,
+                        }
+                })
+            }
+            ComplexSelectorComponent::Combinator(..) => false,
+        })
     }
 
     /// Extends `complex` using `extensions`, and returns the contents of a
@@ -338,7 +406,7 @@ impl Extender {
                         // Make sure that copies of `complex` retain their status as "original"
                         // selectors. This includes selectors that are modified because a :not()
                         // was extended into.
-                        if first && self.originals.contains(&complex.clone()) {
+                        if first && self.originals.contains(&complex) {
                             self.originals.insert(output_complex.clone());
                         }
                         first = false;
@@ -651,13 +719,14 @@ impl Extender {
                         // become `.foo:not(.bar)`. However, this is a narrow edge case and
                         // supporting it properly would make this code and the code calling it
                         // a lot more complicated, so it's not supported for now.
-                        if &*inner_pseudo.normalized_name == "matches" {
+                        if matches!(&*inner_pseudo.normalized_name, "is" | "matches" | "where") {
                             inner_pseudo.selector.clone().unwrap().components
                         } else {
                             Vec::new()
                         }
                     }
-                    "matches" | "any" | "current" | "nth-child" | "nth-last-child" => {
+                    "is" | "matches" | "where" | "any" | "current" | "nth-child"
+                    | "nth-last-child" => {
                         // As above, we could theoretically support :not within :matches, but
                         // doing so would require this method and its callers to handle much
                         // more complex cases that likely aren't worth the pain.
@@ -801,7 +870,7 @@ impl Extender {
         let mut num_originals = 0;
 
         // :outer
-        for i in (0..=(selectors.len().saturating_sub(1))).rev() {
+        for i in (0..selectors.len()).rev() {
             let mut should_continue_to_outer = false;
             let complex1 = selectors.get(i).unwrap();
             if is_original(complex1) {
@@ -826,12 +895,7 @@ impl Extender {
             // generated. In order for `complex1` to be removed, there must be another
             // selector that's a superselector of it *and* that has specificity
             // greater or equal to this.
-            let mut max_specificity = 0;
-            for component in &complex1.components {
-                if let ComplexSelectorComponent::Compound(compound) = component {
-                    max_specificity = max_specificity.max(self.source_specificity_for(compound))
-                }
-            }
+            let max_specificity = self.max_source_specificity(complex1);
 
             // Look in `result` rather than `selectors` for selectors after `i`. This
             // ensures that we aren't comparing against a selector that's already been
@@ -859,6 +923,58 @@ impl Extender {
         Vec::from(result)
     }
 
+    /// Returns whether `trim` would leave the complex selectors of `nodes`
+    /// unchanged, given that they were already trimmed before those of
+    /// `changed` were added.
+    ///
+    /// Only pairs involving one of `changed` can let `trim` remove anything, so
+    /// this avoids comparing every selector with every other one each time a
+    /// list is extended.
+    fn is_trimmed(&self, nodes: &[ExtensionNode], changed: &[usize]) -> bool {
+        let mut is_changed = vec![false; nodes.len()];
+        for &node in changed {
+            is_changed[node] = true;
+        }
+
+        for (i, node) in nodes.iter().enumerate() {
+            let complex1 = &node.complex;
+            if is_changed[i] && self.originals.contains(complex1) {
+                return false;
+            }
+
+            let is_superseded = |j: usize| {
+                let complex2 = &nodes[j].complex;
+                j != i
+                    && complex2.is_super_selector(complex1)
+                    && complex2.min_specificity() >= self.max_source_specificity(complex1)
+            };
+
+            let is_superseded = if is_changed[i] {
+                (0..nodes.len()).any(is_superseded)
+            } else {
+                changed.iter().copied().any(is_superseded)
+            };
+
+            if is_superseded && !self.originals.contains(complex1) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// The maximum specificity of the sources that caused `complex` to be
+    /// generated.
+    fn max_source_specificity(&self, complex: &ComplexSelector) -> i32 {
+        let mut max_specificity = 0;
+        for component in &complex.components {
+            if let ComplexSelectorComponent::Compound(compound) = component {
+                max_specificity = max_specificity.max(self.source_specificity_for(compound))
+            }
+        }
+        max_specificity
+    }
+
     /// Adds `selector` to this extender.
     ///
     /// Extends `selector` using any registered extensions, then returns the resulting
@@ -950,6 +1066,12 @@ impl Extender {
         media_context: &Option<Vec<CssMediaQuery>>,
         span: Option<Span>,
     ) {
+        // A pending extension may add `target` to selectors that don't yet
+        // contain it, and those selectors must be extended too
+        if self.pending_extenders.contains(target) {
+            self.apply_pending_extensions();
+        }
+
         let selectors = self.selectors.get(target).cloned();
         let existing_extensions = self.extensions_by_extender.get(target).cloned();
 
@@ -1025,7 +1147,52 @@ impl Extender {
         }
 
         if let Some(selectors) = selectors {
-            self.extend_existing_selectors(selectors, &new_extensions_by_target);
+            for extensions in new_extensions_by_target.values() {
+                for complex in extensions.keys() {
+                    add_simple_selectors(complex, &mut self.pending_extenders);
+                }
+            }
+            self.pending.push(PendingExtension {
+                selectors,
+                extensions: new_extensions_by_target,
+            });
+        }
+    }
+
+    /// Applies every extension that has been added to the selectors that
+    /// existed when it was added.
+    ///
+    /// Each selector is extended once, using all of the extensions that apply
+    /// to it. This must be called before the selectors returned by
+    /// `Extender::add_selector` are emitted.
+    pub fn apply_pending_extensions(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        self.pending_extenders.clear();
+
+        let mut batches: IndexMap<ExtendedSelector, Vec<usize>> = IndexMap::new();
+        let mut pending_extensions = Vec::with_capacity(self.pending.len());
+        for (i, pending) in mem::take(&mut self.pending).into_iter().enumerate() {
+            for selector in pending.selectors.into_iter() {
+                batches.entry(selector).or_insert_with(Vec::new).push(i);
+            }
+            pending_extensions.push(pending.extensions);
+        }
+
+        for (mut selector, batch) in batches {
+            let extensions = batch
+                .into_iter()
+                .map(|i| &pending_extensions[i])
+                .collect::<Vec<_>>();
+            let added =
+                selector.with_selector_mut(|list| self.extend_existing_selector(list, &extensions));
+
+            // If no extends actually happened (for example becaues unification
+            // failed), we don't need to re-register the selector.
+            if let Some(added) = added {
+                self.register_selector(added, &selector);
+            }
         }
     }
 
@@ -1132,37 +1299,211 @@ impl Extender {
         additional_extensions
     }
 
-    /// Extend `extensions` using `new_extensions`.
-    fn extend_existing_selectors(
+    /// Extends `list` in place using each of `batches` in turn, returning the
+    /// complex selectors that were added to it, or `None` if it was left
+    /// unchanged.
+    ///
+    /// This has the same result as calling `Extender::extend_list` once for
+    /// each batch. Each batch only visits the complex selectors that contain
+    /// one of its targets, which are found using an index from simple
+    /// selectors to the complex selectors that contain them, so a selector
+    /// that is the target of many `@extend`s isn't walked in full for every
+    /// one of them.
+    fn extend_existing_selector(
         &mut self,
-        selectors: SelectorHashSet,
-        new_extensions: &HashMap<SimpleSelector, IndexMap<ComplexSelector, Extension>>,
-    ) {
-        for mut selector in selectors.into_iter() {
-            let old_value = selector.clone().into_selector().0;
-            selector.set_inner(self.extend_list(
-                old_value.clone(),
-                Some(new_extensions),
-                &self.media_contexts.get(&old_value).cloned(),
-            ));
-            /*
-            todo: error handling
-            } on SassException catch (error) {
-            throw SassException(
-                "From ${selector.span.message('')}\n"
-                "${error.message}",
-                error.span);
+        list: &mut SelectorList,
+        batches: &[&HashMap<SimpleSelector, IndexMap<ComplexSelector, Extension>>],
+    ) -> Option<SelectorList> {
+        let media_query_context = if self.media_contexts.is_empty() {
+            None
+        } else {
+            self.media_contexts.get(list).cloned()
+        };
+
+        let mut tree = ExtensionTree::new(list.components.clone());
+        let mut added = Vec::new();
+        let mut any_extended = false;
+        // Whether the selectors in `tree` are already the result of `trim`
+        let mut trimmed = false;
+
+        for &extensions in batches {
+            let mut changed = Vec::new();
+            for node in tree.containing(extensions.keys()) {
+                let complex = &tree.nodes[node].complex;
+                if !self.may_extend_complex(complex, Some(extensions)) {
+                    continue;
+                }
+                let extended = match self.extend_complex(
+                    complex.clone(),
+                    Some(extensions),
+                    &media_query_context,
+                ) {
+                    Some(v) => v,
+                    None => continue,
+                };
+                any_extended = true;
+                tree.replace(node, extended, &mut changed);
             }
+            added.extend(changed.iter().map(|&node| tree.nodes[node].complex.clone()));
 
-            */
+            if changed.is_empty() {
+                continue;
+            }
 
-            // If no extends actually happened (for example becaues unification
-            // failed), we don't need to re-register the selector.
-            let selector_as_selector = selector.clone().into_selector().0;
-            if old_value == selector_as_selector {
+            // `trim` gives up on lists longer than this
+            if tree.nodes.len() > 100 {
+                trimmed = false;
                 continue;
             }
-            self.register_selector(selector_as_selector, &selector);
+
+            if !trimmed || !self.is_trimmed(&tree.nodes, &changed) {
+                let components = tree.into_components();
+                tree = ExtensionTree::new(
+                    self.trim(components, |complex| self.originals.contains(complex)),
+                );
+                trimmed = true;
+            }
+        }
+
+        if !any_extended {
+            return None;
+        }
+
+        let components = tree.into_components();
+        if components == list.components {
+            return None;
+        }
+        list.components = components;
+        list.span = self.span;
+
+        Some(SelectorList {
+            components: added,
+            span: self.span,
+        })
+    }
+}
+
+/// The complex selectors of a selector list as `@extend`s are applied to it
+/// one after another, indexed by the simple selectors they contain.
+struct ExtensionTree {
+    nodes: Vec<ExtensionNode>,
+    /// The nodes of the complex selectors the list started with.
+    roots: Vec<usize>,
+    /// A map from simple selectors, including those nested in selector
+    /// pseudo-classes, to the nodes whose complex selectors contain them.
+    index: HashMap<SimpleSelector, Vec<usize>>,
+}
+
+struct ExtensionNode {
+    complex: ComplexSelector,
+    /// The nodes that come after this one in the list, added by extending
+    /// it. Each extension adds a group, and the most recent group comes
+    /// first, just as it would if it had been spliced into the list.
+    after: Vec<Vec<usize>>,
+}
+
+impl ExtensionTree {
+    fn new(components: Vec<ComplexSelector>) -> Self {
+        let mut tree = Self {
+            nodes: Vec::with_capacity(components.len()),
+            roots: Vec::with_capacity(components.len()),
+            index: HashMap::new(),
+        };
+        for complex in components {
+            let node = tree.push(complex);
+            tree.roots.push(node);
+        }
+        tree
+    }
+
+    fn push(&mut self, complex: ComplexSelector) -> usize {
+        let node = self.nodes.len();
+        self.add_to_index(&complex, node);
+        self.nodes.push(ExtensionNode {
+            complex,
+            after: Vec::new(),
+        });
+        node
+    }
+
+    fn add_to_index(&mut self, complex: &ComplexSelector, node: usize) {
+        let mut simples = HashSet::new();
+        add_simple_selectors(complex, &mut simples);
+        for simple in simples {
+            self.index.entry(simple).or_insert_with(Vec::new).push(node);
+        }
+    }
+
+    /// The nodes whose complex selectors contain any of `simples`, in order.
+    fn containing<'a>(&self, simples: impl Iterator<Item = &'a SimpleSelector>) -> Vec<usize> {
+        let mut nodes: Vec<usize> = simples
+            .filter_map(|simple| self.index.get(simple))
+            .flatten()
+            .copied()
+            .collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+        nodes
+    }
+
+    /// Replaces the complex selector of `node` with `extended`, the result of
+    /// extending it, and adds the nodes whose complex selectors weren't in the
+    /// list before to `changed`.
+    fn replace(&mut self, node: usize, extended: Vec<ComplexSelector>, changed: &mut Vec<usize>) {
+        let mut extended = extended.into_iter();
+        if let Some(first) = extended.next() {
+            if first != self.nodes[node].complex {
+                self.add_to_index(&first, node);
+                self.nodes[node].complex = first;
+                changed.push(node);
+            }
+        }
+        let group: Vec<usize> = extended.map(|complex| self.push(complex)).collect();
+        changed.extend(&group);
+        self.nodes[node].after.push(group);
+    }
+
+    fn into_components(self) -> Vec<ComplexSelector> {
+        fn visit(
+            nodes: &mut [Option<ExtensionNode>],
+            node: usize,
+            components: &mut Vec<ComplexSelector>,
+        ) {
+            let ExtensionNode { complex, after } = nodes[node].take().unwrap();
+            components.push(complex);
+            for group in after.into_iter().rev() {
+                for node in group {
+                    visit(nodes, node, components);
+                }
+            }
+        }
+
+        let mut components = Vec::with_capacity(self.nodes.len());
+        let mut nodes: Vec<Option<ExtensionNode>> = self.nodes.into_iter().map(Some).collect();
+        for node in self.roots {
+            visit(&mut nodes, node, &mut components);
+        }
+        components
+    }
+}
+
+/// Adds every simple selector in `complex`, including those nested in
+/// selector pseudo-classes, to `simples`.
+fn add_simple_selectors(complex: &ComplexSelector, simples: &mut HashSet<SimpleSelector>) {
+    for component in &complex.components {
+        if let ComplexSelectorComponent::Compound(compound) = component {
+            for simple in &compound.components {
+                if let SimpleSelector::Pseudo(Pseudo {
+                    selector: Some(selector),
+                    ..
+                }) = simple
+                {
+                    for complex in &selector.components {
+                        add_simple_selectors(complex, simples);
+                    }
+                }
+                simples.insert(simple.clone());
+            }
         }
     }
 }
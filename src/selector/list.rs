@@ -3,6 +3,7 @@ use std::{
     fmt::{self, Write},
     hash::{Hash, Hasher},
     mem,
+    rc::Rc,
 };
 
 use codemap::Span;
@@ -93,22 +94,29 @@ impl SelectorList {
     /// This has the same format as a list returned by `selector-parse()`.
     pub fn to_sass_list(self) -> Value {
         Value::List(
-            self.components
-                .into_iter()
-                .map(|complex| {
-                    Value::List(
-                        complex
-                            .components
-                            .into_iter()
-                            .map(|complex_component| {
-                                Value::String(complex_component.to_string(), QuoteKind::None)
-                            })
-                            .collect(),
-                        ListSeparator::Space,
-                        Brackets::None,
-                    )
-                })
-                .collect(),
+            Rc::new(
+                self.components
+                    .into_iter()
+                    .map(|complex| {
+                        Value::List(
+                            Rc::new(
+                                complex
+                                    .components
+                                    .into_iter()
+                                    .map(|complex_component| {
+                                        Value::String(
+                                            complex_component.to_string(),
+                                            QuoteKind::None,
+                                        )
+                                    })
+                                    .collect(),
+                            ),
+                            ListSeparator::Space,
+                            Brackets::None,
+                        )
+                    })
+                    .collect(),
+            ),
             ListSeparator::Comma,
             Brackets::None,
         )
@@ -1,12 +1,6 @@
 use codemap::Span;
 
-use crate::{
-    common::unvendor,
-    error::SassResult,
-    parse::Parser,
-    utils::{is_name, is_name_start, read_until_closing_paren},
-    Token,
-};
+use crate::{common::unvendor, error::SassResult, parse::Parser, utils::is_name, Token, TokenKind};
 
 use super::{
     Attribute, Combinator, ComplexSelector, ComplexSelectorComponent, CompoundSelector, Namespace,
@@ -36,9 +30,11 @@ impl DevouredWhitespace {
 }
 
 /// Pseudo-class selectors that take unadorned selectors as arguments.
-const SELECTOR_PSEUDO_CLASSES: [&str; 7] = [
+const SELECTOR_PSEUDO_CLASSES: [&str; 9] = [
     "not",
+    "is",
     "matches",
+    "where",
     "current",
     "any",
     "has",
@@ -91,11 +87,10 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
 
         let mut line_break = false;
 
-        while let Some(Token { kind: ',', .. }) = self.parser.toks.peek() {
-            self.parser.toks.next();
+        while self.parser.scan_char(',') {
             line_break = self.eat_whitespace() == DevouredWhitespace::Newline || line_break;
             match self.parser.toks.peek() {
-                Some(Token { kind: ',', .. }) => continue,
+                Some(tok) if tok.is_symbol(',') => continue,
                 Some(..) => {}
                 None => break,
             }
@@ -112,13 +107,20 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
 
     fn eat_whitespace(&mut self) -> DevouredWhitespace {
         let mut whitespace_devoured = DevouredWhitespace::None;
-        while let Some(tok) = self.parser.toks.peek() {
-            match tok.kind {
-                ' ' | '\t' => whitespace_devoured.found_whitespace(),
-                '\n' => whitespace_devoured.found_newline(),
-                _ => break,
+        if let Some(tok) = self.parser.toks.peek() {
+            if tok.kind == TokenKind::Whitespace {
+                self.parser.toks.next();
+                if self
+                    .parser
+                    .toks
+                    .text(tok)
+                    .contains(&['\n', '\r', '\x0C'][..])
+                {
+                    whitespace_devoured.found_newline();
+                } else {
+                    whitespace_devoured.found_whitespace();
+                }
             }
-            self.parser.toks.next();
         }
 
         whitespace_devoured
@@ -135,37 +137,34 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
             self.parser.whitespace();
 
             // todo: can we do while let Some(..) = self.parser.toks.peek() ?
-            match self.parser.toks.peek() {
-                Some(Token { kind: '+', .. }) => {
+            match self.parser.toks.peek().map(|tok| tok.kind) {
+                Some(TokenKind::Symbol('+')) => {
                     self.parser.toks.next();
                     components.push(ComplexSelectorComponent::Combinator(
                         Combinator::NextSibling,
                     ));
                 }
-                Some(Token { kind: '>', .. }) => {
+                Some(TokenKind::Symbol('>')) => {
                     self.parser.toks.next();
                     components.push(ComplexSelectorComponent::Combinator(Combinator::Child))
                 }
-                Some(Token { kind: '~', .. }) => {
+                Some(TokenKind::Symbol('~')) => {
                     self.parser.toks.next();
                     components.push(ComplexSelectorComponent::Combinator(
                         Combinator::FollowingSibling,
                     ));
                 }
-                Some(Token { kind: '[', .. })
-                | Some(Token { kind: '.', .. })
-                | Some(Token { kind: '#', .. })
-                | Some(Token { kind: '%', .. })
-                | Some(Token { kind: ':', .. })
                 // todo: ampersand?
-                | Some(Token { kind: '&', .. })
-                | Some(Token { kind: '*', .. })
-                | Some(Token { kind: '|', .. }) => {
+                Some(TokenKind::Symbol('[' | '.' | '#' | '%' | ':' | '&' | '*' | '|')) => {
                     components.push(ComplexSelectorComponent::Compound(
                         self.parse_compound_selector()?,
                     ));
-                    if let Some(Token { kind: '&', .. }) = self.parser.toks.peek() {
-                        return Err(("\"&\" may only used at the beginning of a compound selector.", self.span).into());
+                    if matches!(self.parser.toks.peek(), Some(tok) if tok.is_symbol('&')) {
+                        return Err((
+                            "\"&\" may only used at the beginning of a compound selector.",
+                            self.span,
+                        )
+                            .into());
                     }
                 }
                 Some(..) => {
@@ -175,8 +174,12 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
                     components.push(ComplexSelectorComponent::Compound(
                         self.parse_compound_selector()?,
                     ));
-                    if let Some(Token { kind: '&', .. }) = self.parser.toks.peek() {
-                        return Err(("\"&\" may only used at the beginning of a compound selector.", self.span).into());
+                    if matches!(self.parser.toks.peek(), Some(tok) if tok.is_symbol('&')) {
+                        return Err((
+                            "\"&\" may only used at the beginning of a compound selector.",
+                            self.span,
+                        )
+                            .into());
                     }
                 }
                 None => break,
@@ -197,7 +200,7 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
         let mut components = vec![self.parse_simple_selector(true)?];
 
         while let Some(Token { kind, .. }) = self.parser.toks.peek() {
-            if !is_simple_selector_start(*kind) {
+            if !is_simple_selector_start(kind) {
                 break;
             }
 
@@ -218,41 +221,49 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
     /// [the CSS algorithm]: https://drafts.csswg.org/css-syntax-3/#would-start-an-identifier
     fn looking_at_identifier(&mut self) -> bool {
         match self.parser.toks.peek() {
-            Some(Token { kind, .. }) if is_name_start(*kind) || kind == &'\\' => return true,
-            Some(Token { kind: '-', .. }) => {}
-            Some(..) | None => return false,
+            Some(Token {
+                kind: TokenKind::Ident | TokenKind::Number | TokenKind::Symbol('\\'),
+                ..
+            }) => true,
+            Some(tok) if tok.is_symbol('-') => matches!(
+                self.parser.toks.peek_next(),
+                Some(Token {
+                    kind: TokenKind::Number | TokenKind::Symbol('\\'),
+                    ..
+                })
+            ),
+            Some(..) | None => false,
         }
+    }
 
-        match self.parser.toks.peek_forward(1) {
-            Some(Token { kind, .. }) if is_name_start(*kind) || kind == &'-' || kind == &'\\' => {
-                self.parser.toks.reset_cursor();
-                true
-            }
-            Some(..) | None => {
-                self.parser.toks.reset_cursor();
-                false
-            }
-        }
+    /// Returns whether the next character other than whitespace is one of
+    /// `chars`, without consuming anything.
+    fn looking_at_after_whitespace(&mut self, chars: &[char]) -> bool {
+        let start = self.parser.toks.cursor();
+        self.parser.whitespace();
+        let found = matches!(self.parser.toks.peek_char(), Some(c) if chars.contains(&c));
+        self.parser.toks.set_cursor(start);
+        found
     }
 
     fn looking_at_identifier_body(&mut self) -> bool {
-        matches!(self.parser.toks.peek(), Some(t) if is_name(t.kind) || t.kind == '\\')
+        matches!(self.parser.toks.peek_char(), Some(c) if is_name(c) || c == '\\')
     }
 
     /// Consumes a simple selector.
     fn parse_simple_selector(&mut self, allow_parent: bool) -> SassResult<SimpleSelector> {
-        match self.parser.toks.peek() {
-            Some(Token { kind: '[', .. }) => self.parse_attribute_selector(),
-            Some(Token { kind: '.', .. }) => self.parse_class_selector(),
-            Some(Token { kind: '#', .. }) => self.parse_id_selector(),
-            Some(Token { kind: '%', .. }) => {
+        match self.parser.toks.peek().map(|tok| tok.kind) {
+            Some(TokenKind::Symbol('[')) => self.parse_attribute_selector(),
+            Some(TokenKind::Symbol('.')) => self.parse_class_selector(),
+            Some(TokenKind::Symbol('#')) => self.parse_id_selector(),
+            Some(TokenKind::Symbol('%')) => {
                 if !self.allows_placeholder {
                     return Err(("Placeholder selectors aren't allowed here.", self.span).into());
                 }
                 self.parse_placeholder_selector()
             }
-            Some(Token { kind: ':', .. }) => self.parse_pseudo_selector(),
-            Some(Token { kind: '&', .. }) => {
+            Some(TokenKind::Symbol(':')) => self.parse_pseudo_selector(),
+            Some(TokenKind::Symbol('&')) => {
                 if !allow_parent && !self.allows_parent {
                     return Err(("Parent selectors aren't allowed here.", self.span).into());
                 }
@@ -281,18 +292,12 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
 
     fn parse_pseudo_selector(&mut self) -> SassResult<SimpleSelector> {
         self.parser.toks.next();
-        let element = match self.parser.toks.peek() {
-            Some(Token { kind: ':', .. }) => {
-                self.parser.toks.next();
-                true
-            }
-            _ => false,
-        };
+        let element = self.parser.scan_char(':');
 
         let name = self.parser.parse_identifier()?;
 
         match self.parser.toks.peek() {
-            Some(Token { kind: '(', .. }) => self.parser.toks.next(),
+            Some(tok) if tok.is_symbol('(') => self.parser.toks.next(),
             _ => {
                 return Ok(SimpleSelector::Pseudo(Pseudo {
                     // todo: we can store the reference to this
@@ -332,7 +337,7 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
             let found_whitespace = self.parser.whitespace();
             #[allow(clippy::match_same_arms)]
             match (found_whitespace, self.parser.toks.peek()) {
-                (_, Some(Token { kind: ')', .. })) => {}
+                (_, Some(tok)) if tok.is_symbol(')') => {}
                 (true, _) => {
                     self.expect_identifier("of")?;
                     this_arg.push_str(" of");
@@ -385,16 +390,12 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
     ///
     /// These are combined because either one could start with `*`.
     fn parse_type_or_universal_selector(&mut self) -> SassResult<SimpleSelector> {
-        self.parser.toks.peek();
-
         match self.parser.toks.peek() {
-            Some(Token { kind: '*', pos }) => {
-                self.parser.span_before = self.parser.span_before.merge(*pos);
+            Some(tok) if tok.is_symbol('*') => {
+                self.parser.span_before = self.parser.span_before.merge(tok.pos);
                 self.parser.toks.next();
-                if let Some(Token { kind: '|', .. }) = self.parser.toks.peek() {
-                    self.parser.toks.next();
-                    if let Some(Token { kind: '*', .. }) = self.parser.toks.peek() {
-                        self.parser.toks.next();
+                if self.parser.scan_char('|') {
+                    if self.parser.scan_char('*') {
                         return Ok(SimpleSelector::Universal(Namespace::Asterisk));
                     } else {
                         return Ok(SimpleSelector::Type(QualifiedName {
@@ -406,21 +407,16 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
                     return Ok(SimpleSelector::Universal(Namespace::None));
                 }
             }
-            Some(Token { kind: '|', pos }) => {
-                self.parser.span_before = self.parser.span_before.merge(*pos);
+            Some(tok) if tok.is_symbol('|') => {
+                self.parser.span_before = self.parser.span_before.merge(tok.pos);
                 self.parser.toks.next();
-                match self.parser.toks.peek() {
-                    Some(Token { kind: '*', .. }) => {
-                        self.parser.toks.next();
-                        return Ok(SimpleSelector::Universal(Namespace::Empty));
-                    }
-                    _ => {
-                        return Ok(SimpleSelector::Type(QualifiedName {
-                            ident: self.parser.parse_identifier()?.node,
-                            namespace: Namespace::Empty,
-                        }));
-                    }
+                if self.parser.scan_char('*') {
+                    return Ok(SimpleSelector::Universal(Namespace::Empty));
                 }
+                return Ok(SimpleSelector::Type(QualifiedName {
+                    ident: self.parser.parse_identifier()?.node,
+                    namespace: Namespace::Empty,
+                }));
             }
             _ => {}
         }
@@ -428,10 +424,9 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
         let name_or_namespace = self.parser.parse_identifier()?.node;
 
         Ok(match self.parser.toks.peek() {
-            Some(Token { kind: '|', .. }) => {
+            Some(tok) if tok.is_symbol('|') => {
                 self.parser.toks.next();
-                if let Some(Token { kind: '*', .. }) = self.parser.toks.peek() {
-                    self.parser.toks.next();
+                if self.parser.scan_char('*') {
                     SimpleSelector::Universal(Namespace::Other(name_or_namespace.into_boxed_str()))
                 } else {
                     SimpleSelector::Type(QualifiedName {
@@ -453,86 +448,81 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
     fn parse_a_n_plus_b(&mut self) -> SassResult<String> {
         let mut buf = String::new();
 
-        match self.parser.toks.peek() {
-            Some(Token { kind: 'e', .. }) | Some(Token { kind: 'E', .. }) => {
+        match self.parser.toks.peek_char() {
+            Some('e' | 'E') => {
                 self.expect_identifier("even")?;
                 return Ok("even".to_string());
             }
-            Some(Token { kind: 'o', .. }) | Some(Token { kind: 'O', .. }) => {
+            Some('o' | 'O') => {
                 self.expect_identifier("odd")?;
                 return Ok("odd".to_string());
             }
-            Some(t @ Token { kind: '+', .. }) | Some(t @ Token { kind: '-', .. }) => {
-                buf.push(t.kind);
-                self.parser.toks.next();
+            Some(c @ ('+' | '-')) => {
+                buf.push(c);
+                self.parser.toks.next_char();
             }
             _ => {}
         }
 
-        match self.parser.toks.peek() {
-            Some(t) if t.kind.is_ascii_digit() => {
-                while let Some(t) = self.parser.toks.peek() {
-                    if !t.kind.is_ascii_digit() {
-                        break;
-                    }
-                    buf.push(t.kind);
-                    self.parser.toks.next();
+        match self.parser.toks.peek_char() {
+            Some(c) if c.is_ascii_digit() => {
+                if let Some(digits) = self.parser.toks.next_digits() {
+                    buf.push_str(self.parser.toks.text(digits));
                 }
-                self.parser.whitespace();
-                if let Some(t) = self.parser.toks.peek() {
-                    if t.kind != 'n' && t.kind != 'N' {
-                        return Ok(buf);
-                    }
-                    self.parser.toks.next();
+                // whitespace after the argument is left for the caller, which
+                // needs it to find `of`
+                if !self.looking_at_after_whitespace(&['n', 'N']) {
+                    return Ok(buf);
                 }
+                self.parser.whitespace();
+                self.parser.toks.next_char();
             }
-            Some(t) => {
-                if t.kind == 'n' || t.kind == 'N' {
-                    self.parser.toks.next();
-                } else {
-                    return Err(("Expected \"n\".", self.span).into());
-                }
+            Some('n' | 'N') => {
+                self.parser.toks.next_char();
             }
+            Some(..) => return Err(("Expected \"n\".", self.span).into()),
             None => return Err(("expected more input.", self.span).into()),
         }
 
         buf.push('n');
 
+        if !self.looking_at_after_whitespace(&['+', '-']) {
+            return Ok(buf);
+        }
+
         self.parser.whitespace();
 
-        if let Some(t @ Token { kind: '+', .. }) | Some(t @ Token { kind: '-', .. }) =
-            self.parser.toks.peek()
-        {
-            buf.push(t.kind);
-            self.parser.toks.next();
+        if let Some(c) = self.parser.toks.next_char() {
+            buf.push(c.node);
             self.parser.whitespace();
-            match self.parser.toks.peek() {
-                Some(t) if !t.kind.is_ascii_digit() => {
-                    return Err(("Expected a number.", self.span).into())
-                }
+            match self.parser.toks.next_digits() {
+                Some(digits) => buf.push_str(self.parser.toks.text(digits)),
                 None => return Err(("Expected a number.", self.span).into()),
-                Some(..) => {}
-            }
-
-            while let Some(t) = self.parser.toks.peek() {
-                if !t.kind.is_ascii_digit() {
-                    break;
-                }
-                buf.push(t.kind);
-                self.parser.toks.next();
             }
         }
         Ok(buf)
     }
 
     fn declaration_value(&mut self) -> SassResult<String> {
-        // todo: this consumes the closing paren
-        let mut tmp = read_until_closing_paren(self.parser.toks)?;
-        if let Some(Token { kind: ')', .. }) = tmp.pop() {
-        } else {
-            return Err(("expected \")\".", self.span).into());
+        let start = self.parser.toks.cursor();
+        let mut nesting = 0;
+        loop {
+            match self.parser.toks.next() {
+                Some(tok) if tok.is_symbol('(') => nesting += 1,
+                Some(tok) if tok.is_symbol(')') => {
+                    if nesting == 0 {
+                        let text = self.parser.toks.text_from(start);
+                        return Ok(text[..text.len() - 1].to_owned());
+                    }
+                    nesting -= 1;
+                }
+                Some(tok) if tok.is_symbol('\\') => {
+                    self.parser.toks.next_char();
+                }
+                Some(..) => {}
+                None => return Err(("expected \")\".", self.span).into()),
+            }
         }
-        Ok(tmp.into_iter().map(|t| t.kind).collect::<String>())
     }
 
     fn expect_identifier(&mut self, s: &str) -> SassResult<()> {
@@ -546,7 +536,7 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
     }
 
     fn expect_closing_paren(&mut self) -> SassResult<()> {
-        if let Some(Token { kind: ')', .. }) = self.parser.toks.next() {
+        if self.parser.scan_char(')') {
             Ok(())
         } else {
             Err(("expected \")\".", self.span).into())
@@ -556,8 +546,8 @@ impl<'a, 'b> SelectorParser<'a, 'b> {
 
 /// Returns whether `c` can start a simple selector other than a type
 /// selector.
-fn is_simple_selector_start(c: char) -> bool {
-    matches!(c, '*' | '[' | '.' | '#' | '%' | ':')
+fn is_simple_selector_start(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::Symbol('*' | '[' | '.' | '#' | '%' | ':'))
 }
 
 /// Returns whether `name` is the name of a pseudo-element that can be written
@@ -12,7 +12,14 @@ use super::{
     QualifiedName, SelectorList, Specificity,
 };
 
-const SUBSELECTOR_PSEUDOS: [&str; 4] = ["matches", "any", "nth-child", "nth-last-child"];
+const SUBSELECTOR_PSEUDOS: [&str; 6] = [
+    "is",
+    "matches",
+    "where",
+    "any",
+    "nth-child",
+    "nth-last-child",
+];
 
 const BASE_SPECIFICITY: i32 = 1000;
 
@@ -164,6 +171,10 @@ impl SimpleSelector {
         matches!(self, Self::Type(..))
     }
 
+    pub fn is_host(&self) -> bool {
+        matches!(self, Self::Pseudo(pseudo) if pseudo.is_host())
+    }
+
     pub fn unify(self, compound: Vec<Self>) -> Option<Vec<Self>> {
         match self {
             Self::Type(..) => self.unify_type(compound),
@@ -193,7 +204,7 @@ impl SimpleSelector {
     /// Returns `None` if unification is impossible—for example, if there are
     /// multiple ID selectors.
     fn unify_default(self, mut compound: Vec<Self>) -> Option<Vec<Self>> {
-        if compound.len() == 1 && compound[0].is_universal() {
+        if compound.len() == 1 && (compound[0].is_universal() || compound[0].is_host()) {
             return compound.swap_remove(0).unify(vec![self]);
         }
         if compound.contains(&self) {
@@ -223,6 +234,12 @@ impl SimpleSelector {
             return Some(unified);
         }
 
+        // the shadow host has no type, so can't be unified with a type
+        // selector, even a universal one
+        if compound.len() == 1 && compound[0].is_host() {
+            return None;
+        }
+
         if self != Self::Universal(Namespace::Asterisk) && self != Self::Universal(Namespace::None)
         {
             let mut v = vec![self];
@@ -243,29 +260,8 @@ impl SimpleSelector {
     ///
     /// If no such selector can be produced, returns `None`.
     fn unify_universal_and_element(&self, other: &Self) -> Option<Self> {
-        let namespace1;
-        let name1;
-        if let SimpleSelector::Type(name) = self.clone() {
-            namespace1 = name.namespace;
-            name1 = name.ident;
-        } else if let SimpleSelector::Universal(namespace) = self.clone() {
-            namespace1 = namespace;
-            name1 = String::new();
-        } else {
-            todo!("ArgumentError.value(selector1, 'selector1', 'must be a UniversalSelector or a TypeSelector')")
-        }
-
-        let namespace2;
-        let mut name2 = String::new();
-
-        if let SimpleSelector::Universal(namespace) = other {
-            namespace2 = namespace.clone();
-        } else if let SimpleSelector::Type(name) = other {
-            namespace2 = name.namespace.clone();
-            name2 = name.ident.clone();
-        } else {
-            todo!("ArgumentError.value(selector2, 'selector2', 'must be a UniversalSelector or a TypeSelector');")
-        }
+        let (namespace1, name1) = self.namespace_and_name()?;
+        let (namespace2, name2) = other.namespace_and_name()?;
 
         let namespace = if namespace1 == namespace2 || namespace2 == Namespace::Asterisk {
             namespace1
@@ -293,6 +289,18 @@ impl SimpleSelector {
         })
     }
 
+    /// The namespace and element name of a `SimpleSelector::Universal` or
+    /// `SimpleSelector::Type`, with universal selectors having an empty name.
+    ///
+    /// Returns `None` for any other kind of simple selector.
+    fn namespace_and_name(&self) -> Option<(Namespace, String)> {
+        match self {
+            Self::Type(name) => Some((name.namespace.clone(), name.ident.clone())),
+            Self::Universal(namespace) => Some((namespace.clone(), String::new())),
+            _ => None,
+        }
+    }
+
     fn unify_type(self, mut compound: Vec<Self>) -> Option<Vec<Self>> {
         if let Self::Universal(..) | Self::Type(..) = compound[0] {
             let mut unified = vec![self.unify_universal_and_element(&compound[0])?];
@@ -306,7 +314,16 @@ impl SimpleSelector {
     }
 
     fn unify_pseudo(self, mut compound: Vec<Self>) -> Option<Vec<Self>> {
-        if compound.len() == 1 && compound[0].is_universal() {
+        if self.is_host() {
+            // the shadow host is featureless, so it can only be combined
+            // with other pseudo selectors that refer to it or take selectors
+            if !compound.iter().all(|simple| match simple {
+                Self::Pseudo(pseudo) => pseudo.is_host() || pseudo.selector.is_some(),
+                _ => false,
+            }) {
+                return None;
+            }
+        } else if compound.len() == 1 && (compound[0].is_universal() || compound[0].is_host()) {
             return compound.remove(0).unify(vec![self]);
         }
         if compound.contains(&self) {
@@ -346,36 +363,67 @@ impl SimpleSelector {
         Some(result)
     }
 
-    pub fn is_super_selector_of_compound(&self, compound: &CompoundSelector) -> bool {
-        compound.components.iter().any(|their_simple| {
-            if self == their_simple {
-                return true;
+    /// Whether this is a superselector of `other`.
+    ///
+    /// That is, whether this matches every element that `other` matches, as well
+    /// as possibly additional elements.
+    pub fn is_super_selector(&self, other: &Self) -> bool {
+        match self {
+            Self::Type(name) => {
+                if let Self::Type(other_name) = other {
+                    if name.ident == other_name.ident
+                        && (name.namespace == Namespace::Asterisk
+                            || name.namespace == other_name.namespace)
+                    {
+                        return true;
+                    }
+                }
+                self.is_super_selector_default(other)
             }
-            if let SimpleSelector::Pseudo(Pseudo {
-                selector: Some(sel),
-                normalized_name,
-                ..
-            }) = their_simple
-            {
-                if SUBSELECTOR_PSEUDOS.contains(&&**normalized_name) {
-                    return sel.components.iter().all(|complex| {
-                        if complex.components.len() != 1 {
-                            return false;
-                        };
-                        complex
-                            .components
-                            .get(0)
-                            .unwrap()
-                            .as_compound()
+            Self::Universal(namespace) => match other {
+                _ if *namespace == Namespace::Asterisk => true,
+                Self::Type(other_name) => *namespace == other_name.namespace,
+                Self::Universal(other_namespace) => namespace == other_namespace,
+                _ => self.is_super_selector_default(other),
+            },
+            _ => self.is_super_selector_default(other),
+        }
+    }
+
+    fn is_super_selector_default(&self, other: &Self) -> bool {
+        if self == other {
+            return true;
+        }
+
+        if let Self::Pseudo(Pseudo {
+            is_class: true,
+            selector: Some(sel),
+            normalized_name,
+            ..
+        }) = other
+        {
+            if SUBSELECTOR_PSEUDOS.contains(&&**normalized_name) {
+                return sel
+                    .components
+                    .iter()
+                    .all(|complex| match complex.components.last() {
+                        Some(ComplexSelectorComponent::Compound(compound)) => compound
                             .components
-                            .contains(self)
+                            .iter()
+                            .any(|simple| self.is_super_selector(simple)),
+                        Some(ComplexSelectorComponent::Combinator(..)) | None => false,
                     });
-                }
-                false
-            } else {
-                false
             }
-        })
+        }
+
+        false
+    }
+
+    pub fn is_super_selector_of_compound(&self, compound: &CompoundSelector) -> bool {
+        compound
+            .components
+            .iter()
+            .any(|their_simple| self.is_super_selector(their_simple))
     }
 }
 
@@ -490,7 +538,7 @@ impl Pseudo {
     ) -> bool {
         debug_assert!(self.selector.is_some());
         match &*self.normalized_name {
-            "matches" | "any" => {
+            "is" | "matches" | "where" | "any" => {
                 let pseudos = selector_pseudos_named(compound.clone(), &self.name, true);
                 pseudos.iter().any(move |pseudo2| {
                     self.selector
@@ -600,6 +648,13 @@ impl Pseudo {
         }
     }
 
+    /// Whether this is `:host` or `:host-context()`, which match the shadow
+    /// host of a shadow tree
+    pub fn is_host(&self) -> bool {
+        self.is_class
+            && (self.name == "host" || (self.name == "host-context" && self.selector.is_some()))
+    }
+
     #[allow(clippy::missing_const_for_fn)]
     pub fn with_selector(self, selector: Option<SelectorList>) -> Self {
         Self { selector, ..self }
@@ -628,6 +683,11 @@ impl Pseudo {
             }
         };
 
+        // `:where()` never adds specificity, whatever its arguments
+        if &*self.normalized_name == "where" {
+            return Specificity { min: 0, max: 0 };
+        }
+
         if self.name == "not" {
             let mut min = 0;
             let mut max = 0;
@@ -1,28 +1,44 @@
-use crate::utils::IsWhitespace;
-
 use codemap::Span;
 
+/// What a token is made up of
+///
+/// Sass is context-sensitive, so these only describe the text of a token,
+/// not what it means. For example, `a` may be a type selector, a property
+/// name or an unquoted string depending on where it appears.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum TokenKind {
+    /// A run of letters, digits, `_`, `-` and non-ASCII characters that
+    /// doesn't begin with a digit, such as `foo-bar` or `--baz`
+    Ident,
+    /// Digits, optionally followed by a fractional part and an exponent,
+    /// such as `12` or `1.5e3`
+    Number,
+    /// A quoted string, including its quotes and any interpolation within it
+    ///
+    /// An unterminated string ends at the end of its line.
+    String,
+    /// A run of spaces, tabs and newlines
+    Whitespace,
+    /// Any other single character, such as `{` or `$`
+    Symbol(char),
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) struct Token {
+    pub kind: TokenKind,
     pub pos: Span,
-    pub kind: char,
+    /// The byte offsets of the token within the text being lexed
+    pub start: usize,
+    pub end: usize,
 }
 
 impl Token {
-    pub const fn new(pos: Span, kind: char) -> Self {
-        Self { pos, kind }
-    }
-
     pub const fn pos(&self) -> Span {
         self.pos
     }
-}
 
-impl IsWhitespace for Token {
-    fn is_whitespace(&self) -> bool {
-        if self.kind.is_whitespace() {
-            return true;
-        }
-        false
+    /// Whether this is the single character `c`
+    pub fn is_symbol(&self, c: char) -> bool {
+        self.kind == TokenKind::Symbol(c)
     }
 }
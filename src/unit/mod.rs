@@ -1,13 +1,14 @@
-use std::{
-    fmt,
-    ops::{Div, Mul},
-};
+use std::fmt;
 
-pub(crate) use conversion::UNIT_CONVERSION_TABLE;
+use num_traits::One;
+
+use crate::value::Number;
+
+use conversion::UNIT_CONVERSION_TABLE;
 
 mod conversion;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub(crate) enum Unit {
     // Absolute units
     /// Pixels
@@ -103,13 +104,16 @@ pub(crate) enum Unit {
     /// Unspecified unit
     None,
 
-    /// Units multiplied together
-    Mul(Box<[Unit]>),
-
-    /// Units divided by each other
-    Div(Box<DivUnit>),
+    /// Units multiplied and divided by each other, e.g. `px*em/s`
+    ///
+    /// This is always normalized: there is more than one unit in total, and
+    /// no unit in the numerator can be converted to a unit in the denominator.
+    Complex {
+        numer: Vec<Unit>,
+        denom: Vec<Unit>,
+    },
 }
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub(crate) enum UnitKind {
     Absolute,
     FontRelative,
@@ -122,127 +126,153 @@ pub(crate) enum UnitKind {
     None,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub(crate) struct DivUnit {
-    numer: Unit,
-    denom: Unit,
-}
-
-impl DivUnit {
-    pub const fn new(numer: Unit, denom: Unit) -> Self {
-        Self { numer, denom }
+impl Unit {
+    pub fn comparable(&self, other: &Unit) -> bool {
+        *self == Unit::None || *other == Unit::None || self.conversion_factor(other).is_some()
     }
-}
 
-impl fmt::Display for DivUnit {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.numer == Unit::None {
-            write!(f, "{}^-1", self.denom)
-        } else {
-            write!(f, "{}/{}", self.numer, self.denom)
+    /// The number of `to` in one `self`, or `None` if the units can't be
+    /// converted to each other
+    ///
+    /// Complex units can be converted if each unit of the numerator and of
+    /// the denominator can be converted to a distinct unit of the numerator
+    /// or denominator of `to` respectively.
+    pub fn conversion_factor(&self, to: &Unit) -> Option<Number> {
+        if self == to {
+            return Some(Number::one());
+        }
+        match (self, to) {
+            (
+                Unit::Complex { numer, denom },
+                Unit::Complex {
+                    numer: numer2,
+                    denom: denom2,
+                },
+            ) => {
+                let numer_factor = Self::list_conversion_factor(numer, numer2)?;
+                let denom_factor = Self::list_conversion_factor(denom, denom2)?;
+                Some(numer_factor / denom_factor)
+            }
+            (Unit::Complex { .. }, _) | (_, Unit::Complex { .. }) => None,
+            _ => Self::simple_conversion_factor(self, to),
         }
     }
-}
 
-#[allow(clippy::match_same_arms)]
-impl Mul<Unit> for DivUnit {
-    type Output = Unit;
-    fn mul(self, rhs: Unit) -> Self::Output {
-        match rhs {
-            Unit::Mul(..) => todo!(),
-            Unit::Div(..) => todo!(),
-            Unit::None => todo!(),
-            _ => {
-                if self.denom == rhs {
-                    self.numer
-                } else {
-                    match self.denom {
-                        Unit::Mul(..) => todo!(),
-                        Unit::Div(..) => unreachable!(),
-                        _ => match self.numer {
-                            Unit::Mul(..) => todo!(),
-                            Unit::Div(..) => unreachable!(),
-                            Unit::None => {
-                                let numer = Unit::Mul(vec![rhs].into_boxed_slice());
-                                Unit::Div(Box::new(DivUnit::new(numer, self.denom)))
-                            }
-                            _ => {
-                                let numer = Unit::Mul(vec![self.numer, rhs].into_boxed_slice());
-                                Unit::Div(Box::new(DivUnit::new(numer, self.denom)))
-                            }
-                        },
-                    }
-                }
-            }
+    fn list_conversion_factor(from: &[Unit], to: &[Unit]) -> Option<Number> {
+        if from.len() != to.len() {
+            return None;
         }
+        let mut to = to.iter().collect::<Vec<&Unit>>();
+        let mut factor = Number::one();
+        for unit in from {
+            let (idx, unit_factor) = to
+                .iter()
+                .enumerate()
+                .find_map(|(idx, to)| Some((idx, Self::simple_conversion_factor(unit, to)?)))?;
+            factor *= unit_factor;
+            to.remove(idx);
+        }
+        Some(factor)
     }
-}
 
-// impl Div<Unit> for DivUnit {
-//     type Output = Unit;
-//     fn div(self, rhs: Unit) -> Self::Output {
-//         todo!()
-//     }
-// }
+    fn simple_conversion_factor(from: &Unit, to: &Unit) -> Option<Number> {
+        if from == to {
+            return Some(Number::one());
+        }
+        // `x` is an alias for `dppx`
+        let key = |unit: &Unit| match unit {
+            Unit::X => Unit::Dppx.to_string(),
+            _ => unit.to_string(),
+        };
+        UNIT_CONVERSION_TABLE
+            .get(key(to).as_str())?
+            .get(key(from).as_str())
+            .cloned()
+    }
 
-impl Mul<Unit> for Unit {
-    type Output = Unit;
-    fn mul(self, rhs: Unit) -> Self::Output {
+    /// Split the unit into the units of its numerator and denominator
+    fn into_parts(self) -> (Vec<Unit>, Vec<Unit>) {
         match self {
-            Unit::Mul(u) => match rhs {
-                Unit::Mul(u2) => {
-                    let mut unit1 = u.into_vec();
-                    unit1.extend_from_slice(&*u2);
-                    Unit::Mul(unit1.into_boxed_slice())
-                }
-                Unit::Div(..) => todo!(),
-                _ => {
-                    let mut unit1 = u.into_vec();
-                    unit1.push(rhs);
-                    Unit::Mul(unit1.into_boxed_slice())
-                }
-            },
-            Unit::Div(div) => *div * rhs,
-            _ => match rhs {
-                Unit::Mul(u2) => {
-                    let mut unit1 = vec![self];
-                    unit1.extend_from_slice(&*u2);
-                    Unit::Mul(unit1.into_boxed_slice())
-                }
-                Unit::Div(..) => todo!(),
-                _ => Unit::Mul(vec![self, rhs].into_boxed_slice()),
-            },
+            Unit::None => (Vec::new(), Vec::new()),
+            Unit::Complex { numer, denom } => (numer, denom),
+            unit => (vec![unit], Vec::new()),
         }
     }
-}
 
-impl Div<Unit> for Unit {
-    type Output = Unit;
-    fn div(self, rhs: Unit) -> Self::Output {
-        if let Unit::Div(..) = self {
-            todo!()
-        } else if let Unit::Div(..) = rhs {
-            todo!()
-        } else {
-            Unit::Div(Box::new(DivUnit::new(self, rhs)))
+    fn from_parts(mut numer: Vec<Unit>, denom: Vec<Unit>) -> Unit {
+        match (numer.len(), denom.len()) {
+            (0, 0) => Unit::None,
+            (1, 0) => numer.pop().unwrap_or(Unit::None),
+            _ => Unit::Complex { numer, denom },
         }
     }
-}
 
-impl Unit {
-    pub fn comparable(&self, other: &Unit) -> bool {
-        if other == &Unit::None {
-            return true;
-        }
-        match self.kind() {
-            UnitKind::FontRelative | UnitKind::ViewportRelative | UnitKind::Other => self == other,
-            UnitKind::None => true,
-            u => other.kind() == u,
-        }
+    /// Multiply two units, cancelling out units of the numerator that can be
+    /// converted to units of the denominator
+    ///
+    /// Returns the resulting unit, along with the factor the product of the
+    /// two numbers must be multiplied by to account for any conversions.
+    pub fn multiply(self, rhs: Unit) -> (Unit, Number) {
+        let (numer1, denom1) = self.into_parts();
+        let (numer2, denom2) = rhs.into_parts();
+        Self::multiply_parts(numer1, denom1, numer2, denom2)
+    }
+
+    /// Divide two units, cancelling out units of the numerator that can be
+    /// converted to units of the denominator
+    ///
+    /// Returns the resulting unit, along with the factor the quotient of the
+    /// two numbers must be multiplied by to account for any conversions.
+    pub fn divide(self, rhs: Unit) -> (Unit, Number) {
+        let (numer1, denom1) = self.into_parts();
+        let (numer2, denom2) = rhs.into_parts();
+        Self::multiply_parts(numer1, denom1, denom2, numer2)
+    }
+
+    fn multiply_parts(
+        mut numer1: Vec<Unit>,
+        mut denom1: Vec<Unit>,
+        numer2: Vec<Unit>,
+        mut denom2: Vec<Unit>,
+    ) -> (Unit, Number) {
+        // neither unit can be simplified on its own, so only the units of the
+        // numerator of one can cancel out with the denominator of the other,
+        // and it doesn't matter which of them each unit came from
+        numer1.extend(numer2);
+        denom1.append(&mut denom2);
+        let (mut numer, mut denom) = (numer1, denom1);
+
+        // identical units are cancelled out first, so that e.g. `px*in/in`
+        // is `px` rather than a number of inches
+        numer.retain(|unit| match denom.iter().position(|d| d == unit) {
+            Some(idx) => {
+                denom.remove(idx);
+                false
+            }
+            None => true,
+        });
+
+        let mut factor = Number::one();
+        numer.retain(|unit| {
+            let cancelled = denom
+                .iter()
+                .enumerate()
+                .find_map(|(idx, d)| Some((idx, Self::simple_conversion_factor(unit, d)?)));
+            match cancelled {
+                Some((idx, unit_factor)) => {
+                    factor *= unit_factor;
+                    denom.remove(idx);
+                    false
+                }
+                None => true,
+            }
+        });
+
+        (Self::from_parts(numer, denom), factor)
     }
 
     /// Used internally to determine if two units are comparable or not
-    fn kind(&self) -> UnitKind {
+    pub fn kind(&self) -> UnitKind {
         match self {
             Unit::Px | Unit::Mm | Unit::In | Unit::Cm | Unit::Q | Unit::Pt | Unit::Pc => {
                 UnitKind::Absolute
@@ -263,9 +293,7 @@ impl Unit {
             Unit::Hz | Unit::Khz => UnitKind::Frequency,
             Unit::Dpi | Unit::Dpcm | Unit::Dppx | Unit::X => UnitKind::Resolution,
             Unit::None => UnitKind::None,
-            Unit::Fr | Unit::Percent | Unit::Unknown(..) | Unit::Mul(..) | Unit::Div(..) => {
-                UnitKind::Other
-            }
+            Unit::Fr | Unit::Percent | Unit::Unknown(..) | Unit::Complex { .. } => UnitKind::Other,
         }
     }
 }
@@ -353,15 +381,21 @@ impl fmt::Display for Unit {
             Unit::Fr => write!(f, "fr"),
             Unit::Unknown(s) => write!(f, "{}", s),
             Unit::None => Ok(()),
-            Unit::Mul(u) => write!(
-                f,
-                "{}",
-                u.iter()
-                    .map(ToString::to_string)
-                    .collect::<Vec<String>>()
-                    .join("*")
-            ),
-            Unit::Div(u) => write!(f, "{}", u),
+            Unit::Complex { numer, denom } => {
+                let join = |units: &[Unit]| {
+                    units
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<String>>()
+                        .join("*")
+                };
+                match (numer.len(), denom.len()) {
+                    (0, 1) => write!(f, "{}^-1", denom[0]),
+                    (0, _) => write!(f, "({})^-1", join(denom)),
+                    (_, 0) => write!(f, "{}", join(numer)),
+                    _ => write!(f, "{}/{}", join(numer), join(denom)),
+                }
+            }
         }
     }
 }
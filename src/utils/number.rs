@@ -1,7 +1,6 @@
-use codemap::Spanned;
-use peekmore::PeekMoreIterator;
+use codemap::Span;
 
-use crate::{error::SassResult, Token};
+use crate::error::SassResult;
 
 #[derive(Debug)]
 pub(crate) struct ParsedNumber {
@@ -30,98 +29,37 @@ pub(crate) struct ParsedNumber {
 }
 
 impl ParsedNumber {
-    pub const fn new(
-        num: String,
-        dec_len: usize,
-        times_ten: String,
-        times_ten_is_postive: bool,
-    ) -> Self {
-        Self {
+    /// Split the text of a number token, such as `1.5e-3`, into its parts
+    pub fn from_text(text: &str, span: Span) -> SassResult<Self> {
+        let (mantissa, exponent) = match text.find(|c| c == 'e' || c == 'E') {
+            Some(idx) => (&text[..idx], Some(&text[idx + 1..])),
+            None => (text, None),
+        };
+
+        let (whole, dec) = match mantissa.find('.') {
+            Some(idx) => (&mantissa[..idx], &mantissa[idx + 1..]),
+            None => (mantissa, ""),
+        };
+
+        let mut num = String::with_capacity(whole.len() + dec.len());
+        num.push_str(whole);
+        num.push_str(dec);
+
+        let (times_ten, times_ten_is_postive) = match exponent {
+            None => ("", true),
+            Some(exponent) => match exponent.strip_prefix('-') {
+                Some("") => return Err(("Expected digit.", span).into()),
+                Some(digits) => (digits, false),
+                None if exponent.len() > 2 => return Err(("Exponent too large.", span).into()),
+                None => (exponent, true),
+            },
+        };
+
+        Ok(Self {
             num,
-            dec_len,
-            times_ten,
+            dec_len: dec.len(),
+            times_ten: times_ten.to_owned(),
             times_ten_is_postive,
-        }
+        })
     }
 }
-
-pub(crate) fn eat_number<I: Iterator<Item = Token>>(
-    toks: &mut PeekMoreIterator<I>,
-) -> SassResult<Spanned<ParsedNumber>> {
-    let mut span = toks.peek().unwrap().pos;
-    let mut whole = eat_whole_number(toks);
-
-    if toks.peek().is_none() {
-        return Ok(Spanned {
-            node: ParsedNumber::new(whole, 0, String::new(), true),
-            span,
-        });
-    }
-
-    let next_tok = *toks.peek().unwrap();
-
-    let dec_len = if next_tok.kind == '.' {
-        toks.next();
-
-        let dec = eat_whole_number(toks);
-        if dec.is_empty() {
-            return Err(("Expected digit.", next_tok.pos()).into());
-        }
-
-        whole.push_str(&dec);
-
-        dec.len()
-    } else {
-        0
-    };
-
-    let mut times_ten = String::new();
-    let mut times_ten_is_postive = true;
-    if let Some(Token { kind: 'e', .. }) | Some(Token { kind: 'E', .. }) = toks.peek() {
-        if let Some(&tok) = toks.peek_next() {
-            if tok.kind == '-' {
-                toks.next();
-                times_ten_is_postive = false;
-
-                toks.next();
-                times_ten = eat_whole_number(toks);
-
-                if times_ten.is_empty() {
-                    return Err(("Expected digit.", toks.peek().unwrap_or(&tok).pos).into());
-                }
-            } else if matches!(tok.kind, '0'..='9') {
-                toks.next();
-                times_ten = eat_whole_number(toks);
-
-                if times_ten.len() > 2 {
-                    return Err(("Exponent too large.", toks.peek().unwrap_or(&tok).pos).into());
-                }
-            }
-        }
-    }
-
-    if let Ok(Some(Token { pos, .. })) = toks.peek_previous() {
-        span = span.merge(*pos);
-    }
-
-    toks.reset_cursor();
-
-    Ok(Spanned {
-        node: ParsedNumber::new(whole, dec_len, times_ten, times_ten_is_postive),
-        span,
-    })
-}
-
-pub(crate) fn eat_whole_number<I: Iterator<Item = Token>>(
-    toks: &mut PeekMoreIterator<I>,
-) -> String {
-    let mut buf = String::new();
-    while let Some(c) = toks.peek() {
-        if !c.kind.is_ascii_digit() {
-            break;
-        }
-        let tok = toks.next().unwrap();
-        buf.push(tok.kind);
-    }
-    buf
-}
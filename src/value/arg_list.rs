@@ -0,0 +1,75 @@
+use std::cell::Cell;
+
+use codemap::{Span, Spanned};
+
+use crate::{
+    common::{Identifier, QuoteKind},
+    error::SassResult,
+    value::{SassMap, Value},
+};
+
+/// The value of a rest parameter such as `$args...`
+///
+/// Any named arguments that weren't bound to another parameter are kept as
+/// keywords, which can be read with `keywords()`. If they never are, passing
+/// them was an error.
+#[derive(Debug, Clone)]
+pub(crate) struct ArgList {
+    pub elems: Vec<Spanned<Value>>,
+    keywords: Vec<(Identifier, Spanned<Value>)>,
+    were_keywords_accessed: Cell<bool>,
+}
+
+impl PartialEq for ArgList {
+    fn eq(&self, other: &ArgList) -> bool {
+        self.elems == other.elems && self.keywords == other.keywords
+    }
+}
+
+impl Eq for ArgList {}
+
+impl ArgList {
+    pub fn new(elems: Vec<Spanned<Value>>, keywords: Vec<(Identifier, Spanned<Value>)>) -> Self {
+        ArgList {
+            elems,
+            keywords,
+            were_keywords_accessed: Cell::new(false),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elems.is_empty()
+    }
+
+    /// The named arguments, in the order they were passed
+    ///
+    /// This marks the keywords as accessed.
+    pub fn keywords(&self) -> &[(Identifier, Spanned<Value>)] {
+        self.were_keywords_accessed.set(true);
+        &self.keywords
+    }
+
+    /// The named arguments as a map from their names, without `$`, to their
+    /// values, as returned by `keywords()`
+    pub fn keywords_map(&self) -> SassMap {
+        let mut map = SassMap::new();
+        for (name, value) in self.keywords() {
+            map.insert(
+                Value::String(name.to_string(), QuoteKind::None),
+                value.node.clone(),
+            );
+        }
+        map
+    }
+
+    /// Errors if any keywords were passed but never accessed, as they
+    /// didn't correspond to any parameter
+    pub fn verify_keywords_accessed(&self, span: Span) -> SassResult<()> {
+        match self.keywords.first() {
+            Some((name, ..)) if !self.were_keywords_accessed.get() => {
+                Err((format!("No argument named ${}.", name), span).into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
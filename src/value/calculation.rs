@@ -0,0 +1,449 @@
+//! CSS calculations: `calc()`, `clamp()`, `min()` and `max()`
+//!
+//! Calculations are simplified as far as possible when they are created. If
+//! every argument is a number and the units allow it, a calculation becomes a
+//! plain number. Otherwise, it is kept symbolic and emitted as CSS.
+
+use std::fmt::{self, Display};
+
+use codemap::Span;
+
+use num_traits::{Signed, Zero};
+
+use crate::{
+    common::{Op, QuoteKind},
+    error::SassResult,
+    unit::{Unit, UnitKind},
+    value::{Number, Value},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CalculationName {
+    Calc,
+    Min,
+    Max,
+    Clamp,
+}
+
+impl CalculationName {
+    /// The calculation with the given (lowercase) name, if any
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "calc" => Self::Calc,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "clamp" => Self::Clamp,
+            _ => return None,
+        })
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Calc => "calc",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Clamp => "clamp",
+        }
+    }
+}
+
+impl Display for CalculationName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CalculationArg {
+    Number(Number, Unit),
+    Calculation(SassCalculation),
+    /// An unquoted string, such as the result of `var()` or `env()`
+    String(String),
+    /// The result of interpolation, which is parenthesized when it's an
+    /// operand, as it may contain operators of its own
+    Interpolation(String),
+    Operation(Box<CalculationArg>, Op, Box<CalculationArg>),
+}
+
+impl CalculationArg {
+    /// Convert the result of a Sass expression used within a calculation,
+    /// such as a variable or function call
+    ///
+    /// Nested `calc()`s are unwrapped, as they are redundant inside of
+    /// another calculation.
+    pub fn from_value(value: Value, span: Span) -> SassResult<Self> {
+        Ok(match value {
+            Value::Dimension(num, unit, _) => CalculationArg::Number(num, unit),
+            Value::Calculation(SassCalculation {
+                name: CalculationName::Calc,
+                mut args,
+            }) if args.len() == 1 => args.pop().unwrap(),
+            Value::Calculation(calc) => CalculationArg::Calculation(calc),
+            Value::String(s, QuoteKind::None) => CalculationArg::String(s),
+            Value::String(..) => {
+                return Err((
+                    format!(
+                        "Quoted string {} can't be used in a calculation.",
+                        value.inspect(span)?
+                    ),
+                    span,
+                )
+                    .into())
+            }
+            v => {
+                return Err((
+                    format!("Value {} can't be used in a calculation.", v.inspect(span)?),
+                    span,
+                )
+                    .into())
+            }
+        })
+    }
+
+    /// Convert this argument back into a Sass value, as returned by
+    /// `meta.calc-args()`
+    ///
+    /// Anything other than a number or a calculation becomes an unquoted
+    /// string.
+    pub fn into_value(self) -> Value {
+        match self {
+            CalculationArg::Number(num, unit) => Value::Dimension(num, unit, None),
+            CalculationArg::Calculation(calc) => Value::Calculation(calc),
+            arg => Value::String(arg.to_string(), QuoteKind::None),
+        }
+    }
+
+    /// Apply an operator to two arguments, evaluating it if both are numbers
+    /// with compatible units
+    ///
+    /// Within `min()` and `max()`, unitless numbers may be added to numbers
+    /// with units, as they may be in normal Sass arithmetic.
+    pub fn operate(
+        lhs: CalculationArg,
+        op: Op,
+        rhs: CalculationArg,
+        in_min_max: bool,
+        span: Span,
+    ) -> SassResult<Self> {
+        match (op, lhs, rhs) {
+            (Op::Plus, CalculationArg::Number(num, unit), CalculationArg::Number(num2, unit2))
+            | (Op::Minus, CalculationArg::Number(num, unit), CalculationArg::Number(num2, unit2))
+                if can_add(&unit, &unit2, in_min_max) =>
+            {
+                let (num2, unit) = if unit == Unit::None {
+                    (num2, unit2)
+                } else if unit2 == Unit::None || unit == unit2 {
+                    (num2, unit)
+                } else {
+                    let factor = unit2.conversion_factor(&unit).unwrap();
+                    (num2 * factor, unit)
+                };
+                Ok(CalculationArg::Number(
+                    if op == Op::Plus {
+                        num + num2
+                    } else {
+                        num - num2
+                    },
+                    unit,
+                ))
+            }
+            (Op::Plus, lhs, rhs) | (Op::Minus, lhs, rhs) => {
+                verify_compatible_numbers(&[&lhs, &rhs], span)?;
+                Ok(match rhs {
+                    // `1px + -2px` is emitted as `1px - 2px`
+                    CalculationArg::Number(num, unit) if num.is_negative() => {
+                        CalculationArg::Operation(
+                            Box::new(lhs),
+                            if op == Op::Plus { Op::Minus } else { Op::Plus },
+                            Box::new(CalculationArg::Number(-num, unit)),
+                        )
+                    }
+                    rhs => CalculationArg::Operation(Box::new(lhs), op, Box::new(rhs)),
+                })
+            }
+            // division by zero is left as-is, as infinity can't be represented
+            (op, CalculationArg::Number(num, unit), CalculationArg::Number(num2, unit2))
+                if op == Op::Mul || !num2.is_zero() =>
+            {
+                let (unit, factor) = if op == Op::Mul {
+                    unit.multiply(unit2)
+                } else {
+                    unit.divide(unit2)
+                };
+                Ok(CalculationArg::Number(
+                    if op == Op::Mul {
+                        num * num2 * factor
+                    } else {
+                        num / num2 * factor
+                    },
+                    unit,
+                ))
+            }
+            (op, lhs, rhs) => Ok(CalculationArg::Operation(Box::new(lhs), op, Box::new(rhs))),
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, parenthesize: bool) -> fmt::Result {
+        if parenthesize {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl Display for CalculationArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(num, unit) => write!(f, "{}{}", num, unit),
+            Self::Calculation(calc) => write!(f, "{}", calc),
+            Self::String(s) | Self::Interpolation(s) => f.write_str(s),
+            Self::Operation(lhs, op, rhs) => {
+                lhs.fmt_operand(
+                    f,
+                    match &**lhs {
+                        Self::Interpolation(..) => true,
+                        Self::Operation(_, lhs_op, _) => lhs_op.precedence() < op.precedence(),
+                        _ => false,
+                    },
+                )?;
+                write!(f, " {} ", op)?;
+                rhs.fmt_operand(
+                    f,
+                    match &**rhs {
+                        Self::Interpolation(..) => true,
+                        Self::Operation(_, rhs_op, _) => match op {
+                            Op::Div => true,
+                            Op::Plus => false,
+                            _ => matches!(rhs_op, Op::Plus | Op::Minus),
+                        },
+                        _ => false,
+                    },
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SassCalculation {
+    pub name: CalculationName,
+    pub args: Vec<CalculationArg>,
+}
+
+impl SassCalculation {
+    pub fn calc(arg: CalculationArg) -> Value {
+        match arg {
+            CalculationArg::Number(num, unit) => Value::Dimension(num, unit, None),
+            CalculationArg::Calculation(calc) => Value::Calculation(calc),
+            arg => Value::Calculation(SassCalculation {
+                name: CalculationName::Calc,
+                args: vec![arg],
+            }),
+        }
+    }
+
+    /// `min()` or `max()`, which become the smallest or largest of their
+    /// arguments if they are all numbers that can be compared
+    pub fn min_max(
+        name: CalculationName,
+        args: Vec<CalculationArg>,
+        span: Span,
+    ) -> SassResult<Value> {
+        let mut extremum: Option<(&Number, &Unit)> = None;
+        for arg in &args {
+            let (num, unit) = match arg {
+                CalculationArg::Number(num, unit) => (num, unit),
+                _ => {
+                    extremum = None;
+                    break;
+                }
+            };
+            match extremum {
+                Some((best, best_unit)) if unit.comparable(best_unit) => {
+                    let num_in_unit = convert(num, unit, best_unit);
+                    let replace = if name == CalculationName::Min {
+                        &num_in_unit < best
+                    } else {
+                        &num_in_unit > best
+                    };
+                    if replace {
+                        extremum = Some((num, unit));
+                    }
+                }
+                Some(..) => {
+                    extremum = None;
+                    break;
+                }
+                None => extremum = Some((num, unit)),
+            }
+        }
+
+        if let Some((num, unit)) = extremum {
+            return Ok(Value::Dimension(num.clone(), unit.clone(), None));
+        }
+
+        verify_compatible_numbers(&args.iter().collect::<Vec<_>>(), span)?;
+        Ok(Value::Calculation(SassCalculation { name, args }))
+    }
+
+    /// `clamp()`, which becomes the value clamped between the minimum and
+    /// maximum if they are all numbers with compatible units
+    pub fn clamp(args: Vec<CalculationArg>, span: Span) -> SassResult<Value> {
+        if let [CalculationArg::Number(min, min_unit), CalculationArg::Number(val, unit), CalculationArg::Number(max, max_unit)] =
+            args.as_slice()
+        {
+            if has_compatible_units(min_unit, unit) && has_compatible_units(min_unit, max_unit) {
+                return Ok(if val <= &convert(min, min_unit, unit) {
+                    Value::Dimension(min.clone(), min_unit.clone(), None)
+                } else if val >= &convert(max, max_unit, unit) {
+                    Value::Dimension(max.clone(), max_unit.clone(), None)
+                } else {
+                    Value::Dimension(val.clone(), unit.clone(), None)
+                });
+            }
+        }
+
+        verify_compatible_numbers(&args.iter().collect::<Vec<_>>(), span)?;
+
+        // a string may expand to any number of arguments
+        if args.len() != 3
+            && !args.iter().any(|arg| {
+                matches!(
+                    arg,
+                    CalculationArg::String(..) | CalculationArg::Interpolation(..)
+                )
+            })
+        {
+            return Err((
+                format!(
+                    "3 arguments required, but only {} {} passed.",
+                    args.len(),
+                    if args.len() == 1 { "was" } else { "were" }
+                ),
+                span,
+            )
+                .into());
+        }
+
+        Ok(Value::Calculation(SassCalculation {
+            name: CalculationName::Clamp,
+            args,
+        }))
+    }
+}
+
+impl Display for SassCalculation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.name)?;
+        let mut args = self.args.iter();
+        if let Some(arg) = args.next() {
+            write!(f, "{}", arg)?;
+        }
+        for arg in args {
+            write!(f, ", {}", arg)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Convert a number to `to`, which must be comparable to `from`
+///
+/// Unitless numbers aren't converted.
+fn convert(num: &Number, from: &Unit, to: &Unit) -> Number {
+    if from == to || *from == Unit::None || *to == Unit::None {
+        return num.clone();
+    }
+    match from.conversion_factor(to) {
+        Some(factor) => num.clone() * factor,
+        None => num.clone(),
+    }
+}
+
+/// Whether two numbers can be added without either being unitless
+fn has_compatible_units(unit: &Unit, unit2: &Unit) -> bool {
+    match (unit, unit2) {
+        (Unit::None, Unit::None) => true,
+        (Unit::None, _) | (_, Unit::None) => false,
+        _ => unit == unit2 || unit.conversion_factor(unit2).is_some(),
+    }
+}
+
+/// Whether numbers with these units can be added or subtracted in a
+/// calculation, rather than the operation being left for the browser
+///
+/// Like dart-sass, `min()` and `max()` allow a unitless number to be added to
+/// one with units, as they may also be the Sass functions of the same name,
+/// where `1 + 1px` is `2px`. Everywhere else, including in a `calc()` nested
+/// inside `min()`, `1 + 1px` is an error.
+fn can_add(unit: &Unit, unit2: &Unit, in_min_max: bool) -> bool {
+    if in_min_max {
+        unit.comparable(unit2)
+    } else {
+        has_compatible_units(unit, unit2)
+    }
+}
+
+/// The group of units a unit is known to be compatible with in the browser,
+/// even if Sass can't convert between them
+///
+/// Units outside of these groups, such as `%`, may be compatible with any
+/// other unit depending on where they are used.
+fn known_compatibilities(unit: &Unit) -> Option<UnitKind> {
+    match unit.kind() {
+        UnitKind::Absolute | UnitKind::FontRelative | UnitKind::ViewportRelative => {
+            Some(UnitKind::Absolute)
+        }
+        kind @ UnitKind::Angle
+        | kind @ UnitKind::Time
+        | kind @ UnitKind::Frequency
+        | kind @ UnitKind::Resolution => Some(kind),
+        UnitKind::Other | UnitKind::None => None,
+    }
+}
+
+fn verify_compatible_numbers(args: &[&CalculationArg], span: Span) -> SassResult<()> {
+    for arg in args {
+        if let CalculationArg::Number(num, unit @ Unit::Complex { .. }) = arg {
+            return Err((
+                format!(
+                    "Number {}{} isn't compatible with CSS calculations.",
+                    num, unit
+                ),
+                span,
+            )
+                .into());
+        }
+    }
+
+    for (idx, arg) in args.iter().enumerate() {
+        let (num, unit) = match arg {
+            CalculationArg::Number(num, unit) => (num, unit),
+            _ => continue,
+        };
+        for arg2 in &args[idx + 1..] {
+            let (num2, unit2) = match arg2 {
+                CalculationArg::Number(num, unit) => (num, unit),
+                _ => continue,
+            };
+            let compatible = match (unit, unit2) {
+                (Unit::None, Unit::None) => true,
+                (Unit::None, _) | (_, Unit::None) => false,
+                _ if unit.comparable(unit2) => true,
+                _ => match (known_compatibilities(unit), known_compatibilities(unit2)) {
+                    (Some(kind), Some(kind2)) => kind == kind2,
+                    _ => true,
+                },
+            };
+            if !compatible {
+                return Err((
+                    format!("{}{} and {}{} are incompatible.", num, unit, num2, unit2),
+                    span,
+                )
+                    .into());
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,94 +1,122 @@
-use std::{slice::Iter, vec::IntoIter};
+use std::{
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
 
-use codemap::Span;
+use indexmap::{map::IntoIter, Equivalent, IndexMap};
 
 use crate::{
     common::{Brackets, ListSeparator},
-    error::SassResult,
-    parse::{HigherIntermediateValue, Parser, ValueVisitor},
     value::Value,
 };
 
+/// A key in a `SassMap`
+///
+/// Keys are compared using Sass equality rather than structural equality, so
+/// e.g. `"a"` and `a` refer to the same entry.
+#[derive(Debug, Clone)]
+pub(crate) struct MapKey(Value);
+
+impl PartialEq for MapKey {
+    fn eq(&self, other: &MapKey) -> bool {
+        self.0.equals(&other.0)
+    }
+}
+
+impl Eq for MapKey {}
+
+impl Hash for MapKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Equivalent<MapKey> for Value {
+    fn equivalent(&self, key: &MapKey) -> bool {
+        self.equals(&key.0)
+    }
+}
+
+/// An insertion-ordered map, with constant-time lookup of keys
+///
+/// The entries are shared between clones of a map, and are only copied when
+/// a shared map is modified.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct SassMap(Vec<(Value, Value)>);
+pub(crate) struct SassMap(Rc<IndexMap<MapKey, Value>>);
 
 impl SassMap {
-    pub const fn new() -> SassMap {
-        SassMap(Vec::new())
-    }
-
-    pub fn get(
-        self,
-        key: &Value,
-        span: Span,
-        parser: &mut Parser<'_>,
-    ) -> SassResult<Option<Value>> {
-        for (k, v) in self.0 {
-            if ValueVisitor::new(parser, span)
-                .equal(
-                    HigherIntermediateValue::Literal(k),
-                    HigherIntermediateValue::Literal(key.clone()),
-                )?
-                .is_true()
-            {
-                return Ok(Some(v));
-            }
-        }
-        Ok(None)
+    pub fn new() -> SassMap {
+        SassMap(Rc::new(IndexMap::new()))
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.0.get(key)
+    }
+
+    pub fn contains_key(&self, key: &Value) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 
     pub fn remove(&mut self, key: &Value) {
-        self.0.retain(|(ref k, ..)| k != key);
+        if self.0.contains_key(key) {
+            Rc::make_mut(&mut self.0).shift_remove(key);
+        }
     }
 
     pub fn merge(&mut self, other: SassMap) {
-        for (key, value) in other {
-            self.insert(key, value);
-        }
+        Rc::make_mut(&mut self.0).extend(other.into_entries());
     }
 
-    pub fn iter(&self) -> Iter<(Value, Value)> {
-        self.0.iter()
+    pub fn iter(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.0.iter().map(|(k, v)| (&k.0, v))
     }
 
     pub fn keys(self) -> Vec<Value> {
-        self.0.into_iter().map(|(k, ..)| k).collect()
+        self.0.keys().map(|k| k.0.clone()).collect()
     }
 
     pub fn values(self) -> Vec<Value> {
-        self.0.into_iter().map(|(.., v)| v).collect()
+        self.0.values().cloned().collect()
     }
 
     pub fn as_list(self) -> Vec<Value> {
-        self.0
+        self.into_entries()
             .into_iter()
-            .map(|(k, v)| Value::List(vec![k, v], ListSeparator::Space, Brackets::None))
+            .map(|(k, v)| Value::List(Rc::new(vec![k.0, v]), ListSeparator::Space, Brackets::None))
             .collect()
     }
 
-    #[allow(clippy::missing_const_for_fn)]
     pub fn entries(self) -> Vec<(Value, Value)> {
-        self.0
+        self.into_iter().collect()
+    }
+
+    /// Take ownership of the entries, copying them only if they are shared
+    /// with another map
+    fn into_entries(self) -> IndexMap<MapKey, Value> {
+        Rc::try_unwrap(self.0).unwrap_or_else(|map| (*map).clone())
     }
 
     /// Returns true if the key already exists
     pub fn insert(&mut self, key: Value, value: Value) -> bool {
-        for (ref k, ref mut v) in &mut self.0 {
-            if k == &key {
-                *v = value;
-                return true;
-            }
+        let map = Rc::make_mut(&mut self.0);
+        if let Some(v) = map.get_mut(&key) {
+            *v = value;
+            return true;
         }
-        self.0.push((key, value));
+        map.insert(MapKey(key), value);
         false
     }
 }
 
 impl IntoIterator for SassMap {
     type Item = (Value, Value);
-    type IntoIter = IntoIter<Self::Item>;
+    type IntoIter = std::iter::Map<IntoIter<MapKey, Value>, fn((MapKey, Value)) -> (Value, Value)>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        self.into_entries().into_iter().map(|(k, v)| (k.0, v))
     }
 }
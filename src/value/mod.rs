@@ -1,4 +1,8 @@
-use peekmore::PeekMore;
+use std::{
+    hash::{Hash, Hasher},
+    mem,
+    rc::Rc,
+};
 
 use codemap::{Span, Spanned};
 
@@ -6,22 +10,29 @@ use crate::{
     color::Color,
     common::{Brackets, ListSeparator, QuoteKind},
     error::SassResult,
+    lexer::Lexer,
     parse::Parser,
     selector::Selector,
-    unit::Unit,
+    unit::{Unit, UnitKind},
     utils::hex_char_for,
-    {Cow, Token},
+    Cow,
 };
 
+pub(crate) use arg_list::ArgList;
+pub(crate) use calculation::{CalculationArg, CalculationName, SassCalculation};
 use css_function::is_special_function;
 pub(crate) use map::SassMap;
 pub(crate) use number::Number;
 pub(crate) use sass_function::SassFunction;
+pub(crate) use sass_mixin::SassMixin;
 
+mod arg_list;
+mod calculation;
 pub(crate) mod css_function;
 mod map;
 mod number;
 mod sass_function;
+mod sass_mixin;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum Value {
@@ -29,14 +40,26 @@ pub(crate) enum Value {
     True,
     False,
     Null,
-    Dimension(Number, Unit),
-    List(Vec<Value>, ListSeparator, Brackets),
+    /// A number, along with the numerator and denominator it was written as
+    /// if it's a slash-separated number like `12px/1.5` that hasn't been
+    /// divided
+    Dimension(Number, Unit, Option<Box<(Value, Value)>>),
+    /// The elements are shared between clones of a list, and are only copied
+    /// when a shared list is modified
+    List(Rc<Vec<Value>>, ListSeparator, Brackets),
     Color(Box<Color>),
     String(String, QuoteKind),
     Map(SassMap),
-    ArgList(Vec<Spanned<Value>>),
+    /// The value of a rest parameter, which is shared between clones so
+    /// that reading its keywords from any of them is tracked
+    ArgList(Rc<ArgList>),
     /// Returned by `get-function()`
     FunctionRef(SassFunction),
+    /// Returned by `meta.get-mixin()`
+    MixinRef(SassMixin),
+    /// A `calc()`, `clamp()`, `min()` or `max()` that couldn't be simplified
+    /// to a number
+    Calculation(SassCalculation),
 }
 
 fn visit_quoted_string(buf: &mut String, force_double_quote: bool, string: &str) {
@@ -124,13 +147,18 @@ impl Value {
     pub fn to_css_string(&self, span: Span) -> SassResult<Cow<'static, str>> {
         Ok(match self {
             Value::Important => Cow::const_str("!important"),
-            Value::Dimension(num, unit) => match unit {
-                Unit::Mul(..) | Unit::Div(..) => {
+            Value::Dimension(_, _, Some(as_slash)) => Cow::owned(format!(
+                "{}/{}",
+                as_slash.0.to_css_string(span)?,
+                as_slash.1.to_css_string(span)?
+            )),
+            Value::Dimension(num, unit, None) => match unit {
+                Unit::Complex { .. } => {
                     return Err((format!("{}{} isn't a valid CSS value.", num, unit), span).into());
                 }
                 _ => Cow::owned(format!("{}{}", num, unit)),
             },
-            Value::Map(..) | Value::FunctionRef(..) => {
+            Value::Map(..) | Value::FunctionRef(..) | Value::MixinRef(..) => {
                 return Err((
                     format!("{} isn't a valid CSS value.", self.inspect(span)?),
                     span,
@@ -155,6 +183,7 @@ impl Value {
                 )),
             },
             Value::Color(c) => Cow::owned(c.to_string()),
+            Value::Calculation(calc) => Cow::owned(calc.to_string()),
             Value::String(string, QuoteKind::None) => {
                 let mut after_newline = false;
                 let mut buf = String::with_capacity(string.len());
@@ -189,7 +218,8 @@ impl Value {
                 return Err(("() isn't a valid CSS value.", span).into());
             }
             Value::ArgList(args) => Cow::owned(
-                args.iter()
+                args.elems
+                    .iter()
                     .filter(|x| !x.is_null())
                     .map(|a| Ok(a.node.to_css_string(span)?))
                     .collect::<SassResult<Vec<Cow<'static, str>>>>()?
@@ -198,6 +228,17 @@ impl Value {
         })
     }
 
+    /// Divide a slash-separated number such as `12px/1.5`, so that it's
+    /// treated as a single number
+    ///
+    /// Any other value is returned unchanged.
+    pub fn without_slash(self) -> Self {
+        match self {
+            Value::Dimension(num, unit, Some(..)) => Value::Dimension(num, unit, None),
+            v => v,
+        }
+    }
+
     pub fn is_true(&self) -> bool {
         match self {
             Value::Null | Value::False => false,
@@ -208,9 +249,11 @@ impl Value {
     pub fn unquote(self) -> Self {
         match self {
             Value::String(s1, _) => Value::String(s1, QuoteKind::None),
-            Value::List(v, sep, bracket) => {
-                Value::List(v.into_iter().map(Value::unquote).collect(), sep, bracket)
-            }
+            Value::List(v, sep, bracket) => Value::List(
+                Rc::new(v.iter().cloned().map(Value::unquote).collect()),
+                sep,
+                bracket,
+            ),
             v => v,
         }
     }
@@ -226,16 +269,19 @@ impl Value {
             Value::Dimension(..) => "number",
             Value::List(..) => "list",
             Value::FunctionRef(..) => "function",
+            Value::MixinRef(..) => "mixin",
             Value::ArgList(..) => "arglist",
             Value::True | Value::False => "bool",
             Value::Null => "null",
             Value::Map(..) => "map",
+            Value::Calculation(..) => "calculation",
         }
     }
 
     pub fn is_special_function(&self) -> bool {
         match self {
             Value::String(s, QuoteKind::None) => is_special_function(s),
+            Value::Calculation(..) => true,
             _ => false,
         }
     }
@@ -258,11 +304,13 @@ impl Value {
             },
             Value::List(v, sep, brackets) if v.len() == 1 => match brackets {
                 Brackets::None => match sep {
-                    ListSeparator::Space => v[0].inspect(span)?,
+                    ListSeparator::Space | ListSeparator::Slash => v[0].inspect(span)?,
                     ListSeparator::Comma => Cow::owned(format!("({},)", v[0].inspect(span)?)),
                 },
                 Brackets::Bracketed => match sep {
-                    ListSeparator::Space => Cow::owned(format!("[{}]", v[0].inspect(span)?)),
+                    ListSeparator::Space | ListSeparator::Slash => {
+                        Cow::owned(format!("[{}]", v[0].inspect(span)?))
+                    }
                     ListSeparator::Comma => Cow::owned(format!("[{},]", v[0].inspect(span)?)),
                 },
             },
@@ -281,6 +329,7 @@ impl Value {
                 ),
             }),
             Value::FunctionRef(f) => Cow::owned(format!("get-function(\"{}\")", f.name())),
+            Value::MixinRef(m) => Cow::owned(format!("get-mixin(\"{}\")", m.name())),
             Value::Null => Cow::const_str("null"),
             Value::Map(map) => Cow::owned(format!(
                 "({})",
@@ -289,10 +338,16 @@ impl Value {
                     .collect::<SassResult<Vec<String>>>()?
                     .join(", ")
             )),
-            Value::Dimension(num, unit) => Cow::owned(format!("{}{}", num, unit)),
+            Value::Dimension(_, _, Some(as_slash)) => Cow::owned(format!(
+                "{}/{}",
+                as_slash.0.inspect(span)?,
+                as_slash.1.inspect(span)?
+            )),
+            Value::Dimension(num, unit, None) => Cow::owned(format!("{}{}", num, unit)),
             Value::ArgList(args) if args.is_empty() => Cow::const_str("()"),
             Value::ArgList(args) => Cow::owned(
-                args.iter()
+                args.elems
+                    .iter()
                     .filter(|x| !x.is_null())
                     .map(|a| Ok(a.node.inspect(span)?))
                     .collect::<SassResult<Vec<Cow<'static, str>>>>()?
@@ -302,15 +357,45 @@ impl Value {
             | Value::True
             | Value::False
             | Value::Color(..)
+            | Value::Calculation(..)
             | Value::String(..) => self.to_css_string(span)?,
         })
     }
 
+    /// Sass equality, under which quoted and unquoted strings with the same
+    /// contents are equal, as are numbers with compatible units that represent
+    /// the same quantity
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::String(s1, ..), Value::String(s2, ..)) => s1 == s2,
+            (Value::Dimension(n, unit, _), Value::Dimension(n2, unit2, _)) => {
+                if unit == unit2 {
+                    n == n2
+                } else if unit == &Unit::None || unit2 == &Unit::None {
+                    false
+                } else {
+                    match unit2.conversion_factor(unit) {
+                        Some(factor) => n == &(n2.clone() * factor),
+                        None => false,
+                    }
+                }
+            }
+            (Value::List(list1, sep1, brackets1), Value::List(list2, sep2, brackets2)) => {
+                sep1 == sep2
+                    && brackets1 == brackets2
+                    && list1.len() == list2.len()
+                    && list1.iter().zip(list2.iter()).all(|(a, b)| a.equals(b))
+            }
+            (Value::String(..), _) | (Value::Dimension(..), _) | (Value::List(..), _) => false,
+            (s, o) => s == o,
+        }
+    }
+
     pub fn as_list(self) -> Vec<Value> {
         match self {
-            Value::List(v, ..) => v,
+            Value::List(v, ..) => Rc::try_unwrap(v).unwrap_or_else(|v| (*v).clone()),
             Value::Map(m) => m.as_list(),
-            Value::ArgList(v) => v.into_iter().map(|val| val.node).collect(),
+            Value::ArgList(v) => v.elems.iter().map(|val| val.node.clone()).collect(),
             v => vec![v],
         }
     }
@@ -334,12 +419,7 @@ impl Value {
             None => return Err((format!("${}: {} is not a valid selector: it must be a string, a list of strings, or a list of lists of strings.", name, self.inspect(parser.span_before)?), parser.span_before).into()),
         };
         Parser {
-            toks: &mut string
-                .chars()
-                .map(|c| Token::new(parser.span_before, c))
-                .collect::<Vec<Token>>()
-                .into_iter()
-                .peekmore(),
+            toks: &mut Lexer::from_string(string, parser.span_before),
             map: parser.map,
             path: parser.path,
             scopes: parser.scopes,
@@ -351,6 +431,10 @@ impl Value {
             at_root: parser.at_root,
             at_root_has_selector: parser.at_root_has_selector,
             extender: parser.extender,
+            cache: parser.cache,
+            modules: parser.modules,
+            random: parser.random,
+            logger: parser.logger,
         }
         .parse_selector(allows_parent, true, String::new())
     }
@@ -362,11 +446,11 @@ impl Value {
                 let mut result = Vec::new();
                 match sep {
                     ListSeparator::Comma => {
-                        for complex in list {
+                        for complex in list.iter() {
                             if let Value::String(text, ..) = complex {
-                                result.push(text);
+                                result.push(text.clone());
                             } else if let Value::List(_, ListSeparator::Space, ..) = complex {
-                                result.push(match complex.selector_string(span)? {
+                                result.push(match complex.clone().selector_string(span)? {
                                     Some(v) => v,
                                     None => return Ok(None),
                                 });
@@ -375,10 +459,11 @@ impl Value {
                             }
                         }
                     }
+                    ListSeparator::Slash => return Ok(None),
                     ListSeparator::Space => {
-                        for compound in list {
+                        for compound in list.iter() {
                             if let Value::String(text, ..) = compound {
-                                result.push(text);
+                                result.push(text.clone());
                             } else {
                                 return Ok(None);
                             }
@@ -392,3 +477,45 @@ impl Value {
         }))
     }
 }
+
+/// Hashes values consistently with `Value::equals`, so that values may be
+/// used as the keys of a `SassMap`
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Value::String(s, ..) => s.hash(state),
+            Value::Dimension(n, unit, _) => match unit.kind() {
+                // numbers in different units may be equal after conversion,
+                // which cannot be done exactly, so only the kind of unit is
+                // hashed
+                UnitKind::Absolute
+                | UnitKind::Angle
+                | UnitKind::Time
+                | UnitKind::Frequency
+                | UnitKind::Resolution => unit.kind().hash(state),
+                UnitKind::Other => match unit {
+                    // complex units may be converted in the same way
+                    Unit::Complex { numer, denom } => {
+                        numer.len().hash(state);
+                        denom.len().hash(state);
+                    }
+                    _ => {
+                        n.hash(state);
+                        unit.hash(state);
+                    }
+                },
+                _ => {
+                    n.hash(state);
+                    unit.hash(state);
+                }
+            },
+            Value::List(list, sep, brackets) => {
+                sep.hash(state);
+                brackets.hash(state);
+                list.hash(state);
+            }
+            _ => {}
+        }
+    }
+}
@@ -8,7 +8,9 @@ use std::{
 
 use num_bigint::BigInt;
 use num_rational::{BigRational, Rational64};
-use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Num, One, Signed, Zero};
+use num_traits::{
+    CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Num, One, Signed, ToPrimitive, Zero,
+};
 
 use integer::Integer;
 
@@ -16,7 +18,7 @@ mod integer;
 
 const PRECISION: usize = 10;
 
-#[derive(Clone, Eq, PartialEq, Ord)]
+#[derive(Clone, Eq, PartialEq, Ord, Hash)]
 pub(crate) enum Number {
     Small(Rational64),
     Big(Box<BigRational>),
@@ -89,6 +91,18 @@ impl Number {
         }
     }
 
+    /// The closest `f64` to this number
+    ///
+    /// Used by functions such as `math.sqrt()` and `math.sin()`, whose results
+    /// can't be computed exactly.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Self::Small(v) => v.to_f64(),
+            Self::Big(v) => v.to_f64(),
+        }
+        .unwrap_or(f64::NAN)
+    }
+
     pub fn clamp<A: Into<Number> + Zero, B: Into<Number>>(self, min: A, max: B) -> Self {
         let max = max.into();
         if self > max {
@@ -217,7 +231,11 @@ impl From<i64> for Number {
 #[allow(clippy::fallible_impl_from)]
 impl From<f64> for Number {
     fn from(b: f64) -> Self {
-        Number::Big(Box::new(BigRational::from_float(b).unwrap()))
+        let val = BigRational::from_float(b).unwrap();
+        match (val.numer().to_i64(), val.denom().to_i64()) {
+            (Some(numer), Some(denom)) => Number::new_small(Rational64::new_raw(numer, denom)),
+            _ => Number::new_big(val),
+        }
     }
 }
 
@@ -0,0 +1,81 @@
+//! Sass mixins can be passed around as values, as returned by
+//! `meta.get-mixin()`, and included with `meta.apply()`
+//!
+//! Like functions, mixins can be either user-defined or builtin, such as
+//! `meta.load-css()`.
+
+use std::{fmt, rc::Rc};
+
+use crate::{
+    args::CallArgs,
+    atrule::{Content, Mixin},
+    builtin::BuiltinMixin,
+    common::Identifier,
+    error::SassResult,
+    parse::{Parser, Stmt},
+};
+
+/// A reference to a Sass mixin
+///
+/// The mixin name is stored in addition to the body for use in `inspect()`
+#[derive(Clone)]
+pub(crate) enum SassMixin {
+    /// A mixin implemented in rust, along with whether it accepts a content
+    /// block
+    Builtin(BuiltinMixin, Identifier, bool),
+    UserDefined(Box<Mixin>, Identifier),
+}
+
+impl SassMixin {
+    /// Get the name of the mixin referenced
+    pub fn name(&self) -> &Identifier {
+        match self {
+            Self::Builtin(_, name, _) | Self::UserDefined(_, name) => name,
+        }
+    }
+
+    /// Whether the mixin may be passed a content block
+    pub fn accepts_content(&self) -> bool {
+        match self {
+            Self::Builtin(_, _, accepts_content) => *accepts_content,
+            Self::UserDefined(mixin, ..) => mixin.accepts_content_block,
+        }
+    }
+
+    /// Include the mixin with the given arguments and content block
+    pub fn include(
+        self,
+        args: CallArgs,
+        content: Content,
+        parser: &mut Parser<'_>,
+    ) -> SassResult<Vec<Stmt>> {
+        match self {
+            Self::Builtin(mixin, ..) => mixin(args, content, parser),
+            Self::UserDefined(mixin, ..) => parser.include_mixin(*mixin, args, content),
+        }
+    }
+}
+
+impl fmt::Debug for SassMixin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SassMixin")
+            .field("name", &self.name())
+            .finish()
+    }
+}
+
+impl PartialEq for SassMixin {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            // builtin mixins are only ever declared once under each name
+            (Self::Builtin(_, name, _), Self::Builtin(_, name2, _)) => name == name2,
+            // a mixin's body is shared between all references to it
+            (Self::UserDefined(mixin, ..), Self::UserDefined(mixin2, ..)) => {
+                Rc::ptr_eq(&mixin.body, &mixin2.body)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for SassMixin {}
@@ -51,3 +51,58 @@ test!(
     }",
     "a {\n  color: ();\n}\n"
 );
+test!(
+    keywords_of_arglist,
+    "@function foo($args...) {
+        @return inspect(keywords($args));
+    }
+
+    a {
+        color: foo(1, $b: 2, $c: 3);
+    }",
+    "a {\n  color: (b: 2, c: 3);\n}\n"
+);
+test!(
+    keywords_after_positional_parameter,
+    "@mixin foo($a, $args...) {
+        color: inspect(keywords($args));
+    }
+
+    a {
+        @include foo($a: 1, $b: 2);
+    }",
+    "a {\n  color: (b: 2);\n}\n"
+);
+test!(
+    keywords_empty,
+    "@function foo($args...) {
+        @return inspect(keywords($args));
+    }
+
+    a {
+        color: foo(1);
+    }",
+    "a {\n  color: ();\n}\n"
+);
+error!(
+    unused_keyword_to_rest_parameter_in_function,
+    "@function foo($args...) {
+        @return 1;
+    }
+
+    a {
+        color: foo($b: 2);
+    }",
+    "Error: No argument named $b."
+);
+error!(
+    unused_keyword_to_rest_parameter_in_mixin,
+    "@mixin foo($args...) {
+        color: red;
+    }
+
+    a {
+        @include foo($b: 2);
+    }",
+    "Error: No argument named $b."
+);
@@ -64,3 +64,8 @@ error!(
     "@function foo($a) {\n    @return foo;\n}\n\na {\n    color: foo(unit(bar));\n}\n",
     "Error: $number: bar is not a number."
 );
+test!(
+    named_arg_underscore_matches_hyphen,
+    "@function foo($a-b) {\n    @return $a-b;\n}\n\na {\n    color: foo($a_b: red);\n}\n",
+    "a {\n  color: red;\n}\n"
+);
@@ -0,0 +1,124 @@
+#![cfg(test)]
+#![cfg(feature = "commandline")]
+use std::{fs, process::Command};
+
+use tempfile::Builder;
+
+#[test]
+fn batch_errors_are_printed_on_separate_lines() {
+    let dir = Builder::new().prefix("grass-cli").tempdir().unwrap();
+    let a = dir.path().join("a.scss");
+    let b = dir.path().join("b.scss");
+    fs::write(&a, "a { color: red(; }").unwrap();
+    fs::write(&b, "b { color: $undefined; }").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_grass"))
+        .arg(format!(
+            "{}:{}",
+            a.display(),
+            dir.path().join("a.css").display()
+        ))
+        .arg(format!(
+            "{}:{}",
+            b.display(),
+            dir.path().join("b.css").display()
+        ))
+        .args(&["--jobs", "1"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr
+            .lines()
+            .filter(|line| line.starts_with("Error: "))
+            .count(),
+        2,
+        "{}",
+        stderr
+    );
+    assert!(stderr.ends_with('\n'));
+}
+
+/// Compile `input` with the command line interface, returning what was
+/// printed to stderr
+fn stderr_of(input: &str, args: &[&str]) -> String {
+    let dir = Builder::new().prefix("grass-cli").tempdir().unwrap();
+    let path = dir.path().join("input.scss");
+    fs::write(&path, input).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_grass"))
+        .arg(&path)
+        .args(args)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn slash_division_in_loop_warns_once() {
+    let stderr = stderr_of(
+        "@for $i from 1 through 300 {\n  .a-#{$i} { width: $i * 1px / 2; }\n}\n",
+        &[],
+    );
+    assert_eq!(
+        stderr.matches("DEPRECATION WARNING").count(),
+        1,
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("omitted"), "{}", stderr);
+}
+
+#[test]
+fn slash_division_recommendation_uses_source() {
+    let stderr = stderr_of("$a: 4px;\n$b: 2;\n$c: 1px;\na { b: $a * $b / $c; }\n", &[]);
+    assert!(
+        stderr.contains("Recommendation: math.div($a * $b, $c) or calc($a * $b / $c)"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn slash_division_recommendation_nested_division() {
+    let stderr = stderr_of("$a: 4px;\na { b: (1px + $a) / 2 / $a; }\n", &[]);
+    assert!(
+        stderr.contains(
+            "Recommendation: math.div(math.div(1px + $a, 2), $a) or calc((1px + $a) / 2 / $a)"
+        ),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn repeated_deprecation_warnings_are_omitted() {
+    let input = (1..=8)
+        .map(|i| format!("a {{ b{}: $x * {}px / 1px; }}\n", i, i))
+        .collect::<String>();
+    let stderr = stderr_of(&format!("$x: 1;\n{}", input), &[]);
+    assert_eq!(
+        stderr.matches("DEPRECATION WARNING").count(),
+        5,
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.ends_with("\n3 repetitive deprecation warnings omitted.\n"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn quiet_silences_warnings() {
+    let stderr = stderr_of(
+        "a { b: 1px * 1 / 2; }\n@warn \"warning\";\n@debug \"debug\";\n",
+        &["--quiet"],
+    );
+    assert_eq!(stderr, "");
+}
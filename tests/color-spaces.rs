@@ -0,0 +1,215 @@
+#![cfg(test)]
+
+#[macro_use]
+mod macros;
+
+test!(
+    lab_is_preserved,
+    "a {\n  color: lab(50% 20 30);\n}\n",
+    "a {\n  color: lab(50% 20 30);\n}\n"
+);
+test!(
+    lch_hue_is_emitted_in_degrees,
+    "a {\n  color: lch(50% 30 120);\n}\n",
+    "a {\n  color: lch(50% 30 120deg);\n}\n"
+);
+test!(
+    lch_hue_converts_angle_units,
+    "a {\n  color: lch(50% 20 0.5turn);\n}\n",
+    "a {\n  color: lch(50% 20 180deg);\n}\n"
+);
+test!(
+    oklch_with_slash_alpha,
+    "a {\n  color: oklch(70% 0.1 200 / 0.5);\n}\n",
+    "a {\n  color: oklch(70% 0.1 200deg / 0.5);\n}\n"
+);
+test!(
+    oklab_lightness_is_emitted_as_percentage,
+    "a {\n  color: oklab(0.5 0.1 -0.1);\n}\n",
+    "a {\n  color: oklab(50% 0.1 -0.1);\n}\n"
+);
+test!(
+    lab_percent_alpha,
+    "a {\n  color: lab(50% 20 30 / 50%);\n}\n",
+    "a {\n  color: lab(50% 20 30 / 0.5);\n}\n"
+);
+test!(
+    lab_lightness_is_clamped,
+    "a {\n  color: lab(150% 20 30);\n}\n",
+    "a {\n  color: lab(100% 20 30);\n}\n"
+);
+test!(
+    color_display_p3,
+    "a {\n  color: color(display-p3 1 0.5 0);\n}\n",
+    "a {\n  color: color(display-p3 1 0.5 0);\n}\n"
+);
+test!(
+    color_percent_channels,
+    "a {\n  color: color(srgb 50% 20% 10%);\n}\n",
+    "a {\n  color: color(srgb 0.5 0.2 0.1);\n}\n"
+);
+test!(
+    color_xyz,
+    "a {\n  color: color(xyz-d65 0.2 0.3 0.4);\n}\n",
+    "a {\n  color: color(xyz 0.2 0.3 0.4);\n}\n"
+);
+test!(
+    missing_channel,
+    "a {\n  color: oklch(70% none 200);\n}\n",
+    "a {\n  color: oklch(70% none 200deg);\n}\n"
+);
+test!(
+    special_function_in_channels,
+    "a {\n  color: lab(var(--l) 20 30);\n}\n",
+    "a {\n  color: lab(var(--l) 20 30);\n}\n"
+);
+test!(
+    equality,
+    "a {\n  color: lab(50% 20 30) == lab(50% 20 30);\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    transparentize_keeps_space,
+    "a {\n  color: transparentize(lab(50% 20 30), 0.25);\n}\n",
+    "a {\n  color: lab(50% 20 30 / 0.75);\n}\n"
+);
+test!(
+    space,
+    "@use \"sass:color\";\na {\n  color: color.space(lab(50% 0 0));\n  color: color.space(red);\n  color: color.space(hsl(0, 100%, 50%));\n}\n",
+    "a {\n  color: lab;\n  color: rgb;\n  color: hsl;\n}\n"
+);
+test!(
+    is_legacy,
+    "@use \"sass:color\";\na {\n  color: color.is-legacy(red);\n  color: color.is-legacy(oklch(50% 0.1 20));\n}\n",
+    "a {\n  color: true;\n  color: false;\n}\n"
+);
+test!(
+    to_space_legacy_round_trip,
+    "@use \"sass:color\";\na {\n  color: color.to-space(color.to-space(#123456, oklch), rgb);\n}\n",
+    "a {\n  color: #123456;\n}\n"
+);
+test!(
+    to_space_white_has_powerless_hue,
+    "@use \"sass:color\";\na {\n  color: color.to-space(white, oklch);\n}\n",
+    "a {\n  color: oklch(100% 0 none);\n}\n"
+);
+test!(
+    to_space_lab_to_lch,
+    "@use \"sass:color\";\na {\n  color: color.to-space(lab(50% 30 40), lch);\n}\n",
+    "a {\n  color: lch(50% 50 53.1301023542deg);\n}\n"
+);
+test!(
+    channel_in_own_space,
+    "@use \"sass:color\";\na {\n  color: color.channel(oklch(70% 0.1 200), \"lightness\");\n  color: color.channel(oklch(70% 0.1 200), \"hue\");\n  color: color.channel(lab(50% 20 30), \"a\");\n}\n",
+    "a {\n  color: 70%;\n  color: 200deg;\n  color: 20;\n}\n"
+);
+test!(
+    channel_in_other_space,
+    "@use \"sass:color\";\na {\n  color: color.channel(blue, \"hue\", $space: hsl);\n}\n",
+    "a {\n  color: 240deg;\n}\n"
+);
+test!(
+    channel_missing_is_zero,
+    "@use \"sass:color\";\na {\n  color: color.channel(oklch(70% none 200), \"chroma\");\n}\n",
+    "a {\n  color: 0;\n}\n"
+);
+test!(
+    is_missing,
+    "@use \"sass:color\";\na {\n  color: color.is-missing(oklch(70% none 200), \"chroma\");\n  color: color.is-missing(oklch(70% none 200), \"hue\");\n}\n",
+    "a {\n  color: true;\n  color: false;\n}\n"
+);
+test!(
+    is_in_gamut,
+    "@use \"sass:color\";\na {\n  color: color.is-in-gamut(color(display-p3 1 0 0), srgb);\n  color: color.is-in-gamut(color(display-p3 1 0 0));\n  color: color.is-in-gamut(lab(50% 200 30));\n}\n",
+    "a {\n  color: false;\n  color: true;\n  color: true;\n}\n"
+);
+test!(
+    to_gamut_clip,
+    "@use \"sass:color\";\na {\n  color: color.to-gamut(color(srgb 1.5 -0.5 0.5), $method: clip);\n}\n",
+    "a {\n  color: color(srgb 1 0 0.5);\n}\n"
+);
+test!(
+    to_gamut_local_minde_is_in_gamut,
+    "@use \"sass:color\";\na {\n  color: color.is-in-gamut(color.to-gamut(color(display-p3 1 0 0), srgb, $method: local-minde), srgb);\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    mix_in_oklch,
+    "@use \"sass:color\";\na {\n  color: color.mix(oklch(50% 0.1 20), oklch(70% 0.2 40), $method: oklch);\n}\n",
+    "a {\n  color: oklch(60% 0.15 30deg);\n}\n"
+);
+test!(
+    mix_longer_hue,
+    "@use \"sass:color\";\na {\n  color: color.mix(oklch(50% 0.1 20), oklch(50% 0.1 40), $method: oklch longer hue);\n}\n",
+    "a {\n  color: oklch(50% 0.1 210deg);\n}\n"
+);
+test!(
+    mix_missing_channel_takes_other,
+    "@use \"sass:color\";\na {\n  color: color.mix(oklch(50% none 20), oklch(70% 0.2 40), $method: oklch);\n}\n",
+    "a {\n  color: oklch(60% 0.2 30deg);\n}\n"
+);
+test!(
+    mix_weight,
+    "@use \"sass:color\";\na {\n  color: color.mix(lab(0% 0 0), lab(100% 0 0), 25%, $method: lab);\n}\n",
+    "a {\n  color: lab(75% 0 0);\n}\n"
+);
+test!(
+    adjust_non_legacy,
+    "@use \"sass:color\";\na {\n  color: color.adjust(oklch(70% 0.1 200), $lightness: -10%, $hue: 20deg);\n}\n",
+    "a {\n  color: oklch(60% 0.1 220deg);\n}\n"
+);
+test!(
+    scale_non_legacy,
+    "@use \"sass:color\";\na {\n  color: color.scale(oklch(70% 0.1 200), $chroma: 50%);\n}\n",
+    "a {\n  color: oklch(70% 0.25 200deg);\n}\n"
+);
+test!(
+    change_non_legacy,
+    "@use \"sass:color\";\na {\n  color: color.change(lab(50% 20 30), $a: -20, $alpha: 0.5);\n}\n",
+    "a {\n  color: lab(50% -20 30 / 0.5);\n}\n"
+);
+test!(
+    change_legacy_in_space,
+    "@use \"sass:color\";\na {\n  color: color.change(#808080, $lightness: 100%, $space: oklch);\n}\n",
+    "a {\n  color: white;\n}\n"
+);
+error!(
+    wrong_number_of_channels,
+    "a {\n  color: lab(50% 20);\n}\n",
+    "Error: $channels: The lab color space has 3 channels but (50% 20) has 2."
+);
+error!(
+    unknown_color_function_space,
+    "a {\n  color: color(foo 1 2 3);\n}\n",
+    "Error: $description: Unknown color space \"foo\"."
+);
+error!(
+    hue_with_non_angle_unit,
+    "a {\n  color: oklch(50% 0.1 20px);\n}\n",
+    "Error: $hue: Expected 20px to have an angle unit (deg, grad, rad, turn)."
+);
+error!(
+    mix_non_legacy_without_method,
+    "a {\n  color: mix(lab(50% 0 0), red);\n}\n",
+    "Error: To use color.mix() with non-legacy colors, you must provide a $method."
+);
+error!(
+    mix_hue_method_in_rectangular_space,
+    "@use \"sass:color\";\na {\n  color: color.mix(red, blue, $method: lab longer hue);\n}\n",
+    "Error: $method: Hue interpolation method \"longer hue\" may not be set for rectangular color space lab."
+);
+error!(
+    to_gamut_without_method,
+    "@use \"sass:color\";\na {\n  color: color.to-gamut(red, srgb);\n}\n",
+    "Error: color.to-gamut() requires a $method argument for forwards-compatibility with changes in the CSS spec. Suggestion:"
+);
+error!(
+    channel_not_in_space,
+    "@use \"sass:color\";\na {\n  color: color.channel(red, \"hue\");\n}\n",
+    "Error: $channel: Color red has no channel named hue."
+);
+error!(
+    adjust_unknown_channel,
+    "@use \"sass:color\";\na {\n  color: color.scale(lab(50% 20 30), $hue: 10%);\n}\n",
+    "Error: No argument named $hue."
+);
@@ -118,3 +118,87 @@ error!(
     "a {\n  color: 1 / get-function(lighten);\n}\n",
     "Error: get-function(\"lighten\") isn't a valid CSS value."
 );
+test!(
+    slash_between_number_literals_is_preserved,
+    "a {\n  font: 12px/1.5 serif;\n}\n",
+    "a {\n  font: 12px/1.5 serif;\n}\n"
+);
+test!(
+    slash_with_whitespace_between_number_literals_is_preserved,
+    "a {\n  grid-area: 1 / 3;\n}\n",
+    "a {\n  grid-area: 1/3;\n}\n"
+);
+test!(
+    chained_slash_between_number_literals_is_preserved,
+    "a {\n  color: 10px/8px/2;\n}\n",
+    "a {\n  color: 10px/8px/2;\n}\n"
+);
+test!(
+    negative_slash_number_is_preserved,
+    "a {\n  color: -1/2;\n}\n",
+    "a {\n  color: -1/2;\n}\n"
+);
+test!(
+    slash_number_in_plain_css_function_is_preserved,
+    "a {\n  color: foo(1/2);\n}\n",
+    "a {\n  color: foo(1/2);\n}\n"
+);
+test!(
+    slash_number_in_list_is_preserved,
+    "a {\n  color: [1/2], 3/4;\n}\n",
+    "a {\n  color: [1/2], 3/4;\n}\n"
+);
+test!(
+    slash_number_is_divided_in_parens,
+    "a {\n  color: (12px/2);\n}\n",
+    "a {\n  color: 6px;\n}\n"
+);
+test!(
+    slash_number_is_divided_when_assigned_to_variable,
+    "$a: 1/2;\na {\n  color: $a;\n}\n",
+    "a {\n  color: 0.5;\n}\n"
+);
+test!(
+    slash_number_is_divided_when_passed_to_sass_function,
+    "a {\n  color: round(3/2);\n}\n",
+    "a {\n  color: 2;\n}\n"
+);
+test!(
+    slash_number_is_divided_when_returned,
+    "@function foo() {\n  @return 1/2;\n}\na {\n  color: foo();\n}\n",
+    "a {\n  color: 0.5;\n}\n"
+);
+test!(
+    slash_number_is_divided_in_each,
+    "a {\n  @each $i in 1/2 3/4 {\n    color: $i;\n  }\n}\n",
+    "a {\n  color: 0.5;\n  color: 0.75;\n}\n"
+);
+test!(
+    slash_number_is_divided_by_other_arithmetic,
+    "a {\n  color: 1/2 + 1;\n}\n",
+    "a {\n  color: 1.5;\n}\n"
+);
+test!(
+    slash_with_variable_divides,
+    "$a: 2;\na {\n  color: $a/2;\n}\n",
+    "a {\n  color: 1;\n}\n"
+);
+test!(
+    slash_with_function_call_divides,
+    "a {\n  color: round(1.5)/2;\n}\n",
+    "a {\n  color: 1;\n}\n"
+);
+test!(
+    slash_number_in_interpolation_is_preserved,
+    "a {\n  color: #{1/2};\n}\n",
+    "a {\n  color: 1/2;\n}\n"
+);
+test!(
+    slash_separated_zero_denominator,
+    "a {\n  color: 1px/0;\n}\n",
+    "a {\n  color: 1px/0;\n}\n"
+);
+error!(
+    div_by_zero,
+    "$a: 0;\na {\n  color: 1px / $a;\n}\n", "Error: Division by zero isn't supported."
+);
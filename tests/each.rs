@@ -61,3 +61,8 @@ error!(
     missing_closing_curly_brace,
     "@each $i in 1 {", "Error: expected \"}\"."
 );
+test!(
+    if_else_chain_in_body_without_trailing_semicolons,
+    "@each $k, $v in (a: 1, b: 2) {\n  .#{$k} {\n    @if $v > 1 { x: big } @else { x: small }\n  }\n}\n",
+    ".a {\n  x: small;\n}\n\n.b {\n  x: big;\n}\n"
+);
@@ -248,3 +248,50 @@ error!(
     "a {foo: {bar: red", "Error: Expected identifier."
 );
 error!(toplevel_nullbyte, "\u{0}", "Error: expected selector.");
+
+#[test]
+fn error_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<grass::Error>();
+    assert_send_sync::<grass::Result<String>>();
+}
+
+#[test]
+fn compiles_across_threads() {
+    let handles = (0..4)
+        .map(|i| {
+            std::thread::spawn(move || {
+                grass::from_string(format!("a {{\n  color: {} + 1;\n}}\n", i))
+            })
+        })
+        .collect::<Vec<_>>();
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(
+            format!("a {{\n  color: {};\n}}\n", i + 1),
+            handle.join().unwrap().unwrap()
+        );
+    }
+}
+
+#[test]
+fn compiler_moved_between_threads() {
+    let input = "@function foo($a-b) {\n  @return $a_b;\n}\na {\n  color: foo($a-b: red);\n}\n";
+    let mut compiler = grass::Compiler::new();
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        compiler.compile_string(input.to_string()).unwrap()
+    );
+    let result = std::thread::spawn(move || compiler.compile_string(input.to_string()))
+        .join()
+        .unwrap();
+    assert_eq!("a {\n  color: red;\n}\n", result.unwrap());
+}
+
+#[test]
+fn evaluation_error_points_at_statement() {
+    let input = "a {\n  @media screen {\n    b: selector-append(1, 2);\n  }\n}\n";
+    let err = grass::from_string(input.to_string())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("3 |     b: selector-append(1, 2);"), "{}", err);
+}
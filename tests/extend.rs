@@ -1876,6 +1876,31 @@ test!(
     }",
     "foo, a:current(foo),\n:current(foo) {\n  color: black;\n}\n"
 );
+test!(
+    extended_selector_extended_again_in_middle_of_list,
+    ".a, %p, .c {\n  x: y;\n}\n\n.b1 {\n  @extend %p;\n}\n\n.b2 {\n  @extend %p;\n}\n\n.b3 {\n  @extend .c;\n}\n",
+    ".a, .b2, .b1, .c, .b3 {\n  x: y;\n}\n"
+);
 
 // todo: extend_loop (massive test)
 // todo: extend tests in folders
+test!(
+    extend_inside_where_keeps_zero_specificity_selector,
+    ".a:where(.b) {\n  x: y;\n}\n\n.c {\n  @extend .b;\n}\n",
+    ".a:where(.b, .c) {\n  x: y;\n}\n"
+);
+test!(
+    extend_inside_is,
+    ":is(.a, .b) > .c {\n  x: y;\n}\n\n.d {\n  @extend .a;\n}\n",
+    ":is(.a, .d, .b) > .c {\n  x: y;\n}\n"
+);
+test!(
+    extend_with_incompatible_leading_combinators,
+    "> .a .b {\n  x: y;\n}\n\n+ .a {\n  @extend .b;\n}\n",
+    "> .a .b {\n  x: y;\n}\n"
+);
+test!(
+    extend_universal_namespace_type_with_namespaced_universal,
+    "*|a.c {\n  x: y;\n}\n\nns|* {\n  @extend .c;\n}\n",
+    "*|a.c, ns|a {\n  x: y;\n}\n"
+);
@@ -79,3 +79,8 @@ test!(
     }",
     "a {\n  color: red;\n}\n"
 );
+test!(
+    variable_reassigned_across_iterations_in_function,
+    "@function sum($n) {\n  $r: 0;\n  @for $i from 1 through $n {\n    $r: $r + $i;\n  }\n  @return $r;\n}\n\na {\n  color: sum(4);\n}\n",
+    "a {\n  color: 10;\n}\n"
+);
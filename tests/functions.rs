@@ -143,3 +143,13 @@ test!(
     }",
     "a {\n  color: red;\n}\n"
 );
+test!(
+    recursive_function,
+    "@function fib($n) {\n  @if $n < 2 {\n    @return $n;\n  }\n  @return fib($n - 1) + fib($n - 2);\n}\n\na {\n  color: fib(10);\n}\n",
+    "a {\n  color: 55;\n}\n"
+);
+error!(
+    syntax_error_in_function_that_is_never_called,
+    "@function foo() {\n  @if false {\n    @return (1 + ;\n  }\n  @return 1;\n}\n",
+    "Error: Expected expression."
+);
@@ -0,0 +1,128 @@
+#![cfg(test)]
+
+#[macro_use]
+mod macros;
+
+test!(
+    hwb_space_separated,
+    "a {\n  color: hwb(120 30% 40%);\n}\n",
+    "a {\n  color: #4d994d;\n}\n"
+);
+test!(
+    hwb_comma_separated_with_alpha,
+    "a {\n  color: hwb(120, 30%, 40%, 0.5);\n}\n",
+    "a {\n  color: rgba(77, 153, 77, 0.5);\n}\n"
+);
+test!(
+    hwb_slash_alpha,
+    "a {\n  color: hwb(0 0% 0% / 50%);\n}\n",
+    "a {\n  color: rgba(255, 0, 0, 0.5);\n}\n"
+);
+test!(
+    hwb_hue_converts_angle_units,
+    "a {\n  color: hwb(0.5turn 0% 0%);\n}\n",
+    "a {\n  color: aqua;\n}\n"
+);
+test!(
+    hwb_whiteness_and_blackness_sum_over_100_is_gray,
+    "a {\n  color: hwb(0 60% 60%);\n}\n",
+    "a {\n  color: gray;\n}\n"
+);
+test!(
+    hwb_special_function_is_plain_css,
+    "a {\n  color: hwb(var(--hue) 10% 20%);\n}\n",
+    "a {\n  color: hwb(var(--hue) 10% 20%);\n}\n"
+);
+test!(
+    hwb_is_legacy_rgb_color,
+    "@use \"sass:color\";\na {\n  color: color.is-legacy(hwb(120 30% 40%));\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    whiteness_of_gray,
+    "a {\n  color: whiteness(#808080);\n}\n",
+    "a {\n  color: 50.1960784314%;\n}\n"
+);
+test!(
+    blackness_of_gray,
+    "a {\n  color: blackness(#808080);\n}\n",
+    "a {\n  color: 49.8039215686%;\n}\n"
+);
+test!(
+    whiteness_in_color_module,
+    "@use \"sass:color\";\na {\n  color: color.whiteness(white);\n}\n",
+    "a {\n  color: 100%;\n}\n"
+);
+test!(
+    blackness_of_non_legacy_color,
+    "@use \"sass:color\";\na {\n  color: color.blackness(color(srgb 0.5 0.25 0));\n}\n",
+    "a {\n  color: 50%;\n}\n"
+);
+test!(
+    change_color_whiteness,
+    "a {\n  color: change-color(#ff0000, $whiteness: 20%);\n}\n",
+    "a {\n  color: #ff3333;\n}\n"
+);
+test!(
+    change_color_blackness_of_hsl_color,
+    "a {\n  color: change-color(hsl(0, 100%, 50%), $blackness: 50%);\n}\n",
+    "a {\n  color: maroon;\n}\n"
+);
+test!(
+    adjust_color_blackness,
+    "a {\n  color: adjust-color(#ff3333, $blackness: 20%);\n}\n",
+    "a {\n  color: #cc3333;\n}\n"
+);
+error!(
+    adjust_color_rgb_and_hwb_channels,
+    "a {\n  color: adjust-color(#cc6633, $whiteness: 10%, $red: 1);\n}\n",
+    "Error: RGB parameters may not be passed along with HWB parameters."
+);
+error!(
+    change_color_hsl_and_hwb_channels,
+    "a {\n  color: change-color(#cc6633, $whiteness: 10%, $saturation: 1%);\n}\n",
+    "Error: HSL parameters may not be passed along with HWB parameters."
+);
+error!(
+    adjust_color_rgb_and_hsl_channels,
+    "a {\n  color: adjust-color(#cc6633, $red: 1, $hue: 10);\n}\n",
+    "Error: RGB parameters may not be passed along with HSL parameters."
+);
+test!(
+    adjust_color_hue_and_hwb_channels,
+    "a {\n  color: adjust-color(#cc6633, $whiteness: 10%, $hue: 1);\n}\n",
+    "a {\n  color: #cc794d;\n}\n"
+);
+test!(
+    scale_color_whiteness,
+    "a {\n  color: scale-color(#ff3333, $whiteness: -50%);\n}\n",
+    "a {\n  color: #ff1a1a;\n}\n"
+);
+error!(
+    hwb_whiteness_without_percent,
+    "a {\n  color: hwb(120, 30, 40%);\n}\n", "Error: $whiteness: Expected 30 to have unit \"%\"."
+);
+error!(
+    hwb_blackness_out_of_range,
+    "a {\n  color: hwb(120, 30%, 140%);\n}\n",
+    "Error: $blackness: Expected 140% to be within 0% and 100%."
+);
+error!(
+    hwb_hue_not_an_angle,
+    "a {\n  color: hwb(1px, 30%, 40%);\n}\n",
+    "Error: $hue: Expected 1px to have an angle unit (deg, grad, rad, turn)."
+);
+error!(
+    hwb_too_few_channels,
+    "a {\n  color: hwb(1 2%);\n}\n",
+    "Error: $channels: The hwb color space has 3 channels but (1 2%) has 2."
+);
+error!(
+    whiteness_not_a_color,
+    "a {\n  color: whiteness(1);\n}\n", "Error: $color: 1 is not a color."
+);
+error!(
+    change_color_whiteness_with_rgb_channel,
+    "a {\n  color: change-color(red, $whiteness: 10%, $red: 3);\n}\n",
+    "Error: RGB parameters may not be passed along with HWB parameters."
+);
@@ -133,6 +133,259 @@ fn chained_imports_in_directory() {
     );
 }
 
+#[test]
+fn compiler_reuses_cached_import() {
+    let input = "@import \"compiler_reuses_cached_import\";\na {\n color: $a;\n}";
+    tempfile!("compiler_reuses_cached_import.scss", "$a: red;");
+    let mut compiler = grass::Compiler::new();
+    for _ in 0..3 {
+        assert_eq!(
+            "a {\n  color: red;\n}\n",
+            &compiler.compile_string(input.to_string()).expect(input)
+        );
+    }
+    assert_eq!(1, compiler.cached_files());
+}
+
+#[test]
+fn compiler_invalidates_changed_import() {
+    let input = "@import \"compiler_invalidates_changed_import\";\na {\n color: $a;\n}";
+    tempfile!("compiler_invalidates_changed_import.scss", "$a: red;");
+    let mut compiler = grass::Compiler::new();
+    assert_eq!(
+        "a {\n  color: red;\n}\n",
+        &compiler.compile_string(input.to_string()).expect(input)
+    );
+
+    std::fs::write("compiler_invalidates_changed_import.scss", "$a: blue;").unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open("compiler_invalidates_changed_import.scss")
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(10))
+        .unwrap();
+
+    assert_eq!(
+        "a {\n  color: blue;\n}\n",
+        &compiler.compile_string(input.to_string()).expect(input)
+    );
+    assert_eq!(1, compiler.cached_files());
+}
+
+#[test]
+fn compiler_does_not_reparse_cached_import() {
+    tempfile!(
+        "compiler_does_not_reparse_cached_import.scss",
+        "@import \"compiler_does_not_reparse_cached_import__a\";\n@import \"compiler_does_not_reparse_cached_import__a\";\na {\n @include foo;\n}"
+    );
+    tempfile!(
+        "compiler_does_not_reparse_cached_import__a.scss",
+        "$a: red;\n@function bar() { @return $a; }\n@mixin foo { color: bar(); }"
+    );
+    let mut compiler = grass::Compiler::new();
+    for _ in 0..3 {
+        assert_eq!(
+            "a {\n  color: red;\n}\n",
+            &compiler
+                .compile_path("compiler_does_not_reparse_cached_import.scss")
+                .unwrap()
+        );
+    }
+    assert_eq!(1, compiler.parse_count());
+}
+
+#[test]
+fn compiler_reparses_changed_import() {
+    tempfile!(
+        "compiler_reparses_changed_import.scss",
+        "@import \"compiler_reparses_changed_import__a\";\na {\n color: $a;\n}"
+    );
+    tempfile!("compiler_reparses_changed_import__a.scss", "$a: red;");
+    let mut compiler = grass::Compiler::new();
+    compiler
+        .compile_path("compiler_reparses_changed_import.scss")
+        .unwrap();
+
+    std::fs::write("compiler_reparses_changed_import__a.scss", "$a: blue;").unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open("compiler_reparses_changed_import__a.scss")
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(10))
+        .unwrap();
+
+    assert_eq!(
+        "a {\n  color: blue;\n}\n",
+        &compiler
+            .compile_path("compiler_reparses_changed_import.scss")
+            .unwrap()
+    );
+    assert_eq!(2, compiler.parse_count());
+}
+
+#[test]
+fn compiler_releases_compiled_strings() {
+    let input = "@import \"compiler_releases_compiled_strings\";\na {\n color: $a;\n}";
+    tempfile!("compiler_releases_compiled_strings.scss", "$a: red;");
+    let mut compiler = grass::Compiler::new();
+    for _ in 0..100 {
+        assert_eq!(
+            "a {\n  color: red;\n}\n",
+            &compiler.compile_string(input.to_string()).expect(input)
+        );
+    }
+    assert!(compiler.source_len() <= 2 * input.len() as u64 + 8);
+}
+
+#[test]
+fn compiler_releases_changed_imports() {
+    let input = "@import \"compiler_releases_changed_imports\";\na {\n color: $a;\n}";
+    tempfile!("compiler_releases_changed_imports.scss", "$a: red;");
+    let mut compiler = grass::Compiler::new();
+    for i in 0..50 {
+        std::fs::write(
+            "compiler_releases_changed_imports.scss",
+            format!("$a: {};", i),
+        )
+        .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open("compiler_releases_changed_imports.scss")
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(i))
+            .unwrap();
+        assert_eq!(
+            format!("a {{\n  color: {};\n}}\n", i),
+            compiler.compile_string(input.to_string()).expect(input)
+        );
+    }
+    assert!(compiler.source_len() < 4 * input.len() as u64);
+
+    // spans into cached files must still be valid after the sources are moved
+    std::fs::write("compiler_releases_changed_imports.scss", "$a: 1 + red;").unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open("compiler_releases_changed_imports.scss")
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(100))
+        .unwrap();
+    compiler.compile_string(input.to_string()).expect_err(input);
+    compiler.compile_string("a { b: c }".to_string()).unwrap();
+    let err = compiler
+        .compile_string(input.to_string())
+        .expect_err(input)
+        .to_string();
+    assert!(err.contains("1 | $a: 1 + red;"), "{}", err);
+    assert!(
+        err.contains("compiler_releases_changed_imports.scss:1:5"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn compile_result_lists_loaded_files() {
+    let input = "@import \"compile_result_lists_loaded_files__a\";\n@import \"compile_result_lists_loaded_files__b\";\n@import \"compile_result_lists_loaded_files__a\";";
+    tempfile!("compile_result_lists_loaded_files__a.scss", "$a: red;");
+    tempfile!("_compile_result_lists_loaded_files__b.scss", "$b: red;");
+    let result = grass::Compiler::new()
+        .compile_string_to_result(input.to_string())
+        .expect(input);
+    assert_eq!(
+        &[
+            std::path::PathBuf::from("compile_result_lists_loaded_files__a.scss"),
+            std::path::PathBuf::from("_compile_result_lists_loaded_files__b.scss"),
+        ],
+        result.loaded_files()
+    );
+}
+
+#[test]
+fn load_css_nested_in_style_rule() {
+    let input =
+        "@use \"sass:meta\";\na {\n  @include meta.load-css(\"load_css_nested_in_style_rule\");\n}";
+    tempfile!(
+        "load_css_nested_in_style_rule.scss",
+        "b {\n  color: red;\n}"
+    );
+    assert_eq!(
+        "a b {\n  color: red;\n}\n",
+        &grass::from_string(input.to_string()).expect(input)
+    );
+}
+
+#[test]
+fn load_css_with_configuration() {
+    let input = "@use \"sass:meta\";\n@include meta.load-css(\"load_css_with_configuration\", $with: (a: blue));";
+    tempfile!(
+        "load_css_with_configuration.scss",
+        "$a: red !default;\n$b: green !default;\nc {\n  color: $a;\n  background: $b;\n}"
+    );
+    assert_eq!(
+        "c {\n  color: blue;\n  background: green;\n}\n",
+        &grass::from_string(input.to_string()).expect(input)
+    );
+}
+
+#[test]
+fn load_css_configure_variable_without_default() {
+    let input = "@use \"sass:meta\";\n@include meta.load-css(\"load_css_configure_variable_without_default\", $with: (a: blue));";
+    tempfile!(
+        "load_css_configure_variable_without_default.scss",
+        "$a: red;\nc {\n  color: $a;\n}"
+    );
+    assert_eq!(
+        "Error: This variable was not declared with !default in the @used module.",
+        grass::from_string(input.to_string())
+            .expect_err(input)
+            .to_string()
+            .lines()
+            .next()
+            .unwrap()
+    );
+}
+
+#[test]
+fn load_css_configure_undeclared_variable() {
+    let input = "@use \"sass:meta\";\n@include meta.load-css(\"load_css_configure_undeclared_variable\", $with: (a: blue, zzz: 1));";
+    tempfile!(
+        "load_css_configure_undeclared_variable.scss",
+        "$a: red !default;\nc {\n  color: $a;\n}"
+    );
+    assert_eq!(
+        "Error: $zzz was not declared with !default in the @used module.",
+        grass::from_string(input.to_string())
+            .expect_err(input)
+            .to_string()
+            .lines()
+            .next()
+            .unwrap()
+    );
+}
+
+#[test]
+fn load_css_configure_nested_global_default() {
+    let input = "@use \"sass:meta\";\n@include meta.load-css(\"load_css_configure_nested_global_default\", $with: (a: blue));";
+    tempfile!(
+        "load_css_configure_nested_global_default.scss",
+        "@if true {\n  $a: red !default;\n}\nc {\n  color: $a;\n}"
+    );
+    assert_eq!(
+        "c {\n  color: blue;\n}\n",
+        &grass::from_string(input.to_string()).expect(input)
+    );
+}
+
+#[test]
+fn load_css_does_not_leak_variables() {
+    let input = "@use \"sass:meta\";\n@include meta.load-css(\"load_css_does_not_leak_variables\");\na {\n  color: global-variable-exists(a);\n}";
+    tempfile!("load_css_does_not_leak_variables.scss", "$a: red;");
+    assert_eq!(
+        "a {\n  color: false;\n}\n",
+        &grass::from_string(input.to_string()).expect(input)
+    );
+}
+
 error!(
     missing_input_after_import,
     "@import", "Error: expected more input."
@@ -176,5 +429,26 @@ test!(
     "@import url(2..);\n"
 );
 
+error!(
+    load_css_missing_file,
+    "@use \"sass:meta\";\n@include meta.load-css(\"load_css_missing_file\");",
+    "Error: Can't find stylesheet to import."
+);
+error!(
+    load_css_configure_builtin_module,
+    "@use \"sass:meta\";\n@include meta.load-css(\"sass:math\", $with: (a: 1));",
+    "Error: Built-in modules can't be configured."
+);
+error!(
+    load_css_with_content_block,
+    "@use \"sass:meta\";\n@include meta.load-css(\"sass:math\") {\n  a: b;\n}",
+    "Error: Mixin doesn't accept a content block."
+);
+test!(
+    load_css_builtin_module,
+    "@use \"sass:meta\";\n@include meta.load-css(\"sass:math\");\na {\n  color: red;\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+
 // todo: test for calling paths, e.g. `grass b\index.scss`
 // todo: test for absolute paths (how?)
@@ -174,19 +174,16 @@ test!(
     "a {\n  color: true;\n}\n"
 );
 test!(
-    #[ignore = "https://github.com/sass/dart-sass/issues/789"]
     simple_type_universal_namespace_and_explicit_namespace,
     "a {\n  color: is-superselector(\"*|c\", \"d|c\");\n}\n",
     "a {\n  color: true;\n}\n"
 );
 test!(
-    #[ignore = "https://github.com/sass/dart-sass/issues/789"]
     simple_type_universal_namespace_and_implicit_namespace,
     "a {\n  color: is-superselector(\"*|c\", \"c\");\n}\n",
     "a {\n  color: true;\n}\n"
 );
 test!(
-    #[ignore = "https://github.com/sass/dart-sass/issues/789"]
     simple_type_universal_namespace_and_empty_namespace,
     "a {\n  color: is-superselector(\"*|c\", \"|c\");\n}\n",
     "a {\n  color: true;\n}\n"
@@ -474,3 +471,73 @@ test!(
 
 // todo: /spec/core_functions/selector/is_superselector/simple/pseudo/selector_arg/
 // :not, :matches, :nth-child, :nth-last-child
+test!(
+    pseudo_is_superselector_of_member,
+    "a {\n  color: is-superselector(\":is(.c, .d)\", \".c\");\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    pseudo_is_not_superselector_of_non_member,
+    "a {\n  color: is-superselector(\":is(.c, .d)\", \".e\");\n}\n",
+    "a {\n  color: false;\n}\n"
+);
+test!(
+    pseudo_where_superselector_of_compound,
+    "a {\n  color: is-superselector(\":where(.c)\", \".c.d\");\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    simple_superselector_of_pseudo_is,
+    "a {\n  color: is-superselector(\".c\", \":is(.c.d, .c.e)\");\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    pseudo_is_superselector_of_subset_is,
+    "a {\n  color: is-superselector(\":is(.c, .d)\", \":is(.c)\");\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    pseudo_has_superselector,
+    "a {\n  color: is-superselector(\":has(.c)\", \":has(.c.d)\");\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    pseudo_nth_child_of_superselector,
+    "a {\n  color: is-superselector(\":nth-child(2n+1 of .c)\", \":nth-child(2n+1 of .c.d)\");\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    pseudo_nth_child_of_different_argument,
+    "a {\n  color: is-superselector(\":nth-child(2n of .c)\", \":nth-child(2n+1 of .c)\");\n}\n",
+    "a {\n  color: false;\n}\n"
+);
+test!(
+    simple_universal_explicit_namespace_and_type_same_namespace,
+    "a {\n  color: is-superselector(\"ns|*\", \"ns|a\");\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    simple_universal_explicit_namespace_and_type_implicit_namespace,
+    "a {\n  color: is-superselector(\"ns|*\", \"a\");\n}\n",
+    "a {\n  color: false;\n}\n"
+);
+test!(
+    simple_universal_universal_namespace_and_class,
+    "a {\n  color: is-superselector(\"*|*\", \".a\");\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    simple_class_and_is_with_complex_argument,
+    "a {\n  color: is-superselector(\".a\", \":is(.b .a)\");\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    complex_leading_combinator_in_sub,
+    "a {\n  color: is-superselector(\".a\", \"> .a\");\n}\n",
+    "a {\n  color: false;\n}\n"
+);
+test!(
+    complex_trailing_combinator_in_super,
+    "a {\n  color: is-superselector(\".a >\", \".a > .b\");\n}\n",
+    "a {\n  color: false;\n}\n"
+);
@@ -340,3 +340,83 @@ error!(
     invalid_item_in_comma_separated_list_inside_interpolation,
     "a {\n  color: #{red, color * #abc};\n}\n", "Error: Undefined operation \"color * #abc\"."
 );
+test!(
+    append_does_not_modify_original_list,
+    "$a: 1 2;\n$b: append($a, 3);\na {\n  color: $a;\n  color: $b;\n}\n",
+    "a {\n  color: 1 2;\n  color: 1 2 3;\n}\n"
+);
+test!(
+    set_nth_does_not_modify_original_list,
+    "$a: 1 2;\n$b: set-nth($a, 1, 3);\na {\n  color: $a;\n  color: $b;\n}\n",
+    "a {\n  color: 1 2;\n  color: 3 2;\n}\n"
+);
+test!(
+    append_in_loop,
+    "$a: ();\n@for $i from 1 through 5 {\n  $a: append($a, $i, comma);\n}\na {\n  color: $a;\n}\n",
+    "a {\n  color: 1, 2, 3, 4, 5;\n}\n"
+);
+test!(
+    append_in_loop_reading_list_twice,
+    "$a: ();\n@for $i from 1 through 3 {\n  $a: append($a, length($a));\n}\na {\n  color: $a;\n}\n",
+    "a {\n  color: 0 1 2;\n}\n"
+);
+test!(
+    append_to_global_list_in_mixin_shadows_it,
+    "$a: 1;\n@mixin foo {\n  $a: append($a, 2);\n  color: $a;\n}\na {\n  @include foo;\n  color: $a;\n}\n",
+    "a {\n  color: 1 2;\n  color: 1;\n}\n"
+);
+test!(
+    append_value_from_function_reading_list,
+    "$a: 1;\n@function foo() {\n  @return $a;\n}\n$a: append($a, foo());\na {\n  color: $a;\n}\n",
+    "a {\n  color: 1 1;\n}\n"
+);
+test!(
+    user_defined_append_shadows_builtin,
+    "@function append($list, $val) {\n  @return $list;\n}\n$a: 1;\n$a: append($a, 2);\na {\n  color: $a;\n}\n",
+    "a {\n  color: 1;\n}\n"
+);
+test!(
+    list_slash,
+    "@use \"sass:list\";\na {\n  color: list.slash(1px, 2px, 3px);\n}\n",
+    "a {\n  color: 1px / 2px / 3px;\n}\n"
+);
+test!(
+    list_slash_single_list_argument,
+    "@use \"sass:list\";\na {\n  color: list.slash((a, b));\n}\n",
+    "a {\n  color: a / b;\n}\n"
+);
+test!(
+    list_slash_separator,
+    "@use \"sass:list\";\na {\n  color: list.separator(list.slash(a, b));\n}\n",
+    "a {\n  color: slash;\n}\n"
+);
+test!(
+    list_slash_length,
+    "@use \"sass:list\";\na {\n  color: list.length(list.slash(a b, c));\n}\n",
+    "a {\n  color: 2;\n}\n"
+);
+error!(
+    list_slash_one_element,
+    "@use \"sass:list\";\na {\n  color: list.slash(a);\n}\n",
+    "Error: At least two elements are required."
+);
+test!(
+    join_slash_separator,
+    "a {\n  color: join(1 2, 3, $separator: slash);\n}\n",
+    "a {\n  color: 1 / 2 / 3;\n}\n"
+);
+test!(
+    append_slash_separator,
+    "a {\n  color: append(1 2, 3, slash);\n}\n",
+    "a {\n  color: 1 / 2 / 3;\n}\n"
+);
+error!(
+    append_invalid_separator,
+    "a {\n  color: append(1 2, 3, foo);\n}\n",
+    "Error: $separator: Must be \"space\", \"comma\", \"slash\", or \"auto\"."
+);
+test!(
+    list_module_functions,
+    "@use \"sass:list\";\na {\n  color: list.nth(list.append(a b, c), 3);\n  color: list.index(a b c, b);\n  color: list.is-bracketed([a]);\n}\n",
+    "a {\n  color: c;\n  color: 2;\n  color: true;\n}\n"
+);
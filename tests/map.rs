@@ -87,6 +87,11 @@ test!(
     "a {\n  color: inspect(map-merge((a: b), (c: d)));\n}\n",
     "a {\n  color: (a: b, c: d);\n}\n"
 );
+test!(
+    map_merge_in_loop,
+    "$a: ();\n@for $i from 1 through 3 {\n  $a: map-merge($a, ($i: $i * 2));\n}\na {\n  color: inspect($a);\n}\n",
+    "a {\n  color: (1: 2, 2: 4, 3: 6);\n}\n"
+);
 test!(
     map_merge_both_empty,
     "a {\n  color: inspect(map-merge((), ()));\n}\n",
@@ -182,3 +187,127 @@ test!(
     "$a: (foo: red, ); a {\n  color: inspect($a);\n}\n",
     "a {\n  color: (foo: red);\n}\n"
 );
+test!(
+    map_get_quoted_and_unquoted_keys_are_equal,
+    "a {\n  color: map-get((\"foo\": red), foo);\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+test!(
+    map_get_key_with_compatible_unit,
+    "a {\n  color: map-get((1in: red), 96px);\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+test!(
+    map_merge_replaces_key_in_place,
+    "a {\n  color: inspect(map-merge((a: 1, \"b\": 2, c: 3), (b: 4)));\n}\n",
+    "a {\n  color: (a: 1, \"b\": 4, c: 3);\n}\n"
+);
+error!(
+    duplicate_key_quoted_and_unquoted,
+    "a {\n  $a: (foo: a, \"foo\": b);\n}\n", "Error: Duplicate key."
+);
+test!(
+    map_merge_does_not_modify_original_map,
+    "$a: (a: 1);\n$b: map-merge($a, (b: 2));\na {\n  color: inspect($a);\n  color: inspect($b);\n}\n",
+    "a {\n  color: (a: 1);\n  color: (a: 1, b: 2);\n}\n"
+);
+test!(
+    map_get_key_path,
+    "$a: (b: (c: (d: red)));\na {\n  color: map-get($a, b, c, d);\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+test!(
+    map_get_key_path_through_non_map_is_null,
+    "$a: (b: 1px);\na {\n  color: inspect(map-get($a, b, c));\n}\n",
+    "a {\n  color: null;\n}\n"
+);
+test!(
+    map_has_key_key_path,
+    "$a: (b: (c: 1));\na {\n  color: map-has-key($a, b, c);\n  color: map-has-key($a, b, d);\n  color: map-has-key($a, d, c);\n}\n",
+    "a {\n  color: true;\n  color: false;\n  color: false;\n}\n"
+);
+test!(
+    map_module_get,
+    "@use \"sass:map\";\na {\n  color: map.get((a: (b: red)), a, b);\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+test!(
+    map_set_top_level,
+    "@use \"sass:map\";\na {\n  color: inspect(map.set((a: 1, b: 2), a, 3));\n}\n",
+    "a {\n  color: (a: 3, b: 2);\n}\n"
+);
+test!(
+    map_set_key_path,
+    "@use \"sass:map\";\na {\n  color: inspect(map.set((a: (b: 1, c: 2)), a, b, 3));\n}\n",
+    "a {\n  color: (a: (b: 3, c: 2));\n}\n"
+);
+test!(
+    map_set_key_path_creates_maps,
+    "@use \"sass:map\";\na {\n  color: inspect(map.set((a: 1), a, b, c, 2));\n}\n",
+    "a {\n  color: (a: (b: (c: 2)));\n}\n"
+);
+test!(
+    map_set_uses_sass_equality_for_keys,
+    "@use \"sass:map\";\na {\n  color: inspect(map.set((\"a\": 1), a, 2));\n}\n",
+    "a {\n  color: (\"a\": 2);\n}\n"
+);
+error!(
+    map_set_no_key,
+    "@use \"sass:map\";\na {\n  color: map.set((a: 1));\n}\n",
+    "Error: Expected $args to contain a key."
+);
+error!(
+    map_set_no_value,
+    "@use \"sass:map\";\na {\n  color: map.set((a: 1), a);\n}\n",
+    "Error: Expected $args to contain a value."
+);
+test!(
+    map_merge_key_path,
+    "@use \"sass:map\";\na {\n  color: inspect(map.merge((a: (b: 1)), a, (c: 2)));\n}\n",
+    "a {\n  color: (a: (b: 1, c: 2));\n}\n"
+);
+test!(
+    map_merge_key_path_replaces_non_map,
+    "a {\n  color: inspect(map-merge((a: 1), a, (c: 2)));\n}\n",
+    "a {\n  color: (a: (c: 2));\n}\n"
+);
+test!(
+    map_merge_named_args,
+    "a {\n  color: inspect(map-merge($map1: (a: 1), $map2: (b: 2)));\n}\n",
+    "a {\n  color: (a: 1, b: 2);\n}\n"
+);
+error!(
+    map_merge_key_path_last_arg_not_map,
+    "@use \"sass:map\";\na {\n  color: map.merge((a: 1), a, 1);\n}\n",
+    "Error: $map2: 1 is not a map."
+);
+test!(
+    map_deep_merge,
+    "@use \"sass:map\";\na {\n  color: inspect(map.deep-merge((a: (b: 1, c: 2), d: 3), (a: (c: 4), e: 5)));\n}\n",
+    "a {\n  color: (a: (b: 1, c: 4), d: 3, e: 5);\n}\n"
+);
+test!(
+    map_deep_merge_replaces_non_map,
+    "@use \"sass:map\";\na {\n  color: inspect(map.deep-merge((a: 1), (a: (b: 2))));\n}\n",
+    "a {\n  color: (a: (b: 2));\n}\n"
+);
+error!(
+    map_deep_merge_not_a_map,
+    "@use \"sass:map\";\na {\n  color: map.deep-merge((a: 1), 1);\n}\n",
+    "Error: $map2: 1 is not a map."
+);
+test!(
+    map_deep_remove_single_key,
+    "@use \"sass:map\";\na {\n  color: inspect(map.deep-remove((a: 1, b: 2), a));\n}\n",
+    "a {\n  color: (b: 2);\n}\n"
+);
+test!(
+    map_deep_remove_key_path,
+    "@use \"sass:map\";\na {\n  color: inspect(map.deep-remove((a: (b: 1, c: 2)), a, b));\n}\n",
+    "a {\n  color: (a: (c: 2));\n}\n"
+);
+test!(
+    map_deep_remove_missing_path_is_unchanged,
+    "@use \"sass:map\";\na {\n  color: inspect(map.deep-remove((a: (b: 1)), c, b));\n}\n",
+    "a {\n  color: (a: (b: 1));\n}\n"
+);
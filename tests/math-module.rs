@@ -0,0 +1,225 @@
+#![cfg(test)]
+
+#[macro_use]
+mod macros;
+
+test!(
+    use_default_namespace,
+    "@use \"sass:math\";\na {\n  color: math.div(1, 2);\n}\n",
+    "a {\n  color: 0.5;\n}\n"
+);
+test!(
+    use_as_namespace,
+    "@use \"sass:math\" as m;\na {\n  color: m.div(1, 2);\n}\n",
+    "a {\n  color: 0.5;\n}\n"
+);
+test!(
+    use_as_star,
+    "@use \"sass:math\" as *;\na {\n  color: div(1, 2) $pi;\n}\n",
+    "a {\n  color: 0.5 3.1415926536;\n}\n"
+);
+test!(
+    pi,
+    "@use \"sass:math\";\na {\n  color: math.$pi;\n}\n",
+    "a {\n  color: 3.1415926536;\n}\n"
+);
+test!(
+    e,
+    "@use \"sass:math\";\na {\n  color: math.$e;\n}\n",
+    "a {\n  color: 2.7182818285;\n}\n"
+);
+test!(
+    epsilon,
+    "@use \"sass:math\";\na {\n  color: math.$epsilon < 0.0000000001;\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    max_safe_integer,
+    "@use \"sass:math\";\na {\n  color: math.$max-safe-integer;\n}\n",
+    "a {\n  color: 9007199254740991;\n}\n"
+);
+test!(
+    div_units,
+    "@use \"sass:math\";\na {\n  color: math.div(10px, 4);\n}\n",
+    "a {\n  color: 2.5px;\n}\n"
+);
+test!(
+    div_same_units,
+    "@use \"sass:math\";\na {\n  color: math.div(1in, 48px);\n}\n",
+    "a {\n  color: 2;\n}\n"
+);
+test!(
+    div_strings,
+    "@use \"sass:math\";\na {\n  color: math.div(\"a\", b);\n}\n",
+    "a {\n  color: \"a\"/b;\n}\n"
+);
+test!(
+    pow_integer,
+    "@use \"sass:math\";\na {\n  color: math.pow(2, 10);\n}\n",
+    "a {\n  color: 1024;\n}\n"
+);
+test!(
+    pow_decimal,
+    "@use \"sass:math\";\na {\n  color: math.pow(2, 0.5) == math.sqrt(2);\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    pow_result_equals_integer,
+    "@use \"sass:math\";\na {\n  color: math.pow(2, 2) == 4;\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    sqrt,
+    "@use \"sass:math\";\na {\n  color: math.sqrt(2);\n}\n",
+    "a {\n  color: 1.4142135624;\n}\n"
+);
+test!(
+    log_natural,
+    "@use \"sass:math\";\na {\n  color: math.log(math.$e);\n}\n",
+    "a {\n  color: 1;\n}\n"
+);
+test!(
+    log_base,
+    "@use \"sass:math\";\na {\n  color: math.log(8, 2);\n}\n",
+    "a {\n  color: 3;\n}\n"
+);
+test!(
+    hypot,
+    "@use \"sass:math\";\na {\n  color: math.hypot(3px, 4px);\n}\n",
+    "a {\n  color: 5px;\n}\n"
+);
+test!(
+    hypot_converts_units,
+    "@use \"sass:math\";\na {\n  color: math.hypot(1in, 96px);\n}\n",
+    "a {\n  color: 1.4142135624in;\n}\n"
+);
+test!(
+    sin_deg,
+    "@use \"sass:math\";\na {\n  color: math.sin(90deg);\n}\n",
+    "a {\n  color: 1;\n}\n"
+);
+test!(
+    cos_turn,
+    "@use \"sass:math\";\na {\n  color: math.cos(0.5turn);\n}\n",
+    "a {\n  color: -1;\n}\n"
+);
+test!(
+    tan_unitless_is_rad,
+    "@use \"sass:math\";\na {\n  color: math.tan(math.div(math.$pi, 4));\n}\n",
+    "a {\n  color: 1;\n}\n"
+);
+test!(
+    asin,
+    "@use \"sass:math\";\na {\n  color: math.asin(1);\n}\n",
+    "a {\n  color: 90deg;\n}\n"
+);
+test!(
+    acos,
+    "@use \"sass:math\";\na {\n  color: math.acos(0.5);\n}\n",
+    "a {\n  color: 60deg;\n}\n"
+);
+test!(
+    atan,
+    "@use \"sass:math\";\na {\n  color: math.atan(1);\n}\n",
+    "a {\n  color: 45deg;\n}\n"
+);
+test!(
+    atan2,
+    "@use \"sass:math\";\na {\n  color: math.atan2(1px, -1px);\n}\n",
+    "a {\n  color: 135deg;\n}\n"
+);
+test!(
+    clamp_between,
+    "@use \"sass:math\";\na {\n  color: math.clamp(1px, 0.5in, 100px);\n}\n",
+    "a {\n  color: 0.5in;\n}\n"
+);
+test!(
+    clamp_above_max,
+    "@use \"sass:math\";\na {\n  color: math.clamp(1px, 5px, 3px);\n}\n",
+    "a {\n  color: 3px;\n}\n"
+);
+test!(
+    compatible,
+    "@use \"sass:math\";\na {\n  color: math.compatible(1px, 1in);\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    is_unitless,
+    "@use \"sass:math\";\na {\n  color: math.is-unitless(1px);\n}\n",
+    "a {\n  color: false;\n}\n"
+);
+test!(
+    unit,
+    "@use \"sass:math\";\na {\n  color: math.unit(1px * 1px);\n}\n",
+    "a {\n  color: \"px*px\";\n}\n"
+);
+test!(
+    module_members_in_calc,
+    "@use \"sass:math\";\na {\n  color: calc(math.$pi * 1px + math.div(2px, 2));\n}\n",
+    "a {\n  color: 4.1415926536px;\n}\n"
+);
+error!(
+    no_module_with_namespace,
+    "a {\n  color: math.div(1, 2);\n}\n", "Error: There is no module with the namespace \"math\"."
+);
+error!(
+    undefined_module_function,
+    "@use \"sass:math\";\na {\n  color: math.foo(1);\n}\n", "Error: Undefined function."
+);
+error!(
+    undefined_module_variable,
+    "@use \"sass:math\";\na {\n  color: math.$foo;\n}\n", "Error: Undefined variable."
+);
+error!(
+    duplicate_namespace,
+    "@use \"sass:math\";\n@use \"sass:math\";\n",
+    "Error: There's already a module with namespace \"math\"."
+);
+error!(
+    configure_builtin_module,
+    "@use \"sass:math\" with ($a: 1);\n", "Error: Built-in modules can't be configured."
+);
+error!(
+    unknown_builtin_module,
+    "@use \"sass:foo\";\n", "Error: Can't find stylesheet to import."
+);
+error!(
+    div_by_zero,
+    "@use \"sass:math\";\na {\n  color: math.div(1, 0);\n}\n",
+    "Error: Division by zero isn't supported."
+);
+error!(
+    sqrt_negative,
+    "@use \"sass:math\";\na {\n  color: math.sqrt(-1);\n}\n",
+    "Error: The result is NaN, which can't be represented."
+);
+error!(
+    sqrt_with_units,
+    "@use \"sass:math\";\na {\n  color: math.sqrt(4px);\n}\n",
+    "Error: $number: Expected 4px to have no units."
+);
+error!(
+    sin_non_angle,
+    "@use \"sass:math\";\na {\n  color: math.sin(1px);\n}\n",
+    "Error: $number: Expected 1px to be an angle."
+);
+error!(
+    hypot_mixed_units,
+    "@use \"sass:math\";\na {\n  color: math.hypot(1, 2px);\n}\n",
+    "Error: Argument 1 is unitless but argument 2 has unit px. Arguments must all have units or all be unitless."
+);
+error!(
+    hypot_incompatible_units,
+    "@use \"sass:math\";\na {\n  color: math.hypot(1px, 2s);\n}\n",
+    "Error: Incompatible units s and px."
+);
+error!(
+    clamp_unitless_and_unit,
+    "@use \"sass:math\";\na {\n  color: math.clamp(1, 2px, 3px);\n}\n",
+    "Error: $number: 2px and $min: 1 have incompatible units (one has units and the other doesn't)."
+);
+test!(
+    div_does_not_keep_slash,
+    "@use \"sass:math\";\na {\n  color: math.div(1, 2);\n}\n",
+    "a {\n  color: 0.5;\n}\n"
+);
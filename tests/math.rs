@@ -113,3 +113,39 @@ test!(
     "a {\n  color: random(1000000000000000001 - 1000000000000000000);\n}\n",
     "a {\n  color: 1;\n}\n"
 );
+#[test]
+#[cfg(feature = "random")]
+fn random_with_seed_is_deterministic() {
+    let input = "a {\n  a: random();\n  b: random(100);\n  c: unique-id();\n}\n";
+    let compile = |seed| {
+        grass::Compiler::new()
+            .with_random_seed(seed)
+            .compile_string(input.to_string())
+            .expect(input)
+    };
+    assert_eq!(compile(7), compile(7));
+    assert_ne!(compile(7), compile(8));
+}
+#[test]
+#[cfg(feature = "random")]
+fn random_with_seed_is_reseeded_every_compilation() {
+    let input = "a {\n  color: random(1000000);\n}\n";
+    let mut compiler = grass::Compiler::new().with_random_seed(7);
+    let first = compiler.compile_string(input.to_string()).expect(input);
+    assert_eq!(
+        first,
+        compiler.compile_string(input.to_string()).expect(input)
+    );
+}
+#[test]
+#[cfg(feature = "random")]
+fn random_with_seed_is_stable() {
+    let input = "a {\n  color: random(1000000);\n}\n";
+    assert_eq!(
+        "a {\n  color: 140004;\n}\n",
+        grass::Compiler::new()
+            .with_random_seed(7)
+            .compile_string(input.to_string())
+            .expect(input)
+    );
+}
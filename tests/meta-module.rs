@@ -0,0 +1,154 @@
+#![cfg(test)]
+
+#[macro_use]
+mod macros;
+
+test!(
+    keywords_of_forwarded_args,
+    "@use \"sass:meta\";\n@function foo($args...) {\n  @return meta.keywords($args);\n}\n@function bar($args...) {\n  @return foo($args...);\n}\na {\n  color: inspect(bar(1, $b: 2, $c: 3));\n}\n",
+    "a {\n  color: (b: 2, c: 3);\n}\n"
+);
+test!(
+    module_variables,
+    "@use \"sass:meta\";\n@use \"sass:math\";\na {\n  color: map-get(meta.module-variables(\"math\"), \"pi\");\n}\n",
+    "a {\n  color: 3.1415926536;\n}\n"
+);
+test!(
+    module_variables_empty,
+    "@use \"sass:meta\";\n@use \"sass:string\";\na {\n  color: inspect(meta.module-variables(\"string\"));\n}\n",
+    "a {\n  color: ();\n}\n"
+);
+test!(
+    module_functions_are_callable,
+    "@use \"sass:meta\";\n@use \"sass:math\" as m;\na {\n  color: meta.call(map-get(meta.module-functions(\"m\"), \"div\"), 1, 2);\n}\n",
+    "a {\n  color: 0.5;\n}\n"
+);
+test!(
+    module_functions_in_declaration_order,
+    "@use \"sass:meta\";\n@use \"sass:map\";\na {\n  color: inspect(map-keys(meta.module-functions(\"map\")));\n}\n",
+    "a {\n  color: deep-merge, deep-remove, get, has-key, keys, merge, remove, set, values;\n}\n"
+);
+test!(
+    calc_name,
+    "@use \"sass:meta\";\na {\n  color: meta.calc-name(calc(1px + 10%));\n}\n",
+    "a {\n  color: \"calc\";\n}\n"
+);
+test!(
+    calc_name_clamp,
+    "@use \"sass:meta\";\na {\n  color: meta.calc-name(clamp(1px, 10%, 3px));\n}\n",
+    "a {\n  color: \"clamp\";\n}\n"
+);
+test!(
+    calc_args_operation,
+    "@use \"sass:meta\";\na {\n  color: inspect(meta.calc-args(calc(1px + 10%)));\n}\n",
+    "a {\n  color: (1px + 10%,);\n}\n"
+);
+test!(
+    calc_args_numbers_and_strings,
+    "@use \"sass:meta\";\na {\n  $args: meta.calc-args(clamp(1px, 10%, var(--c)));\n  color: type-of(nth($args, 1)) type-of(nth($args, 3));\n}\n",
+    "a {\n  color: number string;\n}\n"
+);
+test!(
+    use_meta_as_star,
+    "@use \"sass:meta\" as *;\n@use \"sass:math\";\na {\n  color: type-of(module-variables(\"math\"));\n}\n",
+    "a {\n  color: map;\n}\n"
+);
+error!(
+    keywords_not_arglist,
+    "@use \"sass:meta\";\na {\n  color: meta.keywords(1);\n}\n",
+    "Error: $args: 1 is not an argument list."
+);
+error!(
+    module_variables_unknown_module,
+    "@use \"sass:meta\";\na {\n  color: meta.module-variables(\"math\");\n}\n",
+    "Error: There is no module with the namespace \"math\"."
+);
+error!(
+    module_functions_not_string,
+    "@use \"sass:meta\";\na {\n  color: meta.module-functions(1);\n}\n",
+    "Error: $module: 1 is not a string."
+);
+error!(
+    calc_name_not_calculation,
+    "@use \"sass:meta\";\na {\n  color: meta.calc-name(1px);\n}\n",
+    "Error: $calc: 1px is not a calculation."
+);
+error!(
+    calc_args_not_calculation,
+    "@use \"sass:meta\";\na {\n  color: meta.calc-args(\"calc(1px)\");\n}\n",
+    "Error: $calc: \"calc(1px)\" is not a calculation."
+);
+error!(
+    undefined_module_mixin,
+    "@use \"sass:meta\";\na {\n  @include meta.foo;\n}\n", "Error: Undefined mixin."
+);
+test!(
+    apply_with_args,
+    "@use \"sass:meta\";\n@mixin foo($a, $b: 2) {\n  a: $a;\n  b: $b;\n}\nx {\n  @include meta.apply(meta.get-mixin(\"foo\"), 1, $b: 3);\n}\n",
+    "x {\n  a: 1;\n  b: 3;\n}\n"
+);
+test!(
+    apply_mixin_passed_as_argument,
+    "@use \"sass:meta\";\n@mixin red {\n  color: red;\n}\n@mixin theme($mixin) {\n  @include meta.apply($mixin);\n}\nx {\n  @include theme(meta.get-mixin(\"red\"));\n}\n",
+    "x {\n  color: red;\n}\n"
+);
+test!(
+    apply_with_content_block,
+    "@use \"sass:meta\";\n@mixin foo {\n  @content;\n}\nx {\n  @include meta.apply(meta.get-mixin(\"foo\")) {\n    color: red;\n  }\n}\n",
+    "x {\n  color: red;\n}\n"
+);
+test!(
+    apply_with_content_block_arguments,
+    "@use \"sass:meta\";\n@mixin foo {\n  @content(1);\n}\nx {\n  @include meta.apply(meta.get-mixin(\"foo\")) using ($a) {\n    color: $a;\n  }\n}\n",
+    "x {\n  color: 1;\n}\n"
+);
+test!(
+    apply_mixin_by_name,
+    "@use \"sass:meta\";\n@mixin foo($a) {\n  color: $a;\n}\nx {\n  @include meta.apply($mixin: meta.get-mixin(\"foo\"), $a: red);\n}\n",
+    "x {\n  color: red;\n}\n"
+);
+test!(
+    get_mixin_from_module,
+    "@use \"sass:meta\";\na {\n  color: meta.accepts-content(meta.get-mixin(\"load-css\", $module: \"meta\"));\n}\n",
+    "a {\n  color: false;\n}\n"
+);
+test!(
+    mixin_ref_inspect_and_type,
+    "@use \"sass:meta\";\n@mixin foo {}\na {\n  color: inspect(meta.get-mixin(\"foo\")) type-of(meta.get-mixin(\"foo\"));\n}\n",
+    "a {\n  color: get-mixin(\"foo\") mixin;\n}\n"
+);
+test!(
+    mixin_ref_equality,
+    "@use \"sass:meta\";\n@mixin foo {}\n@mixin bar {}\na {\n  color: meta.get-mixin(\"foo\") == meta.get-mixin(foo) meta.get-mixin(\"foo\") == meta.get-mixin(\"bar\");\n}\n",
+    "a {\n  color: true false;\n}\n"
+);
+test!(
+    accepts_content,
+    "@use \"sass:meta\";\n@mixin foo {\n  @content;\n}\n@mixin bar {\n  a: b;\n}\na {\n  color: meta.accepts-content(meta.get-mixin(\"foo\")) meta.accepts-content(meta.get-mixin(\"bar\"));\n}\n",
+    "a {\n  color: true false;\n}\n"
+);
+test!(
+    accepts_content_nested_in_control_flow,
+    "@use \"sass:meta\";\n@mixin foo {\n  @if false {\n    @content;\n  }\n}\na {\n  color: meta.accepts-content(meta.get-mixin(\"foo\"));\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+error!(
+    get_mixin_not_found,
+    "@use \"sass:meta\";\na {\n  color: meta.get-mixin(\"foo\");\n}\n",
+    "Error: Mixin not found: foo"
+);
+error!(
+    mixin_ref_as_css_value,
+    "@use \"sass:meta\";\n@mixin foo {}\na {\n  color: meta.get-mixin(\"foo\");\n}\n",
+    "Error: get-mixin(\"foo\") isn't a valid CSS value."
+);
+error!(
+    apply_not_mixin_ref,
+    "@use \"sass:meta\";\na {\n  @include meta.apply(\"foo\");\n}\n",
+    "Error: $mixin: \"foo\" is not a mixin reference."
+);
+error!(
+    apply_content_block_to_mixin_without_content,
+    "@use \"sass:meta\";\n@mixin foo {\n  a: b;\n}\nx {\n  @include meta.apply(meta.get-mixin(\"foo\")) {\n    color: red;\n  }\n}\n",
+    "Error: Mixin doesn't accept a content block."
+);
@@ -4,19 +4,19 @@
 mod macros;
 
 test!(
-    min_not_evaluated_units_percent,
+    min_evaluated_units_percent,
     "a {\n  color: min(1%, 2%);\n}\n",
-    "a {\n  color: min(1%, 2%);\n}\n"
+    "a {\n  color: 1%;\n}\n"
 );
 test!(
-    min_not_evaluated_units_px,
+    min_evaluated_units_px,
     "a {\n  color: min(1px, 2px);\n}\n",
-    "a {\n  color: min(1px, 2px);\n}\n"
+    "a {\n  color: 1px;\n}\n"
 );
 test!(
-    min_not_evaluated_no_units,
+    min_evaluated_no_units,
     "a {\n  color: min(1, 2);\n}\n",
-    "a {\n  color: min(1, 2);\n}\n"
+    "a {\n  color: 1;\n}\n"
 );
 test!(
     min_not_evaluated_incompatible_units,
@@ -46,26 +46,29 @@ error!(
     min_too_few_args,
     "a {\n  color: min();\n}\n", "Error: At least one argument must be passed."
 );
-// note: we explicitly have units in the opposite order of `dart-sass`.
-// see https://github.com/sass/dart-sass/issues/766
+test!(
+    min_not_evaluated_possibly_compatible_units,
+    "$a: 1px;\n$b: 2%;\na {\n  color: min($a, $b);\n}\n",
+    "a {\n  color: min(1px, 2%);\n}\n"
+);
 error!(
     min_incompatible_units,
-    "$a: 1px;\n$b: 2%;\na {\n  color: min($a, $b);\n}\n", "Error: Incompatible units px and %."
+    "a {\n  color: min(1px, 2s);\n}\n", "Error: 1px and 2s are incompatible."
 );
 test!(
-    max_not_evaluated_units_percent,
+    max_evaluated_units_percent,
     "a {\n  color: max(1%, 2%);\n}\n",
-    "a {\n  color: max(1%, 2%);\n}\n"
+    "a {\n  color: 2%;\n}\n"
 );
 test!(
-    max_not_evaluated_units_px,
+    max_evaluated_units_px,
     "a {\n  color: max(1px, 2px);\n}\n",
-    "a {\n  color: max(1px, 2px);\n}\n"
+    "a {\n  color: 2px;\n}\n"
 );
 test!(
-    max_not_evaluated_no_units,
+    max_evaluated_no_units,
     "a {\n  color: max(1, 2);\n}\n",
-    "a {\n  color: max(1, 2);\n}\n"
+    "a {\n  color: 2;\n}\n"
 );
 test!(
     max_not_evaluated_incompatible_units,
@@ -100,10 +103,45 @@ error!(
     max_too_few_args,
     "a {\n  color: max();\n}\n", "Error: At least one argument must be passed."
 );
-// note: we explicitly have units in the opposite order of `dart-sass`.
-// see https://github.com/sass/dart-sass/issues/766
+test!(
+    max_not_evaluated_possibly_compatible_units,
+    "$a: 1px;\n$b: 2%;\na {\n  color: max($a, $b);\n}\n",
+    "a {\n  color: max(1px, 2%);\n}\n"
+);
 error!(
     max_incompatible_units,
-    "$a: 1px;\n$b: 2%;\na {\n  color: max($a, $b);\n}\n", "Error: Incompatible units px and %."
+    "a {\n  color: max(1px, 2s);\n}\n", "Error: 1px and 2s are incompatible."
+);
+test!(
+    min_nested_calc,
+    "$b: 2px;\na {\n  color: min(calc(1px + 2px), $b);\n}\n",
+    "a {\n  color: 2px;\n}\n"
+);
+test!(
+    max_nested_var,
+    "a {\n  color: max(var(--a), calc(1px + 2px));\n}\n",
+    "a {\n  color: max(var(--a), 3px);\n}\n"
+);
+test!(
+    min_unitless_and_unit_in_sum,
+    "a {\n  color: min(1 + 1px, 3px);\n}\n",
+    "a {\n  color: 2px;\n}\n"
+);
+test!(
+    max_unitless_and_unit_in_sum_with_incompatible_unit,
+    "a {\n  color: max(1 + 1px, 1vw);\n}\n",
+    "a {\n  color: max(2px, 1vw);\n}\n"
+);
+error!(
+    min_unitless_and_unit_in_sum_in_nested_calc,
+    "a {\n  color: min(calc(1 + 1px), 3px);\n}\n", "Error: 1 and 1px are incompatible."
+);
+error!(
+    clamp_unitless_and_unit_in_sum,
+    "a {\n  color: clamp(1 + 1px, 2px, 3px);\n}\n", "Error: 1 and 1px are incompatible."
+);
+test!(
+    min_divide_by_zero,
+    "a {\n  color: min(1px / 0, 2px);\n}\n",
+    "a {\n  color: min(1px / 0, 2px);\n}\n"
 );
-// todo: special functions, min(calc(1), $b);
@@ -117,3 +117,23 @@ test!(
     "a {\n  a: b\n}\n\nb {}\n",
     "a {\n  a: b;\n}\n"
 );
+test!(
+    interpolated_property_and_selector_in_nested_rule,
+    "$i: 2;\na {\n  .b-#{$i} , .c {\n    #{\"col\"}or-#{$i}: red;\n  }\n}\n",
+    "a .b-2, a .c {\n  color-2: red;\n}\n"
+);
+test!(
+    style_after_comment_in_nested_rule,
+    "a {\n  b: c /* ; */;\n  // d: e;\n  f: g;\n}\n",
+    "a {\n  b: c;\n  f: g;\n}\n"
+);
+test!(
+    variable_redeclared_between_styles,
+    "$a: 1;\na {\n  b: $a;\n  $a: 2;\n  c: $a;\n}\nd {\n  e: $a;\n}\n",
+    "a {\n  b: 1;\n  c: 2;\n}\n\nd {\n  e: 1;\n}\n"
+);
+test!(
+    crlf_and_non_ascii_characters,
+    "a {\r\n  color: \"ünïcödé\";\r\n\x0C  b: c;\r\n}\r\n",
+    "@charset \"UTF-8\";\na {\n  color: \"ünïcödé\";\n  b: c;\n}\n"
+);
@@ -364,3 +364,47 @@ error!(
     }",
     "Error: Missing argument $a."
 );
+test!(
+    mixin_body_is_evaluated_independently_per_include,
+    "@mixin foo($a) {\n  $b: $a * 2;\n  .b-#{$a} {\n    width: $b;\n  }\n}\n\na {\n  @include foo(1);\n  @include foo(2);\n}\n",
+    "a .b-1 {\n  width: 2;\n}\na .b-2 {\n  width: 4;\n}\n"
+);
+error!(
+    content_block_passed_to_mixin_without_content,
+    "@mixin foo {\n    color: red;\n}\n\na {\n    @include foo {\n        color: red;\n    }\n}\n",
+    "Error: Mixin doesn't accept a content block."
+);
+error!(
+    content_block_passed_to_mixin_with_content_in_comment,
+    "@mixin foo {\n  /* no @content here */\n  color: red;\n}\n\na {\n  @include foo {\n    color: red;\n  }\n}\n",
+    "Error: Mixin doesn't accept a content block."
+);
+error!(
+    content_block_passed_to_mixin_with_content_in_string,
+    "@mixin foo {\n  color: \"@content\";\n}\n\na {\n  @include foo {\n    color: red;\n  }\n}\n",
+    "Error: Mixin doesn't accept a content block."
+);
+test!(
+    escaped_content_rule,
+    "@mixin foo {\n  @\\63ontent;\n}\n\na {\n  @include foo {\n    color: red;\n  }\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+error!(
+    function_inside_mixin_never_included,
+    "@mixin foo() {\n    @function bar() {\n        @return foo;\n    }\n}\n",
+    "Error: Mixins may not contain function declarations."
+);
+error!(
+    syntax_error_in_mixin_that_is_never_included,
+    "@mixin foo {\n  a {\n    color: red(;\n  }\n}\n", "Error: expected \")\"."
+);
+error!(
+    syntax_error_in_untaken_branch_of_mixin,
+    "@mixin foo {\n  @if false {\n    @include bar(1 +);\n  }\n}\n\na {\n  @include foo;\n}\n",
+    "Error: Expected expression."
+);
+test!(
+    mixin_with_style_rule_included_at_root_and_nested,
+    "@mixin foo {\n  .b {\n    color: red;\n  }\n}\n\n@include foo;\n\na {\n  @include foo;\n}\n\n@include foo;\n",
+    ".b {\n  color: red;\n}\n\na .b {\n  color: red;\n}\n\n.b {\n  color: red;\n}\n"
+);
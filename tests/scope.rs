@@ -61,3 +61,42 @@ test!(
     ",
     "a {\n  color: orange;\n}\na b {\n  color: orange;\n}\na b c {\n  color: orange;\n}\na b c d {\n  color: orange;\n}\n"
 );
+test!(
+    function_sees_later_assignment_in_declaring_scope,
+    "a {\n  $x: red;\n  @function f() {\n    @return $x;\n  }\n  $x: blue;\n  color: f();\n}\n",
+    "a {\n  color: blue;\n}\n"
+);
+test!(
+    mixin_sees_later_assignment_in_declaring_scope,
+    "a {\n  $x: red;\n  @mixin m {\n    color: $x;\n  }\n  $x: blue;\n  @include m;\n}\n",
+    "a {\n  color: blue;\n}\n"
+);
+test!(
+    mixin_assigns_to_variable_it_closes_over,
+    "a {\n  $n: 1;\n  @mixin inc {\n    $n: $n + 1;\n  }\n  @include inc;\n  @include inc;\n  width: $n;\n}\n",
+    "a {\n  width: 3;\n}\n"
+);
+test!(
+    content_block_assigns_to_variable_of_include_scope,
+    "@mixin foo {\n  @content;\n}\na {\n  $a: red;\n  @include foo {\n    $a: blue;\n  }\n  color: $a;\n}\n",
+    "a {\n  color: blue;\n}\n"
+);
+test!(
+    content_block_does_not_see_mixin_variables,
+    "@mixin foo {\n  $a: mixin;\n  @content;\n}\na {\n  $a: include;\n  @include foo {\n    color: $a;\n  }\n}\n",
+    "a {\n  color: include;\n}\n"
+);
+test!(
+    control_flow_at_root_assigns_to_existing_global,
+    "$x: red;\n@if true {\n  $x: blue;\n}\na {\n  color: $x;\n}\n",
+    "a {\n  color: blue;\n}\n"
+);
+test!(
+    default_assigns_to_null_variable,
+    "$a: null;\n$a: red !default;\na {\n  color: $a;\n}\n",
+    "a {\n  color: red;\n}\n"
+);
+error!(
+    control_flow_at_root_declares_local_variable,
+    "@if true {\n  $x: red;\n}\na {\n  color: $x;\n}\n", "Error: Undefined variable."
+);
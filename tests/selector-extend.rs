@@ -306,3 +306,58 @@ test!(
 // todo: https://github.com/sass/sass-spec/blob/master/spec/core_functions/selector/extend/simple/pseudo/selector/idempotent.hrx
 // (starting at line 113)
 // todo: https://github.com/sass/sass-spec/tree/master/spec/core_functions/selector/extend/simple/pseudo/selector/
+test!(
+    simple_pseudo_is_extended,
+    "a {\n  color: selector-extend(\":is(.c)\", \".c\", \".d\");\n}\n",
+    "a {\n  color: :is(.c, .d);\n}\n"
+);
+test!(
+    simple_pseudo_where_extended,
+    "a {\n  color: selector-extend(\".e:where(.c)\", \".c\", \".d\");\n}\n",
+    "a {\n  color: .e:where(.c, .d);\n}\n"
+);
+test!(
+    simple_pseudo_idempotent_not_and_is_list,
+    "a {\n  color: selector-extend(\":not(.c)\", \".c\", \":is(.d, .e)\");\n}\n",
+    "a {\n  color: :not(.c):not(.d):not(.e);\n}\n"
+);
+test!(
+    simple_pseudo_idempotent_not_and_where_list,
+    "a {\n  color: selector-extend(\":not(.c)\", \".c\", \":where(.d, .e)\");\n}\n",
+    "a {\n  color: :not(.c):not(.d):not(.e);\n}\n"
+);
+test!(
+    simple_pseudo_idempotent_is_in_is,
+    "a {\n  color: selector-extend(\":is(.c)\", \".c\", \":is(.d, .e)\");\n}\n",
+    "a {\n  color: :is(.c, .d, .e);\n}\n"
+);
+test!(
+    simple_pseudo_has_in_has_is_not_flattened,
+    "a {\n  color: selector-extend(\":has(.c)\", \".c\", \":has(.d)\");\n}\n",
+    "a {\n  color: :has(.c, :has(.d));\n}\n"
+);
+test!(
+    simple_pseudo_nth_child_of_extended,
+    "a {\n  color: selector-extend(\":nth-child(2n of .c)\", \".c\", \".d\");\n}\n",
+    "a {\n  color: :nth-child(2n of .c, .d);\n}\n"
+);
+test!(
+    combinator_only_leading_incompatible,
+    "a {\n  color: selector-extend(\"> .a .b\", \".b\", \"+ .a\");\n}\n",
+    "a {\n  color: > .a .b;\n}\n"
+);
+test!(
+    combinator_only_trailing,
+    "a {\n  color: selector-extend(\".a >\", \".a\", \".b\");\n}\n",
+    "a {\n  color: .a >, .b >;\n}\n"
+);
+test!(
+    namespaced_type_not_extended_by_type_without_namespace,
+    "a {\n  color: selector-extend(\"ns|a\", \"a\", \".b\");\n}\n",
+    "a {\n  color: ns|a;\n}\n"
+);
+test!(
+    universal_namespace_type_unified_with_namespaced_universal,
+    "a {\n  color: selector-extend(\"*|a.c\", \".c\", \"ns|*\");\n}\n",
+    "a {\n  color: *|a.c, ns|a;\n}\n"
+);
@@ -670,3 +670,53 @@ test!(
     "a {\n  color: selector-unify(\":matches(.c)\", \":matches(.d)\");\n}\n",
     "a {\n  color: :matches(.c):matches(.d);\n}\n"
 );
+test!(
+    simple_pseudo_arg_is_different_selector_arg,
+    "a {\n  color: selector-unify(\":is(.c)\", \":is(.d)\");\n}\n",
+    "a {\n  color: :is(.c):is(.d);\n}\n"
+);
+test!(
+    simple_pseudo_host_and_class,
+    "a {\n  color: inspect(selector-unify(\":host\", \".c\"));\n}\n",
+    "a {\n  color: null;\n}\n"
+);
+test!(
+    simple_pseudo_class_and_host,
+    "a {\n  color: inspect(selector-unify(\".c\", \":host\"));\n}\n",
+    "a {\n  color: null;\n}\n"
+);
+test!(
+    simple_pseudo_universal_and_host,
+    "a {\n  color: inspect(selector-unify(\"*\", \":host\"));\n}\n",
+    "a {\n  color: null;\n}\n"
+);
+test!(
+    simple_pseudo_host_and_selector_pseudo,
+    "a {\n  color: selector-unify(\":host\", \":is(.c)\");\n}\n",
+    "a {\n  color: :is(.c):host;\n}\n"
+);
+test!(
+    simple_universal_explicit_namespace_and_type,
+    "a {\n  color: inspect(selector-unify(\"ns|*\", \"a\"));\n}\n",
+    "a {\n  color: null;\n}\n"
+);
+test!(
+    simple_type_universal_namespace_and_universal_explicit_namespace,
+    "a {\n  color: selector-unify(\"*|a\", \"ns|*\");\n}\n",
+    "a {\n  color: ns|a;\n}\n"
+);
+test!(
+    simple_type_different_namespaces,
+    "a {\n  color: inspect(selector-unify(\"ns1|a\", \"ns2|a\"));\n}\n",
+    "a {\n  color: null;\n}\n"
+);
+test!(
+    complex_different_leading_combinators,
+    "a {\n  color: inspect(selector-unify(\"> .a\", \"+ .b\"));\n}\n",
+    "a {\n  color: null;\n}\n"
+);
+test!(
+    complex_trailing_combinators,
+    "a {\n  color: inspect(selector-unify(\".a >\", \".b ~\"));\n}\n",
+    "a {\n  color: null;\n}\n"
+);
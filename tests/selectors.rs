@@ -275,6 +275,11 @@ test!(
     "a /* foo */ b {\n  color: red;\n}\n",
     "a b {\n  color: red;\n}\n"
 );
+test!(
+    interpolation_in_comment_between_selectors,
+    "$a: b;\na /* #{$a} */ c {\n  color: red;\n}\n",
+    "a c {\n  color: red;\n}\n"
+);
 test!(
     interpolates_comma,
     "$x: oo, ba;\nf#{$x}r {\n  baz {\n    color: red;\n  }\n}\n",
@@ -702,3 +707,43 @@ error!(
     toplevel_parent_selector_after_element,
     "a&{}", "Error: \"&\" may only used at the beginning of a compound selector."
 );
+test!(
+    is_pseudo_selector_list,
+    ":is(.a,.b) > c {\n  color: red;\n}\n",
+    ":is(.a, .b) > c {\n  color: red;\n}\n"
+);
+test!(
+    where_pseudo_selector_list,
+    "a:where(.b,  .c) {\n  color: red;\n}\n",
+    "a:where(.b, .c) {\n  color: red;\n}\n"
+);
+test!(
+    has_pseudo_leading_combinator,
+    "a:has(> img) {\n  color: red;\n}\n",
+    "a:has(> img) {\n  color: red;\n}\n"
+);
+test!(
+    is_pseudo_removes_placeholders,
+    "a:is(%b, c) {\n  color: red;\n}\n",
+    "a:is(c) {\n  color: red;\n}\n"
+);
+test!(
+    nth_child_of_selector,
+    ":nth-child(2n+1 of .foo) {\n  color: red;\n}\n",
+    ":nth-child(2n+1 of .foo) {\n  color: red;\n}\n"
+);
+test!(
+    nth_child_of_selector_no_b,
+    ":nth-child(2n of .foo, .bar) {\n  color: red;\n}\n",
+    ":nth-child(2n of .foo, .bar) {\n  color: red;\n}\n"
+);
+test!(
+    nth_last_child_of_selector_integer,
+    ":nth-last-child(2 of .foo) {\n  color: red;\n}\n",
+    ":nth-last-child(2 of .foo) {\n  color: red;\n}\n"
+);
+test!(
+    nth_child_whitespace_around_b,
+    ":nth-child( 2n + 1 ) {\n  color: red;\n}\n",
+    ":nth-child(2n+1) {\n  color: red;\n}\n"
+);
@@ -13,3 +13,18 @@ test!(
     "a {\n  color: simple-selectors(\".foo.bar.baz\");\n}\n",
     "a {\n  color: .foo, .bar, .baz;\n}\n"
 );
+test!(
+    namespaced_type_and_class,
+    "a {\n  color: simple-selectors(\"ns|a.b\");\n}\n",
+    "a {\n  color: ns|a, .b;\n}\n"
+);
+error!(
+    complex_selector,
+    "a {\n  color: simple-selectors(\"a b\");\n}\n",
+    "Error: $selector: expected selector."
+);
+error!(
+    leading_combinator,
+    "a {\n  color: simple-selectors(\"> .a\");\n}\n",
+    "Error: $selector: expected selector."
+);
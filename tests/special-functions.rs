@@ -5,46 +5,176 @@ mod macros;
 
 test!(
     calc_whitespace,
-    "a {\n  color: calc(       1      );\n}\n",
-    "a {\n  color: calc( 1 );\n}\n"
+    "a {\n  color: calc(       1px      );\n}\n",
+    "a {\n  color: 1px;\n}\n"
 );
-test!(
+error!(
     calc_newline,
-    "a {\n  color: calc(\n);\n}\n",
-    "a {\n  color: calc( );\n}\n"
+    "a {\n  color: calc(\n);\n}\n", "Error: Expected number, variable, function, or calculation."
 );
-test!(
+error!(
     calc_multiple_args,
-    "a {\n  color: calc(1, 2, a, b, c);\n}\n"
+    "a {\n  color: calc(1, 2, a, b, c);\n}\n",
+    "Error: expected \"+\", \"-\", \"*\", \"/\", or \")\"."
+);
+test!(
+    calc_evaluates_arithmetic,
+    "a {\n  color: calc(1 + 2);\n}\n",
+    "a {\n  color: 3;\n}\n"
+);
+test!(
+    calc_evaluates_arithmetic_with_compatible_units,
+    "a {\n  color: calc(1px + 2px);\n}\n",
+    "a {\n  color: 3px;\n}\n"
 );
 test!(
-    calc_does_not_evaluate_arithmetic,
-    "a {\n  color: calc(1 + 2);\n}\n"
+    calc_converts_compatible_units,
+    "a {\n  color: calc(1in - 48px);\n}\n",
+    "a {\n  color: 0.5in;\n}\n"
 );
 test!(
     calc_evaluates_interpolated_arithmetic,
     "a {\n  color: calc(#{1 + 2});\n}\n",
     "a {\n  color: calc(3);\n}\n"
 );
-test!(calc_retains_silent_comment, "a {\n  color: calc(//);\n}\n");
 test!(
-    calc_retains_multiline_comment,
-    "a {\n  color: calc(/**/);\n}\n"
+    calc_interpolation_makes_argument_a_string,
+    "$a: 1px;\na {\n  color: calc(#{$a} + 2px);\n}\n",
+    "a {\n  color: calc(1px + 2px);\n}\n"
+);
+test!(
+    calc_parenthesized_interpolation,
+    "a {\n  color: calc(2 * (#{\"1px + 2%\"}));\n}\n",
+    "a {\n  color: calc(2 * (1px + 2%));\n}\n"
+);
+error!(
+    calc_silent_comment,
+    "a {\n  color: calc(//);\n}\n", "Error: Expected number, variable, function, or calculation."
+);
+error!(
+    calc_empty_multiline_comment,
+    "a {\n  color: calc(/**/);\n}\n", "Error: Expected number, variable, function, or calculation."
+);
+error!(
+    calc_empty_nested_parens,
+    "a {\n  color: calc((((()))));\n}\n",
+    "Error: Expected number, variable, function, or calculation."
+);
+test!(
+    calc_nested_parens,
+    "a {\n  color: calc((((1px))));\n}\n",
+    "a {\n  color: 1px;\n}\n"
+);
+test!(
+    calc_incompatible_units_not_evaluated,
+    "a {\n  color: calc(2px + 2px + 5%);\n}\n",
+    "a {\n  color: calc(4px + 5%);\n}\n"
+);
+test!(
+    calc_variables,
+    "$a: 10px;\n$b: 3;\na {\n  color: calc(100% - $a * $b);\n}\n",
+    "a {\n  color: calc(100% - 30px);\n}\n"
+);
+test!(
+    calc_var_function,
+    "a {\n  color: calc(var(--a) + 2px * 3);\n}\n",
+    "a {\n  color: calc(var(--a) + 6px);\n}\n"
+);
+test!(
+    calc_env_function,
+    "a {\n  color: calc(env(safe-area-inset-top, 20px) - 1px);\n}\n",
+    "a {\n  color: calc(env(safe-area-inset-top, 20px) - 1px);\n}\n"
+);
+test!(
+    calc_parenthesized_var_retains_parens,
+    "a {\n  color: calc((var(--a)) * 2);\n}\n",
+    "a {\n  color: calc((var(--a)) * 2);\n}\n"
+);
+test!(
+    calc_nested_calc_is_unwrapped,
+    "a {\n  color: calc(1% + calc(var(--a) * 2));\n}\n",
+    "a {\n  color: calc(1% + var(--a) * 2);\n}\n"
+);
+test!(
+    calc_parenthesizes_lower_precedence_operand,
+    "a {\n  color: calc(2 * (1px + 2%));\n}\n",
+    "a {\n  color: calc(2 * (1px + 2%));\n}\n"
+);
+test!(
+    calc_negative_rhs_flips_operator,
+    "$a: -2%;\na {\n  color: calc(1px + $a);\n}\n",
+    "a {\n  color: calc(1px - 2%);\n}\n"
 );
-test!(calc_nested_parens, "a {\n  color: calc((((()))));\n}\n");
 test!(
-    calc_invalid_arithmetic,
-    "a {\n  color: calc(2px + 2px + 5%);\n}\n"
+    calc_function_call,
+    "a {\n  color: calc(percentage(0.5) - 1px);\n}\n",
+    "a {\n  color: calc(50% - 1px);\n}\n"
 );
 test!(
     calc_uppercase,
     "a {\n  color: CALC(1 + 1);\n}\n",
-    "a {\n  color: calc(1 + 1);\n}\n"
+    "a {\n  color: 2;\n}\n"
 );
 test!(
     calc_mixed_casing,
     "a {\n  color: cAlC(1 + 1);\n}\n",
-    "a {\n  color: calc(1 + 1);\n}\n"
+    "a {\n  color: 2;\n}\n"
+);
+test!(
+    calc_type_of,
+    "a {\n  color: type-of(calc(1px + 1%));\n}\n",
+    "a {\n  color: calculation;\n}\n"
+);
+test!(
+    calc_equality,
+    "a {\n  color: calc(1px + 1%) == calc(1px + 1%);\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    calc_divide_by_zero,
+    "a {\n  color: calc(1px / 0);\n}\n",
+    "a {\n  color: calc(1px / 0);\n}\n"
+);
+error!(
+    calc_plus_and_minus_require_whitespace,
+    "a {\n  color: calc(1px+2px);\n}\n",
+    "Error: \"+\" and \"-\" must be surrounded by whitespace in calculations."
+);
+error!(
+    calc_known_incompatible_units,
+    "a {\n  color: calc(1px + 2s);\n}\n", "Error: 1px and 2s are incompatible."
+);
+error!(
+    calc_unitless_and_unit,
+    "a {\n  color: calc(1 + 2px);\n}\n", "Error: 1 and 2px are incompatible."
+);
+error!(
+    calc_quoted_string,
+    "$a: \"1px\";\na {\n  color: calc($a);\n}\n",
+    "Error: Quoted string \"1px\" can't be used in a calculation."
+);
+error!(
+    calc_arithmetic_on_calculation,
+    "a {\n  color: calc(1px + 1%) + 1;\n}\n", "Error: Undefined operation \"calc(1px + 1%) + 1\"."
+);
+test!(
+    clamp_evaluated,
+    "a {\n  color: clamp(1px, 5px, 3px);\n}\n",
+    "a {\n  color: 3px;\n}\n"
+);
+test!(
+    clamp_converts_units,
+    "a {\n  color: clamp(1in, 10px, 2in);\n}\n",
+    "a {\n  color: 1in;\n}\n"
+);
+test!(
+    clamp_not_evaluated,
+    "a {\n  color: clamp(1px, 50%, 3px);\n}\n",
+    "a {\n  color: clamp(1px, 50%, 3px);\n}\n"
+);
+error!(
+    clamp_too_few_args,
+    "a {\n  color: clamp(1px, 2px);\n}\n", "Error: 3 arguments required, but only 2 were passed."
 );
 test!(
     element_whitespace,
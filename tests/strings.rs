@@ -237,3 +237,73 @@ test!(
     "a {\n  color: \"Xfoo\";\n}\n"
 );
 test!(hash_in_string, "a {\n  color: \"#foo\";\n}\n");
+test!(
+    str_index_counts_code_points,
+    "a {\n  color: str-index(\"👭abc\", b);\n}\n",
+    "a {\n  color: 3;\n}\n"
+);
+test!(
+    str_slice_counts_code_points,
+    "a {\n  color: str-slice(\"👭abc\", 2, 3);\n}\n",
+    "a {\n  color: \"ab\";\n}\n"
+);
+test!(
+    str_slice_multi_byte_char,
+    "a {\n  color: str-slice(\"a👭b\", 2, 2);\n}\n",
+    "@charset \"UTF-8\";\na {\n  color: \"👭\";\n}\n"
+);
+test!(
+    str_slice_negative_end_before_start,
+    "a {\n  color: str-slice(\"abcd\", 3, -3);\n}\n",
+    "a {\n  color: \"\";\n}\n"
+);
+test!(
+    str_slice_empty_string,
+    "a {\n  color: str-slice(\"\", 1, 2);\n}\n",
+    "a {\n  color: \"\";\n}\n"
+);
+test!(
+    str_insert_negative_counts_code_points,
+    "a {\n  color: str-insert(\"👭abc\", \"X\", -2);\n}\n",
+    "@charset \"UTF-8\";\na {\n  color: \"👭abXc\";\n}\n"
+);
+test!(
+    str_insert_after_multi_byte_char,
+    "a {\n  color: str-insert(\"👭👭\", \"X\", 2);\n}\n",
+    "@charset \"UTF-8\";\na {\n  color: \"👭X👭\";\n}\n"
+);
+test!(
+    string_module_split,
+    "@use \"sass:string\";\na {\n  color: inspect(string.split(\"a-b-c\", \"-\"));\n}\n",
+    "a {\n  color: [\"a\", \"b\", \"c\"];\n}\n"
+);
+test!(
+    string_module_split_unquoted,
+    "@use \"sass:string\";\na {\n  color: inspect(string.split(a-b-c, \"-\"));\n}\n",
+    "a {\n  color: [a, b, c];\n}\n"
+);
+test!(
+    string_module_split_limit,
+    "@use \"sass:string\";\na {\n  color: inspect(string.split(\"a-b-c\", \"-\", 1));\n}\n",
+    "a {\n  color: [\"a\", \"b-c\"];\n}\n"
+);
+test!(
+    string_module_split_empty_separator,
+    "@use \"sass:string\";\na {\n  color: inspect(string.split(\"a👭\", \"\"));\n}\n",
+    "@charset \"UTF-8\";\na {\n  color: [\"a\", \"👭\"];\n}\n"
+);
+test!(
+    string_module_split_empty_string,
+    "@use \"sass:string\";\na {\n  color: inspect(string.split(\"\", \"-\"));\n}\n",
+    "a {\n  color: [];\n}\n"
+);
+error!(
+    string_module_split_limit_zero,
+    "@use \"sass:string\";\na {\n  color: string.split(\"a\", \"-\", 0);\n}\n",
+    "Error: $limit: Expected limit 0 to be 1 or greater."
+);
+test!(
+    string_module_index,
+    "@use \"sass:string\";\na {\n  color: string.index(abcd, c);\n}\n",
+    "a {\n  color: 3;\n}\n"
+);
@@ -170,3 +170,8 @@ test!(
     "a {\n  color: unquote(\"foo  \");\n}\n",
     "a {\n  color: foo  ;\n}\n"
 );
+test!(
+    escaped_property_name,
+    "a {\n  \\63olor: red;\n}\n",
+    "a {\n  color: red;\n}\n"
+);
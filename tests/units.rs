@@ -129,12 +129,45 @@ test!(
     "a {\n  color: unit((1 / 1in) * 1in);\n}\n",
     "a {\n  color: \"\";\n}\n"
 );
+test!(
+    unit_identical_units_cancel_before_converting,
+    "a {\n  color: unit((1px * 1in) / 1in);\n  color: (1px * 1in) / 1in;\n}\n",
+    "a {\n  color: \"px\";\n  color: 1px;\n}\n"
+);
+test!(
+    unit_div_mul_converts_and_cancels,
+    "a {\n  color: (1in / 1s) * (1s / 1px);\n}\n",
+    "a {\n  color: 96;\n}\n"
+);
+test!(
+    unit_div_multiple_denominators,
+    "a {\n  color: unit(1 / 1px / 1s);\n}\n",
+    "a {\n  color: \"(px*s)^-1\";\n}\n"
+);
+test!(
+    complex_units_add_with_conversion,
+    "a {\n  color: inspect(1px * 1s + 1in * 1s);\n}\n",
+    "a {\n  color: 97px*s;\n}\n"
+);
+test!(
+    complex_units_compare_with_conversion,
+    "a {\n  color: (1px * 1s) < (1in * 1s);\n}\n",
+    "a {\n  color: true;\n}\n"
+);
+test!(
+    modulo_converts_units,
+    "a {\n  color: 1in % 5px;\n}\n",
+    "a {\n  color: 0.0104166667in;\n}\n"
+);
+error!(
+    complex_units_add_incompatible,
+    "a {\n  color: 1px * 1s + 1px * 1px;\n}\n", "Error: Incompatible units px*px and px*s."
+);
 error!(
     display_single_div_with_none_numerator,
     "a {\n  color: (1 / 1em);\n}\n", "Error: 1em^-1 isn't a valid CSS value."
 );
 error!(
-    #[ignore = "non-comparable inverse units"]
     display_single_div_with_non_comparable_numerator,
     "a {\n  color: (1px / 1em);\n}\n", "Error: 1px/em isn't a valid CSS value."
 );